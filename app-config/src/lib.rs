@@ -0,0 +1,251 @@
+//! The configuration shared by the gui, the server & client, and the compute binaries.
+//!
+//! The configuration is read from a TOML file within the platform's config folder,
+//! such as `~/.config/shut-the-box/config.toml` on Linux.
+//! Each value can then be overridden by an environment variable, which can in turn be overridden by a binary's arguments.
+//! Any value that isn't set is left for the binary to default.
+
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rules::{Ruleset, RulesetError};
+use serde::{Deserialize, Serialize};
+
+/// The folder the config file is kept in, within the platform's config folder.
+pub const CONFIG_FOLDER: &str = "shut-the-box";
+/// The name of the config file.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The environment variable of the config file to read instead of the one in the platform's config folder.
+pub const CONFIG_VAR: &str = "SHUT_THE_BOX_CONFIG";
+/// The environment variable overriding the file of best moves.
+pub const POLICY_VAR: &str = "SHUT_THE_BOX_POLICY";
+/// The environment variable overriding the file of ranked moves.
+pub const RANKED_VAR: &str = "SHUT_THE_BOX_RANKED";
+/// The environment variable overriding the port of the server.
+pub const PORT_VAR: &str = "SHUT_THE_BOX_PORT";
+/// The environment variable overriding the IP address the server is hosted on & the client connects to.
+pub const ADDRESS_VAR: &str = "SHUT_THE_BOX_ADDRESS";
+/// The environment variable overriding the ruleset, such as "golf".
+pub const RULESET_VAR: &str = "SHUT_THE_BOX_RULESET";
+/// The environment variable overriding the amount of tiles.
+pub const TILES_VAR: &str = "SHUT_THE_BOX_TILES";
+/// The environment variable overriding whether one dice can be rolled.
+pub const ONE_DIE_VAR: &str = "SHUT_THE_BOX_ONE_DIE";
+/// The environment variable overriding the theme.
+pub const THEME_VAR: &str = "SHUT_THE_BOX_THEME";
+/// The environment variable overriding the most threads to simulate on.
+pub const THREADS_VAR: &str = "SHUT_THE_BOX_THREADS";
+/// The environment variable overriding the strategy of the bot opponents.
+pub const BOT_VAR: &str = "SHUT_THE_BOX_BOT";
+/// The environment variable overriding how often the bot opponents make their strategy's move.
+pub const BOT_DIFFICULTY_VAR: &str = "SHUT_THE_BOX_BOT_DIFFICULTY";
+/// The environment variable overriding how many seconds the players of the server have to take their turn.
+pub const TURN_TIMEOUT_VAR: &str = "SHUT_THE_BOX_TURN_TIMEOUT";
+/// The environment variable overriding the most players that can join the server.
+pub const MAX_PLAYERS_VAR: &str = "SHUT_THE_BOX_MAX_PLAYERS";
+/// The environment variable overriding the amount of bots the server keeps in its lobby.
+pub const SERVER_BOTS_VAR: &str = "SHUT_THE_BOX_SERVER_BOTS";
+/// The environment variable overriding the clients banned from the server, separated by commas.
+pub const BANNED_VAR: &str = "SHUT_THE_BOX_BANNED";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Couldn't read the config file {}: {source}", .path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Invalid config file {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("Invalid value \"{value}\" for {var}")]
+    Env { var: &'static str, value: String },
+}
+
+/// The colours the gui is drawn with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Theme::Dark => write!(formatter, "dark"),
+            Theme::Light => write!(formatter, "light"),
+        }
+    }
+}
+
+/// The values shared between the binaries, each of which is None if it wasn't set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The file of best moves to load or serve.
+    pub policy_path: Option<PathBuf>,
+    /// The file of ranked moves to load.
+    pub ranked_path: Option<PathBuf>,
+    /// The IP address the server is hosted on & the client connects to.
+    pub address: Option<IpAddr>,
+    /// The port the server is hosted on & connected to.
+    pub port: Option<u16>,
+    /// The name of the ruleset the games are played with, such as "classic" or "golf".
+    pub ruleset: Option<String>,
+    /// The amount of tiles on the board, which overrides the ruleset's.
+    pub tiles: Option<u8>,
+    /// Whether a single dice can be rolled once the 7 & higher pieces are knocked down, which overrides the ruleset's.
+    pub one_die: Option<bool>,
+    /// The theme of the gui.
+    pub theme: Option<Theme>,
+    /// The most threads the games are simulated on.
+    pub threads: Option<u8>,
+    /// The strategy the bot opponents play with, such as "exec:./my-bot" or "policy:table.csv".
+    pub bot_strategy: Option<String>,
+    /// How often the bot opponents make their strategy's move, such as "easy", "hard", or "80%".
+    pub bot_difficulty: Option<String>,
+    /// How many seconds the players of the server have to take their turn before they're out, where 0 is no limit.
+    pub turn_timeout: Option<u64>,
+    /// The most players that can join the server.
+    pub max_players: Option<u8>,
+    /// The amount of bots the server keeps in its lobby, so there's always someone to play against.
+    pub server_bots: Option<u8>,
+    /// The names & MAC addresses of the clients that can't join the server.
+    pub banned: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Returns the config file that is read, which is set by [`CONFIG_VAR`] or is within the platform's config folder.
+    /// Returns None if the platform doesn't have a config folder.
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os(CONFIG_VAR) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::config_dir().map(|config| config.join(CONFIG_FOLDER).join(CONFIG_FILE)),
+        }
+    }
+
+    /// Reads the config file, then overrides it with the environment variables.
+    /// If there isn't a config file, then only the environment variables are used.
+    pub fn load() -> Result<Config, ConfigError> {
+        let config = match Config::path() {
+            Some(path) => Config::read(&path)?,
+            None => Config::default(),
+        };
+        config.with_env()
+    }
+
+    /// Reads the given config file, which is empty if it doesn't exist.
+    pub fn read(path: &Path) -> Result<Config, ConfigError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(source) => {
+                return Err(ConfigError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Overrides the values with the ones set by the environment variables.
+    pub fn with_env(self) -> Result<Config, ConfigError> {
+        let overrides = Config {
+            policy_path: std::env::var_os(POLICY_VAR).map(PathBuf::from),
+            ranked_path: std::env::var_os(RANKED_VAR).map(PathBuf::from),
+            address: env_value(ADDRESS_VAR)?,
+            port: env_value(PORT_VAR)?,
+            ruleset: std::env::var(RULESET_VAR).ok(),
+            tiles: env_value(TILES_VAR)?,
+            one_die: env_value(ONE_DIE_VAR)?,
+            theme: env_value(THEME_VAR)?,
+            threads: env_value(THREADS_VAR)?,
+            bot_strategy: std::env::var(BOT_VAR).ok(),
+            bot_difficulty: std::env::var(BOT_DIFFICULTY_VAR).ok(),
+            turn_timeout: env_value(TURN_TIMEOUT_VAR)?,
+            max_players: env_value(MAX_PLAYERS_VAR)?,
+            server_bots: env_value(SERVER_BOTS_VAR)?,
+            banned: std::env::var(BANNED_VAR).ok().map(|banned| {
+                banned
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|client| !client.is_empty())
+                    .map(String::from)
+                    .collect()
+            }),
+        };
+        Ok(self.override_with(overrides))
+    }
+
+    /// Overrides the values with the ones that are set in the given config, such as from a binary's arguments.
+    pub fn override_with(self, overrides: Config) -> Config {
+        Config {
+            policy_path: overrides.policy_path.or(self.policy_path),
+            ranked_path: overrides.ranked_path.or(self.ranked_path),
+            address: overrides.address.or(self.address),
+            port: overrides.port.or(self.port),
+            ruleset: overrides.ruleset.or(self.ruleset),
+            tiles: overrides.tiles.or(self.tiles),
+            one_die: overrides.one_die.or(self.one_die),
+            theme: overrides.theme.or(self.theme),
+            threads: overrides.threads.or(self.threads),
+            bot_strategy: overrides.bot_strategy.or(self.bot_strategy),
+            bot_difficulty: overrides.bot_difficulty.or(self.bot_difficulty),
+            turn_timeout: overrides.turn_timeout.or(self.turn_timeout),
+            max_players: overrides.max_players.or(self.max_players),
+            server_bots: overrides.server_bots.or(self.server_bots),
+            banned: overrides.banned.or(self.banned),
+        }
+    }
+
+    /// Returns the rules the games are played with, which are the given rules unless a ruleset is named.
+    /// The amount of tiles & the one dice rule are then overridden, if they're set.
+    pub fn ruleset(&self, default: Ruleset) -> Result<Ruleset, RulesetError> {
+        let mut ruleset = match &self.ruleset {
+            Some(name) => name.parse()?,
+            None => default,
+        };
+        if let Some(tiles) = self.tiles {
+            ruleset.tiles = tiles;
+        }
+        if let Some(one_die) = self.one_die {
+            ruleset.one_die = one_die;
+        }
+
+        ruleset.validate()?;
+        Ok(ruleset)
+    }
+}
+
+/// Parses the value of the environment variable, which is None if it isn't set.
+fn env_value<T: FromStr>(var: &'static str) -> Result<Option<T>, ConfigError> {
+    let Ok(value) = std::env::var(var) else {
+        return Ok(None);
+    };
+
+    match value.trim().parse() {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(_) => Err(ConfigError::Env { var, value }),
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use app_config::Config;
+use compute::config::{DEFAULT_OUTPUT, DEFAULT_RANKED_OUTPUT};
+use compute::{list_tiles, BoardRoll, DiceRoll, Policy, RankedMove, MAX_TILES};
+use tracing::error;
+
+/// Prints every legal move for a board & roll, ranked by their chance to win, with the best move marked.
+///
+/// Usage: analyze <board> <roll> [policy file] [ranked file]
+/// The board is either a comma separated list of the tiles still up, such as "1,2,3,9",
+/// or the board's value as a number, such as "511" or "0b111111111".
+/// A board with a single tile up needs a trailing comma, such as "9,", so it isn't read as a value.
+/// Defaults to the files set by the config, or "best_moves.yml" & "ranked_moves.yml".
+fn main() -> ExitCode {
+    let _guard = logging::init("analyze", None);
+    let mut args = env::args().skip(1);
+
+    let (Some(board), Some(roll)) = (args.next(), args.next()) else {
+        error!("Usage: analyze <board> <roll> [policy file] [ranked file]");
+        return ExitCode::FAILURE;
+    };
+    let board = match parse_board(&board) {
+        Ok(board) => board,
+        Err(e) => {
+            error!("Invalid board \"{board}\": {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let roll = match roll.trim().parse::<u8>() {
+        Ok(roll) if (1..=12).contains(&roll) => roll,
+        _ => {
+            error!("Invalid roll \"{roll}\", it must be between 1 & 12");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = match Config::load() {
+        Ok(config) => config.override_with(Config {
+            policy_path: args.next().map(PathBuf::from),
+            ranked_path: args.next().map(PathBuf::from),
+            ..Config::default()
+        }),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let policy_path = config
+        .policy_path
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT));
+    let ranked_path = config
+        .ranked_path
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RANKED_OUTPUT));
+
+    let policy = match Policy::load(&policy_path) {
+        Ok(policy) => {
+            let note = format!("{} ({} moves)", policy_path.display(), policy.len());
+            logging::crash::note("policy", note);
+            policy
+        }
+        Err(e) => {
+            error!("{e} ({})", policy_path.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let ranked_moves = match load_ranked_moves(&ranked_path) {
+        Ok(ranked_moves) => ranked_moves,
+        Err(e) => {
+            error!("{e} ({})", ranked_path.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    analyze(board, roll, &policy, ranked_moves.as_ref());
+    ExitCode::SUCCESS
+}
+
+/// Prints every legal move, from the most likely to win to the least.
+/// Moves without a known chance to win are listed last.
+fn analyze(
+    board: u16,
+    roll: u8,
+    policy: &Policy,
+    ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+) {
+    println!("Board [{}] ({board}), roll {roll}", list_tiles(board));
+
+    let dice = DiceRoll::from(roll);
+    let best = policy.best_move(board, dice);
+    let ranked = ranked_moves.and_then(|ranked| ranked.get(&BoardRoll::new(board, dice)));
+
+    let mut moves: Vec<(u16, Option<u16>)> = game_core::enumerate_moves(board, roll)
+        .into_iter()
+        .map(|moved| {
+            let value = ranked
+                .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == moved))
+                .map(|ranked| ranked.value);
+            (moved, value)
+        })
+        .collect();
+    moves.sort_by_key(|(moved, value)| (std::cmp::Reverse(*value), *moved));
+
+    if moves.is_empty() {
+        println!("There are no legal moves, so the roll is a dying one.");
+        return;
+    }
+
+    for (moved, value) in moves {
+        let marker = match best == Some(moved) {
+            true => "*",
+            false => " ",
+        };
+        let chance = value.map_or(String::from("unknown"), |value| {
+            format!("{:.1}%", value as f32 / 10.)
+        });
+        println!(
+            "{marker} knock down [{}] -> [{}] ({moved}): {chance} to win",
+            list_tiles(board & !moved),
+            list_tiles(moved)
+        );
+    }
+
+    if best.is_none() {
+        println!("The policy doesn't know the best move for this board & roll.");
+    }
+}
+
+/// Parses the board from either a comma separated list of tiles, or the value of the board.
+fn parse_board(text: &str) -> Result<u16, String> {
+    let text = text.trim();
+
+    if let Some(binary) = text.strip_prefix("0b") {
+        return u16::from_str_radix(binary, 2).map_err(|e| e.to_string());
+    }
+    if !text.contains(',') {
+        return text
+            .parse()
+            .map_err(|e: std::num::ParseIntError| e.to_string());
+    }
+
+    text.split(',')
+        .filter(|tile| !tile.trim().is_empty())
+        .try_fold(0, |board, tile| match tile.trim().parse::<u8>() {
+            Ok(tile) if (1..=MAX_TILES).contains(&tile) => Ok(board | 1 << (tile - 1)),
+            _ => Err(format!("\"{tile}\" isn't a tile between 1 & {MAX_TILES}")),
+        })
+}
+
+/// Loads the moves ranked by the simulation, which are None if the file doesn't exist.
+fn load_ranked_moves(path: &Path) -> Result<Option<HashMap<BoardRoll, Vec<RankedMove>>>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // The chances to win are only shown if the moves were ranked.
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Couldn't read ranked moves file: {e}")),
+    };
+
+    compute::codec::from_reader(BufReader::new(file))
+        .map(Some)
+        .map_err(|e| format!("Couldn't parse ranked moves: {e}"))
+}
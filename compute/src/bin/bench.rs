@@ -0,0 +1,98 @@
+use std::env;
+use std::process::ExitCode;
+
+use app_config::Config;
+use compute::{head_to_head, rng, DiceDistribution, HeadToHead, Ruleset, StrategySpec};
+use tracing::error;
+
+/// The amount of games played if it isn't given.
+const DEFAULT_GAMES: u32 = 10000;
+
+/// Plays two strategies against each other with the same rolls, then prints how often each won & their scores.
+///
+/// Usage: bench <first strategy> <second strategy> [games] [seed]
+/// Each strategy is described by [`compute::external`], such as "best_moves.yml" or "highest-tiles-first".
+/// Defaults to 10000 games with a seed derived from the master seed, which is printed so the games can be played again.
+/// The amount of tiles is set by the config's ruleset.
+fn main() -> ExitCode {
+    let _guard = logging::init("bench", None);
+    let mut args = env::args().skip(1);
+
+    let (Some(first), Some(second)) = (args.next(), args.next()) else {
+        error!("Usage: bench <first strategy> <second strategy> [games] [seed]");
+        return ExitCode::FAILURE;
+    };
+    let games = match args.next().map(|games| games.trim().parse::<u32>()) {
+        None => DEFAULT_GAMES,
+        Some(Ok(games)) if games > 0 => games,
+        Some(_) => {
+            error!("The amount of games has to be a number above 0");
+            return ExitCode::FAILURE;
+        }
+    };
+    let seed = match args.next().map(|seed| seed.trim().parse::<u64>()) {
+        None => rng::stream("bench").u64(..),
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => {
+            error!("The seed has to be a number");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let ruleset = Config::load()
+        .map_err(|e| e.to_string())
+        .and_then(|config| config.ruleset(Ruleset::CLASSIC).map_err(|e| e.to_string()));
+    let tiles = match ruleset {
+        Ok(ruleset) => ruleset.tiles,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut strategies = Vec::new();
+    for name in [&first, &second] {
+        match name.parse::<StrategySpec>().and_then(|spec| spec.load()) {
+            Ok(strategy) => strategies.push(strategy),
+            Err(e) => {
+                error!("{e} ({name})");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let results = head_to_head(
+        [strategies[0].as_ref(), strategies[1].as_ref()],
+        games,
+        Some(seed),
+        &DiceDistribution::Fair,
+        tiles,
+    );
+
+    println!("{games} games with {tiles} tiles, seed {seed}");
+    print_results(&results, 0, &first);
+    print_results(&results, 1, &second);
+    println!(
+        "Draws: {} ({:.1}%)",
+        results.draws,
+        results.draws as f64 / results.games as f64 * 100.
+    );
+    ExitCode::SUCCESS
+}
+
+/// Prints the win rate & average score of the strategy, with their 95% confidence intervals.
+fn print_results(results: &HeadToHead, strategy: usize, name: &str) {
+    let (win_low, win_high) = results.win_interval(strategy);
+    let (score_low, score_high) = results.score_interval(strategy);
+
+    println!(
+        "{name}: {} wins, {:.1}% (95% CI {:.1}% - {:.1}%), average score {:.2} (95% CI {:.2} - {:.2})",
+        results.wins[strategy],
+        results.win_rate(strategy) * 100.,
+        win_low * 100.,
+        win_high * 100.,
+        results.average_score(strategy),
+        score_low,
+        score_high
+    );
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use app_config::Config;
+use compute::Policy;
+use tracing::{error, info};
+
+/// Serves the best moves from a policy file over http.
+///
+/// Usage: best-move-server [policy file] [address]
+/// Defaults to the policy file set by the config, or "best_moves.yml", & "127.0.0.1:8080".
+fn main() -> ExitCode {
+    let _guard = logging::init("best-move-server", None);
+    let mut args = env::args().skip(1);
+
+    let config = match Config::load() {
+        Ok(config) => config.override_with(Config {
+            policy_path: args.next().map(PathBuf::from),
+            ..Config::default()
+        }),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let policy_path = config
+        .policy_path
+        .unwrap_or_else(|| PathBuf::from(compute::config::DEFAULT_OUTPUT));
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+
+    let address: SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid address \"{address}\": {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let policy = match Policy::load_detected(&policy_path) {
+        Ok((policy, format)) => {
+            info!("Loaded {} best moves in the {format} format", policy.len());
+            let note = format!(
+                "{} ({format} format, {} moves)",
+                policy_path.display(),
+                policy.len()
+            );
+            logging::crash::note("policy", note);
+            policy
+        }
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    info!(
+        "Serving best moves from \"{}\" on http://{address}/best",
+        policy_path.display()
+    );
+    match compute::http::serve(policy, address) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
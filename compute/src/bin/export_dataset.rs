@@ -0,0 +1,74 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use app_config::Config;
+use compute::{export_dataset, DatasetConfig, Opponent, Ruleset, StrategySpec};
+use tracing::error;
+
+/// Simulates pairs of games & exports every move made in them as a CSV or parquet dataset.
+/// The columns of the dataset are documented on [`compute::export_dataset`].
+///
+/// Usage: export-dataset <pairs of games> [output file] [opponent strategy] [seed]
+/// The format is chosen by the extension of the output file, which defaults to "dataset.csv".
+/// The opponent is a strategy as described by [`compute::external`], such as "best_moves.yml".
+/// Without an opponent, or with an empty one such as "", the second game of each pair is also played randomly.
+/// The rules the games are played with are set by the config.
+fn main() -> ExitCode {
+    let _guard = logging::init("export-dataset", None);
+    let mut args = env::args().skip(1);
+
+    let usage =
+        "Usage: export-dataset <pairs of games> [output file] [opponent policy file] [seed]";
+    let Some(games) = args.next() else {
+        error!("{usage}");
+        return ExitCode::FAILURE;
+    };
+    let Ok(games) = games.trim().parse::<u64>() else {
+        error!("Invalid amount of games \"{games}\"");
+        return ExitCode::FAILURE;
+    };
+
+    let mut config = DatasetConfig::new(games);
+    if let Some(output) = args.next() {
+        config.output = PathBuf::from(output);
+    }
+    if let Some(opponent) = args.next().filter(|opponent| !opponent.is_empty()) {
+        match opponent
+            .parse::<StrategySpec>()
+            .and_then(|spec| spec.load())
+        {
+            Ok(strategy) => config.opponent = Opponent::Fixed(strategy),
+            Err(e) => {
+                error!("{e} ({opponent})");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Some(seed) = args.next() {
+        let Ok(seed) = seed.trim().parse::<u64>() else {
+            error!("Invalid seed \"{seed}\"");
+            return ExitCode::FAILURE;
+        };
+        config.seed = Some(seed);
+    }
+
+    let ruleset = Config::load()
+        .map_err(|e| e.to_string())
+        .and_then(|config| config.ruleset(Ruleset::CLASSIC).map_err(|e| e.to_string()));
+    match ruleset {
+        Ok(ruleset) => config.ruleset = ruleset,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    match export_dataset(&config) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::env;
+use std::process::ExitCode;
+
+use compute::{DiceDistribution, GameNode, GameState, ShutChances, MAX_TILES};
+use tracing::error;
+
+/// How many moves deep the tree is expanded if it isn't given.
+const DEFAULT_DEPTH: usize = 2;
+
+/// Prints the game tree reachable from a board as a GraphViz DOT graph, such as for `dot -Tsvg`.
+///
+/// Usage: game-tree <board> [depth] [--chances]
+/// The board is either a comma separated list of the tiles still up, such as "1,2,3,9",
+/// or the board's value as a number, such as "511" or "0b111111111".
+/// The depth is how many moves deep the tree is expanded, which defaults to 2, & 0 expands the whole tree.
+/// With "--chances", each node is coloured by its chance of shutting the box with fair dice.
+fn main() -> ExitCode {
+    let _guard = logging::init("game-tree", None);
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut args = args.into_iter();
+
+    let Some(board) = args.next() else {
+        error!("Usage: game-tree <board> [depth] [--chances]");
+        return ExitCode::FAILURE;
+    };
+    let board = match parse_board(&board) {
+        Ok(board) => board,
+        Err(e) => {
+            error!("Invalid board \"{board}\": {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let depth = match args.next().map(|depth| depth.trim().parse::<usize>()) {
+        None => DEFAULT_DEPTH,
+        // Every move knocks a tile down, so no tree is deeper than the amount of tiles.
+        Some(Ok(0)) => MAX_TILES as usize,
+        Some(Ok(depth)) => depth,
+        Some(Err(_)) => {
+            error!("The depth has to be a number");
+            return ExitCode::FAILURE;
+        }
+    };
+    let chances = match flags.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => None,
+        ["--chances"] => Some(ShutChances::solve(MAX_TILES, DiceDistribution::Fair)),
+        _ => {
+            error!("The only option is \"--chances\"");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut tree = GameNode::new(GameState::new(board), DiceDistribution::Fair);
+    // Each move is a level for the roll & a level for the move made with it.
+    tree.expand_to_depth(depth * 2);
+    print!("{}", tree.to_dot(chances.as_ref()));
+    ExitCode::SUCCESS
+}
+
+/// Parses the board from either a comma separated list of tiles, or the value of the board.
+fn parse_board(text: &str) -> Result<u16, String> {
+    let text = text.trim();
+
+    if let Some(binary) = text.strip_prefix("0b") {
+        return u16::from_str_radix(binary, 2).map_err(|e| e.to_string());
+    }
+    if !text.contains(',') {
+        return text
+            .parse()
+            .map_err(|e: std::num::ParseIntError| e.to_string());
+    }
+
+    text.split(',')
+        .filter(|tile| !tile.trim().is_empty())
+        .try_fold(0, |board, tile| match tile.trim().parse::<u8>() {
+            Ok(tile) if (1..=MAX_TILES).contains(&tile) => Ok(board | 1 << (tile - 1)),
+            _ => Err(format!("\"{tile}\" isn't a tile between 1 & {MAX_TILES}")),
+        })
+}
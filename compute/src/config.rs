@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use rules::Ruleset;
+
+use crate::dice::DiceDistribution;
+use crate::simulation::playing::Result as GameResult;
+use crate::strategy::Strategy;
+
+/// The file the best moves are written to by default.
+pub const DEFAULT_OUTPUT: &str = "best_moves.yml";
+/// The file the recommended opening moves are written to by default.
+pub const DEFAULT_OPENING_OUTPUT: &str = "opening_rolls.yml";
+/// The file the ranked moves are written to by default.
+pub const DEFAULT_RANKED_OUTPUT: &str = "ranked_moves.yml";
+/// The file the final score histogram is written to by default.
+pub const DEFAULT_HISTOGRAM_OUTPUT: &str = "score_histogram.yml";
+/// The file checkpoints of the best moves are written to by default.
+pub const DEFAULT_CHECKPOINT_OUTPUT: &str = "best_moves.checkpoint.yml";
+/// The file the disagreements between the seeds of an ensemble are written to by default.
+pub const DEFAULT_ENSEMBLE_OUTPUT: &str = "ensemble_report.yml";
+/// The file the comparison of a simulation against the exact values is written to by default.
+pub const DEFAULT_VERIFICATION_OUTPUT: &str = "verification_report.yml";
+/// The file the dataset of simulated moves is written to by default.
+pub const DEFAULT_DATASET_OUTPUT: &str = "dataset.csv";
+
+/// Who the simulated games are played against.
+#[derive(Clone)]
+pub enum Opponent {
+    /// Plays against a random mirror of itself.
+    /// This produces the policy that performs best against any player.
+    Mirror,
+    /// Plays against the given strategy.
+    /// This produces the policy that wins most often against that specific strategy.
+    Fixed(Arc<dyn Strategy>),
+}
+
+/// What the simulated games are trying to achieve, which decides the weight given to the outcome of each game.
+/// The weight of a game is the value given to every move made within it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Weighs each game by whether it was won, drawn, or lost against the opponent.
+    /// The weights are big as it results in higher accuracy during the division for the average calculation.
+    HeadToHead { win: u16, draw: u16, loss: u16 },
+    /// Weighs each game by how low its final score was, ignoring the opponent.
+    /// A shut box has a weight of 1000 & a board with every piece alive has a weight of 0.
+    ExpectedScore,
+}
+
+impl Objective {
+    /// Returns the weight of a game with the given outcome & final score.
+    /// The max score is the score of a board with every piece alive, which depends on the amount of tiles.
+    pub(crate) fn weigh(&self, result: GameResult, score: u8, max_score: u8) -> u32 {
+        match self {
+            Objective::HeadToHead { win, draw, loss } => match result {
+                GameResult::Win => *win as u32,
+                GameResult::Draw => *draw as u32,
+                GameResult::Loss => *loss as u32,
+            },
+            Objective::ExpectedScore => {
+                let max_score = max_score.max(1) as u32;
+                (max_score - (score as u32).min(max_score)) * 1000 / max_score
+            }
+        }
+    }
+
+    /// Returns the highest weight a game can have.
+    pub fn max_weight(&self) -> u16 {
+        match self {
+            Objective::HeadToHead { win, draw, loss } => *win.max(draw).max(loss),
+            Objective::ExpectedScore => 1000,
+        }
+    }
+}
+
+impl Default for Objective {
+    /// Win = 1000
+    /// Draw = 500
+    /// Loss = 0
+    fn default() -> Self {
+        Objective::HeadToHead {
+            win: 1000,
+            draw: 500,
+            loss: 0,
+        }
+    }
+}
+
+/// The settings used when simulating games.
+#[derive(Clone)]
+pub struct SimulationConfig {
+    /// The amount of threads to simulate games on.
+    pub threads: u8,
+    /// The amount of games each thread will simulate.
+    pub games_to_play: u32,
+    /// Who the simulated games are played against.
+    pub opponent: Opponent,
+    /// The file the best moves are written to.
+    pub output: PathBuf,
+    /// The file the recommended move & win probability for each opening roll are written to.
+    pub opening_output: PathBuf,
+    /// The amount of ranked moves to keep for each board & roll.
+    /// If this is 0, then the ranked moves aren't written.
+    pub top_k: usize,
+    /// The file the ranked moves are written to.
+    pub ranked_output: PathBuf,
+    /// The amount of games played with the resulting policy to build the final score histogram.
+    /// If this is 0, then the histogram isn't written.
+    pub histogram_games: u32,
+    /// The file the final score histogram is written to.
+    pub histogram_output: PathBuf,
+    /// The amount of games each thread simulates between checkpoints.
+    /// If this is 0, then there is a single checkpoint once every game has been simulated.
+    pub checkpoint_games: u32,
+    /// The amount of consecutive checkpoints the best moves have to be stable for before the simulation stops early.
+    /// If this is 0, then the simulation never stops early.
+    pub stable_checkpoints: u32,
+    /// The amount of best moves that can change between checkpoints while still being considered stable.
+    pub convergence_tolerance: usize,
+    /// The file the best moves found so far are written to when a checkpoint is flushed.
+    pub checkpoint_output: PathBuf,
+    /// The seed used to simulate the games.
+    /// If this is None, then the seed is derived from the master seed.
+    pub seed: Option<u64>,
+    /// The file the disagreements between the seeds of an ensemble are written to.
+    pub ensemble_output: PathBuf,
+    /// The file the comparison of the simulation against the exact values is written to, when verifying.
+    pub verification_output: PathBuf,
+    /// How likely each roll is to occur in the simulated games.
+    pub dice: DiceDistribution,
+    /// The rules the simulated games are played with, which have to roll both dice.
+    pub ruleset: Ruleset,
+    /// What the simulated games are trying to achieve.
+    pub objective: Objective,
+    /// The max amount of choices kept in memory while simulating.
+    /// Once exceeded, the least taken half of the choices are moved to a temporary file & merged back at the end.
+    /// Checkpoints & the convergence check merge the spilled choices into a copy of those in memory, so they consider every choice.
+    /// If this is None, then the memory is unbounded.
+    pub max_entries: Option<usize>,
+    /// The file the weight of every choice is kept in, so a simulation can carry on from where the last one stopped.
+    /// If the file exists, the simulation starts from its weights instead of from nothing.
+    /// The weights are written back whenever a checkpoint is flushed & once the simulation finishes.
+    /// If this is None, then every simulation starts from nothing.
+    pub resume_file: Option<PathBuf>,
+    /// The parquet file the statistics of every candidate move are written to.
+    /// If this is None, then the statistics aren't written.
+    #[cfg(feature = "parquet")]
+    pub raw_output: Option<PathBuf>,
+}
+
+impl SimulationConfig {
+    /// Creates a config that plays the given amount of games on each thread against a mirror of itself.
+    pub fn new(threads: u8, games_to_play: u32) -> SimulationConfig {
+        SimulationConfig {
+            threads,
+            games_to_play,
+            opponent: Opponent::Mirror,
+            output: PathBuf::from(DEFAULT_OUTPUT),
+            opening_output: PathBuf::from(DEFAULT_OPENING_OUTPUT),
+            top_k: 0,
+            ranked_output: PathBuf::from(DEFAULT_RANKED_OUTPUT),
+            histogram_games: 10000,
+            histogram_output: PathBuf::from(DEFAULT_HISTOGRAM_OUTPUT),
+            checkpoint_games: 10000,
+            stable_checkpoints: 0,
+            convergence_tolerance: 0,
+            checkpoint_output: PathBuf::from(DEFAULT_CHECKPOINT_OUTPUT),
+            seed: None,
+            ensemble_output: PathBuf::from(DEFAULT_ENSEMBLE_OUTPUT),
+            verification_output: PathBuf::from(DEFAULT_VERIFICATION_OUTPUT),
+            dice: DiceDistribution::Fair,
+            ruleset: Ruleset::CLASSIC,
+            objective: Objective::default(),
+            max_entries: None,
+            resume_file: None,
+            #[cfg(feature = "parquet")]
+            raw_output: None,
+        }
+    }
+}
+
+/// The settings used when exporting a dataset of simulated moves.
+#[derive(Clone)]
+pub struct DatasetConfig {
+    /// The amount of pairs of games to play, which each add two games to the dataset.
+    pub games: u64,
+    /// Who the second game of each pair is played by, while the first is always played randomly.
+    pub opponent: Opponent,
+    /// The file the dataset is written to, in the format given by its extension.
+    pub output: PathBuf,
+    /// The seed used to simulate the games.
+    /// If this is None, then the seed is derived from the master seed.
+    pub seed: Option<u64>,
+    /// How likely each roll is to occur in the simulated games.
+    pub dice: DiceDistribution,
+    /// The rules the simulated games are played with, which have to roll both dice.
+    pub ruleset: Ruleset,
+}
+
+impl DatasetConfig {
+    /// Creates a config that plays the given amount of pairs of games against a mirror of itself.
+    pub fn new(games: u64) -> DatasetConfig {
+        DatasetConfig {
+            games,
+            opponent: Opponent::Mirror,
+            output: PathBuf::from(DEFAULT_DATASET_OUTPUT),
+            seed: None,
+            dice: DiceDistribution::Fair,
+            ruleset: Ruleset::CLASSIC,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_score_scales_with_the_tiles() {
+        let max_score = game_core::board_value(game_core::full_board(game_core::MAX_TILES));
+        assert_eq!(max_score, 78);
+
+        let objective = Objective::ExpectedScore;
+        assert_eq!(objective.weigh(GameResult::Draw, 0, max_score), 1000);
+        assert_eq!(objective.weigh(GameResult::Draw, max_score, max_score), 0);
+        // Scores above the max score of a standard board still rank below the lower scores.
+        let fifty = objective.weigh(GameResult::Draw, 50, max_score);
+        let sixty = objective.weigh(GameResult::Draw, 60, max_score);
+        assert!(fifty > sixty && sixty > 0);
+    }
+}
@@ -0,0 +1,154 @@
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ComputeError;
+
+/// The weight of each dice sum from 2 to 12 when rolling two fair dice.
+const FAIR_WEIGHTS: [u32; 11] = [1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1];
+
+/// How likely each roll is to occur when simulating games.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiceDistribution {
+    /// Two fair six-sided dice.
+    #[default]
+    Fair,
+    /// Two dice that are loaded in the same way.
+    /// Each value is the weight of the die landing on the face at that index plus one.
+    LoadedDie([u32; 6]),
+    /// The weight of rolling each sum from 2 to 12, with the sum 2 at index 0.
+    Sums([u32; 11]),
+    /// Always rolls the given sum, which must be between 2 & 12 (inclusive).
+    Always(u8),
+}
+
+impl DiceDistribution {
+    /// Returns the weight of rolling each sum from 2 to 12, with the sum 2 at index 0.
+    /// If the distribution can never roll anything, such as having a weight of 0 for every sum,
+    /// or its weights are too large to be summed, then the weights of fair dice are returned.
+    /// Both are reported by [`DiceDistribution::validate`], which the simulations check first.
+    pub fn sum_weights(&self) -> [u32; 11] {
+        match self.checked_sum_weights() {
+            Some(weights) if weights.iter().any(|weight| *weight > 0) => weights,
+            _ => FAIR_WEIGHTS,
+        }
+    }
+
+    /// Returns an error if the weights of the distribution are too large to be summed,
+    /// or if it can never roll anything, such as always rolling a sum outside of 2 to 12.
+    pub fn validate(&self) -> Result<(), ComputeError> {
+        match self.checked_sum_weights() {
+            Some(weights) if weights.iter().any(|weight| *weight > 0) => Ok(()),
+            Some(_) => Err(ComputeError::ImpossibleDice(*self)),
+            None => Err(ComputeError::DiceWeights(*self)),
+        }
+    }
+
+    /// Returns the weight of rolling each sum, or None if the weights or their total would overflow.
+    fn checked_sum_weights(&self) -> Option<[u32; 11]> {
+        let weights = match self {
+            DiceDistribution::Fair => FAIR_WEIGHTS,
+            DiceDistribution::LoadedDie(faces) => {
+                let mut weights = [0u32; 11];
+                for (one, one_weight) in faces.iter().enumerate() {
+                    for (two, two_weight) in faces.iter().enumerate() {
+                        let weight = one_weight.checked_mul(*two_weight)?;
+                        weights[one + two] = weights[one + two].checked_add(weight)?;
+                    }
+                }
+                weights
+            }
+            DiceDistribution::Sums(weights) => *weights,
+            DiceDistribution::Always(roll) => {
+                let mut weights = [0; 11];
+                if (2..13).contains(roll) {
+                    weights[*roll as usize - 2] = 1;
+                }
+                weights
+            }
+        };
+
+        // The rolls are sampled from the running total of the weights, so the total has to fit too.
+        weights
+            .iter()
+            .try_fold(0u32, |total, weight| total.checked_add(*weight))?;
+        Some(weights)
+    }
+
+    /// Returns the probability of rolling the given sum, between 0 & 1.
+    pub fn probability(&self, roll: u8) -> f32 {
+        if !(2..13).contains(&roll) {
+            return 0.;
+        }
+
+        let weights = self.sum_weights();
+        weights[roll as usize - 2] as f32 / weights.iter().sum::<u32>() as f32
+    }
+
+    /// Creates a sampler that randomly rolls according to this distribution.
+    pub(crate) fn sampler(&self) -> RollSampler {
+        let mut cumulative = self.sum_weights();
+        for index in 1..cumulative.len() {
+            cumulative[index] += cumulative[index - 1];
+        }
+
+        RollSampler { cumulative }
+    }
+}
+
+/// Randomly chooses rolls according to the weights of a [`DiceDistribution`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RollSampler {
+    /// The running total of the weight of each sum from 2 to 12.
+    cumulative: [u32; 11],
+}
+
+impl RollSampler {
+    /// Chooses a random roll, returning its index within the rolls from 2 to 12.
+    pub fn sample_index(&self, rng: &mut Rng) -> usize {
+        let total = self.cumulative[self.cumulative.len() - 1];
+        let chosen = rng.u32(..total);
+
+        self.cumulative
+            .iter()
+            .position(|weight| chosen < *weight)
+            .expect("The chosen weight is always below the total weight.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_weights_are_rejected() {
+        let loaded = DiceDistribution::LoadedDie([u32::MAX / 2, 1, 1, 1, 1, 1]);
+        assert!(loaded.validate().is_err());
+        assert_eq!(loaded.sum_weights(), FAIR_WEIGHTS);
+
+        let sums =
+            DiceDistribution::Sums([u32::MAX / 2, 0, 0, 0, 0, u32::MAX / 2 + 2, 0, 0, 0, 0, 0]);
+        assert!(sums.validate().is_err());
+
+        let loaded = DiceDistribution::LoadedDie([1, 1, 1, 1, 1, 2]);
+        assert!(loaded.validate().is_ok());
+        assert_eq!(loaded.sum_weights()[10], 4);
+    }
+
+    #[test]
+    fn impossible_dice_are_rejected() {
+        for dice in [
+            DiceDistribution::Always(1),
+            DiceDistribution::Always(13),
+            DiceDistribution::Sums([0; 11]),
+            DiceDistribution::LoadedDie([0; 6]),
+        ] {
+            assert!(
+                matches!(dice.validate(), Err(ComputeError::ImpossibleDice(_))),
+                "{dice:?} should be rejected"
+            );
+        }
+
+        assert!(DiceDistribution::Always(2).validate().is_ok());
+        assert!(DiceDistribution::Always(12).validate().is_ok());
+    }
+}
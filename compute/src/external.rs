@@ -0,0 +1,299 @@
+//! Strategies written by third parties, which are loaded at runtime instead of being built in.
+//!
+//! A strategy is chosen by a [`StrategySpec`], which is written as one of:
+//! - The name of a [`BuiltinStrategy`], such as `highest-tiles-first`.
+//! - `policy:<file>` or just `<file>`, for a table of the move to make for each board & roll.
+//! - `exec:<command>`, for a program that is asked for each move over its stdin & stdout.
+//!
+//! # Tables
+//!
+//! A table ending in `.csv` has a `board,roll,move` header, followed by a line for each board & roll.
+//! The board & move are the boards before & after the move, with bit `n` set if tile `n + 1` is up,
+//! & the roll is the summed value of the dice. Blank lines & lines starting with `#` are skipped.
+//! Any other file is read as the best moves written by a simulation, such as `best_moves.yml`.
+//! Every move of a table has to be legal, & a random move is made for the boards & rolls it's missing.
+//!
+//! # Commands
+//!
+//! The command is split on whitespace into the program & its arguments, then started once.
+//! Every line sent to the program ends with a newline & every number is written in decimal.
+//! 1. The engine writes `shut-the-box-strategy <version>`, where the version is [`PROTOCOL_VERSION`].
+//! 2. Whenever a move has to be made, the engine writes `<board> <roll> <move>...`,
+//!    with the board & roll as in a table, followed by every legal move.
+//!    This is only written if there is at least one legal move.
+//! 3. The program replies with the move it chose, which has to be one of the legal moves.
+//!
+//! Once the strategy is dropped, stdin is closed & the program should exit.
+//! If the program exits, doesn't reply within [`RESPONSE_TIMEOUT`], or replies with an illegal move,
+//! then it's stopped & random moves are made instead.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use fastrand::Rng;
+use tracing::warn;
+
+use crate::policy::{Policy, PolicyError};
+use crate::strategy::{BuiltinStrategy, Strategy};
+use crate::{BoardRoll, DiceRoll, Ruleset};
+
+/// The version of the protocol spoken with a strategy command, which is increased whenever it changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// How long a strategy command has to reply with its move.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The header of a strategy table.
+pub const TABLE_HEADER: &str = "board,roll,move";
+
+/// The errors that can occur when loading a strategy.
+#[derive(thiserror::Error, Debug)]
+pub enum StrategyError {
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error("Line {line} of the strategy table is invalid: {reason}")]
+    Table { line: usize, reason: String },
+    #[error("The strategy command is empty")]
+    EmptyCommand,
+    #[error("Couldn't start the strategy command \"{command}\": {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+}
+
+/// Which strategy to load, as chosen by a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StrategySpec {
+    /// One of the strategies built into the engine.
+    Builtin(BuiltinStrategy),
+    /// A table of the move to make for each board & roll.
+    Table(PathBuf),
+    /// A program that is asked for each move.
+    Command(String),
+}
+
+impl StrategySpec {
+    /// Loads the strategy, starting its program if it's a command.
+    pub fn load(&self) -> Result<Arc<dyn Strategy>, StrategyError> {
+        Ok(match self {
+            StrategySpec::Builtin(strategy) => Arc::new(*strategy),
+            StrategySpec::Table(path) => Arc::new(load_table(path)?),
+            StrategySpec::Command(command) => Arc::new(CommandStrategy::spawn(command)?),
+        })
+    }
+}
+
+impl FromStr for StrategySpec {
+    type Err = StrategyError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+
+        if let Some(command) = value.strip_prefix("exec:") {
+            return match command.trim().is_empty() {
+                true => Err(StrategyError::EmptyCommand),
+                false => Ok(StrategySpec::Command(command.trim().to_string())),
+            };
+        }
+        if let Some(path) = value.strip_prefix("policy:") {
+            return Ok(StrategySpec::Table(PathBuf::from(path)));
+        }
+
+        Ok(match BuiltinStrategy::from_name(value) {
+            Some(strategy) => StrategySpec::Builtin(strategy),
+            None => StrategySpec::Table(PathBuf::from(value)),
+        })
+    }
+}
+
+impl Display for StrategySpec {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            StrategySpec::Builtin(strategy) => write!(formatter, "{}", strategy.name()),
+            StrategySpec::Table(path) => write!(formatter, "policy:{}", path.display()),
+            StrategySpec::Command(command) => write!(formatter, "exec:{command}"),
+        }
+    }
+}
+
+/// Loads the table in the given file, which is either a CSV table or the best moves written by a simulation.
+pub fn load_table(path: &Path) -> Result<Policy, StrategyError> {
+    if path.extension().and_then(|extension| extension.to_str()) != Some("csv") {
+        return Ok(Policy::load(path)?);
+    }
+
+    let file = File::open(path).map_err(PolicyError::from)?;
+    parse_table(BufReader::new(file))
+}
+
+/// Parses a CSV table, checking that every move is legal.
+pub fn parse_table(reader: impl BufRead) -> Result<Policy, StrategyError> {
+    let mut moves = HashMap::new();
+    let mut header = false;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(PolicyError::from)?;
+        let line = line.trim();
+        let invalid = |reason: &str| StrategyError::Table {
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !header {
+            match line.replace(' ', "") == TABLE_HEADER {
+                true => header = true,
+                false => return Err(invalid("the table has to start with \"board,roll,move\"")),
+            }
+            continue;
+        }
+
+        let values: Vec<u16> = line
+            .split(',')
+            .map(|value| value.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid("every value has to be a number"))?;
+        let [board, roll, moved] = values[..] else {
+            return Err(invalid("a line has to have a board, roll, & move"));
+        };
+
+        let roll = match u8::try_from(roll) {
+            Ok(roll) if (1..=12).contains(&roll) => roll,
+            _ => return Err(invalid("the roll has to be between 1 & 12")),
+        };
+        if let Err(e) = Ruleset::ANY.is_legal_move(board, roll, moved) {
+            return Err(invalid(&format!("the move isn't legal, {e}")));
+        }
+        moves.insert(BoardRoll::new(board, DiceRoll::from(roll)), moved);
+    }
+
+    Ok(Policy::new(moves))
+}
+
+/// A program that is asked for each move, as described by the [module](self) docs.
+pub struct CommandStrategy {
+    /// The command the program was started with.
+    command: String,
+    /// The running program, which is None once it has failed.
+    process: Mutex<Option<Process>>,
+}
+
+/// A running strategy program.
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    /// Each line written to stdout by the program, which are read on another thread so they can time out.
+    lines: Receiver<String>,
+}
+
+impl CommandStrategy {
+    /// Starts the program of the command & sends it the protocol version.
+    pub fn spawn(command: &str) -> Result<CommandStrategy, StrategyError> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or(StrategyError::EmptyCommand)?;
+        let spawn_error = |source| StrategyError::Spawn {
+            command: command.to_string(),
+            source,
+        };
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        writeln!(stdin, "shut-the-box-strategy {PROTOCOL_VERSION}").map_err(spawn_error)?;
+
+        Ok(CommandStrategy {
+            command: command.to_string(),
+            process: Mutex::new(Some(Process {
+                child,
+                stdin,
+                lines,
+            })),
+        })
+    }
+}
+
+impl Process {
+    /// Asks the program for its move, returning why it failed if it didn't reply with a legal one.
+    fn ask(&mut self, board: u16, roll: DiceRoll, moves: &[u16]) -> Result<u16, String> {
+        let moves_text: Vec<String> = moves.iter().map(u16::to_string).collect();
+        writeln!(
+            self.stdin,
+            "{board} {} {}",
+            roll.get_value(),
+            moves_text.join(" ")
+        )
+        .and_then(|()| self.stdin.flush())
+        .map_err(|e| format!("couldn't send the board: {e}"))?;
+
+        let reply = self
+            .lines
+            .recv_timeout(RESPONSE_TIMEOUT)
+            .map_err(|_| String::from("it didn't reply in time or has exited"))?;
+        match reply.trim().parse() {
+            Ok(chosen) if moves.contains(&chosen) => Ok(chosen),
+            _ => Err(format!("it replied with an illegal move \"{reply}\"")),
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // The program may not exit once stdin is closed, so it's stopped.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Strategy for CommandStrategy {
+    /// Asks the program for its move.
+    /// If the program has failed, then a random valid move is chosen.
+    fn choose_move(&self, board: u16, roll: DiceRoll, moves: &[u16], rng: &mut Rng) -> Option<u16> {
+        if moves.is_empty() {
+            return None;
+        }
+
+        let mut process = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(running) = process.as_mut() {
+            match running.ask(board, roll, moves) {
+                Ok(chosen) => return Some(chosen),
+                Err(e) => {
+                    warn!(
+                        "Stopped the strategy \"{}\" as {e}, so random moves are made instead",
+                        self.command
+                    );
+                    *process = None;
+                }
+            }
+        }
+
+        moves.get(rng.usize(..moves.len())).copied()
+    }
+}
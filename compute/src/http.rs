@@ -0,0 +1,133 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::policy::Policy;
+use crate::DiceRoll;
+
+/// The response to a successful best move lookup.
+#[derive(Serialize, Debug)]
+struct BestMoveResponse {
+    board: u16,
+    roll: u8,
+    /// The board to move to. If this is None, then the roll is a dying one.
+    best_move: Option<u16>,
+}
+
+/// The response to a lookup that couldn't be answered.
+#[derive(Serialize, Debug)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Answers best move lookups with the given policy, until the listener fails.
+///
+/// The only supported request is `GET /best?board=<u16>&roll=<u8>`, which responds with JSON such as
+/// `{"board":511,"roll":7,"best_move":447}`. A `best_move` of null means the roll is a dying one.
+pub fn serve(policy: Policy, address: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let policy = Arc::new(policy);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Couldn't accept lookup connection: {e}");
+                continue;
+            }
+        };
+
+        let policy = policy.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(&policy, stream) {
+                warn!("Couldn't answer lookup: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads a single request from the stream & writes the response to it.
+fn handle_connection(policy: &Policy, mut stream: TcpStream) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = match respond(policy, &request_line) {
+        Ok(response) => ("200 OK", to_json(&response)),
+        Err((status, error)) => (status, to_json(&ErrorResponse { error })),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Creates the response to the given request line, such as `GET /best?board=511&roll=7 HTTP/1.1`.
+/// If the request can't be answered, then the status & reason are returned.
+fn respond(
+    policy: &Policy,
+    request_line: &str,
+) -> Result<BestMoveResponse, (&'static str, String)> {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+
+    if method != "GET" {
+        return Err((
+            "405 Method Not Allowed",
+            "only GET is supported".to_string(),
+        ));
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/best" {
+        return Err(("404 Not Found", format!("unknown path {path}")));
+    }
+
+    let mut board = None;
+    let mut roll = None;
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("board", value)) => board = Some(value),
+            Some(("roll", value)) => roll = Some(value),
+            _ => {}
+        }
+    }
+
+    let bad_request = |reason: &str| ("400 Bad Request", reason.to_string());
+
+    let board = board
+        .ok_or_else(|| bad_request("missing board"))?
+        .parse::<u16>()
+        .map_err(|_| bad_request("board must be a u16"))?;
+    if board > 511 {
+        return Err(bad_request("board cannot have a value above 511"));
+    }
+
+    let roll = roll
+        .ok_or_else(|| bad_request("missing roll"))?
+        .parse::<u8>()
+        .map_err(|_| bad_request("roll must be a u8"))?;
+    if !(2..13).contains(&roll) {
+        return Err(bad_request("roll must be between 2 & 12"));
+    }
+
+    Ok(BestMoveResponse {
+        board,
+        roll,
+        best_move: policy.best_move(board, DiceRoll::from(roll)),
+    })
+}
+
+/// Serializes the given response as JSON.
+fn to_json<T: Serialize>(response: &T) -> String {
+    serde_json::to_string(response).expect("Responses can always be serialized.")
+}
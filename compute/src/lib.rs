@@ -1,26 +1,232 @@
-use core::panic;
-use derive_more::Display;
+// Much of the simulation is only used to write its results, which is left out without files.
+#![cfg_attr(not(feature = "fs"), allow(dead_code))]
+
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use simulation::playing::compute_weights;
+#[cfg(feature = "fs")]
+use simulation::exact::write_exact;
+use simulation::playing::simulate;
+#[cfg(feature = "fs")]
+use simulation::playing::{compute_weights, write_policy, write_yaml};
 use std::fmt::Formatter;
+#[cfg(feature = "fs")]
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::mpsc::Sender;
-use std::sync::OnceLock;
+#[cfg(feature = "fs")]
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
+
+pub use config::{DatasetConfig, Objective, Opponent, SimulationConfig};
+pub use dice::DiceDistribution;
+#[cfg(feature = "fs")]
+pub use external::{CommandStrategy, StrategyError, StrategySpec};
+pub use game_core::{
+    codec, enumerate_moves, full_board, get_rolls, list_tiles, rng, DiceRoll, MAX_TILES,
+    STANDARD_TILES,
+};
+#[cfg(feature = "fs")]
+pub use lock::FileLock;
+pub use policy::{Policy, PolicyError, PolicyFormat};
+pub use rules::{IllegalMove, Ruleset, RulesetError, Scoring, GOLF_HOLES, REGISTRY};
+pub use simulation::bench::{head_to_head, HeadToHead};
+pub use simulation::control::{ComputeCommand, ComputeProgress};
+#[cfg(feature = "fs")]
+pub use simulation::dataset::{export_dataset, DatasetFormat, DatasetRow, DATASET_COLUMNS};
+pub use simulation::ensemble::{run_ensemble, Disagreement, Ensemble, EnsembleReport};
+pub use simulation::histogram::ScoreHistogram;
+pub use simulation::opening::OpeningRoll;
+#[cfg(feature = "parquet")]
+pub use simulation::raw::{write_parquet, RawStatistic};
+#[cfg(feature = "fs")]
+pub use simulation::resume::resumed_games;
+pub use simulation::verify::{
+    run_verification, Deviation, MoveDifference, Verification, NOISE_SIGMAS,
+};
+pub use strategy::{BotDifficulty, BuiltinStrategy, InvalidDifficulty, Strategy};
+pub use tree::{Expansion, GameNode, GameState, GameStateError, ShutChances, GAME_STATE_VERSION};
 
+pub mod config;
+pub mod dice;
+#[cfg(feature = "fs")]
+pub mod external;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "fs")]
+pub mod lock;
+pub mod policy;
 mod simulation;
+pub mod strategy;
+pub mod tree;
+
+/// The errors that can stop a simulation from writing its results.
+#[derive(thiserror::Error, Debug)]
+pub enum ComputeError {
+    #[error("Couldn't write {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Couldn't serialize the results: {0}")]
+    Serialize(#[from] codec::Error),
+    #[error("Couldn't use the spill file: {0}")]
+    Spill(std::io::Error),
+    #[error("{} isn't a known dataset format", .0.display())]
+    UnknownFormat(PathBuf),
+    #[error("Games can't be simulated with {0}, as the simulation always rolls both dice")]
+    UnsupportedRuleset(Ruleset),
+    #[error("The weights of {0:?} are too large, as their total has to fit within a u32")]
+    DiceWeights(DiceDistribution),
+    #[error("{0:?} can never roll a sum from 2 to 12")]
+    ImpossibleDice(DiceDistribution),
+    #[cfg(feature = "parquet")]
+    #[error("Couldn't write the raw statistics: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
 
 /// Randomly simulates the given amount of games to play on the number of given threads.
-/// This method writes the best move for each board-roll combination to "best_moves.yml"
-pub fn compute(threads: u8, games_to_play: u32, sender: Sender<bool>) {
-    compute_weights(threads, games_to_play, sender);
+/// This method writes the best move for each board-roll combination to "best_moves.yml".
+/// The progress is sent down the sender as it's made, ending with [`ComputeProgress::Finished`] once everything has been written.
+#[cfg(feature = "fs")]
+pub fn compute(
+    threads: u8,
+    games_to_play: u32,
+    sender: Sender<ComputeProgress>,
+) -> Result<(), ComputeError> {
+    compute_weights(SimulationConfig::new(threads, games_to_play), sender, None)
+}
+
+/// Simulates games according to the given config.
+/// This method writes the best move for each board-roll combination to the output of the config.
+///
+/// If the config has a [`Opponent::Fixed`] opponent, then the written moves are the best response to that
+/// opponent, rather than the moves that are best against a mirror of itself.
+#[cfg(feature = "fs")]
+pub fn compute_with_config(
+    config: SimulationConfig,
+    sender: Sender<ComputeProgress>,
+) -> Result<(), ComputeError> {
+    compute_weights(config, sender, None)
+}
+
+/// Simulates games according to the given config, while accepting [`ComputeCommand`]s from the given receiver.
+/// This allows the simulation to be paused & resumed without aborting it, or for the
+/// best moves found so far to be written to the checkpoint output of the config.
+///
+/// If the sending half of the control channel is dropped, a paused simulation is resumed.
+#[cfg(feature = "fs")]
+pub fn compute_controlled(
+    config: SimulationConfig,
+    sender: Sender<ComputeProgress>,
+    control: Receiver<ComputeCommand>,
+) -> Result<(), ComputeError> {
+    compute_weights(config, sender, Some(control))
+}
+
+/// Simulates the given config once for each of the given seeds, combining the resulting policies by majority vote.
+/// This method writes the combined best moves to the output of the config,
+/// & the board-roll combinations the seeds disagreed on to the ensemble output of the config.
+#[cfg(feature = "fs")]
+pub fn compute_ensemble(
+    config: SimulationConfig,
+    seeds: &[u64],
+    sender: Sender<ComputeProgress>,
+) -> Result<(), ComputeError> {
+    let ensemble = run_ensemble(&config, seeds)?;
+
+    // Nothing may be listening for the progress, which doesn't stop the simulation from finishing.
+    let _ = sender.send(ComputeProgress::Writing);
+    write_policy(&config.output, &ensemble.policy)?;
+    write_yaml(&config.ensemble_output, &ensemble.report)?;
+
+    let _ = sender.send(ComputeProgress::Finished);
+    Ok(())
+}
+
+/// Simulates games according to the given config, then compares the value of each simulated move against its exact value.
+/// This method writes the comparison to the verification output of the config,
+/// which lists every deviation & the board-roll combinations where the best moves differ beyond statistical noise.
+///
+/// See [`run_verification`] for the games that are simulated.
+#[cfg(feature = "fs")]
+pub fn compute_verification(
+    config: SimulationConfig,
+    sender: Sender<ComputeProgress>,
+) -> Result<Verification, ComputeError> {
+    let verification = run_verification(&config)?;
+
+    // Nothing may be listening for the progress, which doesn't stop the simulation from finishing.
+    let _ = sender.send(ComputeProgress::Writing);
+    write_yaml(&config.verification_output, &verification)?;
+
+    let _ = sender.send(ComputeProgress::Finished);
+    Ok(verification)
+}
+
+/// Solves the best move for each board-roll combination exactly, instead of estimating it from simulated games.
+/// This method writes the best moves to the output of the config, along with the other outputs it asks for,
+/// so the written files are the same as a simulation's except that every move is provably the best.
+///
+/// The moves are solved by working up from the shut box, as every move leads to a board with fewer pieces.
+/// This can only be done for games that don't depend on an opponent, so the moves are always solved for
+/// the [`Objective::ExpectedScore`] objective, whatever the objective & opponent of the config are.
+/// The moves are then the best against any opponent.
+#[cfg(feature = "fs")]
+pub fn solve_exact(
+    config: SimulationConfig,
+    sender: Sender<ComputeProgress>,
+) -> Result<(), ComputeError> {
+    // Solving the moves takes a moment, so most of the time is spent writing them.
+    // Nothing may be listening for the progress, which doesn't stop the moves from being solved.
+    let _ = sender.send(ComputeProgress::Writing);
+    write_exact(&config)?;
+
+    let _ = sender.send(ComputeProgress::Finished);
+    Ok(())
+}
+
+/// Simulates games according to the given config without writing anything, returning how long the simulation took.
+/// This is used to estimate how long a simulation with more games would take.
+pub fn time_simulation(config: &SimulationConfig) -> Result<Duration, ComputeError> {
+    let started = Instant::now();
+    simulate(config, None, None)?;
+    Ok(started.elapsed())
 }
 
-// const  c
+/// Writes the contents to a temporary file next to the given file, then renames it over the given file.
+/// A crash or cancel while writing leaves the old file as it was, instead of a truncated one that could be loaded.
+#[cfg(feature = "fs")]
+pub fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let folder = match path.parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => folder,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+
+    // The temporary file is hidden so it isn't offered by file pickers while it's being written.
+    let prefix = format!(".{name}.");
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix).suffix(".tmp");
+    // Temporary files are only readable by their owner, but the written file should be like any other.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o666));
+    }
+    let mut file = builder.tempfile_in(folder)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    Ok(())
+}
 
 /// Contains a board & a roll.
 /// This is used as a key in a hashmap to the best move.
-#[derive(Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
 pub struct BoardRoll {
     pub(crate) board: u16,
     pub(crate) roll: DiceRoll,
@@ -59,7 +265,7 @@ impl<'de> Visitor<'de> for BoardRollVisitor {
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
         write!(
             formatter,
-            "a u16 between 0 & 511, a dash '-', a valid encoded dice roll"
+            "a u16 between 0 & 4095, a dash '-', a valid encoded dice roll"
         )
     }
 
@@ -79,18 +285,17 @@ impl<'de> Visitor<'de> for BoardRollVisitor {
         };
         // Tries to parse the roll.
         let roll: DiceRoll = match u8::from_str(values.1) {
-            Ok(roll) => DiceRoll(roll),
+            Ok(roll) => DiceRoll::from_encoded(roll),
             Err(_) => {
                 return Err(E::custom("invalid u8 for roll"));
             }
         };
 
         // Validation on the parsed ints.
-        if board > 511 {
-            return Err(E::custom("board cannot have a value above 511"));
+        if board >= 1 << MAX_TILES {
+            return Err(E::custom("board cannot have a value above 4095"));
         }
         if !roll.is_valid() {
-            eprintln!("{}", roll.get_value());
             return Err(E::custom("roll cannot have a value above 12"));
         }
 
@@ -105,68 +310,12 @@ impl<'de> Visitor<'de> for BoardRollVisitor {
     }
 }
 
-/// Contains a dice combination.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Display, Debug)]
-pub struct DiceRoll(u8);
-
-impl DiceRoll {
-    pub fn new_single(one: u8) -> Self {
-        DiceRoll(one << 5)
-    }
-    pub fn new_dual(one: u8, two: u8) -> Self {
-        let one = one << 5;
-        let two = (two & 0b00000111) << 1;
-        DiceRoll(one | two)
-    }
-    /// Returns the summed value of the contained dice.
-    pub fn get_value(self) -> u8 {
-        let one = (self.0 & 0b11100000) >> 5;
-        let two = (self.0 & 0b00001110) >> 1;
-        one + two
-    }
-    /// Returns true if this DiceRoll is a valid roll. False otherwise.
-    pub fn is_valid(self) -> bool {
-        let value = self.get_value();
-        value > 1 && 13 > value
-    }
-}
-
-impl From<u8> for DiceRoll {
-    /// The given value must be between 1 & 12 (inclusive), otherwise this function will panic.
-    fn from(value: u8) -> Self {
-        match value {
-            1 => DiceRoll::new_single(1),
-            2 => DiceRoll::new_dual(1, 1),
-            3 => DiceRoll::new_dual(2, 1),
-            4 => DiceRoll::new_dual(3, 1),
-            5 => DiceRoll::new_dual(4, 1),
-            6 => DiceRoll::new_dual(5, 1),
-            7 => DiceRoll::new_dual(6, 1),
-            8 => DiceRoll::new_dual(6, 2),
-            9 => DiceRoll::new_dual(6, 3),
-            10 => DiceRoll::new_dual(6, 4),
-            11 => DiceRoll::new_dual(6, 5),
-            12 => DiceRoll::new_dual(6, 6),
-            val => panic!(
-                "A DiceRoll cannot be a smaller than 1 or larger than 12! Value found: {val}"
-            ),
-        }
-    }
-}
-
-static DUAL_ROLLS: OnceLock<[DiceRoll; 36]> = OnceLock::new();
-
-pub fn get_rolls() -> &'static [DiceRoll; 36] {
-    DUAL_ROLLS.get_or_init(|| {
-        let mut dual_rolls = [DiceRoll(0); 36];
-
-        for one in 1..7u8 {
-            for two in 1..7u8 {
-                let index = ((one - 1) * 6) + two - 1;
-                dual_rolls[index as usize] = DiceRoll::new_dual(one, two);
-            }
-        }
-
-        dual_rolls
-    })
+/// A move that could be made for a board & roll, along with how good the move is.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RankedMove {
+    /// The board after the move has been made.
+    pub board: u16,
+    /// The average outcome of making this move.
+    /// With the default objective this is the win rate of the move in tenths of a percent.
+    pub value: u16,
 }
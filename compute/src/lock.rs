@@ -0,0 +1,58 @@
+//! Advisory locks on the policy files, so the gui, its background simulation, & the command line binaries
+//! never read a file while another is rebuilding it.
+//!
+//! The lock is held on a hidden file next to the locked one, as the policy files are replaced once written
+//! & a lock on the replaced file wouldn't be seen by anyone opening the new one.
+//! The locks are only advisory, so programs that don't take them can still read & write the files.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A lock on a file, which is released once it's dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    /// The lock file, which is None if it couldn't be created, such as in a read-only folder.
+    _file: Option<File>,
+}
+
+impl FileLock {
+    /// Waits for every reader & writer of the file to finish, then locks it for writing.
+    pub fn exclusive(path: &Path) -> io::Result<FileLock> {
+        let file = open(path)?;
+        file.lock()?;
+        Ok(FileLock { _file: Some(file) })
+    }
+
+    /// Locks the file for reading, which is returned as None if the file is being written.
+    /// If the lock file can't be created, then nothing can be writing the file, so it's read without a lock.
+    pub fn try_shared(path: &Path) -> io::Result<Option<FileLock>> {
+        let Ok(file) = open(path) else {
+            return Ok(Some(FileLock { _file: None }));
+        };
+
+        match file.try_lock_shared() {
+            Ok(()) => Ok(Some(FileLock { _file: Some(file) })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+/// Returns the lock file of the given file, such as ".best_moves.yml.lock" for "best_moves.yml".
+pub fn lock_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    path.with_file_name(format!(".{name}.lock"))
+}
+
+/// Opens the lock file of the given file, creating it if it doesn't exist.
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path(path))
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::ErrorKind;
+use std::io::Read;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fs")]
+use tracing::{info, warn};
+
+use crate::codec;
+#[cfg(feature = "fs")]
+use crate::lock::FileLock;
+#[cfg(feature = "fs")]
+use crate::simulation::playing::write_policy;
+use crate::strategy::Strategy;
+use crate::{BoardRoll, DiceRoll, IllegalMove, Ruleset, MAX_TILES};
+
+/// Contains the best move for each board-roll combination.
+/// This is the data stored within "best_moves.yml".
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Policy {
+    moves: HashMap<BoardRoll, u16>,
+}
+
+/// The file the best moves were written to before rolls were keyed by their encoded dice.
+pub const LEGACY_OUTPUT: &str = "best_move.yml";
+
+/// The format a policy file was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyFormat {
+    /// Each roll is keyed by its encoded [`DiceRoll`], as written by the compute crate.
+    Current,
+    /// Each roll is keyed by its summed value, as written by the older code to "best_move.yml".
+    Legacy,
+}
+
+impl Display for PolicyFormat {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PolicyFormat::Current => write!(formatter, "current"),
+            PolicyFormat::Legacy => write!(formatter, "legacy"),
+        }
+    }
+}
+
+/// The errors that can occur when loading a policy.
+#[derive(thiserror::Error, Debug)]
+pub enum PolicyError {
+    #[error("Couldn't read policy file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Couldn't parse policy: {0}")]
+    Parse(#[from] codec::Error),
+    #[error(
+        "The best move for board {board} & roll {roll} isn't a legal move to {best}: {reason}"
+    )]
+    IllegalMove {
+        board: u16,
+        roll: u8,
+        best: u16,
+        reason: IllegalMove,
+    },
+    #[error("The policy is being rebuilt, so it can be loaded once it has been written")]
+    Rebuilding,
+}
+
+impl Policy {
+    /// Loads the policy stored in the given file, such as "best_moves.yml".
+    /// If the file is locked by something rebuilding it, then [`PolicyError::Rebuilding`] is returned.
+    ///
+    /// Legacy policies are loaded too, see [`Policy::load_detected`].
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Policy, PolicyError> {
+        Self::load_detected(path).map(|(policy, _)| policy)
+    }
+
+    /// Loads the policy stored in the given file, returning the format it was written in.
+    /// If the file is locked by something rebuilding it, then [`PolicyError::Rebuilding`] is returned.
+    ///
+    /// If the file doesn't exist, but a legacy "best_move.yml" does next to it, then the legacy policy is loaded instead.
+    /// A loaded legacy policy is converted into the given file, so it's only converted once.
+    /// Failing to convert it is only logged, as the policy could still be loaded.
+    #[cfg(feature = "fs")]
+    pub fn load_detected(path: impl AsRef<Path>) -> Result<(Policy, PolicyFormat), PolicyError> {
+        let path = path.as_ref();
+        let (policy, format) = match Self::read_detected(path) {
+            Err(PolicyError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                let legacy = legacy_path(path);
+                if legacy == path || !legacy.exists() {
+                    return Err(PolicyError::Io(e));
+                }
+                info!(
+                    "Loading the legacy best moves in \"{}\", as \"{}\" doesn't exist",
+                    legacy.display(),
+                    path.display()
+                );
+                Self::read_detected(&legacy)?
+            }
+            result => result?,
+        };
+
+        if format == PolicyFormat::Legacy {
+            match write_policy(path, &policy) {
+                Ok(()) => info!(
+                    "Converted the legacy best moves into \"{}\"",
+                    path.display()
+                ),
+                Err(e) => warn!("Couldn't convert the legacy best moves: {e}"),
+            }
+        }
+
+        Ok((policy, format))
+    }
+
+    /// Reads the policy stored in the given file without converting it.
+    #[cfg(feature = "fs")]
+    fn read_detected(path: &Path) -> Result<(Policy, PolicyFormat), PolicyError> {
+        let mut file = File::open(path)?;
+        let _lock = FileLock::try_shared(path)?.ok_or(PolicyError::Rebuilding)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Self::decode_detected(&bytes)
+    }
+
+    /// Parses the policy from the given reader.
+    pub fn from_reader(mut reader: impl Read) -> Result<Policy, PolicyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::decode(&bytes)
+    }
+
+    /// Parses the policy from the contents of a policy file, in either format.
+    /// Every best move has to be a legal move for its board & roll, so a corrupted file can't be loaded.
+    pub fn decode(bytes: &[u8]) -> Result<Policy, PolicyError> {
+        Self::decode_detected(bytes).map(|(policy, _)| policy)
+    }
+
+    /// Parses the policy from the contents of a policy file, returning the format it was written in.
+    /// Every best move has to be a legal move for its board & roll, so a corrupted file can't be loaded.
+    pub fn decode_detected(bytes: &[u8]) -> Result<(Policy, PolicyFormat), PolicyError> {
+        let (policy, format) = match Self::detect(bytes)? {
+            Some(legacy) => (legacy, PolicyFormat::Legacy),
+            None => (codec::from_slice(bytes)?, PolicyFormat::Current),
+        };
+
+        for (board_roll, best) in &policy.moves {
+            let (board, roll) = (board_roll.board, board_roll.roll.get_value());
+            // A policy isn't tied to a ruleset, so its moves only have to be legal with one.
+            if let Err(reason) = Ruleset::ANY.is_legal_move(board, roll, *best) {
+                return Err(PolicyError::IllegalMove {
+                    board,
+                    roll,
+                    best: *best,
+                    reason,
+                });
+            }
+        }
+
+        Ok((policy, format))
+    }
+
+    /// Parses the contents as a legacy policy, which is returned as None if they're in the current format.
+    ///
+    /// Every encoded dice roll has a first die, so it's above 12,
+    /// while every summed roll is at most 12. A file is legacy when none of its rolls are above 12.
+    fn detect(bytes: &[u8]) -> Result<Option<Policy>, PolicyError> {
+        let raw: HashMap<String, u16> = codec::from_slice(bytes)?;
+        let mut moves = HashMap::with_capacity(raw.len());
+
+        for (key, best) in raw {
+            let parsed = key.rsplit_once('-').and_then(|(board, roll)| {
+                Some((u16::from_str(board).ok()?, u8::from_str(roll).ok()?))
+            });
+            match parsed {
+                Some((_, roll)) if roll > 12 => return Ok(None),
+                Some((board, roll @ 1..=12)) if board < 1 << MAX_TILES => {
+                    moves.insert(BoardRoll::new(board, DiceRoll::from(roll)), best);
+                }
+                // The key may still be valid in the current format, which will report what's wrong with it.
+                _ => return Ok(None),
+            }
+        }
+
+        match moves.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(Policy::new(moves))),
+        }
+    }
+
+    /// Creates a policy from the given best moves.
+    pub fn new(moves: HashMap<BoardRoll, u16>) -> Policy {
+        Policy { moves }
+    }
+
+    /// Gets the best move for the given board & roll.
+    /// If there is no known move, such as when the roll is a dying one, then None is returned.
+    pub fn best_move(&self, board: u16, roll: DiceRoll) -> Option<u16> {
+        self.moves.get(&BoardRoll::new(board, roll)).copied()
+    }
+
+    /// Returns the best moves contained within this policy.
+    pub fn moves(&self) -> &HashMap<BoardRoll, u16> {
+        &self.moves
+    }
+
+    /// Returns the amount of board-roll combinations this policy has a move for.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Returns true if this policy doesn't contain any moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+/// Returns the legacy policy file next to the given file, such as "best_move.yml" for "best_moves.yml".
+#[cfg(feature = "fs")]
+pub fn legacy_path(path: &Path) -> PathBuf {
+    path.with_file_name(LEGACY_OUTPUT)
+}
+
+impl From<HashMap<BoardRoll, u16>> for Policy {
+    fn from(moves: HashMap<BoardRoll, u16>) -> Self {
+        Policy::new(moves)
+    }
+}
+
+impl Strategy for Policy {
+    /// Chooses the best move from the policy.
+    /// If the policy doesn't have a valid move for the board & roll, then a random valid move is chosen.
+    fn choose_move(&self, board: u16, roll: DiceRoll, moves: &[u16], rng: &mut Rng) -> Option<u16> {
+        if moves.is_empty() {
+            return None;
+        }
+
+        match self.best_move(board, roll) {
+            Some(best_move) if moves.contains(&best_move) => Some(best_move),
+            _ => moves.get(rng.usize(..moves.len())).copied(),
+        }
+    }
+}
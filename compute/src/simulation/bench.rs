@@ -0,0 +1,122 @@
+use fastrand::Rng;
+
+use crate::dice::DiceDistribution;
+use crate::full_board;
+use crate::rng;
+use crate::simulation::board::get_board;
+use crate::simulation::playing::{play, Player};
+use crate::strategy::Strategy;
+
+/// The z-score of a 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// The results of two strategies playing each other.
+/// Each total is indexed by the strategy it's for, with the first strategy at index 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeadToHead {
+    /// The amount of games that were played.
+    pub games: u32,
+    /// The amount of games each strategy won.
+    pub wins: [u32; 2],
+    /// The amount of games that were drawn.
+    pub draws: u32,
+    /// The summed final scores of each strategy.
+    pub scores: [u64; 2],
+    /// The summed squares of the final scores of each strategy, which are used to calculate the variance.
+    pub squared_scores: [u64; 2],
+}
+
+impl HeadToHead {
+    /// Returns the fraction of games the strategy won.
+    pub fn win_rate(&self, strategy: usize) -> f64 {
+        self.wins[strategy] as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the 95% confidence interval of the fraction of games the strategy wins.
+    /// This is the Wilson score interval, which stays within 0 & 1 even when few games are played.
+    pub fn win_interval(&self, strategy: usize) -> (f64, f64) {
+        if self.games == 0 {
+            return (0., 1.);
+        }
+
+        let games = self.games as f64;
+        let rate = self.win_rate(strategy);
+        let z_squared = CONFIDENCE_Z * CONFIDENCE_Z;
+
+        let centre = (rate + z_squared / (2. * games)) / (1. + z_squared / games);
+        let spread = CONFIDENCE_Z / (1. + z_squared / games)
+            * (rate * (1. - rate) / games + z_squared / (4. * games * games)).sqrt();
+        ((centre - spread).max(0.), (centre + spread).min(1.))
+    }
+
+    /// Returns the average final score of the strategy.
+    pub fn average_score(&self, strategy: usize) -> f64 {
+        self.scores[strategy] as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the 95% confidence interval of the average final score of the strategy.
+    pub fn score_interval(&self, strategy: usize) -> (f64, f64) {
+        let games = self.games.max(1) as f64;
+        let mean = self.average_score(strategy);
+        let variance = (self.squared_scores[strategy] as f64 / games - mean * mean).max(0.);
+
+        let spread = CONFIDENCE_Z * (variance / games).sqrt();
+        (mean - spread, mean + spread)
+    }
+
+    /// Records a game that ended with the given final score for each strategy.
+    fn record(&mut self, scores: [u8; 2]) {
+        self.games += 1;
+        match scores[0].cmp(&scores[1]) {
+            std::cmp::Ordering::Less => self.wins[0] += 1,
+            std::cmp::Ordering::Greater => self.wins[1] += 1,
+            std::cmp::Ordering::Equal => self.draws += 1,
+        }
+
+        for (strategy, score) in scores.into_iter().enumerate() {
+            self.scores[strategy] += score as u64;
+            self.squared_scores[strategy] += score as u64 * score as u64;
+        }
+    }
+}
+
+/// Plays the given amount of games between the two strategies from the full board of the given amount of tiles.
+/// Both strategies are given the same rolls in each game, so the results only differ by the moves they make.
+/// The strategy with the lowest final score wins the game.
+/// If a seed is given, then the same games are played every time.
+pub fn head_to_head(
+    strategies: [&dyn Strategy; 2],
+    games: u32,
+    seed: Option<u64>,
+    dice: &DiceDistribution,
+    tiles: u8,
+) -> HeadToHead {
+    let board = get_board(full_board(tiles) as usize).expect("Will exist");
+    let sampler = dice.sampler();
+    let mut rng = match seed {
+        Some(seed) => Rng::with_seed(seed),
+        None => rng::stream("bench"),
+    };
+
+    let mut results = HeadToHead::default();
+    // Only the scores are needed, so the moves of every game are cleared out of the same buffer.
+    let mut choices = Vec::new();
+    for _ in 0..games {
+        let roll_seed = rng.u64(..);
+
+        let scores = strategies.map(|strategy| {
+            choices.clear();
+            play(
+                board,
+                &mut choices,
+                &mut Rng::with_seed(roll_seed),
+                &mut rng.fork(),
+                &Player::Strategic(strategy),
+                &sampler,
+            )
+        });
+        results.record(scores);
+    }
+
+    results
+}
@@ -1,10 +1,10 @@
 use std::sync::{Arc, OnceLock};
 
 use fastrand::Rng;
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
 
+use crate::dice::RollSampler;
 use crate::simulation::roll::Roll;
+use crate::MAX_TILES;
 
 // Binary representation of the board within the u16:
 // 0000000 | 000000000
@@ -14,14 +14,16 @@ use crate::simulation::roll::Roll;
 static BOARDS: OnceLock<Arc<[Board]>> = OnceLock::new();
 
 /// Gets the pre-computed boards.
+/// Every board up to the max amount of tiles is computed, as the moves of a board don't depend on the amount of tiles.
 pub fn get_boards() -> &'static Arc<[Board]> {
     // Gets the pre-computed boards, or if they haven't been computed before, they are computed, cached, & returned.
     BOARDS.get_or_init(|| {
-        let mut possible_boards = Vec::with_capacity(512);
+        let board_amount = 1u16 << MAX_TILES;
+        let mut possible_boards = Vec::with_capacity(board_amount as usize);
 
         // Iterates though every possible board.
-        // From 0b000000000 to 0b111111111.
-        for index in 0..512 {
+        // From 0b000000000000 to 0b111111111111.
+        for index in 0..board_amount {
             possible_boards.push(Board::new(index));
         }
 
@@ -35,11 +37,12 @@ pub fn get_board(binary_board: usize) -> Option<&'static Board> {
     get_boards().get(binary_board)
 }
 
-/// Gets a random board.
-pub fn get_rand_board() -> &'static Board {
+/// Gets a random board that only has pieces within the given amount of tiles.
+/// The board is chosen by the thread-local rng, so seeding it will choose the same boards.
+pub fn get_rand_board(tiles: u8) -> &'static Board {
     get_boards()
-        .choose(&mut thread_rng())
-        .expect("The vec will never be empty.")
+        .get(fastrand::usize(..1 << tiles.min(MAX_TILES)))
+        .expect("Every board within the max amount of tiles exists.")
 }
 
 /// Contains a current state of the board & the possible moves that could be made for each possible roll.
@@ -49,12 +52,6 @@ pub struct Board {
     rolls: Vec<Roll>,
 }
 
-/// Contains each possible roll, which amount each value occurs being the weight of the value to be chosen.
-const POSSIBLE_ROLLS_INDEXES: [u8; 36] = [
-    0, 1, 1, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8,
-    8, 9, 9, 10,
-];
-
 impl Board {
     /// Simulates the possible rolls & their valid moves for the given board.
     ///
@@ -66,32 +63,7 @@ impl Board {
 
         // Computes possible rolls with dual dice
         for roll_value in 2u8..13 {
-            roles.push({
-                let mut boards = Vec::new();
-                // The amount of unique boards is all the alive pieces as the alive pieces are stored in binary.
-                // Counting up to the max value of a binary number with the same number of digits as alive pieces
-                // will iterate though every possible combination of the alive pieces, using the binary value of the
-                // counter.
-                let unique_board_amount = 2u16.pow(board.count_ones());
-                let numeric_board = Roll::pieces(board);
-
-                // Finds every valid move.
-                for unique_board in 1..unique_board_amount {
-                    // Checks if the simulated move would add up to the rolled value.
-                    // If it doesn't, then it's not a valid move.
-                    let possible_move = Roll::sum_move(unique_board, numeric_board.clone());
-                    if possible_move != roll_value {
-                        continue;
-                    }
-
-                    boards.push(Roll::preform_move(unique_board, numeric_board.clone()));
-                }
-
-                Roll {
-                    roll: roll_value.into(),
-                    boards: boards.into(),
-                }
-            });
+            roles.push(Roll::new(roll_value.into(), board));
         }
 
         Board {
@@ -101,32 +73,24 @@ impl Board {
     }
 
     /// Gets a random roll from the board.
-    /// The chance of a roll to be returned directly correlates to the chance it will be rolled.
-    pub fn get_rand_roll(&self, rng: &mut Rng) -> &Roll {
-        let index = rng.usize(..POSSIBLE_ROLLS_INDEXES.len());
-        let roll_index = POSSIBLE_ROLLS_INDEXES
-            .get(index)
-            .expect("Will never be empty");
-
-        return self
-            .rolls
-            .get(*roll_index as usize)
-            .expect("A board always has 11 roles.");
+    /// The chance of a roll to be returned is the chance the sampler gives to it being rolled.
+    pub fn get_rand_roll(&self, rng: &mut Rng, sampler: &RollSampler) -> &Roll {
+        self.rolls
+            .get(sampler.sample_index(rng))
+            .expect("A board always has 11 roles.")
+    }
+
+    /// Returns the boards that can be moved to with the given roll.
+    /// Only the moves of rolls from 2 to 12 are stored, so None is returned for any other roll.
+    pub fn moves_for_roll(&self, roll: u8) -> Option<&[u16]> {
+        self.rolls
+            .get(roll.checked_sub(2)? as usize)
+            .map(|roll| &*roll.boards)
     }
 
     /// Sums up the numeric value of the alive pieces for this board.
     pub fn calculate_value(&self) -> u8 {
-        let mut total_value = 0;
-
-        for index in 0..9 {
-            let piece = self.board >> index;
-
-            if piece & 1 == 1 {
-                total_value += index + 1;
-            }
-        }
-
-        total_value
+        game_core::board_value(self.board)
     }
 
     /// Returns a copy of the raw u16 that represents this board.
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+
+/// The commands that can be sent to an ongoing computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeCommand {
+    /// Temporarily stops simulating games, freeing up the CPU.
+    Pause,
+    /// Continues simulating games after a pause.
+    Resume,
+    /// Writes the best moves found so far to the checkpoint output.
+    FlushCheckpoint,
+    /// Stops simulating games, writing the best moves found so far as if every game had been simulated.
+    Stop,
+}
+
+/// What an ongoing computation is doing, which is sent to the caller as it happens so it can show the progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputeProgress {
+    /// The amount of games simulated so far, out of the games the simulation plays unless it's stopped early.
+    /// The games of the simulation that was resumed are included in both.
+    Games { completed: u64, total: u64 },
+    /// How many games a thread simulated each second during its last batch of games.
+    Throughput { thread: u8, games_per_second: f64 },
+    /// Every game has been simulated, & their results are being merged together.
+    Merging,
+    /// The best moves are being written.
+    Writing,
+    /// Everything has been written, so the computation is done.
+    Finished,
+}
+
+/// The state shared between the main thread & the simulating threads.
+#[derive(Default)]
+pub(crate) struct Controls {
+    /// Whether the threads should stop simulating.
+    stop: AtomicBool,
+    /// Whether the threads should wait before simulating the next game.
+    paused: AtomicBool,
+    /// Used to wake up the paused threads.
+    pause_lock: Mutex<()>,
+    resumed: Condvar,
+}
+
+impl Controls {
+    /// Informs the threads to stop simulating after their current batch.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // A paused thread must be woken up to be able to stop.
+        self.resume();
+    }
+
+    /// Returns true if the threads have been told to stop.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Informs the threads to wait before simulating the next game.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Informs the paused threads to continue simulating.
+    pub fn resume(&self) {
+        let _guard = self.pause_lock.lock().expect("Lock is never poisoned.");
+        self.paused.store(false, Ordering::Relaxed);
+        self.resumed.notify_all();
+    }
+
+    /// Blocks the current thread until the simulation is resumed, if it is paused.
+    pub fn wait_while_paused(&self) {
+        // Avoids locking on every game when not paused.
+        if !self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let guard = self.pause_lock.lock().expect("Lock is never poisoned.");
+        let _guard = self
+            .resumed
+            .wait_while(guard, |_| self.paused.load(Ordering::Relaxed))
+            .expect("Lock is never poisoned.");
+    }
+}
@@ -0,0 +1,258 @@
+//! Exports every move made in simulated games, so models can be trained against the same engine.
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use fastrand::Rng;
+use tracing::info;
+
+use crate::config::DatasetConfig;
+use crate::rng;
+use crate::simulation::board::get_board;
+use crate::simulation::playing::{run_seeded_game, MoveBuffers, Result as GameResult};
+use crate::ComputeError;
+
+/// The names of the columns of a dataset, in the order they're written.
+pub const DATASET_COLUMNS: [&str; 8] = [
+    "game", "player", "turn", "board", "roll", "action", "score", "outcome",
+];
+/// The amount of rows in each record batch of a parquet dataset.
+#[cfg(feature = "parquet")]
+const BATCH_ROWS: usize = 65536;
+
+/// The file formats a dataset can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// Comma separated values, with the column names as the first line.
+    Csv,
+    /// An Apache Parquet file, with each column typed as listed on [`export_dataset`].
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl DatasetFormat {
+    /// Returns the format of the file from its extension, or None if it isn't a known format.
+    pub fn from_path(path: &Path) -> Option<DatasetFormat> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("csv") => Some(DatasetFormat::Csv),
+            #[cfg(feature = "parquet")]
+            Some("parquet") => Some(DatasetFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// A single move made in a simulated game.
+/// The fields match the columns of the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatasetRow {
+    pub game: u64,
+    pub player: u8,
+    pub turn: u8,
+    pub board: u16,
+    pub roll: u8,
+    pub action: Option<u16>,
+    pub score: u8,
+    pub outcome: i8,
+}
+
+/// Simulates the games of the config & writes every move made to its output.
+/// Returns the amount of rows that were written.
+///
+/// Each row of a dataset is a single move, with the columns:
+///
+/// | Column    | Type          | Description                                                              |
+/// |-----------|---------------|--------------------------------------------------------------------------|
+/// | `game`    | u64           | The index of the game, starting at 0. Games `2n` & `2n + 1` are a pair.  |
+/// | `player`  | u8            | 0 if the game was played randomly, or 1 if it was played by the opponent. |
+/// | `turn`    | u8            | The index of the move within the game, starting at 0.                     |
+/// | `board`   | u16           | The board before the move, with bit `n` set if tile `n + 1` is up.        |
+/// | `roll`    | u8            | The summed value of the dice.                                             |
+/// | `action`  | u16, nullable | The board after the move, or empty if the roll was a dying one.          |
+/// | `score`   | u8            | The final score of the game, which is the sum of the tiles left up.       |
+/// | `outcome` | i8            | 1 if the game beat the other game of its pair, 0 if drawn, or -1 if lost. |
+///
+/// Both games of a pair are played with the same rolls, so the outcome compares the moves made.
+/// Every game ends with a dying roll, unless the box was shut.
+pub fn export_dataset(config: &DatasetConfig) -> Result<u64, ComputeError> {
+    let format = DatasetFormat::from_path(&config.output)
+        .ok_or_else(|| ComputeError::UnknownFormat(config.output.clone()))?;
+
+    let rows = match format {
+        DatasetFormat::Csv => write_csv(config)?,
+        #[cfg(feature = "parquet")]
+        DatasetFormat::Parquet => write_parquet(config)?,
+    };
+
+    info!(
+        "Exported {rows} moves from {} games to {}",
+        config.games * 2,
+        config.output.display()
+    );
+    Ok(rows)
+}
+
+/// Plays every pair of games of the config, passing each move made to the given function in order.
+fn simulate_rows<F>(config: &DatasetConfig, mut write_row: F) -> Result<u64, ComputeError>
+where
+    F: FnMut(DatasetRow) -> Result<(), ComputeError>,
+{
+    if !config.ruleset.can_simulate() {
+        return Err(ComputeError::UnsupportedRuleset(config.ruleset));
+    }
+    config.dice.validate()?;
+    let board = get_board(config.ruleset.full_board() as usize).expect("Will exist");
+    let sampler = config.dice.sampler();
+    let mut rng = match config.seed {
+        Some(seed) => Rng::with_seed(seed),
+        None => rng::stream("dataset"),
+    };
+
+    let mut rows = 0;
+    let mut buffers = MoveBuffers::default();
+    for pair in 0..config.games {
+        let (first, second) =
+            run_seeded_game(board, &config.opponent, &sampler, &mut rng, &mut buffers);
+
+        for (player, game) in [first, second].into_iter().enumerate() {
+            let outcome = match game.result {
+                GameResult::Win => 1,
+                GameResult::Draw => 0,
+                GameResult::Loss => -1,
+            };
+
+            for (turn, choice) in game.moves.iter().enumerate() {
+                write_row(DatasetRow {
+                    game: pair * 2 + player as u64,
+                    player: player as u8,
+                    turn: turn as u8,
+                    board: choice.get_root_board(),
+                    roll: choice.get_roll().get_value(),
+                    action: choice.get_chosen_board(),
+                    score: game.score,
+                    outcome,
+                })?;
+                rows += 1;
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Writes the dataset as CSV, with the column names as the first line.
+fn write_csv(config: &DatasetConfig) -> Result<u64, ComputeError> {
+    let write_error = |source| ComputeError::Write {
+        path: config.output.clone(),
+        source,
+    };
+
+    let file = File::create(&config.output).map_err(write_error)?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "{}", DATASET_COLUMNS.join(",")).map_err(write_error)?;
+
+    let rows = simulate_rows(config, |row| {
+        let action = row
+            .action
+            .map(|action| action.to_string())
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "{},{},{},{},{},{action},{},{}",
+            row.game, row.player, row.turn, row.board, row.roll, row.score, row.outcome
+        )
+        .map_err(write_error)
+    })?;
+
+    writer.flush().map_err(write_error)?;
+    Ok(rows)
+}
+
+/// Writes the dataset as parquet, in record batches of [`BATCH_ROWS`] rows.
+#[cfg(feature = "parquet")]
+fn write_parquet(config: &DatasetConfig) -> Result<u64, ComputeError> {
+    use std::sync::Arc;
+
+    use arrow::datatypes::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+
+    let data_types = [
+        DataType::UInt64,
+        DataType::UInt8,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt8,
+        DataType::Int8,
+    ];
+    let fields: Vec<Field> = DATASET_COLUMNS
+        .iter()
+        .zip(data_types)
+        .map(|(name, data_type)| Field::new(*name, data_type, *name == "action"))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+
+    let file = File::create(&config.output).map_err(|source| ComputeError::Write {
+        path: config.output.clone(),
+        source,
+    })?;
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), None)?;
+
+    let mut batch = Vec::with_capacity(BATCH_ROWS);
+    let rows = simulate_rows(config, |row| {
+        batch.push(row);
+        if batch.len() == BATCH_ROWS {
+            writer.write(&record_batch(&schema, &batch)?)?;
+            batch.clear();
+        }
+        Ok(())
+    })?;
+
+    if !batch.is_empty() {
+        writer.write(&record_batch(&schema, &batch)?)?;
+    }
+    writer.close()?;
+    Ok(rows)
+}
+
+/// Converts the rows into a record batch with the given schema.
+#[cfg(feature = "parquet")]
+fn record_batch(
+    schema: &std::sync::Arc<arrow::datatypes::Schema>,
+    rows: &[DatasetRow],
+) -> Result<arrow::record_batch::RecordBatch, parquet::errors::ParquetError> {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int8Array, UInt16Array, UInt64Array, UInt8Array};
+    use arrow::record_batch::RecordBatch;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.game),
+        )),
+        Arc::new(UInt8Array::from_iter_values(
+            rows.iter().map(|row| row.player),
+        )),
+        Arc::new(UInt8Array::from_iter_values(
+            rows.iter().map(|row| row.turn),
+        )),
+        Arc::new(UInt16Array::from_iter_values(
+            rows.iter().map(|row| row.board),
+        )),
+        Arc::new(UInt8Array::from_iter_values(
+            rows.iter().map(|row| row.roll),
+        )),
+        Arc::new(UInt16Array::from_iter(rows.iter().map(|row| row.action))),
+        Arc::new(UInt8Array::from_iter_values(
+            rows.iter().map(|row| row.score),
+        )),
+        Arc::new(Int8Array::from_iter_values(
+            rows.iter().map(|row| row.outcome),
+        )),
+    ];
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SimulationConfig;
+use crate::policy::Policy;
+use crate::simulation::playing::{best_moves, rank_choices, simulate};
+use crate::{BoardRoll, ComputeError};
+
+/// The combined result of running the same config with several seeds.
+#[derive(Debug, Clone)]
+pub struct Ensemble {
+    /// Contains the move that the most seeds agreed was the best, for each board & roll.
+    pub policy: Policy,
+    /// Describes how much the seeds agreed with each other.
+    pub report: EnsembleReport,
+}
+
+/// Describes how much the policies of each seed within an ensemble agreed with each other.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EnsembleReport {
+    /// The seeds that were simulated.
+    pub seeds: Vec<u64>,
+    /// The amount of board-roll combinations that any of the seeds had a best move for.
+    pub cells: usize,
+    /// The board-roll combinations the seeds didn't all agree on, sorted by their board & roll.
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// A board-roll combination that the seeds of an ensemble disagreed on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Disagreement {
+    pub board_roll: BoardRoll,
+    /// The move chosen by the majority of seeds.
+    pub chosen: u16,
+    /// The amount of seeds that voted for each move.
+    pub votes: BTreeMap<u16, u32>,
+}
+
+/// Simulates the given config once per seed, then combines the resulting policies by majority vote.
+/// If a vote is tied, then the move with the highest total value across the seeds is chosen.
+pub fn run_ensemble(config: &SimulationConfig, seeds: &[u64]) -> Result<Ensemble, ComputeError> {
+    // Contains the votes & total value for each move of each board & roll.
+    let mut tallies: HashMap<BoardRoll, BTreeMap<u16, (u32, u64)>> = HashMap::new();
+
+    for seed in seeds {
+        let config = SimulationConfig {
+            seed: Some(*seed),
+            // Each seed has to be simulated independently for their votes to mean anything.
+            resume_file: None,
+            ..config.clone()
+        };
+
+        let ranked_map = rank_choices(&simulate(&config, None, None)?);
+        for (board_roll, best_move) in best_moves(&ranked_map) {
+            let value = ranked_map[&board_roll][0].value as u64;
+
+            let tally = tallies
+                .entry(board_roll)
+                .or_default()
+                .entry(best_move)
+                .or_default();
+            tally.0 += 1;
+            tally.1 += value;
+        }
+    }
+
+    let mut moves = HashMap::with_capacity(tallies.len());
+    let mut disagreements = Vec::new();
+
+    for (board_roll, tally) in &tallies {
+        let (chosen, _) = tally
+            .iter()
+            .max_by_key(|(_, votes_value)| **votes_value)
+            .expect("Every tally has at least one vote.");
+        moves.insert(*board_roll, *chosen);
+
+        if tally.len() > 1 {
+            disagreements.push(Disagreement {
+                board_roll: *board_roll,
+                chosen: *chosen,
+                votes: tally
+                    .iter()
+                    .map(|(board, (votes, _))| (*board, *votes))
+                    .collect(),
+            });
+        }
+    }
+
+    disagreements.sort_unstable_by_key(|disagreement| {
+        (
+            disagreement.board_roll.board,
+            disagreement.board_roll.roll.get_value(),
+        )
+    });
+
+    Ok(Ensemble {
+        policy: moves.into(),
+        report: EnsembleReport {
+            seeds: seeds.to_vec(),
+            cells: tallies.len(),
+            disagreements,
+        },
+    })
+}
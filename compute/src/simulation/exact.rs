@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::config::{Objective, Opponent, SimulationConfig};
+use crate::dice::DiceDistribution;
+use crate::simulation::induction::{self, Reduction};
+use crate::simulation::playing::{write_results, Result as GameResult};
+use crate::{enumerate_moves, full_board, BoardRoll, ComputeError, DiceRoll, RankedMove};
+
+/// The exact value of every board when the best move is always made, which is the weight a game from it is expected to have.
+/// A single game doesn't depend on an opponent, so the games are weighed with the [`Objective::ExpectedScore`] objective.
+pub(crate) struct OptimalValues {
+    /// The expected weight of the games played from each board before it's rolled on, indexed by the board.
+    boards: Vec<f64>,
+}
+
+impl OptimalValues {
+    /// Finds the value of every board with the given amount of tiles, working up from the shut box.
+    /// The best move for a roll is the move to the board with the highest value.
+    pub(crate) fn solve(tiles: u8, dice: &DiceDistribution) -> OptimalValues {
+        let max_score = game_core::board_value(full_board(tiles));
+        // The game ends when there are no moves for the roll, scoring the board.
+        let ending = |board| {
+            Objective::ExpectedScore.weigh(
+                GameResult::Draw,
+                game_core::board_value(board),
+                max_score,
+            ) as f64
+        };
+
+        OptimalValues {
+            boards: induction::solve(tiles, dice, Reduction::Best, ending),
+        }
+    }
+
+    /// Returns the value of the board before it's rolled on.
+    pub(crate) fn of(&self, board: u16) -> f64 {
+        self.boards[board as usize]
+    }
+
+    /// Ranks the moves of every board & roll from the highest value to the lowest, so the first move is the best.
+    /// The values are rounded to fit a ranked move, but the moves are ranked by their exact values.
+    /// Rolls the dice can't make are ranked too, so every board & roll has a best move.
+    pub(crate) fn ranked_moves(&self) -> HashMap<BoardRoll, Vec<RankedMove>> {
+        let mut ranked_map = HashMap::new();
+
+        for board in 0..self.boards.len() as u16 {
+            for roll in 2..13u8 {
+                let mut moves = enumerate_moves(board, roll);
+                if moves.is_empty() {
+                    continue;
+                }
+                // Moves with the same value are ordered by their board, like the simulated moves are.
+                moves.sort_unstable_by(|one, two| {
+                    self.of(*two).total_cmp(&self.of(*one)).then(one.cmp(two))
+                });
+
+                let ranked = moves
+                    .into_iter()
+                    .map(|moved| RankedMove {
+                        board: moved,
+                        value: self.of(moved).round() as u16,
+                    })
+                    .collect();
+                ranked_map.insert(BoardRoll::new(board, DiceRoll::from(roll)), ranked);
+            }
+        }
+
+        ranked_map
+    }
+}
+
+/// Solves the best move for every board & roll of the config exactly, then writes them like a simulation's.
+/// The moves are solved for a game without an opponent, so the config is weighed with the expected score objective,
+/// whatever its own objective is.
+pub(crate) fn write_exact(config: &SimulationConfig) -> Result<(), ComputeError> {
+    if !config.ruleset.can_simulate() {
+        return Err(ComputeError::UnsupportedRuleset(config.ruleset));
+    }
+    config.dice.validate()?;
+    let config = SimulationConfig {
+        opponent: Opponent::Mirror,
+        objective: Objective::ExpectedScore,
+        ..config.clone()
+    };
+
+    let values = OptimalValues::solve(config.ruleset.tiles, &config.dice);
+    write_results(&config, &values.ranked_moves())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::policy::Policy;
+
+    #[test]
+    fn default_config_is_solved_for_the_expected_score() {
+        let folder = tempfile::tempdir().expect("Should create a temporary folder");
+        let config = SimulationConfig {
+            output: folder.path().join("best_moves.yml"),
+            opening_output: folder.path().join("opening.yml"),
+            histogram_games: 0,
+            ..SimulationConfig::new(1, 1)
+        };
+        let (sender, _receiver) = mpsc::channel();
+        crate::solve_exact(config.clone(), sender).expect("Should solve the default config");
+
+        let policy = Policy::load(&config.output).expect("Should read the solved moves");
+        let values = OptimalValues::solve(config.ruleset.tiles, &config.dice);
+        for (board_roll, ranked) in values.ranked_moves() {
+            assert_eq!(
+                policy.best_move(board_roll.board, board_roll.roll),
+                Some(ranked[0].board)
+            );
+        }
+        // A roll of 2 can only knock down the 2.
+        assert_eq!(
+            policy.best_move(full_board(9), DiceRoll::from(2)),
+            Some(full_board(9) & !0b10)
+        );
+    }
+}
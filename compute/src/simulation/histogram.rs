@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dice::DiceDistribution;
+use crate::full_board;
+use crate::rng;
+use crate::simulation::board::get_board;
+use crate::simulation::playing::{play, Player};
+use crate::strategy::Strategy;
+
+/// Contains how often each final score occurred when playing games with a strategy.
+/// The index of each count is the score it counts, so a count at index 0 is the amount of shut boxes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ScoreHistogram {
+    /// The amount of games that were played.
+    pub games: u32,
+    /// The final score counts of every game.
+    pub overall: Vec<u32>,
+    /// The final score counts of the games, split by the value of the first roll of the game.
+    pub by_opening_roll: BTreeMap<u8, Vec<u32>>,
+}
+
+impl ScoreHistogram {
+    /// Creates an empty histogram that can hold every score up to the given max score.
+    fn new(max_score: u8) -> ScoreHistogram {
+        ScoreHistogram {
+            games: 0,
+            overall: vec![0; max_score as usize + 1],
+            by_opening_roll: BTreeMap::new(),
+        }
+    }
+
+    /// Records the final score of a game that started with the given roll.
+    fn record(&mut self, opening_roll: u8, score: u8) {
+        let buckets = self.overall.len();
+
+        self.games += 1;
+        self.overall[score as usize] += 1;
+        self.by_opening_roll
+            .entry(opening_roll)
+            .or_insert_with(|| vec![0; buckets])[score as usize] += 1;
+    }
+}
+
+/// Plays the given amount of games from the full board of the given amount of tiles with the given strategy,
+/// counting how often each final score occurs.
+/// If a seed is given, then the same games are played every time.
+pub fn score_histogram(
+    strategy: &dyn Strategy,
+    games: u32,
+    seed: Option<u64>,
+    dice: &DiceDistribution,
+    tiles: u8,
+) -> ScoreHistogram {
+    let board = get_board(full_board(tiles) as usize).expect("Will exist");
+    let mut histogram = ScoreHistogram::new(board.calculate_value());
+
+    let player = Player::Strategic(strategy);
+    let sampler = dice.sampler();
+    let mut roll_rng = match seed {
+        Some(seed) => Rng::with_seed(seed),
+        None => rng::stream("histogram"),
+    };
+    let mut board_rng = roll_rng.fork();
+
+    // The moves of each game replace the last game's, so they're only allocated once.
+    let mut choices = Vec::new();
+    for _ in 0..games {
+        choices.clear();
+        let score = play(
+            board,
+            &mut choices,
+            &mut roll_rng,
+            &mut board_rng,
+            &player,
+            &sampler,
+        );
+        let opening_roll = choices
+            .first()
+            .expect("Every game has at least one roll.")
+            .get_roll()
+            .get_value();
+
+        histogram.record(opening_roll, score);
+    }
+
+    histogram
+}
@@ -0,0 +1,93 @@
+//! Solves the value of every board exactly, by working up from the shut box.
+//! Every move knocks pieces down, so the boards a move can reach have always been solved before the board it's made from.
+
+use crate::dice::DiceDistribution;
+use crate::{enumerate_moves, full_board};
+
+/// How the values of the moves for a roll are reduced to the value of the board after the roll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reduction {
+    /// The best move is always made, so the roll is worth the highest value of its moves.
+    Best,
+    /// A random move is made, as it is within the simulation, so the roll is worth the average value of its moves.
+    Average,
+}
+
+impl Reduction {
+    /// Returns the value of the board after the roll, from the values of the boards its moves reach.
+    /// Returns None if there are no moves for the roll, which ends the game.
+    /// Boards that haven't been solved have no value.
+    pub(crate) fn after_roll(self, values: &[f64], board: u16, roll: u8) -> Option<f64> {
+        let moves = enumerate_moves(board, roll);
+        let reached = moves
+            .iter()
+            .map(|moved| values.get(*moved as usize).copied().unwrap_or_default());
+
+        match self {
+            Reduction::Best => reached.reduce(f64::max),
+            Reduction::Average => {
+                (!moves.is_empty()).then(|| reached.sum::<f64>() / moves.len() as f64)
+            }
+        }
+    }
+}
+
+/// Finds the value of every board with the given amount of tiles before it's rolled on, indexed by the board.
+/// Each roll is reduced to a value by the reduction, or by the value of ending the game on the board if there are no moves.
+pub(crate) fn solve(
+    tiles: u8,
+    dice: &DiceDistribution,
+    reduction: Reduction,
+    ending: impl Fn(u16) -> f64,
+) -> Vec<f64> {
+    let weights = dice.sum_weights();
+    let total = weights.iter().sum::<u32>() as f64;
+    let boards = full_board(tiles) as usize + 1;
+    let mut values = vec![0.; boards];
+
+    for board in 0..boards as u16 {
+        values[board as usize] = (2..13u8)
+            .zip(weights)
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(roll, weight)| {
+                let value = reduction
+                    .after_roll(&values, board, roll)
+                    .unwrap_or_else(|| ending(board));
+                weight as f64 * value
+            })
+            .sum::<f64>()
+            / total;
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only a shut box is worth anything, like the chance of shutting the box.
+    fn shut(board: u16) -> f64 {
+        (board == 0) as u8 as f64
+    }
+
+    #[test]
+    fn lone_tile_is_shut_by_rolling_it() {
+        let values = solve(2, &DiceDistribution::Fair, Reduction::Best, shut);
+        assert_eq!(values[0], 1.);
+        // A 1 can never be rolled with both dice.
+        assert_eq!(values[0b01], 0.);
+        assert!((values[0b10] - 1. / 36.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn best_moves_are_worth_at_least_random_moves() {
+        let best = solve(9, &DiceDistribution::Fair, Reduction::Best, shut);
+        let average = solve(9, &DiceDistribution::Fair, Reduction::Average, shut);
+        assert!(best
+            .iter()
+            .zip(&average)
+            .all(|(best, average)| best >= average));
+        assert!(best[full_board(9) as usize] > average[full_board(9) as usize]);
+    }
+}
@@ -1,3 +1,20 @@
+pub(crate) mod bench;
+pub(crate) mod board;
+pub(crate) mod control;
+#[cfg(feature = "fs")]
+pub(crate) mod dataset;
+pub(crate) mod ensemble;
+#[cfg(feature = "fs")]
+pub(crate) mod exact;
+pub(crate) mod histogram;
+pub(crate) mod induction;
+pub(crate) mod opening;
 pub mod playing;
-mod board;
-mod roll;
\ No newline at end of file
+#[cfg(feature = "parquet")]
+pub(crate) mod raw;
+#[cfg(feature = "fs")]
+pub(crate) mod resume;
+pub(crate) mod roll;
+#[cfg(feature = "fs")]
+pub(crate) mod spill;
+pub(crate) mod verify;
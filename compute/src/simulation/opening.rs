@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{full_board, BoardRoll, DiceRoll, RankedMove};
+
+/// The recommended move for the first roll of a game.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OpeningRoll {
+    /// The summed value of the dice.
+    pub roll: u8,
+    /// The best board to move to from the full board.
+    /// If this is None, then the roll was never simulated from the full board.
+    pub best_move: Option<u16>,
+    /// The chance of winning the game after making the best move, between 0 & 1.
+    /// If the objective isn't head-to-head, then this is the value of the move relative to the best possible value.
+    pub win_probability: f32,
+}
+
+/// Creates a row for each opening roll from 2 to 12, using the ranked moves from a simulation.
+/// The max weight is the highest value a move can have, which is used to turn the values into probabilities.
+/// The opening rolls are made from the full board with the given amount of tiles.
+pub(crate) fn opening_report(
+    ranked_map: &HashMap<BoardRoll, Vec<RankedMove>>,
+    max_weight: u16,
+    tiles: u8,
+) -> Vec<OpeningRoll> {
+    (2..13u8)
+        .map(|roll| {
+            let best = ranked_map
+                .get(&BoardRoll::new(full_board(tiles), DiceRoll::from(roll)))
+                .and_then(|ranked| ranked.first());
+
+            OpeningRoll {
+                roll,
+                best_move: best.map(|best| best.board),
+                win_probability: best
+                    .map_or(0., |best| best.value as f32 / max_weight.max(1) as f32),
+            }
+        })
+        .collect()
+}
@@ -1,59 +1,135 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::File;
 use std::hash::Hash;
-use std::io::BufWriter;
 use std::ops::Div;
-use std::sync::mpsc;
-use std::sync::mpsc::Sender;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{mpsc, Arc};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use fastrand::Rng;
-
+#[cfg(feature = "fs")]
+use serde::Serialize;
+#[cfg(feature = "fs")]
+use tracing::warn;
+use tracing::{debug, debug_span, info, info_span, Span};
+
+#[cfg(feature = "fs")]
+use crate::codec;
+use crate::config::{Opponent, SimulationConfig};
+use crate::dice::RollSampler;
+#[cfg(feature = "fs")]
+use crate::lock::FileLock;
+#[cfg(feature = "fs")]
+use crate::policy::Policy;
+use crate::rng;
 use crate::simulation::board::{get_board, get_rand_board, Board};
+use crate::simulation::control::{ComputeCommand, ComputeProgress, Controls};
+#[cfg(feature = "fs")]
+use crate::simulation::histogram::score_histogram;
+#[cfg(feature = "fs")]
+use crate::simulation::opening::opening_report;
 use crate::simulation::playing::Result::{Draw, Loss, Win};
-use crate::{BoardRoll, DiceRoll};
+#[cfg(feature = "parquet")]
+use crate::simulation::raw::{raw_statistics, write_parquet};
+#[cfg(feature = "fs")]
+use crate::simulation::resume;
+use crate::simulation::roll::Roll;
+#[cfg(feature = "fs")]
+use crate::simulation::spill::SpillStore;
+use crate::strategy::Strategy;
+#[cfg(feature = "fs")]
+use crate::write_atomically;
+use crate::{full_board, BoardRoll, ComputeError, DiceRoll, RankedMove, Ruleset};
 
 /// A wrapper struct to store the moves taken in a game & the result of the game.
-pub struct Games {
-    pub moves: Vec<Choice>,
+pub struct Games<'a> {
+    pub moves: &'a [Choice],
     pub result: Result,
+    /// The final value of the board.
+    pub score: u8,
 }
 
-impl Games {
-    pub fn new(moves: Vec<Choice>, result: Result) -> Games {
-        Games { moves, result }
+impl<'a> Games<'a> {
+    pub fn new(moves: &'a [Choice], result: Result, score: u8) -> Games<'a> {
+        Games {
+            moves,
+            result,
+            score,
+        }
     }
 }
 
 /// Stores the total value of a choice & the amount of times it was taken.
 /// This allows for the division to be performed after, since division is very intensive.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct Weight {
-    total: u32,
+    total: u64,
     used: u32,
+    /// The total of each amount squared, which is used to calculate the variance.
+    squared: u64,
 }
 
 impl Weight {
+    /// Recreates a weight from its raw totals.
+    pub fn from_parts(total: u64, used: u32, squared: u64) -> Weight {
+        Weight {
+            total,
+            used,
+            squared,
+        }
+    }
+
     /// Adds the given amount to this weight.
     pub fn inc(&mut self, amount: u32) {
-        self.total += amount;
+        self.total += amount as u64;
         self.used += 1;
+        self.squared += amount as u64 * amount as u64;
     }
 
     /// Adds the given weight to this weight.
     pub fn combine(&mut self, other: &Weight) {
         self.total += other.total;
         self.used += other.used;
+        self.squared += other.squared;
     }
 
     /// Calculates the average of if choosing a move would result in a win.
     pub fn calculate(&self) -> u16 {
-        self.total.div(self.used) as u16
+        self.total.div(self.used as u64) as u16
+    }
+
+    /// Returns the amount of times the choice was taken.
+    pub fn samples(&self) -> u32 {
+        self.used
+    }
+
+    /// Returns the total of every amount added to this weight.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the total of every amount added to this weight squared.
+    pub fn squared(&self) -> u64 {
+        self.squared
+    }
+
+    /// Calculates the exact average of the amounts added to this weight.
+    pub fn mean(&self) -> f64 {
+        self.total as f64 / self.used as f64
+    }
+
+    /// Calculates the variance of the amounts added to this weight.
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        (self.squared as f64 / self.used as f64 - mean * mean).max(0.)
     }
 }
 
 /// Stores a possible board that could be "made" from one board state according to a certain roll.
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Eq, PartialEq, Hash, Clone, Copy)]
 pub struct Choice {
     root_board: u16,
     roll: DiceRoll,
@@ -61,73 +137,312 @@ pub struct Choice {
 }
 
 impl Choice {
+    /// Creates a choice of moving from the root board to the chosen board with the given roll.
+    /// If the chosen board is None, then the choice is a dying one.
+    pub fn new(root_board: u16, roll: DiceRoll, chosen_board: Option<u16>) -> Choice {
+        Choice {
+            root_board,
+            roll,
+            chosen_board,
+        }
+    }
+
     /// Sets the value of the chosen board
     pub fn set_chosen_board(&mut self, chosen_board: u16) {
         self.chosen_board = Some(chosen_board);
     }
 
+    /// Returns the board this choice was made from.
+    pub fn get_root_board(&self) -> u16 {
+        self.root_board
+    }
+
+    /// Returns the roll this choice was made for.
+    pub fn get_roll(&self) -> DiceRoll {
+        self.roll
+    }
+
+    /// Returns the board this choice moved to.
+    /// If the choice was a dying one, then None is returned.
+    pub fn get_chosen_board(&self) -> Option<u16> {
+        self.chosen_board
+    }
+
     /// Returns true if the move this choice represents would lead to a game over.
     pub fn is_dying_choice(&self) -> bool {
         self.chosen_board.is_none()
     }
 }
 
-/// Represents the weight of each simulation outcome.
-/// Win = 1000
-/// Draw = 500
-/// Loss = 0
-///
-/// The values are big as it results in higher accuracy during the division for the average win calculation.
-#[derive(Copy, Clone)]
+/// Represents the outcome of a simulated game against the opponent.
+/// The weight of each outcome is given by the [`crate::Objective`] of the simulation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Result {
-    Win = 1000,
-    Draw = 500,
-    Loss = 0,
+    Win,
+    Draw,
+    Loss,
 }
 
-/// Randomly simulates the given amount of games to play on the number of given threads.
-/// This method writes the best move for each board-roll combination to "best_moves.yml"
-pub fn compute_weights(threads: u8, games_to_play: u32, sender: Sender<bool>) {
-    let mut win_weights: HashMap<Choice, Weight> = HashMap::new();
+/// The moves made in each of the two games simulated from a board.
+/// The buffers are cleared rather than dropped between games, so a thread only allocates them once.
+#[derive(Default)]
+pub(crate) struct MoveBuffers {
+    first: Vec<Choice>,
+    second: Vec<Choice>,
+}
+
+/// Decides which move is made during a simulated game.
+pub(crate) enum Player<'a> {
+    /// Makes a random valid move.
+    Random,
+    /// Makes the move chosen by the strategy.
+    Strategic(&'a dyn Strategy),
+}
+
+impl Player<'_> {
+    /// Chooses the board to move to for the given roll.
+    /// If there are no valid moves then None is returned.
+    fn choose(&self, board: &Board, roll: &Roll, rng: &mut Rng) -> Option<u16> {
+        match self {
+            Player::Random => roll.get_rand_board(rng),
+            Player::Strategic(strategy) => {
+                strategy.choose_move(board.get_raw(), roll.roll, &roll.boards, rng)
+            }
+        }
+    }
+}
+
+/// How often the main thread checks for commands while waiting for the simulating threads.
+const CONTROL_POLL: Duration = Duration::from_millis(50);
+
+/// Randomly simulates the games to play on the number of threads given by the config.
+/// This method writes the best move for each board-roll combination to the config output.
+///
+/// If a control receiver is given, the simulation can be paused, resumed, & checkpointed with [`ComputeCommand`]s.
+/// The progress of the simulation is sent down the sender, which ends with [`ComputeProgress::Finished`].
+#[cfg(feature = "fs")]
+pub fn compute_weights(
+    config: SimulationConfig,
+    sender: Sender<ComputeProgress>,
+    control: Option<Receiver<ComputeCommand>>,
+) -> std::result::Result<(), ComputeError> {
+    let win_weights = simulate(&config, control, Some(&sender))?;
+
+    // Contains every choice for each roll for each board, ranked from best to worst.
+    let ranked_map = rank_choices(&win_weights);
+    // Nothing may be listening for the progress, which doesn't stop the simulation from finishing.
+    let _ = sender.send(ComputeProgress::Writing);
+    write_results(&config, &ranked_map)?;
+
+    // Writes the statistics of every choice, if they were requested.
+    #[cfg(feature = "parquet")]
+    if let Some(raw_output) = &config.raw_output {
+        write_parquet(raw_output, &raw_statistics(&win_weights))?;
+    }
+
+    // Dumps the raw & win chances
+    // let chances = File::create("move_chances.yml").expect("Should be able to create file.");
+    // let writer = BufWriter::new(chances);
+    // serde_yaml::to_writer(writer, &choice_map).expect("Should be able to write data to file.");
+
+    let _ = sender.send(ComputeProgress::Finished);
+    Ok(())
+}
+
+/// Writes the best move for each board-roll combination to the config output, which is the first of its ranked moves.
+/// The opening report, the best few moves, & the score histogram are written too, if the config requests them.
+#[cfg(feature = "fs")]
+pub(crate) fn write_results(
+    config: &SimulationConfig,
+    ranked_map: &HashMap<BoardRoll, Vec<RankedMove>>,
+) -> std::result::Result<(), ComputeError> {
+    // Contains the best choice for each roll for each board.
+    let policy: Policy = best_moves(ranked_map).into();
+
+    // Writes the data to the file to be referenced later.
+    write_policy(&config.output, &policy)?;
+
+    // Writes the recommended move for each roll of the first turn of a game.
+    write_yaml(
+        &config.opening_output,
+        &opening_report(
+            ranked_map,
+            config.objective.max_weight(),
+            config.ruleset.tiles,
+        ),
+    )?;
+
+    // Writes the best few choices for each roll for each board, if they were requested.
+    if config.top_k > 0 {
+        let top_map: HashMap<BoardRoll, &[RankedMove]> = ranked_map
+            .iter()
+            .map(|(board_roll, ranked)| (*board_roll, &ranked[..ranked.len().min(config.top_k)]))
+            .collect();
+
+        write_yaml(&config.ranked_output, &top_map)?;
+    }
+
+    // Writes how often each final score occurs when playing with the computed policy.
+    if config.histogram_games > 0 {
+        let histogram = score_histogram(
+            &policy,
+            config.histogram_games,
+            config.seed,
+            &config.dice,
+            config.ruleset.tiles,
+        );
+        write_yaml(&config.histogram_output, &histogram)?;
+    }
+
+    Ok(())
+}
+
+/// Simulates the games to play on the number of threads given by the config, returning the weight of every choice.
+/// If a progress sender is given, then the games simulated & the throughput of each thread are sent down it as they're merged.
+pub(crate) fn simulate(
+    config: &SimulationConfig,
+    mut control: Option<Receiver<ComputeCommand>>,
+    progress: Option<&Sender<ComputeProgress>>,
+) -> std::result::Result<HashMap<Choice, Weight>, ComputeError> {
+    let threads = config.threads;
+    let games_to_play = config.games_to_play;
+    let _span = info_span!("simulation", threads, games = games_to_play).entered();
+    if !config.ruleset.can_simulate() {
+        return Err(ComputeError::UnsupportedRuleset(config.ruleset));
+    }
+    config.dice.validate()?;
+
+    // Carries on from the weights of the last simulation, if it was saved.
+    #[cfg(feature = "fs")]
+    let (resumed_games, mut win_weights) = match &config.resume_file {
+        Some(resume_file) => resume::load(resume_file),
+        None => (0, HashMap::new()),
+    };
+    // Without files there's nothing to resume from.
+    #[cfg(not(feature = "fs"))]
+    let (resumed_games, mut win_weights) = (0, HashMap::new());
     let (tx, rx) = mpsc::channel();
+    // Used to stop the threads once the policy has converged, or to pause them.
+    let controls = Arc::new(Controls::default());
+
+    // The amount of games each thread simulates between checkpoints.
+    let checkpoint_games = match config.checkpoint_games {
+        0 => games_to_play,
+        checkpoint_games => checkpoint_games.min(games_to_play),
+    };
 
     // Creates threads to compute random simulations of the game.
-    for _ in 0..threads {
+    let seed = config
+        .seed
+        .unwrap_or_else(|| rng::stream("simulation").u64(..));
+    for thread_index in 0..threads {
         let tx_thread = tx.clone();
+        let opponent = config.opponent.clone();
+        let controls = controls.clone();
+        let objective = config.objective;
+        let sampler = config.dice.sampler();
+        let tiles = config.ruleset.tiles;
+        let max_score = game_core::board_value(full_board(tiles));
+        let span = info_span!(parent: Span::current(), "simulating", thread = thread_index);
 
         thread::spawn(move || {
-            // Each simulation will start from a random board to get an even distribution
-            let mut win_weights: HashMap<Choice, Weight> = HashMap::new();
-
-            for _ in 0..games_to_play {
-                let board = get_rand_board();
-                let (game_one, game_two) = run_game(board);
-
-                let one = game_one.result as u32;
-                let two = game_two.result as u32;
-
-                update_weights(game_one, one, &mut win_weights);
-                update_weights(game_two, two, &mut win_weights);
+            let _span = span.entered();
+            // Each thread has its own seed so the threads don't simulate the same games.
+            fastrand::seed(seed.wrapping_add(thread_index as u64));
+
+            let mut buffers = MoveBuffers::default();
+            let mut games_remaining = games_to_play;
+            while games_remaining > 0 && !controls.is_stopped() {
+                let batch = games_remaining.min(checkpoint_games);
+                games_remaining -= batch;
+                let _batch = debug_span!("batch", games = batch).entered();
+                let started = Instant::now();
+
+                // Each simulation will start from a random board to get an even distribution
+                let mut win_weights: HashMap<Choice, Weight> = HashMap::new();
+
+                for _ in 0..batch {
+                    controls.wait_while_paused();
+
+                    let board = get_rand_board(tiles);
+                    let (game_one, game_two) = run_game(board, &opponent, &sampler, &mut buffers);
+
+                    let one = objective.weigh(game_one.result, game_one.score, max_score);
+                    let two = objective.weigh(game_two.result, game_two.score, max_score);
+
+                    update_weights(&game_one, one, &mut win_weights);
+                    // The moves of a fixed opponent aren't being learnt.
+                    if let Opponent::Mirror = opponent {
+                        update_weights(&game_two, two, &mut win_weights);
+                    }
+                }
+
+                // Send the results of the games to the main thread for merging.
+                // If the main thread stopped listening, then the results aren't needed.
+                let elapsed = started.elapsed();
+                if tx_thread
+                    .send((thread_index, batch, elapsed, win_weights))
+                    .is_err()
+                {
+                    break;
+                }
             }
-
-            // Send the results of the games to the main thread for merging.
-            tx_thread
-                .send(win_weights)
-                .expect("Should be able to send.");
         });
     }
 
-    // Waits for each thread to finish & merges its results into the main map.
-    for finished_threads in 0..threads {
-        let thread_map = rx.recv().expect("Should always receive a value");
+    // Only the threads hold senders, so the receiver disconnects once every thread has finished.
+    drop(tx);
+
+    let mut games_simulated = resumed_games;
+    let total_games = resumed_games + threads as u64 * games_to_play as u64;
+    // The amount of batches received from each thread, as a faster thread can send its batches ahead of the rest.
+    let mut thread_batches = vec![0u32; threads as usize];
+    let mut checkpoints_reached = 0u32;
+    // Stores the choices that were removed from memory, if the memory is bounded.
+    #[cfg(feature = "fs")]
+    let mut spill = config
+        .max_entries
+        .map(|_| SpillStore::new().map_err(ComputeError::Spill))
+        .transpose()?;
+    // The best moves at the previous checkpoint & the amount of checkpoints they have been stable for.
+    let mut checkpoint_moves = HashMap::new();
+    let mut stable_for = 0u32;
+
+    // Waits for each batch of games & merges its results into the main map.
+    loop {
+        if let Some(receiver) = &control {
+            // Handles every command sent since the last check.
+            loop {
+                match receiver.try_recv() {
+                    Ok(ComputeCommand::Pause) => controls.pause(),
+                    Ok(ComputeCommand::Resume) => controls.resume(),
+                    // Without files there's nowhere to flush the checkpoint to.
+                    Ok(ComputeCommand::FlushCheckpoint) => {
+                        #[cfg(feature = "fs")]
+                        flush_checkpoint(config, games_simulated, &win_weights, spill.as_mut());
+                    }
+                    Ok(ComputeCommand::Stop) => controls.stop(),
+                    Err(TryRecvError::Empty) => break,
+                    // If nothing can send commands anymore, then the simulation can never be resumed.
+                    Err(TryRecvError::Disconnected) => {
+                        controls.resume();
+                        control = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        let (thread_index, batch, elapsed, thread_map) = match rx.recv_timeout(CONTROL_POLL) {
+            Ok(received) => received,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
 
         for choice in thread_map.keys() {
             // If it doesn't contain a value for this choice, add it.
             if !win_weights.contains_key(choice) {
-                win_weights.insert(
-                    choice.clone(),
-                    *thread_map.get(choice).expect("Will exist."),
-                );
+                win_weights.insert(*choice, *thread_map.get(choice).expect("Will exist."));
                 continue;
             }
 
@@ -137,151 +452,364 @@ pub fn compute_weights(threads: u8, games_to_play: u32, sender: Sender<bool>) {
             existing_weight.combine(thread_weight);
         }
 
-        println!(
-            "Games simulated: {}",
-            (finished_threads + 1) as u32 * games_to_play
-        );
+        // Moves the coldest choices to disk if the map has grown too large.
+        #[cfg(feature = "fs")]
+        if let (Some(max_entries), Some(spill)) = (config.max_entries, spill.as_mut()) {
+            if win_weights.len() > max_entries {
+                spill
+                    .spill_coldest(&mut win_weights, max_entries / 2)
+                    .map_err(ComputeError::Spill)?;
+            }
+        }
+
+        games_simulated += batch as u64;
+        thread_batches[thread_index as usize] += 1;
+        debug!("Games simulated: {games_simulated}");
+        if let Some(progress) = progress {
+            // Nothing may be listening for the progress, which doesn't stop the simulation.
+            let _ = progress.send(ComputeProgress::Games {
+                completed: games_simulated,
+                total: total_games,
+            });
+            let _ = progress.send(ComputeProgress::Throughput {
+                thread: thread_index,
+                games_per_second: batch as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            });
+        }
+
+        // A checkpoint is reached once every thread has sent a batch since the last checkpoint.
+        let checkpoints = thread_batches.iter().copied().min().unwrap_or_default();
+        if config.stable_checkpoints == 0 || checkpoints == checkpoints_reached {
+            continue;
+        }
+        checkpoints_reached = checkpoints;
+
+        // The spilled choices count towards the best moves, as they would if the memory was unbounded.
+        #[cfg(feature = "fs")]
+        let weighted = match spill.as_mut() {
+            Some(spill) => spill
+                .with_spilled(&win_weights)
+                .map_err(ComputeError::Spill)?,
+            None => Cow::Borrowed(&win_weights),
+        };
+        #[cfg(not(feature = "fs"))]
+        let weighted = Cow::Borrowed(&win_weights);
+        let best_moves = best_moves(&rank_choices(&weighted));
+        let changed = best_moves
+            .iter()
+            .filter(|(board_roll, best)| checkpoint_moves.get(*board_roll) != Some(*best))
+            .count();
+        checkpoint_moves = best_moves;
+
+        match changed <= config.convergence_tolerance {
+            true => stable_for += 1,
+            false => stable_for = 0,
+        }
+
+        if stable_for >= config.stable_checkpoints && !controls.is_stopped() {
+            info!(
+                "Best moves were stable for {stable_for} checkpoints, stopping early after {games_simulated} games."
+            );
+            controls.stop();
+        }
     }
 
-    // Contains the best choice for each roll for each board.
-    let mut choice_map = HashMap::new();
-    // Contains the win % of the current best choice
-    let mut weight_map = HashMap::new();
+    if let Some(progress) = progress {
+        let _ = progress.send(ComputeProgress::Merging);
+    }
+
+    // Merges every spilled choice back, now that no more games will be simulated.
+    #[cfg(feature = "fs")]
+    if let Some(mut spill) = spill {
+        info!("Merging {} spilled choices.", spill.spilled());
+        spill
+            .merge_into(&mut win_weights)
+            .map_err(ComputeError::Spill)?;
+    }
+
+    #[cfg(feature = "fs")]
+    if let Some(resume_file) = &config.resume_file {
+        resume::save(resume_file, games_simulated, &win_weights)?;
+    }
+
+    Ok(win_weights)
+}
+
+/// Writes the best moves found so far to the checkpoint output of the config, saving the simulation to resume it too.
+/// The spilled choices are merged into a copy of the choices in memory, so every choice is considered.
+/// A failed checkpoint doesn't lose any games, so failures are only logged.
+#[cfg(feature = "fs")]
+fn flush_checkpoint(
+    config: &SimulationConfig,
+    games_simulated: u64,
+    win_weights: &HashMap<Choice, Weight>,
+    spill: Option<&mut SpillStore>,
+) {
+    let win_weights = match spill.map(|spill| spill.with_spilled(win_weights)) {
+        Some(Ok(weighted)) => weighted,
+        Some(Err(e)) => {
+            warn!("Couldn't read the spilled choices for the checkpoint: {e}");
+            return;
+        }
+        None => Cow::Borrowed(win_weights),
+    };
+
+    let checkpoint: Policy = best_moves(&rank_choices(&win_weights)).into();
+    if let Err(e) = write_policy(&config.checkpoint_output, &checkpoint) {
+        warn!("Couldn't write the checkpoint: {e}");
+    }
+
+    if let Some(resume_file) = &config.resume_file {
+        if let Err(e) = resume::save(resume_file, games_simulated, &win_weights) {
+            warn!("Couldn't save the simulation to resume it: {e}");
+        }
+    }
+}
+
+/// Writes the given data to the given file as yaml.
+/// The file is replaced atomically, so it's never left half written.
+#[cfg(feature = "fs")]
+pub(crate) fn write_yaml<T: Serialize>(
+    path: &Path,
+    data: &T,
+) -> std::result::Result<(), ComputeError> {
+    let contents = codec::to_string(data)?;
+    write_atomically(path, contents.as_bytes()).map_err(|source| ComputeError::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
 
-    // Calculates the best choice for each roll for each board.
-    for choice in win_weights.keys() {
-        let weight = win_weights
-            .get(choice)
-            .expect("Iterating over every key so the kye must be in the map.");
-        let win_average = weight.calculate();
+/// Writes the policy to the given file as yaml.
+/// The file is locked while it's written, so it isn't read while being rebuilt.
+#[cfg(feature = "fs")]
+pub(crate) fn write_policy(path: &Path, policy: &Policy) -> std::result::Result<(), ComputeError> {
+    let _lock = FileLock::exclusive(path).map_err(|source| ComputeError::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    write_yaml(path, policy)
+}
+
+/// Groups the weighted choices by their board & roll, then ranks them from the highest value to the lowest.
+pub(crate) fn rank_choices(
+    win_weights: &HashMap<Choice, Weight>,
+) -> HashMap<BoardRoll, Vec<RankedMove>> {
+    let mut ranked_map: HashMap<BoardRoll, Vec<RankedMove>> = HashMap::new();
 
+    for (choice, weight) in win_weights {
         let board_roll = BoardRoll {
             board: choice.root_board,
             roll: choice.roll,
         };
 
-        // If the map contains a choice that looses more often discard this choice.
-        if let Some(existing) = weight_map.get(&board_roll) {
-            if *existing < win_average {
-                continue;
-            }
-        }
-
-        weight_map.insert(board_roll, win_average);
-
-        choice_map.insert(
-            board_roll,
-            choice
+        ranked_map.entry(board_roll).or_default().push(RankedMove {
+            board: choice
                 .chosen_board
                 .expect("None boards are removed before this function."),
-        );
+            value: weight.calculate(),
+        });
     }
 
-    // Writes the data to the file to be referenced later.
-    let file = File::create("best_moves.yml").expect("Should be able to create file.");
-    let writer = BufWriter::new(file);
-    serde_yaml::to_writer(writer, &choice_map).expect("Should be able to write data to file.");
+    // Moves with the same value are ordered by their board, so the ranking is consistent between runs.
+    for ranked in ranked_map.values_mut() {
+        ranked
+            .sort_unstable_by(|one, two| two.value.cmp(&one.value).then(one.board.cmp(&two.board)));
+    }
 
-    // Dumps the raw & win chances
-    // let chances = File::create("move_chances.yml").expect("Should be able to create file.");
-    // let writer = BufWriter::new(chances);
-    // serde_yaml::to_writer(writer, &choice_map).expect("Should be able to write data to file.");
+    ranked_map
+}
 
-    sender
-        .send(true)
-        .expect("Receiver will be listening until this is sent.");
+/// Gets the highest ranked move for each board & roll.
+pub(crate) fn best_moves(
+    ranked_map: &HashMap<BoardRoll, Vec<RankedMove>>,
+) -> HashMap<BoardRoll, u16> {
+    ranked_map
+        .iter()
+        .map(|(board_roll, ranked)| (*board_roll, ranked[0].board))
+        .collect()
+}
+
+/// Simulates two games with the given board state.
+/// The first game is always played randomly, the second game is played by the given opponent.
+/// Both games use the same rolls, which are chosen by the given sampler.
+/// The moves of the games are kept in the given buffers, replacing the moves of the last games.
+pub(crate) fn run_game<'a>(
+    board: &Board,
+    opponent: &Opponent,
+    sampler: &RollSampler,
+    buffers: &'a mut MoveBuffers,
+) -> (Games<'a>, Games<'a>) {
+    run_seeded_game(board, opponent, sampler, &mut Rng::new(), buffers)
 }
 
-/// Simulates two random games with the given board state.
-pub fn run_game(board: &Board) -> (Games, Games) {
+/// Simulates a game like [`run_game`], with the rolls & moves drawn from the given rng.
+pub(crate) fn run_seeded_game<'a>(
+    board: &Board,
+    opponent: &Opponent,
+    sampler: &RollSampler,
+    rng: &mut Rng,
+    buffers: &'a mut MoveBuffers,
+) -> (Games<'a>, Games<'a>) {
     // Ensures that each game has the same roll rng.
-    let rand_seed = fastrand::u64(..);
+    let rand_seed = rng.u64(..);
+
+    let second_player = match opponent {
+        Opponent::Mirror => Player::Random,
+        Opponent::Fixed(strategy) => Player::Strategic(strategy.as_ref()),
+    };
 
     // Simulates the games.
     // Each game has a different board rng.
-    let mut rng_1 = Rng::with_seed(fastrand::u64(..));
-    let first_game = rand(
+    buffers.first.clear();
+    let mut rng_1 = Rng::with_seed(rng.u64(..));
+    let first_score = play(
         board,
-        Vec::new(),
+        &mut buffers.first,
         &mut Rng::with_seed(rand_seed),
         &mut rng_1,
+        &Player::Random,
+        sampler,
     );
 
-    let mut rng_2 = Rng::with_seed(fastrand::u64(..));
-    let second_game = rand(
+    buffers.second.clear();
+    let mut rng_2 = Rng::with_seed(rng.u64(..));
+    let second_score = play(
         board,
-        Vec::new(),
+        &mut buffers.second,
         &mut Rng::with_seed(rand_seed),
         &mut rng_2,
+        &second_player,
+        sampler,
     );
 
     // Assigns the correct win/loss values to each game
-    let result = match (first_game.0, second_game.0) {
-        (first, second) if first > second => (Win, Loss),
-        (first, second) if second > first => (Loss, Win),
-        _ => (Draw, Draw),
-    };
+    let result = results(first_score, second_score);
 
     // Uses the wrapper to store the game data
     (
-        Games::new(first_game.1, result.0),
-        Games::new(second_game.1, result.1),
+        Games::new(&buffers.first, result.0, first_score),
+        Games::new(&buffers.second, result.1, second_score),
     )
 }
 
-/// Performs a random move on the given board recursively, until there are no valid moves.
-/// The returned u8 is the finial value of the board
-fn rand(
-    board: &Board,
-    mut choices: Vec<Choice>,
+/// Returns the outcome of each of two games with the given final scores.
+/// The player with the lowest value left on their board wins, as the best moves are ranked by the highest weight.
+fn results(first_score: u8, second_score: u8) -> (Result, Result) {
+    match (first_score, second_score) {
+        (first, second) if first < second => (Win, Loss),
+        (first, second) if second < first => (Loss, Win),
+        _ => (Draw, Draw),
+    }
+}
+
+/// Performs the move chosen by the player on the given board, until there are no valid moves.
+/// Each move is added to the end of the given choices, including the last roll that had no valid moves.
+/// The returned u8 is the final value of the board
+pub(crate) fn play(
+    mut board: &Board,
+    choices: &mut Vec<Choice>,
     roll_rng: &mut Rng,
     board_rng: &mut Rng,
-) -> (u8, Vec<Choice>) {
-    let rand_roll = board.get_rand_roll(roll_rng);
-
-    let mut choice = Choice {
-        root_board: board.get_raw(),
-        roll: rand_roll.roll,
-        chosen_board: None,
-    };
-
-    // If there are no more valid moves return the board value & the moves leading to the last valid board.
-    // If there are more valid moves randomly simulate them.
-    match rand_roll.get_rand_board(board_rng) {
-        None => {
-            choices.push(choice);
-            (board.calculate_value(), choices)
-        }
-        Some(rand_board) => {
-            choice.set_chosen_board(rand_board);
+    player: &Player,
+    sampler: &RollSampler,
+) -> u8 {
+    loop {
+        let rand_roll = board.get_rand_roll(roll_rng, sampler);
+        let mut choice = Choice::new(board.get_raw(), rand_roll.roll, None);
+
+        // If there are no more valid moves return the board value, as the moves leading to it have been added.
+        let Some(rand_board) = player.choose(board, rand_roll, board_rng) else {
             choices.push(choice);
+            return board.calculate_value();
+        };
 
-            let board = get_board(rand_board as usize).expect("Will exist");
-            rand(board, choices, roll_rng, board_rng)
-        }
+        // Any board up to the max amount of tiles is simulated, so the moves are checked with the loosest rules.
+        debug_assert_eq!(
+            Ruleset::ANY.is_legal_move(board.get_raw(), rand_roll.roll.get_value(), rand_board),
+            Ok(()),
+            "The simulation made an illegal move"
+        );
+        choice.set_chosen_board(rand_board);
+        choices.push(choice);
+
+        board = get_board(rand_board as usize).expect("Will exist");
     }
 }
 
 /// Updates the HashMap with the outcome of the choices in the game.
-fn update_weights(game: Games, value: u32, win_weights: &mut HashMap<Choice, Weight>) {
+fn update_weights(game: &Games, value: u32, win_weights: &mut HashMap<Choice, Weight>) {
     for game_move in game.moves {
         // If the move caused a death, don't even consider it.
         if game_move.is_dying_choice() {
             continue;
         }
 
-        // If the move hasn't been chosen before create a new weight for it.
-        win_weights
-            .entry(game_move.clone())
-            .or_insert_with(|| Weight {
-                total: value,
-                used: 1,
-            });
+        // If the move hasn't been chosen before create a new weight for it,
+        // then update the weight with the outcome of the game.
+        win_weights.entry(*game_move).or_default().inc(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Objective;
+
+    #[test]
+    fn lowest_score_wins() {
+        assert_eq!(results(0, 12), (Win, Loss));
+        assert_eq!(results(30, 4), (Loss, Win));
+        assert_eq!(results(7, 7), (Draw, Draw));
+    }
+
+    #[test]
+    fn best_move_is_the_one_that_wins_most() {
+        let objective = Objective::default();
+        let mut win_weights = HashMap::new();
+        // The games went on to shut the box after knocking down the 7, but ended with the 7 up after knocking down the 1 & 6.
+        // The opponent always finished with 3 left on their board.
+        for (chosen, score) in [(0b0100001, 0), (0b1000000, 7)] {
+            let (result, _) = results(score, 3);
+            win_weights
+                .entry(Choice::new(0b1100001, DiceRoll::from(7), Some(chosen)))
+                .or_insert_with(Weight::default)
+                .inc(objective.weigh(result, score, 45));
+        }
+
+        let ranked = rank_choices(&win_weights);
+        let best = best_moves(&ranked);
+        assert_eq!(
+            best[&BoardRoll::new(0b1100001, DiceRoll::from(7))],
+            0b0100001
+        );
+    }
+
+    #[test]
+    fn capped_memory_weighs_the_same_as_unbounded_memory() {
+        // The best moves are checked at every checkpoint, but the simulation never stops early.
+        let config = SimulationConfig {
+            seed: Some(3),
+            checkpoint_games: 100,
+            stable_checkpoints: u32::MAX,
+            ..SimulationConfig::new(1, 2000)
+        };
+        let weigh = |max_entries| {
+            let config = SimulationConfig {
+                max_entries,
+                ..config.clone()
+            };
+            let win_weights = simulate(&config, None, None).expect("Should simulate");
+            win_weights
+                .into_iter()
+                .map(|(choice, weight)| {
+                    (choice, (weight.total(), weight.samples(), weight.squared()))
+                })
+                .collect::<HashMap<_, _>>()
+        };
 
-        // Update the existing weight with the outcome of the game
-        let weight = win_weights
-            .get_mut(&game_move)
-            .expect("The map will contain this value");
-        weight.inc(value);
+        let unbounded = weigh(None);
+        assert!(unbounded.len() > 64, "The capped run should have spilled");
+        assert!(weigh(Some(64)) == unbounded);
     }
 }
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::playing::{Choice, Weight};
+
+/// The statistics gathered about a single candidate move of a board & roll during a simulation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RawStatistic {
+    /// The board the move was made from.
+    pub board: u16,
+    /// The summed value of the dice.
+    pub roll: u8,
+    /// The board after the move was made.
+    pub candidate: u16,
+    /// The amount of simulated games the move was made in.
+    pub samples: u32,
+    /// The average outcome of the games the move was made in.
+    pub mean: f64,
+    /// The variance of the outcome of the games the move was made in.
+    pub variance: f64,
+}
+
+/// Converts the weight of every choice into its statistics, sorted by board, roll, & candidate.
+pub(crate) fn raw_statistics(win_weights: &HashMap<Choice, Weight>) -> Vec<RawStatistic> {
+    let mut statistics: Vec<RawStatistic> = win_weights
+        .iter()
+        .map(|(choice, weight)| RawStatistic {
+            board: choice.get_root_board(),
+            roll: choice.get_roll().get_value(),
+            candidate: choice
+                .get_chosen_board()
+                .expect("None boards are removed before this function."),
+            samples: weight.samples(),
+            mean: weight.mean(),
+            variance: weight.variance(),
+        })
+        .collect();
+
+    statistics
+        .sort_unstable_by_key(|statistic| (statistic.board, statistic.roll, statistic.candidate));
+    statistics
+}
+
+/// Writes the given statistics to the given file in the parquet format.
+///
+/// The columns are "board" (u16), "roll" (u8), "candidate" (u16), "samples" (u32),
+/// "mean" (f64), & "variance" (f64), matching the fields of [`RawStatistic`].
+pub fn write_parquet(
+    path: &Path,
+    statistics: &[RawStatistic],
+) -> Result<(), parquet::errors::ParquetError> {
+    use std::fs::File;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Float64Array, UInt16Array, UInt32Array, UInt8Array};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    let column = |array: ArrayRef| array;
+    let batch = RecordBatch::try_from_iter([
+        (
+            "board",
+            column(Arc::new(UInt16Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.board),
+            ))),
+        ),
+        (
+            "roll",
+            column(Arc::new(UInt8Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.roll),
+            ))),
+        ),
+        (
+            "candidate",
+            column(Arc::new(UInt16Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.candidate),
+            ))),
+        ),
+        (
+            "samples",
+            column(Arc::new(UInt32Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.samples),
+            ))),
+        ),
+        (
+            "mean",
+            column(Arc::new(Float64Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.mean),
+            ))),
+        ),
+        (
+            "variance",
+            column(Arc::new(Float64Array::from_iter_values(
+                statistics.iter().map(|statistic| statistic.variance),
+            ))),
+        ),
+    ])?;
+
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
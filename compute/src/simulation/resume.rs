@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::simulation::playing::{write_yaml, Choice, Weight};
+use crate::{codec, ComputeError, DiceRoll};
+
+/// The weight of a single choice, as it is kept in the resume file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct SavedChoice {
+    board: u16,
+    roll: u8,
+    /// The board after the move, or None if the choice was a dying one.
+    chosen: Option<u16>,
+    total: u64,
+    used: u32,
+    squared: u64,
+}
+
+/// Everything a simulation needs to carry on from where it stopped.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct SavedSimulation {
+    /// The amount of games that have been simulated, across every resumed simulation.
+    games: u64,
+    choices: Vec<SavedChoice>,
+}
+
+/// Only the amount of games is read, so the choices can be skipped over.
+#[derive(Deserialize)]
+struct SavedGames {
+    games: u64,
+}
+
+/// Returns the amount of games the simulation saved to the given resume file has simulated,
+/// or None if the file doesn't exist or couldn't be read.
+pub fn resumed_games(path: &Path) -> Option<u64> {
+    let file = File::open(path).ok()?;
+    let saved: SavedGames = codec::from_reader(BufReader::new(file)).ok()?;
+    Some(saved.games)
+}
+
+/// Reads the weights of the simulation saved to the given resume file, along with how many games it simulated.
+/// If the file doesn't exist or couldn't be read, then the simulation starts from nothing.
+pub(crate) fn load(path: &Path) -> (u64, HashMap<Choice, Weight>) {
+    let saved: SavedSimulation = match File::open(path) {
+        Ok(file) => codec::from_reader(BufReader::new(file)).unwrap_or_else(|e| {
+            warn!(
+                "Couldn't read {}, starting from nothing: {e}",
+                path.display()
+            );
+            SavedSimulation::default()
+        }),
+        Err(_) => SavedSimulation::default(),
+    };
+
+    let weights = saved
+        .choices
+        .into_iter()
+        .map(|saved| {
+            (
+                Choice::new(saved.board, DiceRoll::from(saved.roll), saved.chosen),
+                Weight::from_parts(saved.total, saved.used, saved.squared),
+            )
+        })
+        .collect();
+
+    (saved.games, weights)
+}
+
+/// Writes the weights of the simulation to the given resume file, along with how many games it has simulated.
+pub(crate) fn save(
+    path: &Path,
+    games: u64,
+    win_weights: &HashMap<Choice, Weight>,
+) -> Result<(), ComputeError> {
+    let choices = win_weights
+        .iter()
+        .map(|(choice, weight)| SavedChoice {
+            board: choice.get_root_board(),
+            roll: choice.get_roll().get_value(),
+            chosen: choice.get_chosen_board(),
+            total: weight.total(),
+            used: weight.samples(),
+            squared: weight.squared(),
+        })
+        .collect();
+
+    write_yaml(path, &SavedSimulation { games, choices })
+}
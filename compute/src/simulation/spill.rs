@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use crate::simulation::playing::{Choice, Weight};
+use crate::DiceRoll;
+
+/// The size in bytes of a single spilled choice & its weight.
+const RECORD_SIZE: usize = 26;
+
+/// A temporary on-disk store for the weights of choices that were removed from memory.
+/// The file is deleted once the store is dropped.
+pub(crate) struct SpillStore {
+    file: BufWriter<File>,
+    /// The amount of records that have been spilled.
+    spilled: u64,
+}
+
+impl SpillStore {
+    /// Creates an empty store backed by a new temporary file.
+    pub fn new() -> io::Result<SpillStore> {
+        Ok(SpillStore {
+            file: BufWriter::new(tempfile::tempfile()?),
+            spilled: 0,
+        })
+    }
+
+    /// Returns the amount of records that have been spilled.
+    pub fn spilled(&self) -> u64 {
+        self.spilled
+    }
+
+    /// Moves the coldest choices, those that have been taken the least, out of the map & into the store,
+    /// until the map contains at most the given amount of choices.
+    pub fn spill_coldest(
+        &mut self,
+        win_weights: &mut HashMap<Choice, Weight>,
+        keep: usize,
+    ) -> io::Result<()> {
+        if win_weights.len() <= keep {
+            return Ok(());
+        }
+
+        let mut by_samples: Vec<(u32, Choice)> = win_weights
+            .iter()
+            .map(|(choice, weight)| (weight.samples(), *choice))
+            .collect();
+        by_samples.sort_unstable_by_key(|(samples, _)| *samples);
+
+        let to_spill = win_weights.len() - keep;
+        for (_, choice) in by_samples.into_iter().take(to_spill) {
+            let weight = win_weights
+                .remove(&choice)
+                .expect("The choice is in the map.");
+            self.write_record(&choice, &weight)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads every spilled choice back from the store, combining it into the given map.
+    /// The choices stay in the store, which carries on being spilled into afterwards.
+    pub fn merge_into(&mut self, win_weights: &mut HashMap<Choice, Weight>) -> io::Result<()> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(0))?;
+
+        let mut reader = BufReader::new(&*file);
+        let mut buffer = [0u8; RECORD_SIZE];
+        for _ in 0..self.spilled {
+            reader.read_exact(&mut buffer)?;
+            let (choice, weight) = Self::decode(&buffer);
+            win_weights.entry(choice).or_default().combine(&weight);
+        }
+
+        // The next choices are spilled after the last record.
+        file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Returns every choice weighted so far, by merging the spilled choices into a copy of the given map.
+    /// The map is borrowed as is if nothing has been spilled.
+    pub fn with_spilled<'a>(
+        &mut self,
+        win_weights: &'a HashMap<Choice, Weight>,
+    ) -> io::Result<Cow<'a, HashMap<Choice, Weight>>> {
+        if self.spilled == 0 {
+            return Ok(Cow::Borrowed(win_weights));
+        }
+
+        let mut merged = win_weights.clone();
+        self.merge_into(&mut merged)?;
+        Ok(Cow::Owned(merged))
+    }
+
+    /// Appends the given choice & weight to the store.
+    fn write_record(&mut self, choice: &Choice, weight: &Weight) -> io::Result<()> {
+        let mut buffer = [0u8; RECORD_SIZE];
+        buffer[0..2].copy_from_slice(&choice.get_root_board().to_le_bytes());
+        buffer[2] = choice.get_roll().encoded();
+        // The chosen board is never None, as dying choices are never weighted.
+        buffer[3..5].copy_from_slice(&choice.get_chosen_board().unwrap_or_default().to_le_bytes());
+        buffer[5] = choice.get_chosen_board().is_some() as u8;
+        buffer[6..14].copy_from_slice(&weight.total().to_le_bytes());
+        buffer[14..18].copy_from_slice(&weight.samples().to_le_bytes());
+        buffer[18..26].copy_from_slice(&weight.squared().to_le_bytes());
+
+        self.file.write_all(&buffer)?;
+        self.spilled += 1;
+        Ok(())
+    }
+
+    /// Converts a record written by [`SpillStore::write_record`] back into its choice & weight.
+    fn decode(buffer: &[u8; RECORD_SIZE]) -> (Choice, Weight) {
+        let u16_at = |index: usize| u16::from_le_bytes([buffer[index], buffer[index + 1]]);
+        let u32_at = |index: usize| {
+            u32::from_le_bytes(
+                buffer[index..index + 4]
+                    .try_into()
+                    .expect("Slice is 4 long."),
+            )
+        };
+        let u64_at = |index: usize| {
+            u64::from_le_bytes(
+                buffer[index..index + 8]
+                    .try_into()
+                    .expect("Slice is 8 long."),
+            )
+        };
+
+        let chosen_board = match buffer[5] {
+            0 => None,
+            _ => Some(u16_at(3)),
+        };
+
+        (
+            Choice::new(u16_at(0), DiceRoll::from_encoded(buffer[2]), chosen_board),
+            Weight::from_parts(u64_at(6), u32_at(14), u64_at(18)),
+        )
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Objective, Opponent, SimulationConfig};
+use crate::dice::DiceDistribution;
+use crate::simulation::induction::{self, Reduction};
+use crate::simulation::playing::Result as GameResult;
+use crate::simulation::playing::{best_moves, rank_choices, simulate, Weight};
+use crate::{enumerate_moves, full_board, BoardRoll, ComputeError};
+
+/// How many standard errors a deviation has to be for it to be more than statistical noise.
+pub const NOISE_SIGMAS: f64 = 3.;
+
+/// The comparison of a simulation against the exact values the simulation is estimating.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Verification {
+    /// The amount of games that were simulated.
+    pub games: u64,
+    /// The amount of moves that were simulated at least once, which each have a deviation.
+    pub states: usize,
+    /// The average distance between the simulated & exact value of each move.
+    pub mean_deviation: f64,
+    /// The furthest the simulated value of a move was from its exact value.
+    pub max_deviation: f64,
+    /// The amount of moves whose simulated value is further from the exact value than statistical noise.
+    pub beyond_noise: usize,
+    /// The deviation of every simulated move, sorted by their board, roll, & move.
+    pub deviations: Vec<Deviation>,
+    /// The board-roll combinations where the simulated best move isn't the exact best move,
+    /// sorted by their board & roll.
+    pub differences: Vec<MoveDifference>,
+}
+
+/// How far the simulated value of a move is from its exact value.
+/// The values are the average weight of the games the move was made in, so they're between 0 & 1000.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Deviation {
+    pub board_roll: BoardRoll,
+    /// The board after the move has been made.
+    pub board: u16,
+    /// The amount of games the move was made in.
+    pub samples: u32,
+    pub simulated: f64,
+    pub exact: f64,
+    /// How many standard errors the simulated value is from the exact value.
+    /// This is None if every game had the same weight, so there's no standard error.
+    pub sigmas: Option<f64>,
+    /// Whether the deviation is more than statistical noise.
+    pub beyond_noise: bool,
+}
+
+/// A board-roll combination where the simulated best move isn't the exact best move.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MoveDifference {
+    pub board_roll: BoardRoll,
+    /// The best move according to the simulation.
+    pub simulated: u16,
+    /// The best move according to the exact values.
+    pub exact: u16,
+    /// How much better the exact best move is than the simulated one, according to the exact values.
+    pub exact_gap: f64,
+    /// How many standard errors the simulated gap between the moves is from the exact gap.
+    /// This is None if the exact best move was never simulated, or neither move had a standard error.
+    pub sigmas: Option<f64>,
+    /// Whether the simulation preferring the wrong move is more than statistical noise.
+    pub beyond_noise: bool,
+}
+
+/// The exact value of every board, when every move is chosen randomly as it is within the simulation.
+struct ExactValues {
+    /// The average weight of the games played from each board before it's rolled on, indexed by the board.
+    boards: Vec<f64>,
+}
+
+impl ExactValues {
+    /// Finds the value of every board with the given amount of tiles, working up from the shut box.
+    fn solve(tiles: u8, dice: &DiceDistribution) -> ExactValues {
+        let max_score = game_core::board_value(full_board(tiles));
+        // The expected score objective ignores the result, as it doesn't depend on the opponent.
+        let ending = |board| {
+            Objective::ExpectedScore.weigh(
+                GameResult::Draw,
+                game_core::board_value(board),
+                max_score,
+            ) as f64
+        };
+
+        ExactValues {
+            boards: induction::solve(tiles, dice, Reduction::Average, ending),
+        }
+    }
+
+    /// Returns the exact value of moving to the given board.
+    fn of(&self, board: u16) -> f64 {
+        self.boards[board as usize]
+    }
+}
+
+/// Simulates the given config, then compares the value of each simulated move against its exact value.
+///
+/// The exact values can only be solved for games that don't depend on an opponent,
+/// so the games are always played against a mirror of themselves with the [`Objective::ExpectedScore`] objective.
+/// Both games of each simulated pair share their rolls, so the standard errors are a little smaller than they should be
+/// & slightly more deviations are beyond noise than the amount of sigmas alone would suggest.
+pub fn run_verification(config: &SimulationConfig) -> Result<Verification, ComputeError> {
+    let config = SimulationConfig {
+        opponent: Opponent::Mirror,
+        objective: Objective::ExpectedScore,
+        // The verification is of the given games, so it doesn't carry on from another simulation.
+        resume_file: None,
+        ..config.clone()
+    };
+    let exact = ExactValues::solve(config.ruleset.tiles, &config.dice);
+    let weights = simulate(&config, None, None)?;
+
+    // Groups the weight of each move by its board & roll.
+    let mut grouped: HashMap<BoardRoll, Vec<(u16, Weight)>> = HashMap::new();
+    for (choice, weight) in &weights {
+        let Some(moved) = choice.get_chosen_board() else {
+            continue;
+        };
+        grouped
+            .entry(BoardRoll::new(choice.get_root_board(), choice.get_roll()))
+            .or_default()
+            .push((moved, *weight));
+    }
+
+    let mut deviations = Vec::with_capacity(weights.len());
+    for (board_roll, moves) in &grouped {
+        for (moved, weight) in moves {
+            let simulated = weight.mean();
+            let exact = exact.of(*moved);
+            let sigmas = sigmas(simulated - exact, standard_error(weight));
+
+            deviations.push(Deviation {
+                board_roll: *board_roll,
+                board: *moved,
+                samples: weight.samples(),
+                simulated,
+                exact,
+                sigmas,
+                beyond_noise: is_beyond_noise(simulated - exact, sigmas),
+            });
+        }
+    }
+    deviations.sort_unstable_by_key(|deviation| {
+        let board_roll = deviation.board_roll;
+        (
+            board_roll.board,
+            board_roll.roll.get_value(),
+            deviation.board,
+        )
+    });
+
+    let mut differences = Vec::new();
+    for (board_roll, simulated) in best_moves(&rank_choices(&weights)) {
+        let Some(best) = enumerate_moves(board_roll.board, board_roll.roll.get_value())
+            .into_iter()
+            .max_by(|one, two| exact.of(*one).total_cmp(&exact.of(*two)))
+        else {
+            continue;
+        };
+        let exact_gap = exact.of(best) - exact.of(simulated);
+        // Moves with the same exact value are both the best move.
+        if exact_gap <= f64::EPSILON {
+            continue;
+        }
+
+        let moves = &grouped[&board_roll];
+        let weight_of = |board: u16| {
+            moves
+                .iter()
+                .find(|(moved, _)| *moved == board)
+                .map(|(_, weight)| *weight)
+        };
+        let (simulated_weight, best_weight) = (weight_of(simulated), weight_of(best));
+        let sigmas = match (simulated_weight, best_weight) {
+            (Some(simulated_weight), Some(best_weight)) => {
+                let simulated_gap = best_weight.mean() - simulated_weight.mean();
+                let error = standard_error(&simulated_weight).hypot(standard_error(&best_weight));
+                sigmas(exact_gap - simulated_gap, error)
+            }
+            _ => None,
+        };
+
+        differences.push(MoveDifference {
+            board_roll,
+            simulated,
+            exact: best,
+            exact_gap,
+            sigmas,
+            beyond_noise: sigmas.is_some_and(|sigmas| sigmas > NOISE_SIGMAS),
+        });
+    }
+    differences.sort_unstable_by_key(|difference| {
+        (
+            difference.board_roll.board,
+            difference.board_roll.roll.get_value(),
+        )
+    });
+
+    let total_deviation: f64 = deviations
+        .iter()
+        .map(|deviation| (deviation.simulated - deviation.exact).abs())
+        .sum();
+
+    Ok(Verification {
+        games: config.threads as u64 * config.games_to_play as u64,
+        states: deviations.len(),
+        mean_deviation: total_deviation / deviations.len().max(1) as f64,
+        max_deviation: deviations
+            .iter()
+            .map(|deviation| (deviation.simulated - deviation.exact).abs())
+            .fold(0., f64::max),
+        beyond_noise: deviations
+            .iter()
+            .filter(|deviation| deviation.beyond_noise)
+            .count(),
+        deviations,
+        differences,
+    })
+}
+
+/// Returns the standard error of the average of the weight.
+fn standard_error(weight: &Weight) -> f64 {
+    (weight.variance() / weight.samples() as f64).sqrt()
+}
+
+/// Returns how many standard errors the deviation is, or None if there is no standard error.
+fn sigmas(deviation: f64, error: f64) -> Option<f64> {
+    match error > 0. {
+        true => Some(deviation.abs() / error),
+        false => None,
+    }
+}
+
+/// Returns true if the deviation is more than statistical noise.
+/// Without a standard error every game had the same weight, so any deviation beyond rounding is too much.
+fn is_beyond_noise(deviation: f64, sigmas: Option<f64>) -> bool {
+    match sigmas {
+        Some(sigmas) => sigmas > NOISE_SIGMAS,
+        None => deviation.abs() > 1e-6,
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::DiceRoll;
+
+/// Decides which move to make for a given board & roll.
+pub trait Strategy: Send + Sync {
+    /// Chooses one of the given moves to make from the given board with the given roll.
+    /// The moves are the boards that can legally be reached, as returned by [`crate::enumerate_moves`].
+    /// If there are no moves to choose from, then None is returned.
+    fn choose_move(&self, board: u16, roll: DiceRoll, moves: &[u16], rng: &mut Rng) -> Option<u16>;
+}
+
+/// Simple strategies that are commonly used by people playing the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinStrategy {
+    /// Chooses a random valid move.
+    Random,
+    /// Knocks down the highest valued tiles possible.
+    HighestTilesFirst,
+    /// Knocks down the lowest valued tiles possible.
+    LowestTilesFirst,
+    /// Knocks down as few tiles as possible.
+    FewestTiles,
+    /// Knocks down as many tiles as possible.
+    MostTiles,
+}
+
+impl BuiltinStrategy {
+    /// Every built-in strategy.
+    pub const ALL: [BuiltinStrategy; 5] = [
+        BuiltinStrategy::Random,
+        BuiltinStrategy::HighestTilesFirst,
+        BuiltinStrategy::LowestTilesFirst,
+        BuiltinStrategy::FewestTiles,
+        BuiltinStrategy::MostTiles,
+    ];
+
+    /// Returns the human readable name of this strategy.
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinStrategy::Random => "random",
+            BuiltinStrategy::HighestTilesFirst => "highest-tiles-first",
+            BuiltinStrategy::LowestTilesFirst => "lowest-tiles-first",
+            BuiltinStrategy::FewestTiles => "fewest-tiles",
+            BuiltinStrategy::MostTiles => "most-tiles",
+        }
+    }
+
+    /// Gets the built-in strategy with the given name, as returned by [`BuiltinStrategy::name`].
+    pub fn from_name(name: &str) -> Option<BuiltinStrategy> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == name)
+    }
+}
+
+impl Strategy for BuiltinStrategy {
+    fn choose_move(
+        &self,
+        board: u16,
+        _roll: DiceRoll,
+        moves: &[u16],
+        rng: &mut Rng,
+    ) -> Option<u16> {
+        if moves.is_empty() {
+            return None;
+        }
+
+        // The tiles knocked down by a move.
+        // As higher tiles are stored in higher bits, comparing the knocked down tiles numerically
+        // compares them by their highest tile first.
+        let knocked_down = |chosen: &&u16| board & !**chosen;
+
+        let chosen = match self {
+            BuiltinStrategy::Random => moves.get(rng.usize(..moves.len())),
+            BuiltinStrategy::HighestTilesFirst => moves.iter().max_by_key(knocked_down),
+            BuiltinStrategy::LowestTilesFirst => moves.iter().min_by_key(knocked_down),
+            BuiltinStrategy::FewestTiles => moves.iter().max_by_key(|chosen| chosen.count_ones()),
+            BuiltinStrategy::MostTiles => moves.iter().min_by_key(|chosen| chosen.count_ones()),
+        };
+
+        chosen.copied()
+    }
+}
+
+/// The difficulty given wasn't a preset or a fraction of the moves.
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid difficulty \"{0}\", it has to be easy, medium, hard, perfect, or how often the strategy's move is made, such as 80%")]
+pub struct InvalidDifficulty(pub String);
+
+/// How often a bot makes the move its strategy chose, as it otherwise makes a random legal move.
+/// This lets any strategy be played at an easier level, as the mistakes are made the same way for each.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "f32", into = "f32")]
+pub struct BotDifficulty {
+    /// The chance of making the strategy's move, from 0 to 1.
+    accuracy: f32,
+}
+
+impl BotDifficulty {
+    /// Makes the strategy's move 40% of the time.
+    pub const EASY: BotDifficulty = BotDifficulty { accuracy: 0.4 };
+    /// Makes the strategy's move 70% of the time.
+    pub const MEDIUM: BotDifficulty = BotDifficulty { accuracy: 0.7 };
+    /// Makes the strategy's move 95% of the time.
+    pub const HARD: BotDifficulty = BotDifficulty { accuracy: 0.95 };
+    /// Always makes the strategy's move.
+    pub const PERFECT: BotDifficulty = BotDifficulty { accuracy: 1. };
+    /// Every preset, from the easiest to the hardest.
+    pub const PRESETS: [BotDifficulty; 4] = [
+        BotDifficulty::EASY,
+        BotDifficulty::MEDIUM,
+        BotDifficulty::HARD,
+        BotDifficulty::PERFECT,
+    ];
+
+    /// Creates a difficulty that makes the strategy's move with the given chance, from 0 to 1.
+    /// Returns None if the chance is outside of that range.
+    pub fn new(accuracy: f32) -> Option<BotDifficulty> {
+        (0. ..=1.)
+            .contains(&accuracy)
+            .then_some(BotDifficulty { accuracy })
+    }
+
+    /// Returns the chance of making the strategy's move, from 0 to 1.
+    pub fn accuracy(self) -> f32 {
+        self.accuracy
+    }
+
+    /// Returns the name of the preset, or None if this isn't a preset.
+    pub fn name(self) -> Option<&'static str> {
+        let names = ["easy", "medium", "hard", "perfect"];
+        BotDifficulty::PRESETS
+            .iter()
+            .position(|preset| *preset == self)
+            .map(|index| names[index])
+    }
+
+    /// Chooses the strategy's move, unless a mistake is made, in which case a random legal move is chosen.
+    /// If there are no moves to choose from, then None is returned.
+    pub fn choose_move(
+        self,
+        strategy: &dyn Strategy,
+        board: u16,
+        roll: DiceRoll,
+        moves: &[u16],
+        rng: &mut Rng,
+    ) -> Option<u16> {
+        if moves.is_empty() {
+            return None;
+        }
+
+        // A perfect bot doesn't roll for mistakes, so the strategy's random choices stay the same.
+        if self.accuracy < 1. && rng.f32() >= self.accuracy {
+            return moves.get(rng.usize(..moves.len())).copied();
+        }
+        strategy.choose_move(board, roll, moves, rng)
+    }
+}
+
+impl Default for BotDifficulty {
+    fn default() -> Self {
+        BotDifficulty::PERFECT
+    }
+}
+
+impl FromStr for BotDifficulty {
+    type Err = InvalidDifficulty;
+
+    /// Parses the name of a preset, or the chance of making the strategy's move, such as "0.8" or "80%".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        if let Some(preset) = BotDifficulty::PRESETS.into_iter().find(|preset| {
+            preset
+                .name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(trimmed))
+        }) {
+            return Ok(preset);
+        }
+
+        let accuracy = match trimmed.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f32>().map(|percent| percent / 100.),
+            None => trimmed.parse::<f32>(),
+        };
+        accuracy
+            .ok()
+            .and_then(BotDifficulty::new)
+            .ok_or_else(|| InvalidDifficulty(value.to_string()))
+    }
+}
+
+impl Display for BotDifficulty {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(formatter, "{name}"),
+            None => write!(formatter, "{}%", self.accuracy * 100.),
+        }
+    }
+}
+
+impl TryFrom<f32> for BotDifficulty {
+    type Error = InvalidDifficulty;
+
+    fn try_from(accuracy: f32) -> Result<Self, Self::Error> {
+        BotDifficulty::new(accuracy).ok_or_else(|| InvalidDifficulty(accuracy.to_string()))
+    }
+}
+
+impl From<BotDifficulty> for f32 {
+    fn from(difficulty: BotDifficulty) -> Self {
+        difficulty.accuracy
+    }
+}
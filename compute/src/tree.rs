@@ -0,0 +1,400 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dice::DiceDistribution;
+use crate::simulation::board::get_board;
+use crate::simulation::induction::{self, Reduction};
+use crate::{enumerate_moves, list_tiles, DiceRoll, MAX_TILES};
+
+/// The bits of a [`GameState`] that hold the board.
+const BOARD_BITS: u16 = (1 << MAX_TILES) - 1;
+/// How far the roll is shifted within a [`GameState`], which puts it above the board.
+const ROLL_SHIFT: u16 = MAX_TILES as u16;
+/// The version of the serialized [`GameState`], which is increased whenever its representation changes.
+/// States of a newer version than this can't be read.
+pub const GAME_STATE_VERSION: u8 = 1;
+
+/// The errors that can occur when reading a [`GameState`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum GameStateError {
+    #[error(
+        "The game state is version {0}, but only up to version {GAME_STATE_VERSION} can be read"
+    )]
+    UnsupportedVersion(u8),
+    #[error("{0} isn't a valid game state, as its roll is above 12")]
+    InvalidRoll(u16),
+}
+
+/// A position in a game, which is either a board waiting to be rolled on or a board that has been rolled on.
+///
+/// Binary representation of the state within the u16:
+///  0000 | 000000000000
+///  roll | board
+///
+/// A roll of 0 means the board hasn't been rolled on yet, otherwise it's the summed value of the dice.
+///
+/// A state is serialized as the [`GAME_STATE_VERSION`] it was written with, followed by the packed u16.
+/// For example, the board with the 1, 2, & 3 pieces alive that has been rolled a 2 is `{version: 1, state: 8199}`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(into = "VersionedState", try_from = "VersionedState")]
+pub struct GameState(u16);
+
+/// The serialized representation of a [`GameState`].
+#[derive(Serialize, Deserialize)]
+struct VersionedState {
+    version: u8,
+    state: u16,
+}
+
+impl From<GameState> for VersionedState {
+    fn from(state: GameState) -> Self {
+        VersionedState {
+            version: GAME_STATE_VERSION,
+            state: state.packed(),
+        }
+    }
+}
+
+impl TryFrom<VersionedState> for GameState {
+    type Error = GameStateError;
+
+    fn try_from(versioned: VersionedState) -> Result<Self, Self::Error> {
+        if versioned.version > GAME_STATE_VERSION {
+            return Err(GameStateError::UnsupportedVersion(versioned.version));
+        }
+        GameState::from_packed(versioned.state)
+    }
+}
+
+impl GameState {
+    /// Creates the state of a board that is waiting to be rolled on.
+    pub fn new(board: u16) -> GameState {
+        GameState(board & BOARD_BITS)
+    }
+
+    /// Creates the state of a board that has been rolled on, which is waiting for a move.
+    pub fn rolled(board: u16, roll: DiceRoll) -> GameState {
+        GameState(board & BOARD_BITS | (roll.get_value() as u16) << ROLL_SHIFT)
+    }
+
+    /// Recreates a state from its packed u16, as returned by [`GameState::packed`].
+    /// Returns an error if the roll is above 12.
+    pub fn from_packed(packed: u16) -> Result<GameState, GameStateError> {
+        match packed >> ROLL_SHIFT {
+            0..=12 => Ok(GameState(packed)),
+            _ => Err(GameStateError::InvalidRoll(packed)),
+        }
+    }
+
+    /// Returns the packed u16 of the state, with the roll above the board.
+    pub fn packed(self) -> u16 {
+        self.0
+    }
+
+    /// Returns the board of the state.
+    pub fn board(self) -> u16 {
+        self.0 & BOARD_BITS
+    }
+
+    /// Returns the roll made on the board, or None if it hasn't been rolled on yet.
+    pub fn roll(self) -> Option<DiceRoll> {
+        match self.0 >> ROLL_SHIFT {
+            0 => None,
+            roll => Some(DiceRoll::from(roll as u8)),
+        }
+    }
+
+    /// Returns the states that can directly follow this one.
+    /// A board waiting to be rolled on is followed by every roll the dice can make,
+    /// & a rolled board is followed by the board of every move that can be made.
+    pub fn children(self, dice: &DiceDistribution) -> Vec<GameState> {
+        match self.roll() {
+            None => possible_rolls(dice)
+                .map(|roll| GameState::rolled(self.board(), DiceRoll::from(roll)))
+                .collect(),
+            Some(roll) => self.children_for_roll(roll),
+        }
+    }
+
+    /// Returns the states of the boards that the board of this state can be moved to with the given roll.
+    /// The moves are taken from the boards shared with the simulation, so they're only ever found once.
+    pub fn children_for_roll(self, roll: DiceRoll) -> Vec<GameState> {
+        let roll = roll.get_value();
+        match get_board(self.board() as usize).and_then(|board| board.moves_for_roll(roll)) {
+            Some(moves) => moves.iter().copied().map(GameState::new).collect(),
+            // The moves of a single dice roll aren't shared, so they're found here.
+            None => enumerate_moves(self.board(), roll)
+                .into_iter()
+                .map(GameState::new)
+                .collect(),
+        }
+    }
+}
+
+/// The game tree below a state, whose nodes are only found once they're expanded.
+///
+/// The same state can be reached through different moves, such as knocking down the 1 & 3 or the 4.
+/// The children of each state are kept in a transposition table that is shared by every parent reaching it,
+/// so the tree is a DAG where each state is only expanded once, no matter how many ways it can be reached.
+/// The expanded part of the tree can be serialized, so it can be saved & shared.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameNode {
+    /// The state at the root of the tree.
+    pub state: GameState,
+    /// The dice the boards are rolled with.
+    dice: DiceDistribution,
+    /// The children of every state that has been expanded.
+    table: HashMap<GameState, Vec<GameState>>,
+}
+
+impl GameNode {
+    /// Creates the tree of the given state, which hasn't been expanded yet.
+    pub fn new(state: GameState, dice: DiceDistribution) -> GameNode {
+        GameNode {
+            state,
+            dice,
+            table: HashMap::new(),
+        }
+    }
+
+    /// Returns the states that directly follow the given state, finding them if it hasn't been expanded yet.
+    pub fn expand(&mut self, state: GameState) -> &[GameState] {
+        let dice = &self.dice;
+        self.table
+            .entry(state)
+            .or_insert_with(|| state.children(dice))
+    }
+
+    /// Returns the states that the board of the given state can be moved to with the given roll,
+    /// expanding the rolled state if it hasn't been expanded yet.
+    pub fn children_for_roll(&mut self, state: GameState, roll: DiceRoll) -> &[GameState] {
+        self.expand(GameState::rolled(state.board(), roll))
+    }
+
+    /// Returns the states that directly follow the given state, or None if it hasn't been expanded.
+    pub fn children(&self, state: GameState) -> Option<&[GameState]> {
+        self.table.get(&state).map(Vec::as_slice)
+    }
+
+    /// Returns true if the given state has been expanded.
+    pub fn is_expanded(&self, state: GameState) -> bool {
+        self.table.contains_key(&state)
+    }
+
+    /// Returns the amount of distinct states that have been expanded.
+    pub fn expanded(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Expands every state that can be reached from the root, which is the whole game tree.
+    /// As each state is only expanded once, this visits at most one state for each board & roll.
+    pub fn expand_all(&mut self) {
+        self.expand_lazily().for_each(drop);
+    }
+
+    /// Expands every state less than the given depth below the root, leaving the states at the depth unexpanded.
+    /// Rolling the dice & making a move are each a level, so a depth of 2 expands every roll of a board & their moves.
+    pub fn expand_to_depth(&mut self, depth: usize) {
+        let mut reached = HashSet::from([self.state]);
+        let mut level = vec![self.state];
+
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for state in level {
+                for child in self.expand(state) {
+                    if reached.insert(*child) {
+                        next.push(*child);
+                    }
+                }
+            }
+            level = next;
+        }
+    }
+
+    /// Returns an iterator over the states reachable from the root, along with how deep below the root they are.
+    /// The states are visited breadth first & each state is only expanded once it's returned,
+    /// so the tree can be explored incrementally by only taking as many states as are needed.
+    pub fn expand_lazily(&mut self) -> Expansion<'_> {
+        let root = self.state;
+        Expansion {
+            tree: self,
+            pending: VecDeque::from([(root, 0)]),
+            reached: HashSet::from([root]),
+        }
+    }
+
+    /// Returns the dice the boards are rolled with.
+    pub fn dice(&self) -> &DiceDistribution {
+        &self.dice
+    }
+
+    /// Writes the expanded part of the tree as a GraphViz DOT graph.
+    /// Boards waiting to be rolled on are ellipses, rolled boards are boxes, & states that haven't been expanded are dashed.
+    /// The same state can be reached through different moves, so each state is only drawn once.
+    ///
+    /// If chances are given, then each state is coloured from red to green by its chance of shutting the box.
+    pub fn to_dot(&self, chances: Option<&ShutChances>) -> String {
+        let mut dot =
+            String::from("digraph game_tree {\n    node [style=filled, fillcolor=white];\n");
+        let mut drawn = HashSet::new();
+        self.write_dot(self.state, &mut dot, &mut drawn, chances);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the state & the states below it that haven't been drawn yet, along with the edges to its children.
+    fn write_dot(
+        &self,
+        state: GameState,
+        dot: &mut String,
+        drawn: &mut HashSet<GameState>,
+        chances: Option<&ShutChances>,
+    ) {
+        if !drawn.insert(state) {
+            return;
+        }
+
+        let board = state.board();
+        let (label, shape) = match state.roll() {
+            None if board == 0 => (String::from("shut"), "doublecircle"),
+            None => (format!("[{}]", list_tiles(board)), "ellipse"),
+            Some(roll) => (
+                format!("[{}]\\nroll {}", list_tiles(board), roll.get_value()),
+                "box",
+            ),
+        };
+        let children = self.children(state);
+        let mut style = String::from("filled");
+        if children.is_none() {
+            style.push_str(",dashed");
+        }
+        // The writes can't fail, as they're to a string.
+        let _ = write!(
+            dot,
+            "    s{} [label=\"{label}\", shape={shape}, style=\"{style}\"",
+            state.0
+        );
+        if let Some(chances) = chances {
+            let chance = chances.state(state);
+            // The hue goes from red at 0 to green at a third of the way around.
+            let _ = write!(
+                dot,
+                ", fillcolor=\"{:.3} 0.5 1.0\", tooltip=\"{:.1}%\"",
+                chance / 3.,
+                chance * 100.
+            );
+        }
+        dot.push_str("];\n");
+
+        let Some(children) = children else {
+            return;
+        };
+        for child in children {
+            let label = match (state.roll(), child.roll()) {
+                (_, Some(roll)) => roll.get_value().to_string(),
+                // A move is labelled by the tiles it knocks down.
+                _ => format!("-{}", list_tiles(board & !child.board())),
+            };
+            let _ = writeln!(dot, "    s{} -> s{} [label=\"{label}\"];", state.0, child.0);
+            self.write_dot(*child, dot, drawn, chances);
+        }
+    }
+}
+
+/// Walks the states of a [`GameNode`] breadth first, expanding each state as it's returned.
+/// Each state is only returned once, at the shallowest depth it can be reached.
+pub struct Expansion<'a> {
+    tree: &'a mut GameNode,
+    /// The states that have been reached but not returned yet, along with their depth.
+    pending: VecDeque<(GameState, usize)>,
+    /// Every state that has been reached, so states reached through different moves are only returned once.
+    reached: HashSet<GameState>,
+}
+
+impl Iterator for Expansion<'_> {
+    type Item = (GameState, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (state, depth) = self.pending.pop_front()?;
+        for child in self.tree.expand(state) {
+            if self.reached.insert(*child) {
+                self.pending.push_back((*child, depth + 1));
+            }
+        }
+        Some((state, depth))
+    }
+}
+
+/// The exact chance of shutting the box from every board, when the move with the best chance is always made.
+/// This is found by working up from the shut box, as every move leads to a board with fewer pieces.
+#[derive(Clone, Debug)]
+pub struct ShutChances {
+    /// The chance of shutting the box from each board before it is rolled on, indexed by the board.
+    chances: Vec<f64>,
+    /// The amount of tiles the chances were solved for.
+    tiles: u8,
+    dice: DiceDistribution,
+}
+
+impl ShutChances {
+    /// Finds the chance of shutting the box from every board with the given amount of tiles.
+    pub fn solve(tiles: u8, dice: DiceDistribution) -> ShutChances {
+        // Only a shut box is shut once the game ends, which has nothing left to roll for.
+        let ending = |board| (board == 0) as u8 as f64;
+
+        ShutChances {
+            chances: induction::solve(tiles, &dice, Reduction::Best, ending),
+            tiles,
+            dice,
+        }
+    }
+
+    /// Returns the chance of shutting the box from the board before it is rolled on, between 0 & 1.
+    /// Boards with more tiles than were solved for have no chance.
+    pub fn board(&self, board: u16) -> f64 {
+        self.chances
+            .get(board as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the chance of shutting the box from the board after the roll, when the best move is made.
+    /// If there are no moves for the roll, then there is no chance.
+    pub fn after_roll(&self, board: u16, roll: u8) -> f64 {
+        Reduction::Best
+            .after_roll(&self.chances, board, roll)
+            .unwrap_or_default()
+    }
+
+    /// Returns the chance of shutting the box from the state, between 0 & 1.
+    pub fn state(&self, state: GameState) -> f64 {
+        match state.roll() {
+            None => self.board(state.board()),
+            Some(roll) => self.after_roll(state.board(), roll.get_value()),
+        }
+    }
+
+    /// Returns the move with the best chance of shutting the box, or None if there are no moves.
+    pub fn best_move(&self, board: u16, roll: u8) -> Option<u16> {
+        enumerate_moves(board, roll)
+            .into_iter()
+            .max_by(|one, two| self.board(*one).total_cmp(&self.board(*two)))
+    }
+
+    /// Returns the amount of tiles the chances were solved for.
+    pub fn tiles(&self) -> u8 {
+        self.tiles
+    }
+
+    /// Returns the dice the chances were solved with.
+    pub fn dice(&self) -> &DiceDistribution {
+        &self.dice
+    }
+}
+
+/// Returns every sum the dice can roll.
+fn possible_rolls(dice: &DiceDistribution) -> impl Iterator<Item = u8> + '_ {
+    (2..13).filter(|roll| dice.probability(*roll) > 0.)
+}
@@ -0,0 +1,9 @@
+//! Decodes a single packet as a malicious client could send it to the server.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protocol::ClientMessages;
+
+fuzz_target!(|packet: &[u8]| {
+    let _ = networked::frame::decode::<ClientMessages>(packet);
+});
@@ -0,0 +1,8 @@
+//! Decodes the bytes as a policy file, as a corrupted "best_moves.yml" could contain.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = compute::Policy::decode(bytes);
+});
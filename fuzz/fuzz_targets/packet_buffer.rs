@@ -0,0 +1,22 @@
+//! Feeds the bytes to the packet buffer in chunks, as they could be read from a connection.
+//! The first byte decides how large each chunk is.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use networked::frame::{PacketBuffer, MAX_PACKET_LEN};
+use protocol::ClientMessages;
+
+fuzz_target!(|bytes: &[u8]| {
+    let Some((chunk_len, bytes)) = bytes.split_first() else {
+        return;
+    };
+
+    let mut packets = PacketBuffer::default();
+    for chunk in bytes.chunks(*chunk_len as usize + 1) {
+        if packets.push::<ClientMessages>(chunk).is_err() {
+            // The buffer only fails once the unended packet is too long.
+            assert!(bytes.len() > MAX_PACKET_LEN);
+            return;
+        }
+    }
+});
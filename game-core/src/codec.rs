@@ -0,0 +1,36 @@
+//! Reads & writes the YAML files & packets that are shared between the crates.
+//!
+//! Every crate goes through these helpers rather than its own YAML library,
+//! so a file written by one crate can always be parsed by another.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Read, Write};
+
+/// Why a value couldn't be encoded or decoded.
+pub type Error = serde_yaml::Error;
+
+/// Encodes the value as YAML into the writer.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<(), Error> {
+    serde_yaml::to_writer(writer, value)
+}
+
+/// Decodes a value from the YAML read from the reader.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T, Error> {
+    serde_yaml::from_reader(reader)
+}
+
+/// Encodes the value as a YAML string.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    serde_yaml::to_string(value)
+}
+
+/// Decodes a value from the YAML string.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, Error> {
+    serde_yaml::from_str(text)
+}
+
+/// Decodes a value from the YAML bytes.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    serde_yaml::from_slice(bytes)
+}
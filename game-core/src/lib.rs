@@ -0,0 +1,281 @@
+//! The rules of shut the box, which are shared by the simulation, the server, & the gui.
+//!
+//! Binary representation of a board within the u16:
+//!  0000 | 000000000000
+//!  ____ | CBA987654321
+//!
+//! Each bit is a piece, which is alive if the bit is set.
+
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+
+pub mod codec;
+pub mod rng;
+
+/// The amount of tiles on a standard board.
+pub const STANDARD_TILES: u8 = 9;
+/// The most tiles a board can have.
+/// Each tile is a bit of the u16 board, while the highest tile has to be reachable with two dice.
+pub const MAX_TILES: u8 = 12;
+
+/// The pieces that have to be knocked down before a single dice can be rolled.
+/// These are the 7, 8, & 9 pieces, along with any higher pieces.
+const SINGLE_DICE_PIECES: u16 = !0b111111;
+
+/// Returns the board with every piece alive for the given amount of tiles, which every game starts from.
+/// The amount of tiles is capped to [`MAX_TILES`].
+pub fn full_board(tiles: u8) -> u16 {
+    (1 << tiles.min(MAX_TILES)) - 1
+}
+
+/// Sums up the numeric value of the alive pieces for the given board, which is the score of a finished game.
+pub fn board_value(board: u16) -> u8 {
+    (0..MAX_TILES)
+        .filter(|index| (board >> index) & 1 == 1)
+        .map(|index| index + 1)
+        .sum()
+}
+
+/// Lists the tiles of the board that are still up, from the lowest to the highest, such as "1 2 9".
+pub fn list_tiles(board: u16) -> String {
+    alive_pieces(board)
+        .iter()
+        .map(|tile| tile.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns true if a single dice can be rolled for the given board, for the variants that allow it.
+/// This is only possible once the 7 & higher pieces have been knocked down.
+pub fn can_roll_single(board: u16) -> bool {
+    board & SINGLE_DICE_PIECES == 0
+}
+
+/// Returns true if moving from the board to the moved board is valid for the given roll.
+/// A move is valid if it only knocks down alive pieces, which sum to the roll.
+pub fn is_valid_move(board: u16, moved_board: u16, roll: u8) -> bool {
+    // Pieces cannot be brought back up.
+    if moved_board & !board != 0 {
+        return false;
+    }
+
+    board_value(board & !moved_board) == roll
+}
+
+/// Returns every legal board that can be reached from the given board with the given roll.
+///
+/// The roll is the summed value of the dice, which must be between 1 & 12 (inclusive).
+/// If the roll is outside of that range, or there are no legal moves, then the returned vec is empty.
+pub fn enumerate_moves(board: u16, roll: u8) -> Vec<u16> {
+    if !(1..13).contains(&roll) {
+        return Vec::new();
+    }
+
+    // Counting up to the max value of a binary number with the same number of digits as alive pieces
+    // will iterate though every possible combination of the alive pieces, using the binary value of the counter.
+    let pieces = alive_pieces(board);
+    let combinations = 1u16 << pieces.len();
+
+    (1..combinations)
+        .filter(|combination| sum_combination(*combination, &pieces) == roll)
+        .map(|combination| knock_down(board, combination, &pieces))
+        .collect()
+}
+
+/// Returns the numeric value of each alive piece of the board, from the lowest to the highest.
+fn alive_pieces(board: u16) -> Vec<u8> {
+    (0..MAX_TILES)
+        .filter(|index| (board >> index) & 1 == 1)
+        .map(|index| index + 1)
+        .collect()
+}
+
+/// Sums up the value of the pieces picked by the combination.
+/// Each bit of the combination picks the alive piece at the same index.
+fn sum_combination(combination: u16, pieces: &[u8]) -> u8 {
+    pieces
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| (combination >> index) & 1 == 1)
+        .map(|(_, piece)| piece)
+        .sum()
+}
+
+/// Returns the board after the pieces picked by the combination have been knocked down.
+fn knock_down(board: u16, combination: u16, pieces: &[u8]) -> u16 {
+    pieces
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| (combination >> index) & 1 == 1)
+        .fold(board, |board, (_, piece)| board & !(1 << (piece - 1)))
+}
+
+/// Contains a dice combination.
+///
+/// Binary representation of the dice within the u8:
+///  000 | 0 | 000 | 0
+///  one | _ | two | _
+///
+/// A second dice of 0 means only a single dice was rolled.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DiceRoll(u8);
+
+impl DiceRoll {
+    pub fn new_single(one: u8) -> Self {
+        DiceRoll(one << 5)
+    }
+    pub fn new_dual(one: u8, two: u8) -> Self {
+        let one = one << 5;
+        let two = (two & 0b00000111) << 1;
+        DiceRoll(one | two)
+    }
+    /// Recreates the dice from their encoded value, as returned by [`DiceRoll::encoded`].
+    /// The dice may not be valid, which can be checked with [`DiceRoll::is_valid`].
+    pub fn from_encoded(encoded: u8) -> Self {
+        DiceRoll(encoded)
+    }
+    /// Returns the encoded value of the dice, which is how they are kept in files.
+    pub fn encoded(self) -> u8 {
+        self.0
+    }
+    /// Returns the summed value of the contained dice.
+    pub fn get_value(self) -> u8 {
+        let one = (self.0 & 0b11100000) >> 5;
+        let two = (self.0 & 0b00001110) >> 1;
+        one + two
+    }
+    /// Returns true if this DiceRoll is a valid roll. False otherwise.
+    pub fn is_valid(self) -> bool {
+        let value = self.get_value();
+        value > 0 && 13 > value
+    }
+}
+
+impl Display for DiceRoll {
+    /// Writes the encoded value of the dice, as the best moves files key each roll by it.
+    /// Use [`DiceRoll::get_value`] to show the summed value instead.
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+impl From<u8> for DiceRoll {
+    /// The given value must be between 1 & 12 (inclusive), otherwise this function will panic.
+    fn from(value: u8) -> Self {
+        match value {
+            1 => DiceRoll::new_single(1),
+            2 => DiceRoll::new_dual(1, 1),
+            3 => DiceRoll::new_dual(2, 1),
+            4 => DiceRoll::new_dual(3, 1),
+            5 => DiceRoll::new_dual(4, 1),
+            6 => DiceRoll::new_dual(5, 1),
+            7 => DiceRoll::new_dual(6, 1),
+            8 => DiceRoll::new_dual(6, 2),
+            9 => DiceRoll::new_dual(6, 3),
+            10 => DiceRoll::new_dual(6, 4),
+            11 => DiceRoll::new_dual(6, 5),
+            12 => DiceRoll::new_dual(6, 6),
+            val => panic!(
+                "A DiceRoll cannot be a smaller than 1 or larger than 12! Value found: {val}"
+            ),
+        }
+    }
+}
+
+static DUAL_ROLLS: OnceLock<[DiceRoll; 36]> = OnceLock::new();
+
+/// Returns every combination two dice can roll, where the order of the dice matters.
+pub fn get_rolls() -> &'static [DiceRoll; 36] {
+    DUAL_ROLLS.get_or_init(|| {
+        let mut dual_rolls = [DiceRoll(0); 36];
+
+        for one in 1..7u8 {
+            for two in 1..7u8 {
+                let index = ((one - 1) * 6) + two - 1;
+                dual_rolls[index as usize] = DiceRoll::new_dual(one, two);
+            }
+        }
+
+        dual_rolls
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sorts the moves, as the order they're enumerated in isn't part of what's being tested.
+    fn sorted_moves(board: u16, roll: u8) -> Vec<u16> {
+        let mut moves = enumerate_moves(board, roll);
+        moves.sort_unstable();
+        moves
+    }
+
+    #[test]
+    fn full_board_has_every_combination_summing_to_the_roll() {
+        let board = full_board(STANDARD_TILES);
+        // 7 can be knocked down as 7, 1 & 6, 2 & 5, 3 & 4, or 1 & 2 & 4.
+        let mut expected = vec![
+            board & !0b1000000,
+            board & !0b0100001,
+            board & !0b0010010,
+            board & !0b0001100,
+            board & !0b0001011,
+        ];
+        expected.sort_unstable();
+        assert_eq!(sorted_moves(board, 7), expected);
+    }
+
+    #[test]
+    fn shut_box_has_no_moves() {
+        for roll in 1..13 {
+            assert!(enumerate_moves(0, roll).is_empty(), "roll of {roll}");
+        }
+    }
+
+    #[test]
+    fn lowest_roll_knocks_down_only_the_2() {
+        let board = full_board(STANDARD_TILES);
+        // 2 can't be made from 1 & 1, as there's only one 1 piece.
+        assert_eq!(sorted_moves(board, 2), vec![board & !0b10]);
+    }
+
+    #[test]
+    fn highest_roll_knocks_down_every_combination_summing_to_12() {
+        let board = full_board(STANDARD_TILES);
+        let moves = sorted_moves(board, 12);
+        // 12 can be made from 12 combinations of the standard pieces.
+        assert_eq!(moves.len(), 12);
+        for moved in &moves {
+            assert!(is_valid_move(board, *moved, 12), "{moved:b}");
+        }
+        assert!(moves.contains(&(board & !0b100000100)));
+        assert!(moves.contains(&(board & !0b000011011)));
+    }
+
+    #[test]
+    fn roll_without_a_legal_move_has_no_moves() {
+        // Only the 1 & 2 pieces are alive, which can't make 4 or more.
+        assert!(enumerate_moves(0b11, 4).is_empty());
+        assert!(enumerate_moves(0b11, 12).is_empty());
+        // The 3 piece is down, while 1 & 2 can make 3.
+        assert_eq!(enumerate_moves(0b1011, 3), vec![0b1000]);
+        // A single piece can't be knocked down twice.
+        assert!(enumerate_moves(0b1, 2).is_empty());
+    }
+
+    #[test]
+    fn roll_can_knock_down_several_pieces() {
+        // 1, 2, 3, & 4 are alive, which only make 10 together.
+        assert_eq!(enumerate_moves(0b1111, 10), vec![0]);
+        // 6 can be knocked down as 2 & 4, or 1 & 2 & 3.
+        assert_eq!(sorted_moves(0b1111, 6), vec![0b0101, 0b1000]);
+    }
+
+    #[test]
+    fn rolls_outside_of_the_dice_have_no_moves() {
+        let board = full_board(MAX_TILES);
+        assert!(enumerate_moves(board, 0).is_empty());
+        assert!(enumerate_moves(board, 13).is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+//! Derives the randomness of every crate from a single master seed, so whole sessions can be reproduced.
+//!
+//! Each use of randomness takes a named stream, such as "server" or "simulation".
+//! The n-th stream taken with a name is always seeded the same for the same master seed,
+//! so a session replays bit-for-bit as long as it takes its streams in the same order.
+//!
+//! The master seed is read from [`SEED_VAR`], or picked at random if it isn't set.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use fastrand::Rng;
+
+/// The environment variable of the master seed, such as `SHUT_THE_BOX_SEED=42`.
+pub const SEED_VAR: &str = "SHUT_THE_BOX_SEED";
+
+/// The master seed, which is chosen the first time it's needed.
+static MASTER_SEED: OnceLock<u64> = OnceLock::new();
+/// The amount of streams taken with each name so far.
+static TAKEN: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// Returns the seed every stream is derived from.
+/// This is the value of [`SEED_VAR`] if it's a number, otherwise a random seed.
+pub fn master_seed() -> u64 {
+    *MASTER_SEED.get_or_init(|| {
+        std::env::var(SEED_VAR)
+            .ok()
+            .and_then(|seed| seed.trim().parse().ok())
+            .unwrap_or_else(|| fastrand::u64(..))
+    })
+}
+
+/// Returns the next random number generator of the named stream.
+pub fn stream(name: &str) -> Rng {
+    // A poisoned lock only means another thread panicked while counting its stream.
+    let mut taken = TAKEN.lock().unwrap_or_else(|e| e.into_inner());
+    let index = taken.entry(name.to_string()).or_default();
+    let seed = derive_seed(master_seed(), name, *index);
+    *index += 1;
+    Rng::with_seed(seed)
+}
+
+/// Derives the seed of the n-th stream with the given name from the master seed.
+pub fn derive_seed(master_seed: u64, name: &str, index: u64) -> u64 {
+    // FNV-1a, as the std hasher isn't guaranteed to be the same between releases.
+    let name = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    mix(master_seed ^ mix(name ^ mix(index)))
+}
+
+/// Scrambles the bits of the value, which is the finaliser of SplitMix64.
+fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
@@ -0,0 +1,297 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use compute::{ComputeCommand, ComputeProgress, FileLock, Policy, PolicyError, SimulationConfig};
+
+use crate::variant::Variant;
+
+/// How many seconds the user has to stop interacting for before the background simulation continues.
+const IDLE_DELAY: f64 = 5.;
+/// How often the best moves found in the background are merged into the loaded best moves.
+const MERGE_INTERVAL: Duration = Duration::from_secs(120);
+/// How long the files written by a checkpoint have to stop changing for before they are read.
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+/// The amount of games simulated between each merge of the simulating thread's results.
+/// This is kept small so a checkpoint includes most of the games simulated before it.
+const BATCH_GAMES: u32 = 1000;
+
+/// A simulation running in the background to improve a file of best moves.
+struct Running {
+    /// The file of best moves being improved.
+    policy_path: PathBuf,
+    variant: Variant,
+    control: Sender<ComputeCommand>,
+    progress: Receiver<ComputeProgress>,
+    /// How many games the simulation played each second during its last batch, once it's finished one.
+    games_per_second: Option<f64>,
+    paused: bool,
+    /// When the best moves found in the background were last merged, or when the simulation started.
+    merged_at: Instant,
+    /// When the last checkpoint was requested, if it hasn't been merged yet.
+    flushed_at: Option<SystemTime>,
+}
+
+/// The result of merging the best moves found in the background into the loaded best moves.
+pub struct Merged {
+    /// The amount of board & roll combinations whose best move was added or changed.
+    pub changed: usize,
+    /// The amount of games the loaded best moves now reflect, if the background moves replaced them.
+    pub games: Option<u64>,
+}
+
+/// Improves the loaded best moves on a single thread, only while the user is idle, so the gui stays responsive.
+/// The games simulated in the background are kept between restarts, so each run carries on from the last.
+#[derive(Default)]
+pub struct BackgroundCompute {
+    running: Option<Running>,
+    /// Simulations that were told to stop, which are still writing their results.
+    stopping: Vec<Receiver<ComputeProgress>>,
+    /// The time of the gui input the user last interacted at.
+    last_interaction: f64,
+}
+
+/// Returns the file the best moves found in the background are written to, next to the best moves they improve.
+fn checkpoint_file(policy_path: &Path) -> PathBuf {
+    policy_path.with_extension("background.yml")
+}
+
+/// Returns the file the weights of the background simulation are kept in, so it can be resumed.
+fn resume_file(policy_path: &Path) -> PathBuf {
+    policy_path.with_extension("weights.yml")
+}
+
+impl BackgroundCompute {
+    /// Starts, pauses, resumes, or stops the background simulation, merging its best moves once they're ready.
+    ///
+    /// The simulation only runs while it's enabled & the user is idle, & is paused while the gui is busy,
+    /// such as when a game is in progress.
+    /// The known amount of games simulated for the loaded best moves decides whether they are replaced
+    /// by the background moves, or only have their gaps filled in.
+    /// Returns the merge, if the loaded best moves were changed.
+    pub fn update(
+        &mut self,
+        context: &egui::Context,
+        enabled: bool,
+        busy: bool,
+        policy_path: &Path,
+        variant: Variant,
+        known_games: Option<u64>,
+    ) -> Result<Option<Merged>, String> {
+        let (interacted, now) = context.input(|input| {
+            let interacted = !input.events.is_empty() || input.pointer.is_moving();
+            (interacted, input.time)
+        });
+        if interacted {
+            self.last_interaction = now;
+        }
+        let idle_for = now - self.last_interaction;
+
+        // Forgets the stopped simulations once they have finished writing.
+        self.stopping
+            .retain(|progress| poll_progress(progress, &mut None) == Some(false));
+
+        let wanted = enabled && variant.can_simulate();
+        if let Some(running) = &self.running {
+            if !wanted || running.policy_path != policy_path || running.variant != variant {
+                self.stop();
+            }
+        }
+        if !wanted {
+            return Ok(None);
+        }
+
+        let idle = idle_for >= IDLE_DELAY && !busy;
+        // A new simulation can't start until the last one has saved its weights, as it would resume from them.
+        if self.running.is_none() && idle && self.stopping.is_empty() {
+            self.start(policy_path, variant);
+        }
+        let Some(running) = &mut self.running else {
+            // Checks again once the user might be idle.
+            context.request_repaint_after(Duration::from_secs_f64(IDLE_DELAY));
+            return Ok(None);
+        };
+
+        // The simulation disconnecting without finishing means it failed.
+        if poll_progress(&running.progress, &mut running.games_per_second).is_none() {
+            self.running = None;
+            return Err(String::from(
+                "The background simulation failed, so it was stopped.",
+            ));
+        }
+
+        if idle == running.paused {
+            let command = match idle {
+                true => ComputeCommand::Resume,
+                false => ComputeCommand::Pause,
+            };
+            // The simulation has finished if it can't be sent commands.
+            let _ = running.control.send(command);
+            running.paused = !idle;
+        }
+        if running.paused {
+            context.request_repaint_after(Duration::from_secs_f64(IDLE_DELAY));
+            return Ok(None);
+        }
+
+        match running.flushed_at {
+            None if running.merged_at.elapsed() >= MERGE_INTERVAL => {
+                let _ = running.control.send(ComputeCommand::FlushCheckpoint);
+                running.flushed_at = Some(SystemTime::now());
+                context.request_repaint_after(SETTLE_TIME);
+                Ok(None)
+            }
+            None => {
+                context.request_repaint_after(MERGE_INTERVAL - running.merged_at.elapsed());
+                Ok(None)
+            }
+            Some(flushed_at) => {
+                context.request_repaint_after(SETTLE_TIME);
+                if !is_settled(&resume_file(&running.policy_path), flushed_at) {
+                    return Ok(None);
+                }
+
+                running.flushed_at = None;
+                running.merged_at = Instant::now();
+                merge(&running.policy_path, known_games).map(Some)
+            }
+        }
+    }
+
+    /// Returns a short description of what the background simulation is doing, if it's running.
+    pub fn status(&self) -> Option<String> {
+        self.running
+            .as_ref()
+            .map(|running| match (running.paused, running.games_per_second) {
+                (true, _) => String::from("Background: paused"),
+                (false, None) => String::from("Background: improving the best moves"),
+                (false, Some(games_per_second)) => {
+                    format!("Background: improving the best moves ({games_per_second:.0} games/s)")
+                }
+            })
+    }
+
+    /// Starts simulating games in the background for the best moves in the given file.
+    fn start(&mut self, policy_path: &Path, variant: Variant) {
+        let (tx, rx) = mpsc::channel();
+        let (control_tx, control_rx) = mpsc::channel();
+
+        // The simulation is stopped rather than finished, so it can play as many games as it wants.
+        let mut config = SimulationConfig::new(1, u32::MAX);
+        config.ruleset = variant;
+        config.checkpoint_games = BATCH_GAMES;
+        config.output = checkpoint_file(policy_path);
+        config.checkpoint_output = checkpoint_file(policy_path);
+        config.resume_file = Some(resume_file(policy_path));
+        // Only the best moves are wanted, so the other outputs are skipped or kept out of the way.
+        config.histogram_games = 0;
+        config.opening_output = std::env::temp_dir().join(compute::config::DEFAULT_OPENING_OUTPUT);
+
+        thread::spawn(move || {
+            // The channel disconnecting without finishing tells the gui the simulation failed.
+            if let Err(e) = compute::compute_controlled(config, tx, control_rx) {
+                tracing::error!("The background simulation failed: {e}");
+            }
+        });
+
+        self.running = Some(Running {
+            policy_path: policy_path.to_path_buf(),
+            variant,
+            control: control_tx,
+            progress: rx,
+            games_per_second: None,
+            paused: false,
+            merged_at: Instant::now(),
+            flushed_at: None,
+        });
+    }
+
+    /// Stops the background simulation, which saves its weights so it can be resumed later.
+    fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            let _ = running.control.send(ComputeCommand::Stop);
+            self.stopping.push(running.progress);
+        }
+    }
+}
+
+/// Reads the progress the simulation has sent since it was last polled, keeping the throughput of its last batch.
+/// Returns whether the simulation has finished, or None if it stopped sending progress without finishing.
+fn poll_progress(
+    progress: &Receiver<ComputeProgress>,
+    games_per_second: &mut Option<f64>,
+) -> Option<bool> {
+    let mut finished = false;
+    loop {
+        match progress.try_recv() {
+            Ok(ComputeProgress::Throughput {
+                games_per_second: throughput,
+                ..
+            }) => *games_per_second = Some(throughput),
+            Ok(ComputeProgress::Finished) => finished = true,
+            Ok(_) => {}
+            Err(TryRecvError::Empty) => return Some(finished),
+            Err(TryRecvError::Disconnected) => return finished.then_some(true),
+        }
+    }
+}
+
+/// Returns true if the file was changed after the given time & hasn't changed for a while since.
+fn is_settled(path: &Path, after: SystemTime) -> bool {
+    let Ok(modified) = std::fs::metadata(path).and_then(|metadata| metadata.modified()) else {
+        return false;
+    };
+
+    modified >= after
+        && modified
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed >= SETTLE_TIME)
+}
+
+/// Merges the best moves found in the background into the best moves in the given file, writing them back.
+/// The background moves replace the loaded ones once they're from at least as many games,
+/// otherwise they only fill in the board & roll combinations the loaded moves are missing.
+/// If it isn't known how many games the loaded moves are from, then they are never replaced.
+fn merge(policy_path: &Path, known_games: Option<u64>) -> Result<Merged, String> {
+    let background = Policy::load(checkpoint_file(policy_path))
+        .map_err(|e| format!("Couldn't read the best moves found in the background: {e}"))?;
+    let background_games = compute::resumed_games(&resume_file(policy_path)).unwrap_or_default();
+
+    // The file is locked until the merged moves are written, so nothing else rebuilds it in between.
+    let _lock = FileLock::exclusive(policy_path)
+        .map_err(|e| format!("Couldn't lock the best moves to merge into: {e}"))?;
+    // The loaded best moves may not exist yet, in which case every background move is used.
+    // They're read without taking the lock again, as it's already held.
+    let mut moves = File::open(policy_path)
+        .map_err(PolicyError::from)
+        .and_then(|file| Policy::from_reader(BufReader::new(file)))
+        .map(|policy| policy.moves().clone())
+        .unwrap_or_default();
+    let replace = known_games.is_some_and(|games| background_games >= games);
+
+    let mut changed = 0;
+    for (board_roll, best) in background.moves() {
+        let existing = moves.get(board_roll);
+        if existing == Some(best) || (existing.is_some() && !replace) {
+            continue;
+        }
+        moves.insert(*board_roll, *best);
+        changed += 1;
+    }
+
+    if changed > 0 {
+        // The file is replaced atomically, so the policy is never loaded half written.
+        let contents = compute::codec::to_string(&Policy::new(moves))
+            .map_err(|e| format!("Couldn't write the improved best moves: {e}"))?;
+        compute::write_atomically(policy_path, contents.as_bytes())
+            .map_err(|e| format!("Couldn't write the improved best moves: {e}"))?;
+    }
+
+    Ok(Merged {
+        changed,
+        games: replace.then_some(background_games),
+    })
+}
@@ -0,0 +1,93 @@
+use egui::{ScrollArea, SidePanel};
+use serde::{Deserialize, Serialize};
+
+use crate::variant::Variant;
+use crate::Main;
+
+/// The key the bookmarks are persisted under.
+pub const BOOKMARKS_KEY: &str = "bookmarks";
+
+// The id for the bookmarks panel.
+const BOOKMARKS_PANEL: &str = "Bookmarks";
+
+/// A board the user wants to come back to later.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bookmark {
+    pub board: u16,
+    /// The variant the board is from, as the same board can be in multiple variants.
+    pub variant: Variant,
+}
+
+/// The boards the user has bookmarked, in the order they were added.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Returns true if the given board is bookmarked.
+    pub fn contains(&self, bookmark: Bookmark) -> bool {
+        self.bookmarks.contains(&bookmark)
+    }
+
+    /// Adds the given board if it isn't bookmarked, otherwise it's removed.
+    pub fn toggle(&mut self, bookmark: Bookmark) {
+        match self
+            .bookmarks
+            .iter()
+            .position(|existing| *existing == bookmark)
+        {
+            Some(index) => {
+                self.bookmarks.remove(index);
+            }
+            None => self.bookmarks.push(bookmark),
+        }
+    }
+
+    /// Draws the side panel listing every bookmark, if there are any.
+    /// Returns the bookmark that was clicked on, if any.
+    pub fn side_panel(&mut self, context: &egui::Context) -> Option<Bookmark> {
+        // The panel would only take up space without any bookmarks.
+        if self.bookmarks.is_empty() {
+            return None;
+        }
+
+        let mut clicked = None;
+        let mut removed = None;
+
+        SidePanel::left(BOOKMARKS_PANEL)
+            .resizable(true)
+            .show(context, |ui| {
+                ui.heading(BOOKMARKS_PANEL);
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for (index, bookmark) in self.bookmarks.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("x").on_hover_text("Remove").clicked() {
+                                    removed = Some(index);
+                                }
+
+                                let name = format!(
+                                    "{} ({})",
+                                    Main::board_name(bookmark.board),
+                                    bookmark.variant.name()
+                                );
+                                if ui.selectable_label(false, name).clicked() {
+                                    clicked = Some(*bookmark);
+                                }
+                            });
+                        }
+                    });
+            });
+
+        if let Some(index) = removed {
+            self.bookmarks.remove(index);
+        }
+
+        clicked
+    }
+}
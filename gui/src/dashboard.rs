@@ -0,0 +1,203 @@
+use egui::{RichText, Window};
+
+use stats::{GameMode, GameRecord, OpponentSummary, Outcome, Stats, Summary};
+
+use crate::profiles::format_date;
+
+// The id for the stats dashboard window.
+const DASHBOARD: &str = "Stats";
+/// The amount of recent games listed in the dashboard.
+const RECENT_GAMES: usize = 20;
+
+/// What the dashboard shows, which is read from the database whenever it changes.
+struct View {
+    summary: Summary,
+    recent: Vec<GameRecord>,
+    opponents: Vec<OpponentSummary>,
+}
+
+/// Records every finished game to the stats database & shows the totals of them.
+#[derive(Default)]
+pub struct Dashboard {
+    /// Whether the dashboard window is open.
+    pub window_open: bool,
+    /// The database the games are recorded to, or None if it couldn't be opened.
+    stats: Option<Stats>,
+    /// The mode the totals are shown for, or None to show every game.
+    mode: Option<GameMode>,
+    /// The stats being shown, or None if they need to be read again.
+    view: Option<View>,
+}
+
+impl Dashboard {
+    /// Opens the stats database, creating it if this is the first run.
+    /// If it can't be opened, then the games aren't recorded.
+    pub fn load(&mut self) -> Result<(), String> {
+        let stats = Stats::open_default().map_err(|e| format!("Couldn't open the stats: {e}"))?;
+        self.stats = Some(stats);
+        self.view = None;
+        Ok(())
+    }
+
+    /// Adds the finished game to the stats.
+    pub fn record(&mut self, game: &GameRecord) -> Result<(), String> {
+        let Some(stats) = &mut self.stats else {
+            return Ok(());
+        };
+
+        self.view = None;
+        stats
+            .record(game)
+            .map_err(|e| format!("Couldn't record the game: {e}"))
+    }
+
+    /// Reads the stats being shown from the database.
+    fn read_view(&self) -> Result<Option<View>, stats::StatsError> {
+        let Some(stats) = &self.stats else {
+            return Ok(None);
+        };
+
+        Ok(Some(View {
+            summary: stats.summary(self.mode)?,
+            recent: stats.recent(self.mode, RECENT_GAMES)?,
+            opponents: stats.opponents()?,
+        }))
+    }
+
+    /// Draws the dashboard window with the totals & most recent games.
+    /// Returns the problem reading the stats, if there was one.
+    pub fn window(&mut self, context: &egui::Context) -> Option<String> {
+        if !self.window_open {
+            return None;
+        }
+
+        let mut error = None;
+        if self.view.is_none() {
+            match self.read_view() {
+                Ok(view) => self.view = view,
+                Err(e) => {
+                    error = Some(format!("Couldn't read the stats: {e}"));
+                    self.window_open = false;
+                }
+            }
+        }
+
+        let mut open = self.window_open;
+        let mut mode = self.mode;
+        Window::new(DASHBOARD).open(&mut open).show(context, |ui| {
+            let Some(view) = &self.view else {
+                ui.label("The stats couldn't be opened, so no games are being recorded.");
+                return;
+            };
+            if let Some(path) = Stats::path() {
+                ui.label(format!("Kept in {}", path.display()));
+            }
+
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut mode, None, "All games");
+                for game_mode in GameMode::ALL {
+                    ui.selectable_value(&mut mode, Some(game_mode), mode_name(game_mode));
+                }
+            });
+            ui.add_space(10.);
+
+            show_summary(ui, &view.summary);
+            ui.add_space(10.);
+
+            ui.label(RichText::new("Recent games").strong());
+            if view.recent.is_empty() {
+                ui.label("No games have been played yet.");
+            }
+            egui::Grid::new("Recent games")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    for game in &view.recent {
+                        ui.label(format_date(game.played_at));
+                        ui.label(game.opponents.join(", "));
+                        ui.label(match game.outcome {
+                            Outcome::Won => "Won",
+                            Outcome::Drew => "Drew",
+                            Outcome::Lost => "Lost",
+                        });
+                        ui.label(format!("Score: {}", game.score));
+                        ui.label(game.accuracy.map_or(String::from("-"), |accuracy| {
+                            format!("{:.0}% accurate", accuracy * 100.)
+                        }));
+                        ui.end_row();
+                    }
+                });
+
+            if !view.opponents.is_empty() {
+                ui.add_space(10.);
+                ui.label(RichText::new("Opponents").strong());
+                egui::Grid::new("Opponents")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for opponent in &view.opponents {
+                            ui.label(&opponent.name);
+                            ui.label(format!(
+                                "{} games, {} wins, {} losses",
+                                opponent.games, opponent.wins, opponent.losses
+                            ));
+                            ui.end_row();
+                        }
+                    });
+            }
+        });
+
+        self.window_open &= open;
+        if mode != self.mode {
+            self.mode = mode;
+            self.view = None;
+        }
+        error
+    }
+}
+
+/// Returns the human readable name of the mode.
+fn mode_name(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::Local => "Against the AI",
+        GameMode::Networked => "Networked",
+    }
+}
+
+/// Draws the totals of the games.
+fn show_summary(ui: &mut egui::Ui, summary: &Summary) {
+    egui::Grid::new("Summary").show(ui, |ui| {
+        ui.label("Games");
+        ui.label(summary.games.to_string());
+        ui.end_row();
+
+        ui.label("Wins");
+        ui.label(format!(
+            "{} ({} draws, {} losses)",
+            summary.wins, summary.draws, summary.losses
+        ));
+        ui.end_row();
+
+        ui.label("Average score");
+        ui.label(
+            summary
+                .average_score
+                .map_or(String::from("-"), |score| format!("{score:.1}")),
+        );
+        ui.end_row();
+
+        ui.label("Average accuracy");
+        ui.label(
+            summary
+                .average_accuracy
+                .map_or(String::from("-"), |accuracy| {
+                    format!("{:.1}%", accuracy * 100.)
+                }),
+        );
+        ui.end_row();
+
+        ui.label("Shut boxes");
+        ui.label(summary.shut_boxes.to_string());
+        ui.end_row();
+    });
+}
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use egui::{ColorImage, Rect};
+
+/// Crops the screenshot to the given area, in points.
+pub fn crop(screenshot: &ColorImage, area: Rect, pixels_per_point: f32) -> ColorImage {
+    screenshot.region(&area, Some(pixels_per_point))
+}
+
+/// Asks the user where to save the image, then saves it there as a png.
+/// Returns the path the image was saved to, or None if the user cancelled.
+pub fn save_png(image: &ColorImage) -> Result<Option<PathBuf>, String> {
+    let picked = rfd::FileDialog::new()
+        .add_filter("PNG image", &["png"])
+        .set_file_name("board.png")
+        .save_file();
+
+    let Some(path) = picked else {
+        return Ok(None);
+    };
+
+    let [width, height] = image.size;
+    image::save_buffer(
+        &path,
+        image.as_raw(),
+        width as u32,
+        height as u32,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|e| format!("Couldn't save image: {e}"))?;
+
+    Ok(Some(path))
+}
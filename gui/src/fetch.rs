@@ -0,0 +1,50 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/// The contents of a fetched file, which is None if the server doesn't have it, or why it couldn't be fetched.
+pub type Fetched = Result<Option<Vec<u8>>, String>;
+
+/// Fetches a file over http, which is how the web app loads the best moves.
+/// The files are fetched relative to the page, so they're served next to it, such as "best_moves.yml".
+#[derive(Default)]
+pub struct Fetch {
+    /// Receives the contents of the file once it has been fetched.
+    /// This is None if nothing is being fetched.
+    receiver: Option<Receiver<Fetched>>,
+}
+
+impl Fetch {
+    /// Starts fetching the given url, replacing the file being fetched, if there is one.
+    pub fn start(&mut self, url: &str) {
+        let (tx, rx) = mpsc::channel();
+
+        ehttp::fetch(ehttp::Request::get(url), move |response| {
+            let fetched = match response {
+                Ok(response) if response.ok => Ok(Some(response.bytes)),
+                // The files are optional, so they only have to exist once they're needed.
+                Ok(response) if response.status == 404 => Ok(None),
+                Ok(response) => Err(format!("{} {}", response.status, response.status_text)),
+                Err(e) => Err(e),
+            };
+            // The fetch may have been replaced, in which case this file isn't needed anymore.
+            let _ = tx.send(fetched);
+        });
+        self.receiver = Some(rx);
+    }
+
+    /// Returns the fetched file, if it has finished being fetched.
+    pub fn poll(&mut self) -> Option<Fetched> {
+        let fetched = match self.receiver.as_ref()?.try_recv() {
+            Ok(fetched) => fetched,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(String::from("The request was dropped")),
+        };
+
+        self.receiver = None;
+        Some(fetched)
+    }
+
+    /// Returns true if a file is being fetched.
+    pub fn is_pending(&self) -> bool {
+        self.receiver.is_some()
+    }
+}
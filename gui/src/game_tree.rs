@@ -0,0 +1,96 @@
+use egui::text::LayoutJob;
+use egui::{CollapsingHeader, TextFormat, Ui};
+
+use compute::{GameNode, GameState, ShutChances};
+
+use crate::settings::BoardColors;
+use crate::Main;
+
+/// Explores the game tree of the root board, showing the exact chance of shutting the box at each node.
+#[derive(Default)]
+pub struct TreeExplorer {
+    /// The tree of the root board, which keeps the states that have been expanded.
+    tree: Option<GameNode>,
+}
+
+impl TreeExplorer {
+    /// Draws the game tree of the given board, where each node can be expanded to show the nodes that follow it.
+    pub fn show(&mut self, ui: &mut Ui, board: u16, chances: &ShutChances, colors: &BoardColors) {
+        let tiles = chances.tiles();
+
+        // The expanded states are only kept while the root board & dice stay the same.
+        let state = GameState::new(board);
+        let tree = match &mut self.tree {
+            Some(tree) if tree.state == state && tree.dice() == chances.dice() => tree,
+            tree => tree.insert(GameNode::new(state, *chances.dice())),
+        };
+
+        tree_children(ui, tree, state, chances, tiles, colors);
+    }
+}
+
+/// Draws the children of the state, each of which can be expanded to show their own children.
+/// The children of a board are its rolls, & the children of a roll are its moves.
+fn tree_children(
+    ui: &mut Ui,
+    tree: &mut GameNode,
+    parent: GameState,
+    chances: &ShutChances,
+    tiles: u8,
+    colors: &BoardColors,
+) {
+    let dice = *chances.dice();
+    let best_move = parent
+        .roll()
+        .and_then(|roll| chances.best_move(parent.board(), roll.get_value()));
+
+    let children = tree.expand(parent).to_vec();
+    if children.is_empty() {
+        ui.label("There are no moves, so the game ends.");
+        return;
+    }
+
+    for child in children {
+        let board = child.board();
+        let mut header = match (parent.roll(), child.roll()) {
+            // The moves are coloured by the pieces they knock down.
+            (Some(roll), _) => {
+                Main::generate_board(parent.board(), roll.get_value(), board, tiles, colors)
+            }
+            (None, Some(roll)) => {
+                let mut header = LayoutJob::default();
+                let probability = dice.probability(roll.get_value()) as f64;
+                header.append(
+                    &format!("Roll {} ({})", roll.get_value(), format_chance(probability)),
+                    0.,
+                    TextFormat::default(),
+                );
+                header
+            }
+            (None, None) => LayoutJob::default(),
+        };
+
+        let mut summary = format!("  {} to shut", format_chance(chances.state(child)));
+        if best_move == Some(board) && parent.roll().is_some() {
+            summary.push_str(" (best)");
+        }
+        header.append(&summary, 0., TextFormat::default());
+
+        // A shut box has nothing to expand.
+        if board == 0 && child.roll().is_none() {
+            ui.label(header);
+            continue;
+        }
+
+        CollapsingHeader::new(header)
+            .id_source(child)
+            .show(ui, |ui| {
+                tree_children(ui, tree, child, chances, tiles, colors)
+            });
+    }
+}
+
+/// Formats the chance, which is between 0 & 1, as a percentage.
+pub fn format_chance(chance: f64) -> String {
+    format!("{:.2}%", chance * 100.)
+}
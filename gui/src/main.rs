@@ -1,35 +1,348 @@
+// The web app leaves out computing the best moves & everything else that needs files, threads, or sockets.
+// Their state is still kept, so the desktop & web apps can share the same code.
+#![cfg_attr(
+    any(target_arch = "wasm32", not(feature = "network")),
+    allow(dead_code)
+)]
+
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
-use std::io::BufReader;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{BufReader, ErrorKind};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
+#[cfg(not(target_arch = "wasm32"))]
 use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::mpsc::Receiver;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::TryRecvError;
 use std::thread;
+use std::time::{Duration, Instant};
 
 use eframe::egui;
 use eframe::epaint::Color32;
 use egui::ahash::HashMap;
 use egui::text::LayoutJob;
-use egui::{FontId, Id, Rect, RichText, TextFormat, Ui, Vec2, Window};
-
-use compute::BoardRoll;
+#[cfg(not(target_arch = "wasm32"))]
+use egui::{Event, ViewportCommand};
+use egui::{
+    FontId, Id, Key, Label, Modifiers, Rect, RichText, Sense, TextFormat, Ui, WidgetInfo,
+    WidgetType, Window,
+};
+
+use egui_extras::{Column, TableBuilder};
+use serde::{Deserialize, Serialize};
+
+use app_config::{Config, Theme};
+use compute::{
+    BoardRoll, ComputeProgress, DiceDistribution, Policy, PolicyFormat, RankedMove, Scoring,
+    ShutChances, StrategySpec, GOLF_HOLES,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use compute::{PolicyError, SimulationConfig};
+use game_core::board_value;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use tracing::info;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::background::BackgroundCompute;
+use crate::bookmarks::{Bookmark, Bookmarks, BOOKMARKS_KEY};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::dashboard::Dashboard;
+#[cfg(target_arch = "wasm32")]
+use crate::fetch::Fetch;
+use crate::game_tree::{format_chance, TreeExplorer};
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use crate::network::NetworkState;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use crate::play::SaveRequest;
+use crate::play::{tiles_layout, PlayState};
+use crate::playback::Playback;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::profiles::Profiles;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use crate::replay::ReplayViewer;
+use crate::settings::{BoardColors, Settings, SETTINGS_KEY};
+use crate::sound::Audio;
+use crate::telemetry::{Telemetry, Usage, TELEMETRY_KEY};
+use crate::toasts::Toasts;
+use crate::trainer::TrainerState;
+use crate::variant::{Variant, VariantFiles, TILE_COUNTS, VARIANT_KEY};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::watcher::PolicyWatcher;
+
+// The web app only explores, plays against the AI, & trains, as the rest needs files, threads, or sockets.
+#[cfg(not(target_arch = "wasm32"))]
+mod background;
+mod bookmarks;
+#[cfg(not(target_arch = "wasm32"))]
+mod dashboard;
+#[cfg(not(target_arch = "wasm32"))]
+mod export;
+#[cfg(target_arch = "wasm32")]
+mod fetch;
+mod game_tree;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+mod network;
+mod play;
+mod playback;
+#[cfg(not(target_arch = "wasm32"))]
+mod profiles;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+mod replay;
+mod settings;
+mod sound;
+mod telemetry;
+mod toasts;
+mod trainer;
+mod variant;
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher;
 
 // The id's for the panels.
-const WINDOW_NAME: &'static str = "Shut The Box";
-const TOP_PANEL: &'static str = "Top Panel";
-const RECALCULATE: &'static str = "Recalculate";
-const ROLL_BOARD_TABLE: &'static str = "Roll Board Table";
+const WINDOW_NAME: &str = "Shut The Box";
+const TOP_PANEL: &str = "Top Panel";
+const RECALCULATE: &str = "Recalculate";
+const BOARD_VIEW: &str = "Board View";
+const GETTING_STARTED: &str = "Getting Started";
+const FINAL_SCORE: &str = "Final Score";
+const STATUS_BAR: &str = "Status Bar";
+/// The canvas the web app is drawn on, within "index.html".
+#[cfg(target_arch = "wasm32")]
+const CANVAS_ID: &str = "shut_the_box";
+
+// The keys for the persisted values.
+const POLICY_PATH_KEY: &str = "policy_path";
+const ROOT_BOARD_KEY: &str = "root_board";
+const HISTORY_KEY: &str = "history";
+const MODE_KEY: &str = "mode";
+const MOVES_VIEW_KEY: &str = "moves_view";
+
+/// How often the messages from the server are checked.
+const NETWORK_POLL: Duration = Duration::from_millis(100);
+/// How often the best moves are loaded again while something else is rebuilding them.
+const REBUILD_RETRY: Duration = Duration::from_millis(500);
+/// How often the fetched best moves are checked for, while they're being fetched by the web app.
+#[cfg(target_arch = "wasm32")]
+const FETCH_POLL: Duration = Duration::from_millis(100);
+
+/// The amount of games each thread simulates for the quick & thorough presets of the first run.
+const QUICK_GAMES: u32 = 10000;
+const THOROUGH_GAMES: u32 = 1000000;
+
+/// The amount of games each thread simulates to estimate how long a recalculation will take.
+const CALIBRATION_GAMES: u32 = 5000;
+
+/// The most moves listed when hovering over the best move of a roll.
+const HOVER_ALTERNATIVES: usize = 4;
+
+/// What the central panel is displaying.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Browsing the best move for each roll of a board.
+    Explore,
+    /// Playing a game against the AI.
+    Play,
+    /// Playing a game against other people over the network.
+    Online,
+    /// Answering what move should be made for random situations.
+    Trainer,
+    /// Stepping through a game that was recorded by the server.
+    Replay,
+}
+
+impl From<Mode> for Usage {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Explore => Usage::Explore,
+            Mode::Play => Usage::Play,
+            Mode::Online => Usage::Online,
+            Mode::Trainer => Usage::Trainer,
+            Mode::Replay => Usage::Replay,
+        }
+    }
+}
+
+/// How long a short burst of games took to simulate, which recalculations are estimated from.
+#[derive(Debug, Clone, Copy)]
+struct Calibration {
+    /// The amount of threads the games were simulated on.
+    threads: u8,
+    /// The amount of tiles the games were played with.
+    tiles: u8,
+    /// How long it took for each thread to simulate [`CALIBRATION_GAMES`] games.
+    duration: Duration,
+}
+
+/// What the ongoing recalculation has reported, which is shown as its progress.
+#[derive(Debug)]
+struct RecalculationProgress {
+    receiver: Receiver<ComputeProgress>,
+    /// The amount of games simulated so far & the amount being simulated, once the first batch has finished.
+    games: Option<(u64, u64)>,
+    /// How many games each thread simulated per second during its last batch, indexed by the thread.
+    throughput: Vec<f64>,
+    /// What the recalculation is doing once every game has been simulated.
+    phase: Option<ComputeProgress>,
+}
+
+impl RecalculationProgress {
+    fn new(receiver: Receiver<ComputeProgress>) -> RecalculationProgress {
+        RecalculationProgress {
+            receiver,
+            games: None,
+            throughput: Vec::new(),
+            phase: None,
+        }
+    }
+
+    /// Records the progress the recalculation has sent since it was last polled.
+    fn poll(&mut self) {
+        while let Ok(progress) = self.receiver.try_recv() {
+            match progress {
+                ComputeProgress::Games { completed, total } => {
+                    self.games = Some((completed, total))
+                }
+                ComputeProgress::Throughput {
+                    thread,
+                    games_per_second,
+                } => {
+                    let thread = thread as usize;
+                    if self.throughput.len() <= thread {
+                        self.throughput.resize(thread + 1, 0.);
+                    }
+                    self.throughput[thread] = games_per_second;
+                }
+                phase => self.phase = Some(phase),
+            }
+        }
+    }
+
+    /// Shows how many of the games have been simulated, along with how long the rest should take.
+    fn show(&self, ui: &mut Ui, started: Instant) {
+        match self.phase {
+            Some(ComputeProgress::Merging) => {
+                ui.label("Merging the results of every thread...");
+                return;
+            }
+            Some(ComputeProgress::Writing | ComputeProgress::Finished) => {
+                ui.label("Writing the best moves...");
+                return;
+            }
+            _ => {}
+        }
+        let Some((completed, total)) = self.games else {
+            ui.label("Simulating the first games...");
+            return;
+        };
+
+        ui.add(
+            egui::ProgressBar::new(completed as f32 / total.max(1) as f32)
+                .text(format!("{completed} / {total} games")),
+        );
+        // The rest of the games are expected to be simulated as quickly as the games so far were.
+        if completed > 0 {
+            let remaining = started
+                .elapsed()
+                .mul_f64(total.saturating_sub(completed) as f64 / completed as f64);
+            ui.label(format!("About {} left", format_duration(remaining)));
+        }
+        let games_per_second: f64 = self.throughput.iter().sum();
+        ui.label(format!(
+            "{games_per_second:.0} games/s over {} threads",
+            self.throughput.len()
+        ));
+    }
+}
+
+/// How the moves for each roll of the root board are displayed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum MovesView {
+    /// A board for each roll, coloured by the pieces that are flipped.
+    Boards,
+    /// A table of the chance & best moves for each roll.
+    Table,
+    /// The game tree, with the exact chance of shutting the box at each node.
+    Tree,
+}
+
+/// What happens when a displayed board is clicked on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoardClick {
+    /// Moves back to the previous root board.
+    Back,
+    /// Moves to the best move for the roll.
+    Roll(u8),
+    /// Moves to the given board.
+    Move(u16),
+    /// Shows how the final score of the root board is made up, as the roll has no moves.
+    GameOver,
+}
 
 struct Main {
+    /// What the central panel is displaying.
+    mode: Mode,
+    /// The game against the AI.
+    play: PlayState,
+    /// The game against other people over the network.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    network: NetworkState,
+    /// The quiz of the best moves.
+    trainer: TrainerState,
+    /// The recorded game being replayed.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    replay: ReplayViewer,
+    /// Plays out the best moves from the root board.
+    playback: Playback,
+    /// The preferences of the user.
+    settings: Settings,
+    /// Whether the settings window is open.
+    settings_window_open: bool,
+    /// Plays the sound effects of the games.
+    audio: Audio,
+    /// The area the board view was last drawn in, which is the part of the screenshot that gets exported.
+    board_view_rect: Rect,
+    /// Whether a screenshot of the board view has been requested, but not received yet.
+    export_requested: bool,
+    /// The result of the last export of the board view.
+    export_message: Option<String>,
+    /// The problems that are shown to the user for a short while.
+    toasts: Toasts,
+    /// Counts how the gui is used, if the user opted in.
+    telemetry: Telemetry,
+    /// Records the finished games & shows their stats.
+    #[cfg(not(target_arch = "wasm32"))]
+    dashboard: Dashboard,
+
     // Vars to do with the recalculation window
     /// Whether the window to recalculate the best moves is open.
     recalculate_window_open: bool,
     /// Whether the best moves are being recalculated.
     recalculation_in_progress: bool,
-    /// Contains the receiver connected to the game recalculation.
-    recalculation_receiver: Option<Receiver<bool>>,
+    /// Contains the receiver connected to the game recalculation, which is sent why it failed, if it did.
+    recalculation_receiver: Option<Receiver<Result<(), String>>>,
     /// Whether there was an error recalculating.
     recalculation_error: bool,
-
+    /// When the ongoing recalculation was started.
+    recalculation_started: Option<Instant>,
+    /// What the ongoing recalculation has reported so far.
+    recalculation_progress: Option<RecalculationProgress>,
+    /// The file the ongoing recalculation writes to, along with the total amount of games it simulates.
+    /// This is added to the profiles once the recalculation finishes.
+    recalculation_profile: Option<(PathBuf, u64)>,
+    /// Whether the user closed the window explaining how to get the best moves.
+    getting_started_dismissed: bool,
+
+    /// The amount of threads the system has, which is the most the games can be simulated on.
+    available_threads: u8,
+    /// The amount of threads to simulate the games on.
+    threads: u8,
+    /// How long a short burst of games took, for the current amount of threads & tiles.
+    calibration: Option<Calibration>,
+    /// Contains the receiver connected to the ongoing calibration, if there is one.
+    calibration_receiver: Option<Receiver<Calibration>>,
     /// The amount of games to simulate.
     games_to_simulate: u32,
     /// The unvalidated amount of games to simulate.
@@ -38,339 +351,2116 @@ struct Main {
     could_parse_games: bool,
 
     // Vars to do with display the boards
+    /// The rules of the game the moves are for.
+    variant: Variant,
     /// The current board having its moves displayed.
     root_board: u16,
     /// Stores the previous root board that was displayed
     previous_boards: Vec<u16>,
+    /// Stores the root boards that were moved back from, so they can be redone.
+    next_boards: Vec<u16>,
+    /// How the moves for each roll are displayed.
+    moves_view: MovesView,
+    /// The game tree of the root board.
+    tree_explorer: TreeExplorer,
+    /// The exact chance of shutting the box from each board, which is solved once it is first shown.
+    /// This is solved again whenever the amount of tiles changes.
+    shut_chances: Option<ShutChances>,
+    /// The roll to show every move for, instead of only the best move.
+    pinned_roll: Option<u8>,
+    /// The tile numbers typed into the search box, to jump to the board with those pieces alive.
+    board_search: String,
+    /// Why the typed tile numbers aren't a valid board, if they aren't.
+    board_search_error: Option<String>,
+    /// The board whose final score is being broken down, after clicking on a dying roll.
+    score_breakdown: Option<u16>,
+    /// The boards the user wants to come back to later.
+    bookmarks: Bookmarks,
+    /// The file the best moves are loaded from.
+    policy_path: PathBuf,
+    /// The file the ranked moves are loaded from, if the config chose one rather than the variant's file.
+    /// This is forgotten once the variant is switched.
+    ranked_path: Option<PathBuf>,
+    /// The best moves the user has calculated or imported, kept in the platform's data folder.
+    #[cfg(not(target_arch = "wasm32"))]
+    profiles: Profiles,
+    /// Improves the loaded best moves while the gui is idle, if it's enabled in the settings.
+    #[cfg(not(target_arch = "wasm32"))]
+    background: BackgroundCompute,
     /// Stores the pre-calculated best moves from a simulation.
-    parsed_moves: Option<HashMap<BoardRoll, u16>>,
+    parsed_moves: Option<Policy>,
+    /// Whether the best moves couldn't be loaded as something else is rebuilding them.
+    /// They're loaded again until the rebuilt moves have been written.
+    policy_rebuilding: bool,
+    /// The format the best moves were written in, as legacy best moves are converted when loaded.
+    policy_format: PolicyFormat,
+    /// Stores every move for each board & roll, ranked by their value.
+    ranked_moves: Option<HashMap<BoardRoll, Vec<RankedMove>>>,
+    /// Watches the files the moves are loaded from, so changes made outside of the gui are loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    policy_watcher: PolicyWatcher,
+    /// Fetches the best moves from the server the web app was loaded from.
+    #[cfg(target_arch = "wasm32")]
+    policy_fetch: Fetch,
+    /// Fetches the ranked moves from the server the web app was loaded from.
+    #[cfg(target_arch = "wasm32")]
+    ranked_fetch: Fetch,
 }
 
 impl Default for Main {
     fn default() -> Self {
         Main {
+            mode: Mode::Explore,
+            play: PlayState::default(),
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            network: NetworkState::default(),
+            trainer: TrainerState::default(),
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            replay: ReplayViewer::default(),
+            playback: Playback::default(),
+            settings: Settings::default(),
+            settings_window_open: false,
+            audio: Audio::new(),
+            board_view_rect: Rect::NOTHING,
+            export_requested: false,
+            export_message: None,
+            toasts: Toasts::default(),
+            telemetry: Telemetry::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            dashboard: Dashboard::default(),
             recalculate_window_open: false,
             recalculation_in_progress: false,
             recalculation_receiver: None,
             recalculation_error: false,
+            recalculation_started: None,
+            recalculation_progress: None,
+            recalculation_profile: None,
+            getting_started_dismissed: false,
+            available_threads: available_threads(),
+            threads: available_threads(),
+            calibration: None,
+            calibration_receiver: None,
             games_to_simulate: 100000,
             unvalidated_games_to_simulate: String::from("100000"),
             could_parse_games: true,
+            variant: Variant::default(),
             root_board: 511,
             previous_boards: Vec::new(),
-            parsed_moves: parse_moves(),
+            next_boards: Vec::new(),
+            moves_view: MovesView::Boards,
+            tree_explorer: TreeExplorer::default(),
+            shut_chances: None,
+            pinned_roll: None,
+            board_search: String::new(),
+            board_search_error: None,
+            score_breakdown: None,
+            bookmarks: Bookmarks::default(),
+            policy_path: PathBuf::from(compute::config::DEFAULT_OUTPUT),
+            ranked_path: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiles: Profiles::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            background: BackgroundCompute::default(),
+            parsed_moves: None,
+            policy_rebuilding: false,
+            policy_format: PolicyFormat::Current,
+            ranked_moves: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            policy_watcher: PolicyWatcher::default(),
+            #[cfg(target_arch = "wasm32")]
+            policy_fetch: Fetch::default(),
+            #[cfg(target_arch = "wasm32")]
+            ranked_fetch: Fetch::default(),
+        }
+    }
+}
+
+impl Main {
+    /// Creates the app, loading the best moves from the last used file.
+    fn new(creation_context: &eframe::CreationContext) -> Self {
+        let mut main = Main::default();
+
+        if let Some(storage) = creation_context.storage {
+            if let Some(policy_path) = eframe::get_value(storage, POLICY_PATH_KEY) {
+                main.policy_path = policy_path;
+            }
+            if let Some(settings) = eframe::get_value(storage, SETTINGS_KEY) {
+                main.settings = settings;
+            }
+            if let Some(variant) = eframe::get_value(storage, VARIANT_KEY) {
+                main.variant = variant;
+            }
+            if let Some(bookmarks) = eframe::get_value(storage, BOOKMARKS_KEY) {
+                main.bookmarks = bookmarks;
+            }
+            if let Some(telemetry) = eframe::get_value(storage, TELEMETRY_KEY) {
+                main.telemetry = telemetry;
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match Profiles::load() {
+                Ok(profiles) => main.profiles = profiles,
+                Err(error) => main.toasts.error(error),
+            }
+            if let Err(error) = main.dashboard.load() {
+                main.toasts.error(error);
+            }
+        }
+
+        // The config is applied over the state from the last run, as it's only set on purpose.
+        match Config::load() {
+            Ok(config) => main.apply_config(config),
+            Err(error) => main.toasts.error(error.to_string()),
+        }
+
+        // The zoom is changed through the settings instead, so it's kept between restarts.
+        creation_context
+            .egui_ctx
+            .options_mut(|options| options.zoom_with_keyboard = false);
+        main.settings.apply(&creation_context.egui_ctx);
+        main.root_board = main.variant.full_board();
+        main.play.set_variant(main.variant);
+        #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+        {
+            main.play.has_saved_game = replay::has_saved_game();
+        }
+        main.trainer.set_variant(main.variant);
+
+        // Carries on from where the user left off, as long as the boards are still within the variant.
+        if let Some(storage) = creation_context.storage {
+            if let Some(mode) = eframe::get_value(storage, MODE_KEY) {
+                main.mode = mode;
+            }
+            if let Some(moves_view) = eframe::get_value(storage, MOVES_VIEW_KEY) {
+                main.moves_view = moves_view;
+            }
+
+            let full_board = main.variant.full_board();
+            let fits = |board: &u16| board & !full_board == 0;
+
+            let root_board = eframe::get_value::<u16>(storage, ROOT_BOARD_KEY).filter(fits);
+            if let Some(root_board) = root_board {
+                main.root_board = root_board;
+                if let Some((previous, next)) =
+                    eframe::get_value::<(Vec<u16>, Vec<u16>)>(storage, HISTORY_KEY)
+                {
+                    main.previous_boards = previous.into_iter().filter(fits).collect();
+                    main.next_boards = next.into_iter().filter(fits).collect();
+                }
+            }
+        }
+
+        main.reload_moves();
+        main
+    }
+
+    /// Loads the best moves & ranked moves from their files again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_moves(&mut self) {
+        self.policy_rebuilding = false;
+        match Policy::load_detected(&self.policy_path) {
+            Ok((policy, format)) => {
+                logging::crash::note(
+                    "policy",
+                    format!(
+                        "{} ({format} format, {} moves)",
+                        self.policy_path.display(),
+                        policy.len()
+                    ),
+                );
+                self.parsed_moves = Some(policy);
+                self.policy_format = format;
+            }
+            // The old moves are kept until the rebuilt ones have been written.
+            Err(PolicyError::Rebuilding) => self.policy_rebuilding = true,
+            Err(e) => {
+                self.toasts
+                    .error(format!("{e} ({})", self.policy_path.display()));
+                self.parsed_moves = None;
+            }
+        }
+        self.ranked_moves = parse_ranked_moves(&self.ranked_file(), &mut self.toasts);
+    }
+
+    /// Fetches the best moves & ranked moves again, as the web app can't read files.
+    /// They're loaded once they've been fetched, by [`Main::poll_fetches`].
+    #[cfg(target_arch = "wasm32")]
+    fn reload_moves(&mut self) {
+        self.policy_fetch.start(&self.policy_path.to_string_lossy());
+        self.ranked_fetch
+            .start(&self.ranked_file().to_string_lossy());
+    }
+
+    /// Loads the best moves & ranked moves that have finished being fetched.
+    #[cfg(target_arch = "wasm32")]
+    fn poll_fetches(&mut self) {
+        if let Some(fetched) = self.policy_fetch.poll() {
+            let loaded = fetched
+                .and_then(|bytes| bytes.ok_or_else(|| String::from("Couldn't find the best moves")))
+                .and_then(|bytes| Policy::decode_detected(&bytes).map_err(|e| e.to_string()));
+            match loaded {
+                Ok((policy, format)) => {
+                    self.parsed_moves = Some(policy);
+                    self.policy_format = format;
+                }
+                Err(e) => {
+                    self.toasts
+                        .error(format!("{e} ({})", self.policy_path.display()));
+                    self.parsed_moves = None;
+                }
+            }
+        }
+
+        if let Some(fetched) = self.ranked_fetch.poll() {
+            let path = self.ranked_file();
+            self.ranked_moves = match fetched {
+                Ok(Some(bytes)) => match compute::codec::from_slice(&bytes) {
+                    Ok(ranked_moves) => Some(ranked_moves),
+                    Err(e) => {
+                        self.toasts.error(format!(
+                            "Couldn't parse ranked moves: {e} ({})",
+                            path.display()
+                        ));
+                        None
+                    }
+                },
+                // The ranked moves are optional, so they only have to exist once they're needed.
+                Ok(None) => None,
+                Err(e) => {
+                    self.toasts.error(format!(
+                        "Couldn't fetch ranked moves: {e} ({})",
+                        path.display()
+                    ));
+                    None
+                }
+            };
+        }
+    }
+
+    /// Returns the file the ranked moves are loaded from.
+    fn ranked_file(&self) -> PathBuf {
+        self.ranked_path
+            .clone()
+            .unwrap_or_else(|| self.variant.ranked_file())
+    }
+
+    /// Applies the values set by the config, leaving the rest as they were.
+    fn apply_config(&mut self, config: Config) {
+        if let Some(theme) = config.theme {
+            self.settings.dark_mode = theme == Theme::Dark;
+        }
+
+        if config.ruleset.is_some() || config.tiles.is_some() || config.one_die.is_some() {
+            match config.ruleset(self.variant) {
+                Ok(variant) if TILE_COUNTS.contains(&variant.tiles) => self.set_variant(variant),
+                Ok(variant) => self.toasts.error(format!(
+                    "The config's {} tiles can't be played with, so the variant wasn't changed.",
+                    variant.tiles
+                )),
+                Err(e) => self
+                    .toasts
+                    .error(format!("{e}, so the variant wasn't changed.")),
+            }
+        }
+        // The files are set after the variant, as switching variants would replace them.
+        if let Some(policy_path) = config.policy_path {
+            self.policy_path = policy_path;
+        }
+        self.ranked_path = config.ranked_path;
+
+        if let Some(threads) = config.threads {
+            self.available_threads = self.available_threads.min(threads.max(1));
+            self.threads = self.threads.min(self.available_threads);
+        }
+        #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+        if let Some(port) = config.port {
+            self.network.set_port(port);
+        }
+        if let Some(bot_strategy) = config.bot_strategy {
+            match bot_strategy
+                .parse::<StrategySpec>()
+                .and_then(|spec| spec.load())
+            {
+                Ok(strategy) => self.play.set_external(Some(strategy)),
+                Err(e) => self.toasts.error(format!(
+                    "Couldn't load the bot strategy \"{bot_strategy}\": {e}"
+                )),
+            }
+        }
+        if let Some(bot_difficulty) = config.bot_difficulty {
+            match bot_difficulty.parse() {
+                Ok(accuracy) => self.play.accuracy = accuracy,
+                Err(e) => self.toasts.error(e.to_string()),
+            }
+        }
+    }
+
+    /// Switches to the given variant, loading its best moves & starting over from its full board.
+    fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.ranked_path = None;
+        // The default profile of the variant is preferred over the file in the working directory.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.policy_path = match self.profiles.default_for(variant) {
+                Some(profile) => profile.path.clone(),
+                None => variant.policy_file(),
+            };
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.policy_path = variant.policy_file();
+        }
+        self.reload_moves();
+
+        self.root_board = variant.full_board();
+        self.previous_boards.clear();
+        self.next_boards.clear();
+        self.pinned_roll = None;
+
+        self.play.set_variant(variant);
+        self.trainer.set_variant(variant);
+    }
+
+    /// Continues the saved game against the AI, switching to its variant if it's different.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    fn continue_game(&mut self) {
+        let saved = replay::take_saved_game();
+        self.play.has_saved_game = replay::has_saved_game();
+        let saved = match saved {
+            Ok(saved) => saved,
+            Err(error) => {
+                self.toasts.error(error);
+                return;
+            }
+        };
+
+        if saved.variant() != self.variant {
+            self.set_variant(saved.variant());
+        }
+        if let Err(error) = self.play.resume(saved) {
+            self.toasts.error(error);
+        }
+    }
+}
+
+/// Gets the amount of threads the system has.
+/// Defaults to 4.
+fn available_threads() -> u8 {
+    match thread::available_parallelism() {
+        Ok(number) => number.get().min(u8::MAX as usize) as u8,
+        Err(_) => 4,
+    }
+}
+
+/// Formats a duration as minutes & seconds, such as "2m 5s".
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 60 {
+        0 => format!("{seconds}s"),
+        minutes => format!("{minutes}m {}s", seconds % 60),
+    }
+}
+
+/// Describes the move from the root board to the moved board for screen readers, such as "Roll 7, flip tiles 3 and 4".
+fn describe_move(root_board: u16, roll: u8, move_board: u16) -> String {
+    let flipped = flipped_tiles(root_board, move_board);
+
+    match flipped.len() {
+        1 => format!("Roll {roll}, flip tile {}", flipped[0]),
+        _ => format!("Roll {roll}, flip tiles {}", flipped.join(" and ")),
+    }
+}
+
+/// Gets the tiles that are flipped to move from the root board to the moved board, from the lowest to highest.
+fn flipped_tiles(root_board: u16, move_board: u16) -> Vec<String> {
+    (0..game_core::MAX_TILES)
+        .filter(|index| (root_board & !move_board) >> index & 1 == 1)
+        .map(|index| (index + 1).to_string())
+        .collect()
+}
+
+/// Formats the win chance of a move, which is in tenths of a percent, or "?" if it isn't known.
+fn format_win_chance(value: Option<u16>) -> String {
+    value.map_or(String::from("?"), |value| {
+        format!("{:.1}%", value as f32 / 10.)
+    })
+}
+
+/// Parses the tile numbers of the alive pieces, separated by spaces or commas, into a board.
+/// Returns why the text isn't a valid board for the amount of tiles, if it isn't.
+fn parse_board(text: &str, tiles: u8) -> Result<u16, String> {
+    let mut board = 0;
+
+    for tile in text
+        .split(|char: char| char.is_whitespace() || char == ',')
+        .filter(|tile| !tile.is_empty())
+    {
+        let number = match u8::from_str(tile) {
+            Ok(number) if (1..=tiles).contains(&number) => number,
+            _ => return Err(format!("\"{tile}\" isn't a tile from 1 to {tiles}")),
+        };
+
+        if board >> (number - 1) & 1 == 1 {
+            return Err(format!("Tile {number} is typed more than once"));
+        }
+        board |= 1 << (number - 1);
+    }
+
+    Ok(board)
+}
+
+/// Formats the raw u16 of a board along with its binary representation, such as "511 0b111111111".
+/// The binary has a digit for each tile, with the highest tile first.
+fn raw_encoding(board: u16, tiles: u8) -> String {
+    format!("{board} 0b{board:0width$b}", width = tiles as usize)
+}
+
+/// Describes how likely the given roll is with fair dice, such as "3/36".
+/// A roll of 1 can only be made with a single dice, so it's out of 6 instead.
+fn roll_chance(roll: u8) -> String {
+    match roll {
+        1 => String::from("1/6"),
+        roll => {
+            let ways = DiceDistribution::Fair.sum_weights()[roll as usize - 2];
+            format!("{ways}/36")
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_ranked_moves(
+    path: &Path,
+    toasts: &mut Toasts,
+) -> Option<HashMap<BoardRoll, Vec<RankedMove>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // The ranked moves are optional, so they only have to exist once they're needed.
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            toasts.error(format!(
+                "Couldn't read ranked moves file: {e} ({})",
+                path.display()
+            ));
+            return None;
+        }
+    };
+    let reader = BufReader::new(file);
+    match compute::codec::from_reader(reader) {
+        Ok(val) => val,
+        Err(e) => {
+            toasts.error(format!(
+                "Couldn't parse ranked moves: {e} ({})",
+                path.display()
+            ));
+            None
+        }
+    }
+}
+
+// The web app can't compute the best moves, as it has no threads to simulate the games on.
+#[cfg(not(target_arch = "wasm32"))]
+impl Main {
+    /// Draws the window to recalculate the best moves, while it's open.
+    /// The best moves are loaded once the recalculation has finished.
+    fn recalculate_window(&mut self, context: &egui::Context) {
+        // If there is a recalculation in progress then don't let the window close.
+        if self.recalculation_in_progress {
+            self.recalculate_window_open = true
+        };
+
+        // Estimates how long a recalculation will take, while the window is open.
+        self.poll_calibration();
+        let calibration = self.calibration.filter(|calibration| {
+            calibration.threads == self.threads && calibration.tiles == self.variant.tiles
+        });
+        if self.recalculate_window_open
+            && calibration.is_none()
+            && !self.recalculation_in_progress
+            && self.variant.can_simulate()
+        {
+            if self.calibration_receiver.is_none() {
+                self.start_calibration();
+            }
+            // Keeps checking for the calibration without any user input.
+            context.request_repaint();
+        }
+
+        // Stores the amount of games to simulate, if a recalculation was requested.
+        let mut start_recalculation = None;
+        // Whether the ongoing recalculation finished, so its best moves can be loaded.
+        let mut recalculation_finished = false;
+
+        // Creates a new window for the recalculating options.
+        Window::new(RECALCULATE)
+            .open(&mut self.recalculate_window_open)
+            .show(context, |ui| {
+                ui.set_width_range(100f32..=200f32);
+
+                ui.heading(RichText::new("WARNING:").underline());
+                ui.label("This is very intensive.");
+
+                ui.add_space(10.);
+
+                // Displays the amount of games to be simulated.
+                ui.label("Games to simulate on each thread:");
+                ui.horizontal(|ui| {
+                    // The text box for the value to parse.
+                    let text_box = ui.add(egui::TextEdit::singleline(
+                        &mut self.unvalidated_games_to_simulate,
+                    ));
+
+                    // If the text can't be parsed as an unsigned int show an error.
+                    match u32::from_str(self.unvalidated_games_to_simulate.as_ref()) {
+                        Ok(to_simulate) => {
+                            self.games_to_simulate = to_simulate;
+                            self.could_parse_games = true;
+                        }
+                        Err(_) => {
+                            ui.label("⚠");
+                            self.could_parse_games = false;
+                        }
+                    }
+
+                    // If the input is invalid then the text will lose focus.
+                    text_box.request_focus();
+                });
+
+                ui.add_space(10.);
+
+                ui.label(format!("Variant: {}", self.variant.name()));
+                if !self.variant.can_simulate() {
+                    ui.label("The one dice rule can't be simulated, so its best moves have to be opened from a file.");
+                    return;
+                }
+
+                // Lets the user trade the CPU usage against how long the recalculation takes.
+                ui.horizontal(|ui| {
+                    ui.label("Threads:");
+                    ui.add_enabled(
+                        !self.recalculation_in_progress,
+                        egui::Slider::new(&mut self.threads, 1..=self.available_threads),
+                    );
+                });
+                match calibration {
+                    Some(calibration) if self.could_parse_games => {
+                        let estimate = calibration.duration.mul_f64(
+                            self.games_to_simulate as f64 / CALIBRATION_GAMES as f64,
+                        );
+                        ui.label(format!("Estimated time: about {}", format_duration(estimate)))
+                    }
+                    Some(_) => ui.label("Estimated time: -"),
+                    None => ui.label("Estimating the time..."),
+                };
+                ui.add_space(10.);
+
+                // If there isn't an ongoing calculation then display the option to start one.
+                if !self.recalculation_in_progress {
+                    let recalculate_button =
+                        ui.button(RichText::new("Recalculate").color(Color32::LIGHT_RED));
+
+                    // Recalculates the values if the button is clicked.
+                    if recalculate_button.clicked() && self.could_parse_games {
+                        start_recalculation = Some(self.games_to_simulate);
+                    }
+
+                    return;
+                }
+
+                // Will execute if there is an ongoing calculation.
+
+                // Spin! :)
+                ui.spinner();
+
+                let receiver = match self.recalculation_receiver.as_ref() {
+                    Some(receiver) => receiver,
+                    // If there was no receiver then an error occurred
+                    None => {
+                        self.recalculation_error = true;
+                        return;
+                    }
+                };
+
+                let failure = match receiver.try_recv() {
+                    // If the result was okay then the calculation finished successfully.
+                    Ok(Ok(())) => {
+                        self.recalculation_in_progress = false;
+                        self.recalculation_receiver = None;
+                        self.recalculation_started = None;
+                        self.recalculation_progress = None;
+                        self.recalculation_error = false;
+                        recalculation_finished = true;
+                        None
+                    }
+                    Ok(Err(error)) => Some(error),
+                    // If no message has been sent continue waiting.
+                    Err(TryRecvError::Empty) => None,
+                    // If the channel disconnected the recalculation must have crashed.
+                    Err(TryRecvError::Disconnected) => Some(String::from(
+                        "The recalculation failed, so the best moves weren't changed.",
+                    )),
+                };
+
+                // The recalculation is stopped, so the window can be closed again.
+                if let Some(error) = failure {
+                    self.recalculation_in_progress = false;
+                    self.recalculation_receiver = None;
+                    self.recalculation_started = None;
+                    self.recalculation_progress = None;
+                    self.recalculation_error = true;
+                    self.recalculation_profile = None;
+                    self.toasts.error(error);
+                }
+            });
+
+        if let Some(games_to_simulate) = start_recalculation {
+            self.start_recalculation(games_to_simulate);
+        }
+        if recalculation_finished {
+            self.finish_profile();
+            self.reload_moves();
+        }
+    }
+
+    /// Starts recalculating the best moves of the current variant in the background.
+    /// The best moves are written to a new profile, unless the platform has no folder for them.
+    fn start_recalculation(&mut self, games_to_simulate: u32) {
+        let output =
+            Profiles::new_policy_path(self.variant).unwrap_or_else(|| self.policy_path.clone());
+        let (receiver, progress) = Self::recalculate_best(
+            games_to_simulate,
+            self.threads,
+            output.clone(),
+            self.ranked_file(),
+            self.variant,
+        );
+        self.recalculation_profile = Some((output, games_to_simulate as u64 * self.threads as u64));
+        self.telemetry.record(Usage::ComputeRun);
+
+        // Default values for recalculation.
+        self.recalculation_receiver = Some(receiver);
+        self.recalculation_in_progress = true;
+        self.recalculation_error = false;
+        self.recalculation_started = Some(Instant::now());
+        self.recalculation_progress = Some(RecalculationProgress::new(progress));
+    }
+
+    /// Adds the best moves of the finished recalculation to the profiles & switches to them.
+    fn finish_profile(&mut self) {
+        let Some((path, games)) = self.recalculation_profile.take() else {
+            return;
+        };
+
+        self.profiles.add(path.clone(), self.variant, Some(games));
+        if let Err(error) = self.profiles.save() {
+            self.toasts.error(error);
+        }
+        self.policy_path = path;
+    }
+
+    /// Simulates a short burst of games in the background, to estimate how long a recalculation will take.
+    fn start_calibration(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        let threads = self.threads;
+        let tiles = self.variant.tiles;
+
+        let mut config = SimulationConfig::new(threads, CALIBRATION_GAMES);
+        config.ruleset.tiles = tiles;
+
+        thread::spawn(move || {
+            // The estimate is left out if the calibration failed.
+            let Ok(duration) = compute::time_simulation(&config) else {
+                return;
+            };
+            // The window may have stopped listening, in which case the result isn't needed.
+            let _ = tx.send(Calibration {
+                threads,
+                tiles,
+                duration,
+            });
+        });
+        self.calibration_receiver = Some(rx);
+    }
+
+    /// Stores the result of the ongoing calibration, if it has finished.
+    fn poll_calibration(&mut self) {
+        let Some(receiver) = &self.calibration_receiver else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(calibration) => {
+                self.calibration = Some(calibration);
+                self.calibration_receiver = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            // The calibration failed, so the estimate is left out.
+            Err(TryRecvError::Disconnected) => self.calibration_receiver = None,
+        }
+    }
+
+    /// Asks the user for a file to load the best moves from.
+    fn open_policy(&mut self) {
+        let picked = rfd::FileDialog::new()
+            .add_filter("Best moves", &["yml", "yaml"])
+            .pick_file();
+
+        if let Some(policy_path) = picked {
+            self.policy_path = policy_path;
+            self.reload_moves();
+        }
+    }
+
+    fn recalculate_best(
+        games_to_simulate: u32,
+        threads: u8,
+        output: PathBuf,
+        ranked_output: PathBuf,
+        variant: Variant,
+    ) -> (Receiver<Result<(), String>>, Receiver<ComputeProgress>) {
+        // Creates channels to check the status & the progress of the recalculation.
+        let (tx, rx) = mpsc::channel();
+        let (progress_tx, progress_rx) = mpsc::channel();
+        // Runs the calculation async so the gui still works.
+        let mut config = SimulationConfig::new(threads, games_to_simulate);
+        config.output = output;
+        config.ruleset = variant;
+        config.ranked_output = ranked_output;
+        // Keeps every ranked move, so the win chance of any move can be shown.
+        config.top_k = usize::MAX;
+
+        thread::spawn(move || {
+            let result = compute::compute_with_config(config, progress_tx).map_err(|e| {
+                format!("The recalculation failed, so the best moves weren't changed: {e}")
+            });
+            // The window may have stopped listening, in which case the result isn't needed.
+            let _ = tx.send(result);
+        });
+        (rx, progress_rx)
+    }
+}
+
+// The core function for drawing a gui.
+impl eframe::App for Main {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, POLICY_PATH_KEY, &self.policy_path);
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+        eframe::set_value(storage, VARIANT_KEY, &self.variant);
+        eframe::set_value(storage, BOOKMARKS_KEY, &self.bookmarks);
+        eframe::set_value(storage, TELEMETRY_KEY, &self.telemetry);
+        eframe::set_value(storage, MODE_KEY, &self.mode);
+        eframe::set_value(storage, MOVES_VIEW_KEY, &self.moves_view);
+        eframe::set_value(storage, ROOT_BOARD_KEY, &self.root_board);
+        eframe::set_value(
+            storage,
+            HISTORY_KEY,
+            &(&self.previous_boards, &self.next_boards),
+        );
+    }
+
+    fn update(&mut self, context: &egui::Context, _frame: &mut eframe::Frame) {
+        self.settings
+            .window(context, &mut self.settings_window_open);
+        if let Some(error) = self.telemetry.window(context) {
+            self.toasts.error(error);
+        }
+        self.settings.zoom_shortcuts(context);
+        self.settings.detect_touch(context);
+        let touch = self.settings.is_touch();
+
+        // Handles the server, the files, & the background simulation, which only the desktop app has.
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_desktop(context);
+        // Loads the best moves once they've been fetched, as the web app has no files to load them from.
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.poll_fetches();
+            if self.policy_fetch.is_pending() || self.ranked_fetch.is_pending() {
+                context.request_repaint_after(FETCH_POLL);
+            }
+        }
+
+        // Saves the requested screenshot of the board view, once it has been taken.
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.export_requested {
+            self.export_board_view(context);
+        }
+
+        // Guides the user through getting the best moves, if there are none.
+        self.getting_started(context);
+        self.score_breakdown_window(context);
+        self.playback
+            .window(context, self.parsed_moves.as_ref(), &self.settings.colors);
+
+        // Sets the content of the top panel.
+        // The touch layout has the buttons along the bottom instead, so they're within reach of the thumbs.
+        let action_panel = match touch {
+            true => egui::TopBottomPanel::bottom(Id::new(TOP_PANEL)),
+            false => egui::TopBottomPanel::top(Id::new(TOP_PANEL)),
+        };
+        action_panel.show(context, |ui| self.top_panel(context, ui));
+        // Shows where the best moves came from along the bottom, which the touch layout leaves out.
+        if !touch {
+            egui::TopBottomPanel::bottom(Id::new(STATUS_BAR))
+                .show(context, |ui| self.status_bar(ui));
+        }
+        self.toasts.show(context);
+
+        // The move history is only kept for games against the AI.
+        if self.mode == Mode::Play {
+            self.play.side_panel(context);
+        }
+        // The touch layout leaves out the bookmarks, so the boards have more room.
+        if self.mode == Mode::Explore && !touch {
+            if let Some(bookmark) = self.bookmarks.side_panel(context) {
+                self.jump_to_bookmark(bookmark);
+            }
+        }
+
+        // Sets the content of the main window.
+        // The content can be scrolled, so everything can be reached at small window sizes.
+        let board_view = egui::CentralPanel::default().show(context, |ui| {
+            egui::ScrollArea::both()
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    // If a recalculation is in progress, don't display the boards.
+                    if self.recalculation_in_progress {
+                        ui.heading("Recalculating...");
+                        if let Some(started) = self.recalculation_started {
+                            ui.label(format!("Running for {}s", started.elapsed().as_secs()));
+                            if let Some(progress) = &mut self.recalculation_progress {
+                                progress.poll();
+                                progress.show(ui, started);
+                            }
+                            context.request_repaint_after(Duration::from_secs(1));
+                        }
+                        ui.spinner();
+                        return;
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+                    if self.mode == Mode::Online {
+                        // Networked games are always standard, so other variants' best moves can't judge them.
+                        let best_moves = self
+                            .parsed_moves
+                            .as_ref()
+                            .filter(|_| self.variant.is_standard());
+                        self.network.show(ui, &self.settings.colors, best_moves);
+                        return;
+                    }
+
+                    if self.mode == Mode::Trainer {
+                        let opened = self.trainer.show(
+                            ui,
+                            &self.settings.colors,
+                            self.parsed_moves.as_ref(),
+                            self.ranked_moves.as_ref(),
+                        );
+                        // Studies the reviewed situation in the explorer, with every move of its roll shown.
+                        if let Some((board, roll)) = opened {
+                            self.navigate(board);
+                            self.pinned_roll = Some(roll);
+                            self.moves_view = MovesView::Boards;
+                            self.mode = Mode::Explore;
+                        }
+                        return;
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+                    if self.mode == Mode::Replay {
+                        self.replay.show(
+                            ui,
+                            &self.settings.colors,
+                            self.parsed_moves.as_ref(),
+                            self.ranked_moves.as_ref(),
+                        );
+                        return;
+                    }
+
+                    if self.mode == Mode::Play {
+                        self.play.show(
+                            ui,
+                            &self.settings.colors,
+                            !self.settings.skip_animations,
+                            self.parsed_moves.as_ref(),
+                            self.ranked_moves.as_ref(),
+                        );
+                        for sound in self.play.take_sounds() {
+                            self.audio.play(sound, self.settings.muted);
+                        }
+                        if self.play.take_finished() {
+                            self.telemetry.record(Usage::LocalGame);
+                            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+                            match replay::save(&self.play.replay()) {
+                                Ok(path) => info!("Wrote the replay to {}.", path.display()),
+                                Err(error) => self.toasts.error(error),
+                            }
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if let Err(error) = self.dashboard.record(&self.play.game_record()) {
+                                self.toasts.error(error);
+                            }
+                        }
+                        #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+                        match self.play.take_save_request() {
+                            Some(SaveRequest::Save) => match replay::save_game(&self.play.save()) {
+                                Ok(path) => {
+                                    info!("Saved the game to {}.", path.display());
+                                    self.play.has_saved_game = true;
+                                }
+                                Err(error) => self.toasts.error(error),
+                            },
+                            Some(SaveRequest::Continue) => self.continue_game(),
+                            None => {}
+                        }
+                        return;
+                    }
+
+                    // Allows right clicking on the background to export the board view.
+                    // This is created before the boards, so they still get clicked on instead.
+                    // The web app has nowhere to save the image to.
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let background =
+                            ui.interact(ui.max_rect(), Id::new(BOARD_VIEW), Sense::click());
+                        background.context_menu(|ui| {
+                            if ui.button("Export as image").clicked() {
+                                self.export_requested = true;
+                                self.telemetry.record(Usage::ImageExport);
+                                context.send_viewport_cmd(ViewportCommand::Screenshot);
+                                ui.close_menu();
+                            }
+                        });
+                    }
+
+                    if let Some(message) = &self.export_message {
+                        ui.label(message);
+                    }
+
+                    // Moves through the history of root boards with the keyboard.
+                    self.history_shortcuts(context);
+                    // Draws the previously visited boards.
+                    self.breadcrumbs(ui);
+
+                    // Draws the best possible moves, returning the board that was clicked on.
+                    let clicked_on = self.central_panel(ui);
+
+                    // If none of the boards were clicked on, return.
+                    let Some(clicked_on) = clicked_on else {
+                        return;
+                    };
+
+                    let roll = match clicked_on {
+                        // If the root board was clicked on move back.
+                        BoardClick::Back => {
+                            self.undo();
+                            return;
+                        }
+                        // If one of the moves of the pinned roll was clicked on, move to it.
+                        BoardClick::Move(move_board) => {
+                            self.navigate(move_board);
+                            return;
+                        }
+                        // If a dying roll was clicked on, show the final score.
+                        BoardClick::GameOver => {
+                            self.score_breakdown = Some(self.root_board);
+                            return;
+                        }
+                        BoardClick::Roll(roll) => roll.into(),
+                    };
+
+                    // The best moves exist if board info could be shown, but nothing is done if they don't.
+                    let Some(best_moves) = self.parsed_moves.as_ref() else {
+                        return;
+                    };
+
+                    // If the value doesn't exist, then the roll wasn't simulated.
+                    if let Some(best_move) = best_moves.best_move(self.root_board, roll) {
+                        self.navigate(best_move);
+                    }
+                })
+        });
+        self.board_view_rect = board_view.response.rect;
+    }
+}
+
+impl Main {
+    /// Handles the messages from the server, even when they aren't displayed.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    fn update_network(&mut self, context: &egui::Context) {
+        let was_connected = self.network.is_connected();
+        let was_hosting = self.network.is_hosting();
+        self.network.poll();
+        for game in self.network.take_finished() {
+            self.telemetry.record(Usage::NetworkedGame);
+            if let Err(error) = self.dashboard.record(&game) {
+                self.toasts.error(error);
+            }
+        }
+        if was_connected && !self.network.is_connected() {
+            let reason = self.network.error().unwrap_or("Disconnected from server.");
+            self.toasts.error(format!("Lost the connection: {reason}"));
+        }
+        if self.network.is_connected() || self.network.is_hosting() {
+            // Keeps checking for messages from the server without any user input.
+            context.request_repaint_after(NETWORK_POLL);
+        }
+        if self.network.connect_window(context) {
+            self.mode = Mode::Online;
+        }
+        // Networked games are always standard, so other variants' best moves can't be hinted.
+        let best_moves = self
+            .parsed_moves
+            .as_ref()
+            .filter(|_| self.variant.is_standard());
+        let ranked_moves = self
+            .ranked_moves
+            .as_ref()
+            .filter(|_| self.variant.is_standard());
+        if self.network.host_window(context, best_moves, ranked_moves) {
+            self.mode = Mode::Online;
+        }
+        if !was_hosting && self.network.is_hosting() {
+            self.telemetry.record(Usage::Hosting);
+        }
+    }
+
+    /// Returns true if a networked game is being played or hosted.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_networking(&self) -> bool {
+        #[cfg(feature = "network")]
+        return self.network.is_connected() || self.network.is_hosting();
+        #[cfg(not(feature = "network"))]
+        false
+    }
+
+    /// Handles the files the moves are loaded from, & the background simulation.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_desktop(&mut self, context: &egui::Context) {
+        logging::crash::note("mode", format!("{:?}", self.mode));
+        logging::crash::note("board", self.root_board);
+        let (user_board, ai_board) = self.play.boards();
+        logging::crash::note(
+            "played boards",
+            format!("{user_board} & {ai_board} for the AI"),
+        );
+
+        if let Some(error) = self.dashboard.window(context) {
+            self.toasts.error(error);
+        }
+
+        #[cfg(feature = "network")]
+        self.update_network(context);
+        let (chosen, error) = self
+            .profiles
+            .window(context, &self.policy_path, self.variant);
+        if let Some(error) = error {
+            self.toasts.error(error);
+        }
+        if let Some(profile) = chosen {
+            // The profile's variant is switched to first, so the moves are shown on the right boards.
+            if profile.variant != self.variant {
+                self.set_variant(profile.variant);
+            }
+            self.policy_path = profile.path;
+            self.reload_moves();
+        }
+
+        // Reloads the moves when their files are changed, such as by the cli.
+        // The recalculation reloads the moves itself once it's finished.
+        let ranked_file = self.ranked_file();
+        if let Err(error) = self
+            .policy_watcher
+            .watch(&[&self.policy_path, &ranked_file], context)
+        {
+            self.toasts.error(error);
+        }
+        let changed = self.policy_watcher.poll(context) || self.policy_rebuilding;
+        if changed && !self.recalculation_in_progress {
+            self.reload_moves();
+        }
+        if self.policy_rebuilding {
+            context.request_repaint_after(REBUILD_RETRY);
+        }
+
+        // The background simulation is paused while a game is being played, so it doesn't slow the game down.
+        let busy = self.recalculation_in_progress
+            || self.is_networking()
+            || (self.mode == Mode::Play && self.play.is_in_progress());
+        let known_games = self
+            .profiles
+            .find(&self.policy_path)
+            .and_then(|profile| profile.games);
+        match self.background.update(
+            context,
+            self.settings.background_compute,
+            busy,
+            &self.policy_path,
+            self.variant,
+            known_games,
+        ) {
+            Ok(Some(merged)) if merged.changed > 0 => {
+                if let Some(games) = merged.games {
+                    self.profiles.set_games(&self.policy_path, games);
+                    if let Err(error) = self.profiles.save() {
+                        self.toasts.error(error);
+                    }
+                }
+                self.reload_moves();
+            }
+            Ok(_) => {}
+            Err(error) => self.toasts.error(error),
+        }
+    }
+}
+
+// Sub-functions for drawing the gui.
+impl Main {
+    /// The code for drawing the top panel of the gui.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn top_panel(&mut self, context: &egui::Context, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            // Creates a button that will be used to recalculate the best moves.
+            // The web app can't simulate any games, so the best moves are only served to it.
+            let recalculate_window_button = ui
+                .add_enabled(
+                    cfg!(not(target_arch = "wasm32")),
+                    egui::Button::new("Recalculate"),
+                )
+                .on_disabled_hover_text(
+                    "The best moves can only be recalculated by the desktop app.",
+                );
+            // Creates a button that will be used to reset the root board.
+            let reset_button = ui.button("Reset");
+
+            // Switches between exploring the best moves, playing games, the trainer, & replays.
+            let previous_mode = self.mode;
+            ui.selectable_value(&mut self.mode, Mode::Explore, "Explore");
+            ui.selectable_value(&mut self.mode, Mode::Play, "Play");
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            ui.selectable_value(&mut self.mode, Mode::Online, "Online");
+            ui.selectable_value(&mut self.mode, Mode::Trainer, "Trainer");
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            ui.selectable_value(&mut self.mode, Mode::Replay, "Replay");
+            if self.mode != previous_mode {
+                self.telemetry.record(Usage::from(self.mode));
+            }
+
+            // Switches the rules of the game, which changes the file the best moves are loaded from.
+            let mut variant = self.variant;
+            egui::ComboBox::from_id_source(VARIANT_KEY)
+                .selected_text(variant.name())
+                .show_ui(ui, |ui| {
+                    for tiles in TILE_COUNTS {
+                        ui.selectable_value(&mut variant.tiles, tiles, format!("{tiles} tiles"));
+                    }
+                    ui.checkbox(&mut variant.one_die, "One dice rule");
+                    ui.checkbox(&mut variant.instant_shut, "Instant shut")
+                        .on_hover_text("Shutting the box wins straight away.");
+
+                    let mut golf = variant.scoring == Scoring::Golf;
+                    if ui
+                        .checkbox(&mut golf, "Golf scoring")
+                        .on_hover_text(format!("The scores are added up over {GOLF_HOLES} games."))
+                        .changed()
+                    {
+                        variant.scoring = match golf {
+                            true => Scoring::Golf,
+                            false => Scoring::Lowest,
+                        };
+                    }
+                });
+            if variant != self.variant {
+                self.set_variant(variant);
+            }
+
+            // Jumps straight to the board with the typed pieces alive.
+            if self.mode == Mode::Explore {
+                self.board_search(ui);
+            }
+
+            // Chooses a different file to load the best moves from.
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("Open").clicked() {
+                self.open_policy();
+            }
+
+            // Loads the best moves from the file again, in case it was changed.
+            if ui.button("Reload").clicked() {
+                self.reload_moves();
+            }
+
+            // Opens the window to manage the calculated best moves.
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("Profiles").clicked() {
+                self.profiles.window_open = true;
+            }
+
+            // Opens the window showing the stats of the finished games.
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("Stats").clicked() {
+                self.dashboard.window_open = true;
+            }
+
+            // Opens the window to change the preferences.
+            if ui.button("Settings").clicked() {
+                self.settings_window_open = true;
+            }
+
+            // Opens the window to opt in to the usage stats.
+            if ui.button("Usage").clicked() {
+                self.telemetry.window_open = true;
+            }
+
+            // Opens the window to connect to a networked game.
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            if ui.button("Connect").clicked() {
+                self.network.connect_window_open = true;
+            }
+
+            // Opens the window to host a networked game.
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            if ui.button("Host").clicked() {
+                self.network.host_window_open = true;
+            }
+
+            // Opens the window when the button is clicked.
+            if recalculate_window_button.clicked() {
+                self.recalculate_window_open = true;
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.recalculate_window(context);
+
+            // Resets the shown moves when clicked.
+            if reset_button.clicked() {
+                self.root_board = self.variant.full_board();
+                self.previous_boards = Vec::new();
+                self.next_boards = Vec::new();
+            }
+        });
+    }
+
+    /// Draws the file the best moves were loaded from, along with how much they cover.
+    fn status_bar(&self, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            match &self.parsed_moves {
+                _ if self.policy_rebuilding => ui.label(
+                    RichText::new(format!(
+                        "Best moves: {} is being rebuilt, so it will be loaded once it's written",
+                        self.policy_path.display()
+                    ))
+                    .color(Color32::YELLOW),
+                ),
+                Some(best_moves) => ui.label(format!(
+                    "Best moves: {} ({} board & roll combinations{})",
+                    self.policy_path.display(),
+                    best_moves.len(),
+                    match self.policy_format {
+                        PolicyFormat::Current => "",
+                        PolicyFormat::Legacy => ", converted from the legacy format",
+                    }
+                )),
+                None => ui.label(
+                    RichText::new(format!(
+                        "No best moves loaded from {}",
+                        self.policy_path.display()
+                    ))
+                    .color(Color32::LIGHT_RED),
+                ),
+            };
+            ui.separator();
+
+            match &self.ranked_moves {
+                Some(ranked_moves) => {
+                    ui.label(format!("Ranked moves: {} combinations", ranked_moves.len()))
+                }
+                None => ui.label("No ranked moves"),
+            };
+            ui.separator();
+
+            ui.label(format!("Variant: {}", self.variant.name()));
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(status) = self.background.status() {
+                ui.separator();
+                ui.label(status);
+            }
+        });
+    }
+
+    /// Draws the search box that moves to the board with the typed pieces alive, as soon as they are valid.
+    fn board_search(&mut self, ui: &mut Ui) {
+        let search = ui.add(
+            egui::TextEdit::singleline(&mut self.board_search)
+                .hint_text("Jump to, e.g. 1 4 7 9")
+                .desired_width(120.),
+        );
+
+        if search.changed() {
+            // An empty search box is waiting for input, rather than being invalid.
+            match self.board_search.trim().is_empty() {
+                true => self.board_search_error = None,
+                false => match parse_board(&self.board_search, self.variant.tiles) {
+                    Ok(board) => {
+                        self.board_search_error = None;
+                        if board != self.root_board {
+                            self.navigate(board);
+                        }
+                    }
+                    Err(error) => self.board_search_error = Some(error),
+                },
+            }
         }
-    }
-}
 
-fn parse_moves() -> Option<HashMap<BoardRoll, u16>> {
-    let file = match File::open("best_moves.yml") {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("{e}");
-            return None;
-        }
-    };
-    let reader = BufReader::new(file);
-    match serde_yaml::from_reader(reader) {
-        Ok(val) => val,
-        Err(e) => {
-            eprintln!("{e}");
-            None
+        if let Some(error) = &self.board_search_error {
+            ui.label(RichText::new(format!("⚠ {error}")).color(Color32::LIGHT_RED));
         }
     }
-}
 
-impl Main {
-    fn recalculate_best(games_to_simulate: u32) -> Receiver<bool> {
-        // Gets the amount of threads a system has.
-        // Defaults to 4.
-        let threads = match thread::available_parallelism() {
-            Ok(number) => number.get() as u8,
-            Err(_) => 4,
+    /// Draws the window showing which pieces make up the final score of a dying board.
+    fn score_breakdown_window(&mut self, context: &egui::Context) {
+        let Some(board) = self.score_breakdown else {
+            return;
         };
 
-        // Creates channels to check the status of the recalculation.
-        let (tx, rx) = mpsc::channel();
-        // Runs the calculation async so the gui still works.
-        thread::spawn(move || {
-            compute::compute(threads, games_to_simulate, tx);
-        });
-        rx
+        let mut open = true;
+        Window::new(FINAL_SCORE)
+            .open(&mut open)
+            .collapsible(false)
+            .show(context, |ui| {
+                ui.label(tiles_layout(
+                    board,
+                    self.variant.tiles,
+                    &self.settings.colors,
+                ));
+
+                let pieces: Vec<String> = Self::board_to_array(board, self.variant.tiles)
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(_, alive)| **alive)
+                    .map(|(index, _)| (index + 1).to_string())
+                    .collect();
+
+                match pieces.is_empty() {
+                    true => ui.label("The box was shut, so the score is 0."),
+                    false => ui.label(format!(
+                        "The pieces left add up to the score: {} = {}",
+                        pieces.join(" + "),
+                        board_value(board)
+                    )),
+                };
+                ui.label("The lowest score wins.");
+            });
+
+        if !open {
+            self.score_breakdown = None;
+        }
     }
-}
 
-// The core function for drawing a gui.
-impl eframe::App for Main {
-    fn update(&mut self, context: &egui::Context, _frame: &mut eframe::Frame) {
-        context.set_pixels_per_point(1.5);
+    /// Draws the window explaining how to get the best moves, offering presets to compute them.
+    /// This is only shown while exploring without any best moves loaded.
+    fn getting_started(&mut self, context: &egui::Context) {
+        if self.parsed_moves.is_some()
+            || self.recalculation_in_progress
+            || self.getting_started_dismissed
+            || self.mode != Mode::Explore
+        {
+            return;
+        }
 
-        // Sets the content of the top panel
-        egui::TopBottomPanel::top(Id::new(TOP_PANEL))
-            .show(context, |ui| self.top_panel(context, ui));
+        let mut open = true;
+        let mut start_recalculation = None;
+        let mut open_file = false;
 
-        // Sets the content of the main window.
-        egui::CentralPanel::default()
+        Window::new(GETTING_STARTED)
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
             .show(context, |ui| {
-                // If a recalculation is in progress, don't display the boards.
-                if self.recalculation_in_progress {
-                    ui.heading("Recalculating...");
-                    ui.spinner();
+                ui.set_width_range(150f32..=300f32);
+
+                ui.label(format!("No best moves were found at {}.", self.policy_path.display()));
+                // The web app can't simulate the games, so the best moves have to be served next to it.
+                if cfg!(target_arch = "wasm32") {
+                    ui.label("The best moves have to be computed by the desktop app, then served next to this page.");
                     return;
                 }
+                ui.label("The best moves are computed by simulating lots of random games, which only has to be done once.");
+                ui.add_space(10.);
+
+                if !self.variant.can_simulate() {
+                    ui.label(format!("The best moves for {} can't be computed, so they have to be opened from a file.", self.variant.name()));
+                } else {
+                    ui.label("Quick: a rough table, which takes a few seconds.");
+                    if ui.button("Compute quickly").clicked() {
+                        start_recalculation = Some(QUICK_GAMES);
+                    }
+                    ui.add_space(5.);
+
+                    ui.label("Thorough: an accurate table, which can take several minutes.");
+                    if ui.button("Compute thoroughly").clicked() {
+                        start_recalculation = Some(THOROUGH_GAMES);
+                    }
+                    ui.add_space(10.);
+                }
 
-                // Draws the best possible moves
-                let board_info = self.central_panel(context, ui);
+                ui.label("Or load the best moves that were computed before:");
+                if ui.button("Open").clicked() {
+                    open_file = true;
+                }
+            });
 
-                // If the moves haven't been calculated yet return.
-                if board_info.is_none() { return; }
-                let board_info = board_info.unwrap();
+        self.getting_started_dismissed = !open;
 
-                // Checks if any of the move have been clicked on.
-                let mut clicked_on = None;
-                for board_index in 0..board_info.len() {
-                    let (board_id, board_rect) = *board_info.get(board_index).expect("Will exist");
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(games_to_simulate) = start_recalculation {
+                self.start_recalculation(games_to_simulate);
+            }
+            if open_file {
+                self.open_policy();
+            }
+        }
+    }
 
-                    let clicked = ui.interact(board_rect, board_id, egui::Sense::click()).clicked();
-                    if !clicked { continue; }
+    /// Draws the root board & the moves that can be made from it.
+    /// If one of the boards was clicked on, then what should happen is returned.
+    fn central_panel(&mut self, ui: &mut Ui) -> Option<BoardClick> {
+        // Solves the chances of shutting the box for the tiles of the variant, if they haven't been already.
+        let tiles = self.variant.tiles;
+        if self
+            .shut_chances
+            .as_ref()
+            .is_none_or(|chances| chances.tiles() != tiles)
+        {
+            self.shut_chances = Some(ShutChances::solve(tiles, DiceDistribution::Fair));
+        }
 
-                    clicked_on = Some(board_index as u8)
+        // Checks if best moves have been calculated.
+        if let Some(best_moves) = &self.parsed_moves {
+            ui.horizontal(|ui| {
+                // Switches between the boards & the table of the moves.
+                ui.selectable_value(&mut self.moves_view, MovesView::Boards, "Boards");
+                ui.selectable_value(&mut self.moves_view, MovesView::Table, "Table");
+                let tree = ui
+                    .selectable_value(&mut self.moves_view, MovesView::Tree, "Tree")
+                    .on_hover_text("Explore the game tree in depth.");
+                if tree.changed() {
+                    self.telemetry.record(Usage::GameTree);
                 }
-
-                // If none of the boards were clicked on, return.
-                if clicked_on == None { return; }
-                let clicked_on = clicked_on.unwrap();
-
-                // Checks if the root board was clicked on.
-                // If it was move back.
-                if clicked_on == 0 {
-                    self.previous_boards.pop()
-                        .and_then(|last_board| {
-                            self.root_board = last_board;
-                            None::<u16>
+                ui.separator();
+
+                // Selects the roll to show every move for, which only the boards can show.
+                ui.add_enabled_ui(self.moves_view == MovesView::Boards, |ui| {
+                    egui::ComboBox::from_label("Pinned roll")
+                        .selected_text(
+                            self.pinned_roll
+                                .map_or(String::from("None"), |roll| roll.to_string()),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.pinned_roll, None, "None");
+                            for roll in self.variant.rolls() {
+                                ui.selectable_value(
+                                    &mut self.pinned_roll,
+                                    Some(roll),
+                                    roll.to_string(),
+                                );
+                            }
                         });
+                });
+                ui.separator();
 
-                    return;
+                // Plays out a game from the root board, to show how the best moves unfold.
+                if ui.button("Play out").clicked() {
+                    self.playback.start(self.root_board, self.variant);
                 }
+            });
+            ui.add_space(10.);
+
+            // Stores what should happen for the board that was clicked on, if any.
+            let mut clicked_on = None;
+            // Stores the index of the root piece that was clicked on, if any.
+            let mut toggled_piece = None;
+            // Stores whether the bookmark of the root board was toggled.
+            let mut toggled_bookmark = false;
+            let bookmark = Bookmark {
+                board: self.root_board,
+                variant: self.variant,
+            };
 
-                let best_moves = self.parsed_moves.as_ref().expect("Will exist as board info must exist to get to this point. Board info requires this to be some.");
+            // Generates the layout for the root board.
+            let colors = &self.settings.colors;
+            let tiles = self.variant.tiles;
+            let (back_layout, piece_layouts) =
+                Self::generate_root_board(self.root_board, tiles, colors);
 
-                let board_roll = BoardRoll::new(
-                    self.root_board,
-                    (clicked_on + 1).into(), // Clicked-on is one less than the value it should be.
-                );
+            // Displays the root board.
+            ui.horizontal(|ui| {
+                // Removes the gaps between the pieces, so it lines up with the other boards.
+                ui.spacing_mut().item_spacing.x = 0.;
+
+                let back = ui.add(Label::new(back_layout).sense(Sense::click()));
+                back.widget_info(|| WidgetInfo::labeled(WidgetType::Button, "Back"));
+                if back.clicked() {
+                    clicked_on = Some(BoardClick::Back);
+                }
 
-                // If the value doesn't exist, then it's a dying move.
-                match best_moves.get(&board_roll) {
-                    Some(best_move) => {
-                        self.previous_boards.push(self.root_board);
-                        self.root_board = *best_move
-                    }
-                    None => {
-                        // Will execute on dying move
+                // Each piece can be clicked on to knock it down or bring it back up.
+                for (piece_index, piece_layout) in piece_layouts {
+                    let piece = ui.add(Label::new(piece_layout).sense(Sense::click()));
+                    let alive = self.root_board >> piece_index & 1 == 1;
+                    piece.widget_info(|| {
+                        let state = match alive {
+                            true => "up",
+                            false => "down",
+                        };
+                        WidgetInfo::labeled(
+                            WidgetType::Button,
+                            format!("Tile {}, {state}", piece_index + 1),
+                        )
+                    });
+                    if piece.clicked() {
+                        toggled_piece = Some(piece_index);
                     }
-                };
+                }
+
+                ui.add_space(10.);
+                let bookmarked = self.bookmarks.contains(bookmark);
+                let star = ui
+                    .selectable_label(bookmarked, "⭐")
+                    .on_hover_text(match bookmarked {
+                        true => "Remove bookmark",
+                        false => "Bookmark this board",
+                    });
+                if star.clicked() {
+                    toggled_bookmark = true;
+                }
+
+                if self.settings.debug_view {
+                    ui.add_space(10.);
+                    ui.label(RichText::new(raw_encoding(self.root_board, tiles)).monospace());
+                }
             });
+
+            if toggled_bookmark {
+                self.bookmarks.toggle(bookmark);
+            }
+
+            // Shows how likely the box is to be shut from the root board.
+            if let Some(chances) = &self.shut_chances {
+                ui.label(format!(
+                    "Chance to shut the box: {}",
+                    format_chance(chances.board(self.root_board))
+                ))
+                .on_hover_text(
+                    "The exact chance, assuming both dice are always rolled & the move with the best chance of shutting the box is always made.",
+                );
+            }
+            ui.add_space(10.);
+
+            // Shows the moves for each roll either as boards or as a table.
+            let clicked_row = match self.moves_view {
+                MovesView::Boards => self.stacked_boards(ui, best_moves),
+                MovesView::Table => self.moves_table(ui, best_moves),
+                MovesView::Tree => {
+                    if let Some(chances) = &self.shut_chances {
+                        self.tree_explorer.show(
+                            ui,
+                            self.root_board,
+                            chances,
+                            &self.settings.colors,
+                        );
+                    }
+                    None
+                }
+            };
+            if clicked_row.is_some() {
+                clicked_on = clicked_row;
+            }
+
+            // Sets up the new board to analyse, which has no history.
+            if let Some(piece_index) = toggled_piece {
+                self.root_board ^= 1 << piece_index;
+                self.previous_boards.clear();
+                self.next_boards.clear();
+            }
+
+            return clicked_on;
+        }
+
+        ui.heading("No moves found ;-;");
+        None
     }
-}
 
-// Sub-functions for drawing the gui.
-impl Main {
-    /// The code for drawing the top panel of the gui.
-    fn top_panel(&mut self, context: &egui::Context, ui: &mut Ui) {
-        ui.horizontal(|ui| {
-            // Creates a button that will be used to recalculate the best moves.
-            let recalculate_window_button = ui.button("Recalculate");
-            // Creates a button that will be used to reset the root board.
-            let reset_button = ui.button("Reset");
+    /// Draws the best move for each roll of the root board as a stack of boards.
+    /// The pinned roll has a board for every move, ranked from best to worst.
+    /// If one of the boards was clicked on, then what should happen is returned.
+    fn stacked_boards(&self, ui: &mut Ui, best_moves: &Policy) -> Option<BoardClick> {
+        let mut clicked_on = None;
+        let colors = &self.settings.colors;
+        let tiles = self.variant.tiles;
+
+        // Generates the layout for the best moves for each roll.
+        // The pinned roll has a layout for every move, ranked from best to worst.
+        let mut board_layouts = Vec::with_capacity(12);
+        for roll in self.variant.rolls() {
+            if self.pinned_roll == Some(roll) {
+                for (move_board, value) in self.ranked_alternatives(best_moves, roll) {
+                    let mut layout =
+                        Self::generate_board(self.root_board, roll, move_board, tiles, colors);
+
+                    let mut description = describe_move(self.root_board, roll, move_board);
+
+                    // Adds the win chance of the move, if it is known.
+                    let value = value.map_or(String::from(" ?"), |value| {
+                        let percent = value as f32 / 10.;
+                        description.push_str(&format!(", {percent:.1}% win chance"));
+                        format!(" {percent:.1}%")
+                    });
+                    layout.append(value.as_str(), 0., TextFormat::default());
+                    board_layouts.push((roll, layout, BoardClick::Move(move_board), description));
+                }
 
-            // Opens the window when the button is clicked.
-            if recalculate_window_button.clicked() {
-                self.recalculate_window_open = true;
+                // If there are no moves then the roll is a dying one.
+                if !game_core::enumerate_moves(self.root_board, roll).is_empty() {
+                    continue;
+                }
+            }
+
+            // If there are no moves, then the game ends with this roll.
+            if game_core::enumerate_moves(self.root_board, roll).is_empty() {
+                board_layouts.push((
+                    roll,
+                    Self::generate_dying_board(self.root_board, roll, colors),
+                    BoardClick::GameOver,
+                    format!(
+                        "Roll {roll}, game over with a score of {}",
+                        board_value(self.root_board)
+                    ),
+                ));
+                continue;
             }
 
-            // If there is a recalculation in progress then don't let the window close.
-            if self.recalculation_in_progress == true {
-                self.recalculate_window_open = true
+            // If the roll wasn't simulated, then the root board is shown without any moves.
+            let (layout, description) = match best_moves.best_move(self.root_board, roll.into()) {
+                Some(best_move) => (
+                    Self::generate_board(self.root_board, roll, best_move, tiles, colors),
+                    describe_move(self.root_board, roll, best_move),
+                ),
+                None => {
+                    let mut layout =
+                        Self::generate_board(self.root_board, roll, self.root_board, tiles, colors);
+                    layout.append(" ?", 0., TextFormat::default());
+                    (layout, format!("Roll {roll}, not simulated"))
+                }
             };
+            board_layouts.push((roll, layout, BoardClick::Roll(roll), description));
+        }
 
-            // Creates a new window for the recalculating options.
-            Window::new(RECALCULATE)
-                .open(&mut self.recalculate_window_open)
-                .show(context, |ui| {
-                    ui.set_width_range(100f32..=200f32);
+        // Iterates over the generate board & displays them.
+        for (roll, mut layout, board_click, description) in board_layouts {
+            // Adds how likely the roll of the board is.
+            layout.append(
+                format!(" ({})", roll_chance(roll)).as_str(),
+                0.,
+                TextFormat::default(),
+            );
 
-                    ui.heading(RichText::new("WARNING:").underline());
-                    ui.label("This is very intensive.");
+            // Adds the encoding of the board the move ends up on, for debugging.
+            if self.settings.debug_view {
+                let move_board = match board_click {
+                    BoardClick::Move(move_board) => move_board,
+                    BoardClick::Roll(roll) => best_moves
+                        .best_move(self.root_board, roll.into())
+                        .unwrap_or(self.root_board),
+                    BoardClick::Back | BoardClick::GameOver => self.root_board,
+                };
+                layout.append(
+                    format!("  {}", raw_encoding(move_board, tiles)).as_str(),
+                    0.,
+                    TextFormat::simple(
+                        egui::TextStyle::Monospace.resolve(ui.style()),
+                        ui.visuals().weak_text_color(),
+                    ),
+                );
+            }
 
-                    ui.add_space(10.);
+            // Each board is a label that can be clicked on.
+            let board = ui.add(Label::new(layout).sense(Sense::click()));
+            // Screen readers announce the move instead of the text of the tiles.
+            board.widget_info(|| WidgetInfo::labeled(WidgetType::Button, &description));
+
+            // Hovering over the best move shows how it compares to the other moves.
+            let board = match board_click {
+                BoardClick::Roll(roll) if self.ranked_moves.is_some() => board.on_hover_ui(|ui| {
+                    self.alternatives_tooltip(ui, best_moves, roll, tiles, colors)
+                }),
+                _ => board,
+            };
+            if board.clicked() {
+                clicked_on = Some(board_click);
+            }
+        }
 
-                    // Displays the amount of games to be simulated.
-                    ui.label("Games to simulate:");
-                    ui.horizontal(|ui| {
-                        // The text box for the value to parse.
-                        let text_box = ui.add(egui::TextEdit::singleline(
-                            &mut self.unvalidated_games_to_simulate,
-                        ));
+        clicked_on
+    }
 
-                        // If the text can't be parsed as an unsigned int show an error.
-                        match u32::from_str(self.unvalidated_games_to_simulate.as_ref()) {
-                            Ok(to_simulate) => {
-                                self.games_to_simulate = to_simulate;
-                                self.could_parse_games = true;
-                            }
-                            Err(_) => {
-                                ui.label("⚠");
-                                self.could_parse_games = false;
-                            }
+    /// Draws the chance, best move, & second best move for each roll of the root board as a table.
+    /// If one of the moves was clicked on, then what should happen is returned.
+    fn moves_table(&self, ui: &mut Ui, best_moves: &Policy) -> Option<BoardClick> {
+        let mut clicked_on = None;
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto().at_least(100.))
+            .column(Column::auto())
+            .column(Column::remainder())
+            .header(20., |mut header| {
+                for title in ["Roll", "Chance", "Best move", "Win %", "Second best"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for roll in self.variant.rolls() {
+                    let alternatives = self.ranked_alternatives(best_moves, roll);
+                    let best_move = best_moves.best_move(self.root_board, roll.into());
+
+                    body.row(20., |mut row| {
+                        row.col(|ui| {
+                            ui.label(roll.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(roll_chance(roll));
+                        });
+
+                        // If there are no moves, then the game ends with this roll.
+                        if alternatives.is_empty() {
+                            row.col(|ui| {
+                                let game_over =
+                                    format!("Game over, score {}", board_value(self.root_board));
+                                if ui.link(game_over).clicked() {
+                                    clicked_on = Some(BoardClick::GameOver);
+                                }
+                            });
+                            row.col(|ui| {
+                                ui.label("-");
+                            });
+                            row.col(|ui| {
+                                ui.label("-");
+                            });
+                            return;
                         }
 
-                        // If the input is invalid then the text will lose focus.
-                        text_box.request_focus();
-                    });
+                        // If the roll wasn't simulated, then there is no best move to show.
+                        if best_move.is_none() {
+                            row.col(|ui| {
+                                ui.label("Not simulated");
+                            });
+                            row.col(|ui| {
+                                ui.label("?");
+                            });
+                            row.col(|ui| {
+                                ui.label("-");
+                            });
+                            return;
+                        }
 
-                    ui.add_space(10.);
+                        // The best move is always first.
+                        let (best_board, best_value) = alternatives[0];
+                        row.col(|ui| {
+                            let name = flipped_tiles(self.root_board, best_board).join(" & ");
+                            if ui.link(name).clicked() {
+                                clicked_on = Some(BoardClick::Roll(roll));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.label(format_win_chance(best_value));
+                        });
+                        row.col(|ui| match alternatives.get(1) {
+                            Some(&(second_board, second_value)) => {
+                                let name = format!(
+                                    "{} ({})",
+                                    flipped_tiles(self.root_board, second_board).join(" & "),
+                                    format_win_chance(second_value)
+                                );
+                                if ui.link(name).clicked() {
+                                    clicked_on = Some(BoardClick::Move(second_board));
+                                }
+                            }
+                            // The best move is the only move.
+                            None => {
+                                ui.label("-");
+                            }
+                        });
+                    });
+                }
+            });
 
-                    // If there isn't an ongoing calculation then display the option to start one.
-                    if !self.recalculation_in_progress {
-                        let recalculate_button =
-                            ui.button(RichText::new("Recalculate").color(Color32::LIGHT_RED));
+        clicked_on
+    }
 
-                        // Recalculates the values if the button is clicked.
-                        if recalculate_button.clicked() && self.could_parse_games {
-                            let receiver = Self::recalculate_best(self.games_to_simulate);
+    /// Moves to the bookmarked board, switching to its variant if needed.
+    fn jump_to_bookmark(&mut self, bookmark: Bookmark) {
+        if bookmark.variant != self.variant {
+            self.set_variant(bookmark.variant);
+        }
 
-                            // Default values for recalculation.
-                            self.recalculation_receiver = Some(receiver);
-                            self.recalculation_in_progress = true;
-                            self.recalculation_error = false;
-                        }
+        if bookmark.board != self.root_board {
+            self.navigate(bookmark.board);
+        }
+    }
 
-                        return;
-                    }
+    /// Moves to the given board, remembering the current root board so it can be moved back to.
+    fn navigate(&mut self, board: u16) {
+        self.previous_boards.push(self.root_board);
+        self.root_board = board;
+        self.next_boards.clear();
+    }
 
-                    // Will execute if there is an ongoing calculation.
+    /// Moves back to the previous root board, if there is one.
+    fn undo(&mut self) {
+        if let Some(last_board) = self.previous_boards.pop() {
+            self.next_boards.push(self.root_board);
+            self.root_board = last_board;
+        }
+    }
 
-                    // Spin! :)
-                    ui.spinner();
+    /// Moves forward to the root board that was last moved back from, if there is one.
+    fn redo(&mut self) {
+        if let Some(next_board) = self.next_boards.pop() {
+            self.previous_boards.push(self.root_board);
+            self.root_board = next_board;
+        }
+    }
 
-                    let receiver = match self.recalculation_receiver.as_ref() {
-                        Some(receiver) => receiver,
-                        // If there was no receiver then an error occurred
-                        None => {
-                            self.recalculation_error = true;
-                            return;
-                        }
-                    };
+    /// Returns the previous boards, the root board, & the next boards in the order they were visited.
+    fn history(&self) -> Vec<u16> {
+        let mut history = self.previous_boards.clone();
+        history.push(self.root_board);
+        history.extend(self.next_boards.iter().rev());
+        history
+    }
 
-                    let result = receiver.try_recv();
+    /// Moves to the board at the given index of the history.
+    fn jump_to(&mut self, index: usize) {
+        let mut history = self.history();
+        let Some(&board) = history.get(index) else {
+            return;
+        };
 
-                    // If the result was okay then the calculation finished successfully.
-                    if result.is_ok() {
-                        self.recalculation_in_progress = false;
-                        self.recalculation_receiver = None;
-                        self.recalculation_error = false;
-                        self.parsed_moves = parse_moves();
-                    }
+        let next_boards = history.split_off(index + 1);
+        history.truncate(index);
 
-                    if result.is_err() {
-                        match result.unwrap_err() {
-                            // If no message has been sent continue waiting.
-                            TryRecvError::Empty => {
-                                return;
-                            }
-                            // If the channel disconnected there must have been an error.
-                            TryRecvError::Disconnected => {
-                                self.recalculation_error = true;
-                            }
-                        }
-                    }
-                });
+        self.previous_boards = history;
+        self.root_board = board;
+        self.next_boards = next_boards.into_iter().rev().collect();
+    }
 
-            // Resets the shown moves when clicked.
-            if reset_button.clicked() {
-                self.root_board = 511;
-                self.previous_boards = Vec::new()
-            }
+    /// Saves the board view from the screenshot, if it has been taken.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_board_view(&mut self, context: &egui::Context) {
+        let screenshot = context.input(|input| {
+            input.raw.events.iter().find_map(|event| match event {
+                Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
         });
+
+        let Some(screenshot) = screenshot else {
+            return;
+        };
+        self.export_requested = false;
+
+        let image = export::crop(
+            &screenshot,
+            self.board_view_rect,
+            context.pixels_per_point(),
+        );
+        self.export_message = match export::save_png(&image) {
+            Ok(Some(path)) => Some(format!("Exported the board view to {}", path.display())),
+            Ok(None) => None,
+            Err(e) => Some(e),
+        };
     }
 
-    fn central_panel(&self, context: &egui::Context, ui: &mut Ui) -> Option<Vec<(Id, Rect)>> {
-        // Checks if best moves have been calculated.
-        if let Some(best_moves) = &self.parsed_moves {
-            // Creates a vec which will store the position & id of each displayed board.
-            let mut board_info = Vec::with_capacity(13);
+    /// Handles the keyboard shortcuts for undo & redo.
+    /// Ctrl+Z undoes, while Ctrl+Y or Ctrl+Shift+Z redoes.
+    fn history_shortcuts(&mut self, context: &egui::Context) {
+        let (undo, redo) = context.input_mut(|input| {
+            let redo = input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                || input.consume_key(Modifiers::COMMAND, Key::Y);
+            let undo = input.consume_key(Modifiers::COMMAND, Key::Z);
+            (undo, redo)
+        });
 
-            // Generates the layout for the root board.
-            let root_layout = Self::generate_root_board(self.root_board);
-            let gallery = context.fonts(|fonts| fonts.layout_job(root_layout));
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+    }
 
-            // Displays the root board.
-            ui.painter()
-                .galley(ui.next_widget_position(), gallery, Color32::WHITE);
-            // Saves the info about the root board to use later.
-            board_info.push(ui.allocate_space(Vec2::new(100., 30.)));
+    /// Draws a clickable bar of every visited board, with the root board highlighted.
+    fn breadcrumbs(&mut self, ui: &mut Ui) {
+        let history = self.history();
+        let current = self.previous_boards.len();
+
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, board) in history.into_iter().enumerate() {
+                if index != 0 {
+                    ui.label(">");
+                }
 
-            // Generates the layout for the best moves for each roll.
-            let mut board_layouts = Vec::with_capacity(12);
-            for roll in 2..13 {
-                let board_roll = BoardRoll::new(self.root_board, roll.into());
-                let best_move = *best_moves.get(&board_roll).unwrap_or(&0u16);
+                let mut text = RichText::new(Self::board_name(board));
+                if index == current {
+                    text = text.strong().underline();
+                }
 
-                board_layouts.push(Self::generate_board(self.root_board, roll, best_move));
+                if ui.add(Label::new(text).sense(Sense::click())).clicked() {
+                    clicked = Some(index);
+                }
             }
+        });
+        ui.add_space(5.);
 
-            // Iterates over the generate board & displays them.
-            for layout in board_layouts {
-                let gallery = context.fonts(|fonts| fonts.layout_job(layout));
+        if let Some(index) = clicked {
+            self.jump_to(index);
+        }
+    }
 
-                // Draws the board
-                ui.painter()
-                    .galley(ui.next_widget_position(), gallery, Color32::WHITE);
-                // Saves the info about the drawn board for later use.
-                board_info.push(ui.allocate_space(Vec2::new(100., 20.)));
-            }
+    /// Creates a short name for the board, which is the alive pieces from highest to lowest.
+    fn board_name(board: u16) -> String {
+        let alive: Vec<String> = Self::board_to_array(board, game_core::MAX_TILES)
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, alive)| **alive)
+            .map(|(index, _)| (index + 1).to_string())
+            .collect();
+
+        // The pieces are only separated once they can have two digits, so they can still be told apart.
+        let alive = match board >> 9 {
+            0 => alive.concat(),
+            _ => alive.join(" "),
+        };
 
-            return Some(board_info);
+        match alive.is_empty() {
+            true => String::from("Shut"),
+            false => alive,
         }
+    }
 
-        ui.heading("No moves found ;-;");
-        None
+    /// Gets every legal move for the root board & roll, along with its value if it is known.
+    /// The best move is first, followed by the other moves from the highest value to the lowest.
+    fn ranked_alternatives(&self, best_moves: &Policy, roll: u8) -> Vec<(u16, Option<u16>)> {
+        let ranked = self.ranked_moves.as_ref().and_then(|ranked_moves| {
+            ranked_moves.get(&BoardRoll::new(self.root_board, roll.into()))
+        });
+
+        let mut alternatives: Vec<(u16, Option<u16>)> =
+            game_core::enumerate_moves(self.root_board, roll)
+                .into_iter()
+                .map(|move_board| {
+                    let value = ranked
+                        .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == move_board))
+                        .map(|ranked| ranked.value);
+                    (move_board, value)
+                })
+                .collect();
+
+        // The best move is always first, even if the ranked moves are missing.
+        let best_move = best_moves.best_move(self.root_board, roll.into());
+        alternatives.sort_by(|one, two| {
+            (Some(two.0) == best_move)
+                .cmp(&(Some(one.0) == best_move))
+                .then(two.1.cmp(&one.1))
+        });
+
+        alternatives
     }
 
-    fn generate_root_board(root_board: u16) -> LayoutJob {
-        let root_pieces = Self::board_to_array(root_board);
-        let mut board_text = LayoutJob::default();
+    /// Draws the highest ranked moves for the root board & roll, along with their win chance.
+    fn alternatives_tooltip(
+        &self,
+        ui: &mut Ui,
+        best_moves: &Policy,
+        roll: u8,
+        tiles: u8,
+        colors: &BoardColors,
+    ) {
+        let alternatives = self.ranked_alternatives(best_moves, roll);
+
+        ui.label(format!("The best moves for a roll of {roll}:"));
+        for (move_board, value) in alternatives.iter().take(HOVER_ALTERNATIVES) {
+            let mut layout =
+                Self::generate_board(self.root_board, roll, *move_board, tiles, colors);
+            layout.append(
+                format!(" {}", format_win_chance(*value)).as_str(),
+                0.,
+                TextFormat::default(),
+            );
+            ui.label(layout);
+        }
+
+        if alternatives.len() > HOVER_ALTERNATIVES {
+            ui.label(format!(
+                "{} more moves, pin the roll to see them all.",
+                alternatives.len() - HOVER_ALTERNATIVES
+            ));
+        }
+    }
 
-        board_text.append(
+    /// Generates the layout for the root board.
+    /// The first layout is the button to go back, followed by the layout & index of each piece,
+    /// from the highest to lowest pieces.
+    fn generate_root_board(
+        root_board: u16,
+        tiles: u8,
+        colors: &BoardColors,
+    ) -> (LayoutJob, Vec<(u8, LayoutJob)>) {
+        let root_pieces = Self::board_to_array(root_board, tiles);
+        let mut back_text = LayoutJob::default();
+
+        back_text.append(
             "<--",
             0.,
             TextFormat {
-                background: Color32::BLUE,
+                background: colors.roll,
                 ..Default::default()
             },
         );
 
-        board_text.append(" || ", 0., TextFormat::default());
+        back_text.append(" || ", 0., TextFormat::default());
 
         // Iterates from the highest to lowest pieces.
-        for piece_index in (0..9u8).rev() {
+        let mut pieces = Vec::with_capacity(tiles as usize);
+        for piece_index in (0..tiles).rev() {
             let root_piece = root_pieces[piece_index as usize];
 
             let background = match root_piece {
                 // If the piece is alive then it should be green.
-                true => Color32::DARK_GREEN,
+                true => colors.alive,
                 // If the piece is down, then it should be grayed out.
-                false => Color32::DARK_GRAY,
+                false => colors.down,
             };
 
             // Gets the value of the piece as a string.
             let mut piece_value = (piece_index + 1).to_string();
             // Adds a space for padding.
-            piece_value.push_str(" ");
+            piece_value.push(' ');
 
-            // Adds the piece string to the layout
-            board_text.append(
+            // Creates a separate layout for each piece, so they can be clicked on individually.
+            let mut piece_text = LayoutJob::default();
+            piece_text.append(
                 piece_value.as_str(),
                 0.,
                 TextFormat {
@@ -378,51 +2468,36 @@ impl Main {
                     ..Default::default()
                 },
             );
+            pieces.push((piece_index, piece_text));
         }
 
-        board_text
+        (back_text, pieces)
     }
 
-    fn generate_board(root_board: u16, roll_value: u8, move_board: u16) -> LayoutJob {
-        let root_pieces = Self::board_to_array(root_board);
-        let move_pieces = Self::board_to_array(move_board);
-
-        let mut board_text = LayoutJob::default();
-
-        // Adds the roll value first.
-        let mut roll_string = roll_value.to_string();
-        roll_string.push_str(" ");
-
-        // If the roll is only a single digit add an extra two spaces, so
-        // it lines up with the two digit rolls.
-        if roll_value < 10 {
-            roll_string.push_str("  ");
-        }
-
-        board_text.append(
-            roll_string.as_str(),
-            0.,
-            TextFormat {
-                background: Color32::BLUE,
-                ..Default::default()
-            },
-        );
+    fn generate_board(
+        root_board: u16,
+        roll_value: u8,
+        move_board: u16,
+        tiles: u8,
+        colors: &BoardColors,
+    ) -> LayoutJob {
+        let root_pieces = Self::board_to_array(root_board, tiles);
+        let move_pieces = Self::board_to_array(move_board, tiles);
 
-        // Adds padding text to separate the roll value from the board.
-        board_text.append(" || ", 0., TextFormat::default());
+        let mut board_text = Self::generate_roll(roll_value, colors);
 
         // Iterates from the highest to lowest pieces.
-        for piece_index in (0..9u8).rev() {
+        for piece_index in (0..tiles).rev() {
             let root_piece = root_pieces[piece_index as usize];
             let move_piece = move_pieces[piece_index as usize];
 
-            let mut background = match (root_piece, move_piece) {
+            let background = match (root_piece, move_piece) {
                 // If both pieces are alive, it wasn't affected in the move.
-                (true, true) => Color32::DARK_GREEN,
+                (true, true) => colors.alive,
                 // If both piece are down, then they should be grayed out.
-                (false, false) => Color32::DARK_GRAY,
+                (false, false) => colors.down,
                 // If the root piece is alive & the move one isn't then it will get knocked down.
-                (true, false) => Color32::GOLD,
+                (true, false) => colors.knocked,
                 // It shouldn't be possible that a root piece is dead, yet a move piece is alive.
                 (false, true) => {
                     return LayoutJob::simple_singleline(
@@ -433,13 +2508,8 @@ impl Main {
                 }
             };
 
-            // If the move is a dying one then colour every piece gray.
-            if move_board == 0 {
-                background = Color32::DARK_GRAY;
-            }
-
             let mut piece_value = (piece_index + 1).to_string();
-            piece_value.push_str(" ");
+            piece_value.push(' ');
 
             board_text.append(
                 piece_value.as_str(),
@@ -454,17 +2524,62 @@ impl Main {
         board_text
     }
 
-    /// Converts a binary representation of the board to an array.
+    /// Generates the layout for a roll that has no valid moves, which ends the game with the score of the root board.
+    fn generate_dying_board(root_board: u16, roll_value: u8, colors: &BoardColors) -> LayoutJob {
+        let mut board_text = Self::generate_roll(roll_value, colors);
+
+        board_text.append(
+            format!("GAME OVER — score {}", board_value(root_board)).as_str(),
+            0.,
+            TextFormat {
+                background: colors.down,
+                ..Default::default()
+            },
+        );
+
+        board_text
+    }
+
+    /// Generates the layout for the roll value at the start of a board, along with the padding after it.
+    fn generate_roll(roll_value: u8, colors: &BoardColors) -> LayoutJob {
+        let mut roll_text = LayoutJob::default();
+
+        // Adds the roll value first.
+        let mut roll_string = roll_value.to_string();
+        roll_string.push(' ');
+
+        // If the roll is only a single digit add an extra two spaces, so
+        // it lines up with the two digit rolls.
+        if roll_value < 10 {
+            roll_string.push_str("  ");
+        }
+
+        roll_text.append(
+            roll_string.as_str(),
+            0.,
+            TextFormat {
+                background: colors.roll,
+                ..Default::default()
+            },
+        );
+
+        // Adds padding text to separate the roll value from the board.
+        roll_text.append(" || ", 0., TextFormat::default());
+
+        roll_text
+    }
+
+    /// Converts a binary representation of the board with the given amount of tiles to a vec.
     /// The 0th index represents piece 1.
     /// The 8th index represents piece 9.
-    fn board_to_array(board: u16) -> [bool; 9] {
-        let mut root_pieces = [false; 9];
+    fn board_to_array(board: u16, tiles: u8) -> Vec<bool> {
+        let mut root_pieces = vec![false; tiles as usize];
 
-        for index in 0..9 {
+        for (index, root_piece) in root_pieces.iter_mut().enumerate() {
             let piece = board >> index;
             // if the piece is alive mark it as so.
             if piece & 1 == 1 {
-                root_pieces[index] = true;
+                *root_piece = true;
             }
         }
 
@@ -472,7 +2587,10 @@ impl Main {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
+    let _guard = logging::init("gui", None);
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size((350.0, 550.0)),
         ..eframe::NativeOptions::default()
@@ -481,6 +2599,22 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         WINDOW_NAME,
         native_options,
-        Box::new(|_| Box::<Main>::default()),
+        Box::new(|creation_context| Box::new(Main::new(creation_context))),
     )
 }
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    let web_options = eframe::WebOptions::default();
+
+    wasm_bindgen_futures::spawn_local(async {
+        eframe::WebRunner::new()
+            .start(
+                CANVAS_ID,
+                web_options,
+                Box::new(|creation_context| Box::new(Main::new(creation_context))),
+            )
+            .await
+            .expect("The web app couldn't be started.");
+    });
+}
@@ -0,0 +1,895 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
+use std::str::FromStr;
+use std::sync::mpsc::TryRecvError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eframe::epaint::Color32;
+use egui::ahash::HashMap;
+use egui::{Label, RichText, Sense, Ui, Window};
+
+use compute::{BoardRoll, Policy, RankedMove};
+use game_core::{DiceRoll, STANDARD_TILES};
+use networked::client_states::ClientError;
+use networked::server_state::{Advice, Advisor, Host, Lobby};
+use networked::{ChannelError, Channels};
+use protocol::{
+    ClientMessages, ClientMove, ClientMovedBoard, ClientToMove, RollRequest, ServerError,
+    ServerMessages, SpectatedTurn, SpectatorEvent, PROTOCOL_VERSION,
+};
+use stats::{GameMode, GameRecord, Outcome};
+
+use crate::play::tiles_layout;
+use crate::settings::BoardColors;
+use crate::Main;
+
+// The id for the connect window.
+const CONNECT: &str = "Connect";
+// The id for the host window.
+const HOST_GAME: &str = "Host Game";
+
+/// The board with every piece alive, which every game starts from.
+/// Networked games are always played with the standard variant.
+const FULL_BOARD: u16 = 511;
+/// How long to wait for the server to accept the connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the connected client is currently doing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// Waiting for the server to accept the join request.
+    Joining,
+    /// Waiting for every player to be ready.
+    Lobby,
+    /// Waiting for the other players to make their moves.
+    Waiting,
+    /// Choosing how many dice to roll.
+    Rolling,
+    /// Choosing the move to make with the rolled dice, along with the move the server hinted, if it did.
+    Moving(ClientToMove, Option<u16>),
+    /// Watching the games of the other players.
+    Spectating,
+}
+
+/// The games being watched by a spectating client.
+#[derive(Debug, Default)]
+struct Spectated {
+    /// The board of each player, in the order the server plays them.
+    boards: Vec<u16>,
+    /// Whether each player can no longer make moves.
+    out: Vec<bool>,
+    /// The most recent turn, which is highlighted.
+    last_turn: Option<SpectatedTurn>,
+    /// The result of the last game that was watched.
+    last_result: Option<String>,
+}
+
+impl Spectated {
+    /// Updates the watched games with the event the server sent.
+    fn handle(&mut self, event: SpectatorEvent) {
+        match event {
+            SpectatorEvent::GameStarted(players) => {
+                self.boards = vec![FULL_BOARD; players as usize];
+                self.out = vec![false; players as usize];
+                self.last_turn = None;
+                self.last_result = None;
+            }
+            SpectatorEvent::Turn(turn) => {
+                let player = turn.player as usize;
+                // Ignores players the game wasn't started with, as the event was missed.
+                if player >= self.boards.len() {
+                    return;
+                }
+                match turn.moved_board {
+                    Some(moved_board) => self.boards[player] = moved_board,
+                    None => self.out[player] = true,
+                }
+                self.last_turn = Some(turn);
+            }
+            SpectatorEvent::GameEnded(winning) => {
+                self.last_result = Some(format!(
+                    "The game ended with a winning score of {}.",
+                    winning.0
+                ))
+            }
+        }
+    }
+
+    /// Draws a compact row with the board & score of every player, marking the user's own row.
+    fn players(&self, ui: &mut Ui, own_index: Option<u8>, colors: &BoardColors) {
+        egui::Grid::new("Players")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (player, &board) in self.boards.iter().enumerate() {
+                    match own_index == Some(player as u8) {
+                        true => ui.label(RichText::new("You").strong()),
+                        false => ui.label(format!("Player {}", player + 1)),
+                    };
+                    ui.label(tiles_layout(board, STANDARD_TILES, colors));
+
+                    let score = game_core::board_value(board);
+                    match self.out[player] {
+                        true => ui.label(format!("Score: {score} (out)")),
+                        false => ui.label(format!("Score: {score}")),
+                    };
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Draws the board of every player, highlighting the most recent roll & move.
+    fn show(&self, ui: &mut Ui, colors: &BoardColors) {
+        if let Some(result) = &self.last_result {
+            ui.heading(result);
+            ui.add_space(10.);
+        }
+
+        if self.boards.is_empty() {
+            ui.label("Waiting for a game to start...");
+            ui.spinner();
+            return;
+        }
+
+        for (player, &board) in self.boards.iter().enumerate() {
+            let status = match self.out[player] {
+                true => " (out)",
+                false => "",
+            };
+            ui.label(RichText::new(format!("Player {}{status}", player + 1)).strong());
+
+            let last_turn = self.last_turn.filter(|turn| turn.player as usize == player);
+            let layout = match last_turn {
+                Some(turn) => {
+                    ui.label(format!("Rolled {}.", turn.roll));
+                    match turn.moved_board {
+                        Some(moved_board) => Main::generate_board(
+                            turn.board,
+                            turn.roll,
+                            moved_board,
+                            STANDARD_TILES,
+                            colors,
+                        ),
+                        None => Main::generate_dying_board(turn.board, turn.roll, colors),
+                    }
+                }
+                None => tiles_layout(board, STANDARD_TILES, colors),
+            };
+            ui.label(layout);
+            ui.add_space(5.);
+        }
+    }
+}
+
+/// A connection to a networked game.
+struct Connection {
+    channels: Channels<ServerMessages, ClientMessages>,
+    phase: Phase,
+    /// The board of this client in the current game.
+    board: u16,
+    /// The amount of players connected to the server.
+    connected: u8,
+    /// The amount of players that are ready.
+    ready_count: u8,
+    /// Whether this client is ready for the game to start.
+    ready: bool,
+    /// The result of the last game that was played.
+    last_result: Option<String>,
+    /// The last error the server sent.
+    last_error: Option<String>,
+    /// Whether this client joined to watch the games rather than play in them.
+    spectator: bool,
+    /// The games being played, as broadcast by the server.
+    /// Spectators only see these, while players see them alongside their own board.
+    spectated: Spectated,
+    /// The index the server refers to this client by in the current game, if this client is playing.
+    index: Option<u8>,
+    /// The amount of this client's moves in the current game that had a known best move.
+    judged_moves: u32,
+    /// The amount of this client's moves in the current game that were the best move.
+    best_moves_made: u32,
+    /// Whether a hint was requested for the move being made, which is only requested once.
+    hint_requested: bool,
+    /// The chance of winning with the hinted move, if the server sent it.
+    hint_win_rate: Option<f32>,
+}
+
+/// The state of the networked multiplayer.
+pub struct NetworkState {
+    /// Whether the window to connect to a server is open.
+    pub connect_window_open: bool,
+    /// The unvalidated IP address of the server.
+    address: String,
+    /// The unvalidated port of the server.
+    port: String,
+    /// The name displayed to the other players.
+    name: String,
+    /// Whether to watch the games rather than play in them.
+    spectate: bool,
+    /// The connection to the server, if connected.
+    connection: Option<Connection>,
+    /// The reason the last connection failed or ended.
+    error: Option<String>,
+    /// Whether the window to host a server is open.
+    pub host_window_open: bool,
+    /// The unvalidated port to host the server on.
+    host_port: String,
+    /// Whether the hosted server hints the players the best move.
+    host_assisted: bool,
+    /// The amount of hints each player of the hosted server can request in each game.
+    host_hint_budget: u8,
+    /// The server being hosted, if one is.
+    host: Option<Host>,
+    /// The reason the server couldn't be hosted.
+    host_error: Option<String>,
+    /// The stats of the games this client finished, which haven't been recorded yet.
+    finished: Vec<GameRecord>,
+}
+
+impl Default for NetworkState {
+    fn default() -> Self {
+        NetworkState {
+            connect_window_open: false,
+            address: String::from("127.0.0.1"),
+            port: protocol::DEFAULT_PORT.to_string(),
+            name: String::from("Player"),
+            spectate: false,
+            connection: None,
+            error: None,
+            host_window_open: false,
+            host_port: protocol::DEFAULT_PORT.to_string(),
+            host_assisted: false,
+            host_hint_budget: 0,
+            host: None,
+            host_error: None,
+            finished: Vec::new(),
+        }
+    }
+}
+
+impl NetworkState {
+    /// Sets the port that is connected to & hosted on, until the user changes it.
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port.to_string();
+        self.host_port = port.to_string();
+    }
+
+    /// Returns true if there is a connection to a server.
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Draws the window to connect to a server.
+    /// Returns true if a connection was made.
+    pub fn connect_window(&mut self, context: &egui::Context) -> bool {
+        let mut connected = false;
+        let mut open = self.connect_window_open;
+
+        Window::new(CONNECT).open(&mut open).show(context, |ui| {
+            ui.set_width_range(100f32..=200f32);
+
+            ui.label("IP address:");
+            ui.text_edit_singleline(&mut self.address);
+            ui.label("Port:");
+            ui.text_edit_singleline(&mut self.port);
+            ui.label("Name:");
+            ui.add_enabled(!self.spectate, egui::TextEdit::singleline(&mut self.name));
+            ui.checkbox(&mut self.spectate, "Spectate")
+                .on_hover_text("Watch the games without playing in them.");
+
+            ui.add_space(10.);
+
+            if ui.button("Connect").clicked() {
+                match self.connect() {
+                    Ok(connection) => {
+                        self.connection = Some(connection);
+                        self.error = None;
+                        connected = true;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+            }
+        });
+
+        self.connect_window_open = open && !connected;
+        connected
+    }
+
+    /// Returns true if a server is being hosted.
+    pub fn is_hosting(&self) -> bool {
+        self.host.is_some()
+    }
+
+    /// Draws the window to host a server, showing who has joined it.
+    /// The best moves are hinted to the players, along with their win rates if the moves were ranked,
+    /// so they should be for the standard variant.
+    /// Returns true if the host joined their own server.
+    pub fn host_window(
+        &mut self,
+        context: &egui::Context,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) -> bool {
+        let mut joined = false;
+        let mut open = self.host_window_open;
+
+        Window::new(HOST_GAME).open(&mut open).show(context, |ui| {
+            ui.set_width_range(150f32..=250f32);
+
+            let Some(host) = &self.host else {
+                ui.label("Port:");
+                ui.text_edit_singleline(&mut self.host_port);
+                ui.add_enabled(
+                    best_moves.is_some(),
+                    egui::Checkbox::new(&mut self.host_assisted, "Assisted"),
+                )
+                .on_hover_text("Hint the players the best move.")
+                .on_disabled_hover_text("The best moves have to be loaded to hint them.");
+                ui.add_enabled_ui(best_moves.is_some(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Hints per game:");
+                        ui.add(egui::DragValue::new(&mut self.host_hint_budget));
+                    })
+                    .response
+                    .on_hover_text("How many hints each player can request in each game.");
+                });
+                ui.add_space(10.);
+
+                if ui.button("Host").clicked() {
+                    match self.start_hosting(best_moves, ranked_moves) {
+                        Ok(host) => {
+                            self.host = Some(host);
+                            self.host_error = None;
+                        }
+                        Err(e) => self.host_error = Some(e),
+                    }
+                }
+
+                if let Some(error) = &self.host_error {
+                    ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+                }
+                return;
+            };
+
+            let port = host.address().port();
+            let status = host.status();
+
+            ui.label("Other players can join at:");
+            ui.add(Label::new(
+                RichText::new(format!("{}:{port}", local_ip())).monospace(),
+            ));
+            ui.add_space(10.);
+
+            ui.label(format!("Connected players: {}", status.players.len()));
+            for (name, ready) in &status.players {
+                match ready {
+                    true => ui.label(format!("{name} (ready)")),
+                    false => ui.label(name),
+                };
+            }
+            if status.spectators > 0 {
+                ui.label(format!("Spectators: {}", status.spectators));
+            }
+            ui.add_space(10.);
+
+            // The server only has the best moves to hint if they were loaded when it started.
+            ui.add_enabled_ui(status.advised, |ui| {
+                let mut assisted = status.assisted;
+                let toggle = ui
+                    .checkbox(&mut assisted, "Assisted")
+                    .on_hover_text("Hint the players the best move.");
+                if toggle.changed() {
+                    host.set_assisted(assisted);
+                }
+
+                let mut hint_budget = status.hint_budget;
+                ui.horizontal(|ui| {
+                    ui.label("Hints per game:");
+                    if ui.add(egui::DragValue::new(&mut hint_budget)).changed() {
+                        host.set_hint_budget(hint_budget);
+                    }
+                })
+                .response
+                .on_hover_text("How many hints each player can request, from the next game.");
+            });
+
+            if status.playing {
+                ui.label("A game is being played.");
+            } else {
+                // Starts the game even if some players aren't ready.
+                let start =
+                    ui.add_enabled(!status.players.is_empty(), egui::Button::new("Start game"));
+                if start.clicked() {
+                    host.start_game();
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.connection.is_none(), egui::Button::new("Join"))
+                    .on_hover_text("Join the hosted game as a player.")
+                    .clicked()
+                {
+                    self.address = Ipv4Addr::LOCALHOST.to_string();
+                    self.port = port.to_string();
+                    match self.connect() {
+                        Ok(connection) => {
+                            self.connection = Some(connection);
+                            self.error = None;
+                            joined = true;
+                        }
+                        Err(e) => self.host_error = Some(e),
+                    }
+                }
+
+                // The server finishes the current game before it stops.
+                if ui.button("Stop hosting").clicked() {
+                    self.host = None;
+                }
+            });
+
+            if let Some(error) = &self.host_error {
+                ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+            }
+        });
+
+        self.host_window_open = open;
+        joined
+    }
+
+    /// Starts the server on the chosen port, accepting connections from any address.
+    /// The players can be hinted the given best moves, along with their win rates if the moves were ranked.
+    fn start_hosting(
+        &self,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) -> Result<Host, String> {
+        let port = u16::from_str(self.host_port.trim()).map_err(|_| "Invalid port.")?;
+        let ranked_moves = ranked_moves.cloned().unwrap_or_default();
+        let advisor = best_moves.cloned().map(|policy| -> Advisor {
+            Arc::new(move |board, roll| {
+                let roll = DiceRoll::from(roll);
+                let best_move = policy.best_move(board, roll)?;
+                // The ranked values are win rates in tenths of a percent.
+                let win_rate = ranked_moves
+                    .get(&BoardRoll::new(board, roll))
+                    .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == best_move))
+                    .map(|ranked| ranked.value as f32 / 1000.);
+                Some(Advice {
+                    best_move,
+                    win_rate,
+                })
+            })
+        });
+        let lobby = Lobby {
+            advisor,
+            assisted: self.host_assisted,
+            hint_budget: self.host_hint_budget,
+            ..Lobby::default()
+        };
+        networked::server_state::host(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port),
+            lobby,
+        )
+        .map_err(|e| format!("Couldn't host server: {e}"))
+    }
+
+    /// Connects to the server & requests to join the game.
+    fn connect(&self) -> Result<Connection, String> {
+        let ip_address = IpAddr::from_str(self.address.trim()).map_err(|_| "Invalid IP.")?;
+        let port = u16::from_str(self.port.trim()).map_err(|_| "Invalid port.")?;
+
+        let stream =
+            TcpStream::connect_timeout(&SocketAddr::new(ip_address, port), CONNECT_TIMEOUT)
+                .map_err(|e| format!("Couldn't connect to server: {e}"))?;
+
+        // The mac address is only used to identify the player, so a default one is fine.
+        let mac_address = mac_address2::get_mac_address()
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
+        let opt_in = match self.spectate {
+            true => ClientMessages::OptInForSpectating,
+            false => ClientMessages::OptInForPlaying {
+                mac_address,
+                name: self.name.clone(),
+            },
+        };
+
+        let channels = networked::initialize_channels(stream)
+            .map_err(|e| format!("Couldn't set up the connection: {e}"))?;
+        // The server checks the client speaks the same version of the protocol before it opts in.
+        channels
+            .writing
+            .send(ClientMessages::Version(PROTOCOL_VERSION))
+            .and_then(|_| channels.writing.send(opt_in))
+            .map_err(|_| "Couldn't send join request.")?;
+
+        Ok(Connection {
+            channels,
+            phase: Phase::Joining,
+            board: FULL_BOARD,
+            connected: 0,
+            ready_count: 0,
+            ready: false,
+            last_result: None,
+            last_error: None,
+            spectator: self.spectate,
+            spectated: Spectated::default(),
+            index: None,
+            judged_moves: 0,
+            best_moves_made: 0,
+            hint_requested: false,
+            hint_win_rate: None,
+        })
+    }
+
+    /// Returns why the last connection was closed, if it wasn't closed by the user.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Closes the connection to the server.
+    fn disconnect(&mut self, reason: Option<String>) {
+        self.connection = None;
+        self.error = reason;
+    }
+
+    /// Returns the stats of the games finished since the last call, in the order they finished.
+    pub fn take_finished(&mut self) -> Vec<GameRecord> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Handles every message the server has sent since the last call.
+    pub fn poll(&mut self) {
+        let Some(connection) = self.connection.as_mut() else {
+            return;
+        };
+
+        loop {
+            let message = match connection.channels.reading.try_recv() {
+                Ok(Ok(message)) => message,
+                Ok(Err(ChannelError::BadPacket(packet))) => {
+                    connection.last_error = Some(format!("Server sent a bad packet: {packet}"));
+                    continue;
+                }
+                // The connection is closed after a read error.
+                Ok(Err(ChannelError::ReadError(_))) => continue,
+                Err(TryRecvError::Empty) => return,
+                // Servers older than the version exchange drop the client for sending a message they can't read.
+                Err(TryRecvError::Disconnected) if connection.phase == Phase::Joining => {
+                    return self.disconnect(Some(ClientError::OlderServer.to_string()))
+                }
+                Err(TryRecvError::Disconnected) => {
+                    return self.disconnect(Some(String::from("Disconnected from server.")))
+                }
+            };
+
+            match message {
+                ServerMessages::Version(PROTOCOL_VERSION) => {}
+                ServerMessages::Version(version)
+                | ServerMessages::Error(ServerError::IncompatibleVersion(version)) => {
+                    let error = ClientError::IncompatibleVersion(version);
+                    return self.disconnect(Some(error.to_string()));
+                }
+                ServerMessages::OptInAccept => {
+                    connection.phase = match connection.spectator {
+                        true => Phase::Spectating,
+                        false => Phase::Lobby,
+                    }
+                }
+                ServerMessages::OptInDeny => {
+                    return self.disconnect(Some(String::from("Connection refused.")))
+                }
+                ServerMessages::PlayersConnected(amount) => connection.connected = amount,
+                ServerMessages::PlayersReady(amount) => connection.ready_count = amount,
+                ServerMessages::PlayerIndex(index) => connection.index = Some(index),
+                ServerMessages::QueryClientRoll => connection.phase = Phase::Rolling,
+                ServerMessages::QueryClientForMove(to_move, hint) => {
+                    connection.board = match to_move {
+                        ClientToMove::OneDice(board, _) => board,
+                        ClientToMove::TwoDice { board, .. } => board,
+                    };
+                    connection.phase = Phase::Moving(to_move, hint);
+                    connection.hint_requested = false;
+                    connection.hint_win_rate = None;
+                }
+                ServerMessages::Hint {
+                    best_move,
+                    win_rate,
+                } => {
+                    // The hint is only needed while the move it's for is being made.
+                    if let Phase::Moving(to_move, _) = connection.phase {
+                        connection.phase = Phase::Moving(to_move, best_move);
+                        connection.hint_win_rate = win_rate;
+                    }
+                }
+                ServerMessages::SendWin => {
+                    let score = game_core::board_value(connection.board);
+                    self.finished
+                        .push(connection.game_record(Outcome::Won, score));
+                    connection.end_game(String::from("You won!"))
+                }
+                ServerMessages::SendDraw(drawing) => {
+                    let score = game_core::board_value(connection.board);
+                    self.finished
+                        .push(connection.game_record(Outcome::Drew, score));
+                    connection.end_game(format!("You drew with {} other players.", drawing.0))
+                }
+                ServerMessages::SendLoss(winning) => {
+                    self.finished
+                        .push(connection.game_record(Outcome::Lost, winning.0));
+                    connection.end_game(format!("You lost. The winning score was {}.", winning.0))
+                }
+                ServerMessages::Spectate(event) => connection.spectated.handle(event),
+                ServerMessages::Error(error) => {
+                    connection.last_error = Some(format!("Server error: {error:?}"))
+                }
+                // These are skipped by the connection before they're received.
+                ServerMessages::Unknown => {}
+            }
+        }
+    }
+
+    /// Draws the lobby or the current game, handling the input of the user.
+    /// The best moves are only used to work out the user's accuracy, so should be for the standard variant.
+    pub fn show(&mut self, ui: &mut Ui, colors: &BoardColors, best_moves: Option<&Policy>) {
+        let Some(connection) = self.connection.as_mut() else {
+            ui.heading("Not connected");
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+            }
+            if ui.button("Connect").clicked() {
+                self.connect_window_open = true;
+            }
+            return;
+        };
+
+        if ui.button("Disconnect").clicked() {
+            return self.disconnect(None);
+        }
+
+        ui.add_space(10.);
+
+        if let Some(error) = &connection.last_error {
+            ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+        }
+
+        // Shows how the other players are doing during the game, so the user can judge their risks.
+        let in_game = matches!(
+            connection.phase,
+            Phase::Waiting | Phase::Rolling | Phase::Moving(..)
+        );
+        if in_game && !connection.spectated.boards.is_empty() {
+            connection.spectated.players(ui, connection.index, colors);
+            ui.add_space(10.);
+        }
+
+        let sent = match connection.phase {
+            Phase::Joining => {
+                ui.label("Joining...");
+                ui.spinner();
+                Ok(())
+            }
+            Phase::Lobby => connection.lobby(ui),
+            Phase::Waiting => {
+                ui.label(tiles_layout(connection.board, STANDARD_TILES, colors));
+                ui.label("Waiting for the other players...");
+                ui.spinner();
+                Ok(())
+            }
+            Phase::Rolling => connection.rolling(ui, colors),
+            Phase::Moving(to_move, hint) => {
+                connection.moving(ui, colors, to_move, hint, best_moves)
+            }
+            Phase::Spectating => {
+                connection.spectated.show(ui, colors);
+                Ok(())
+            }
+        };
+
+        if sent.is_err() {
+            self.disconnect(Some(String::from("Disconnected from server.")));
+        }
+    }
+}
+
+impl Connection {
+    /// Sends the message to the server.
+    /// If the message couldn't be sent, then the server has disconnected.
+    fn write(&self, message: ClientMessages) -> Result<(), ()> {
+        self.channels.writing.send(message).map_err(|_| ())
+    }
+
+    /// Returns the stats of the game that just finished, with the given outcome & winning score.
+    /// The other players are named by their turn order, as the server doesn't send their names.
+    fn game_record(&self, outcome: Outcome, winning_score: u8) -> GameRecord {
+        let opponents = (0..self.spectated.boards.len())
+            .filter(|player| self.index != Some(*player as u8))
+            .map(|player| format!("Player {}", player + 1))
+            .collect();
+
+        GameRecord::now(
+            GameMode::Networked,
+            opponents,
+            STANDARD_TILES,
+            game_core::board_value(self.board),
+            winning_score,
+            outcome,
+        )
+        .with_accuracy(self.best_moves_made, self.judged_moves)
+    }
+
+    /// Goes back to the lobby after a game has finished.
+    fn end_game(&mut self, result: String) {
+        self.last_result = Some(result);
+        self.last_error = None;
+        self.phase = Phase::Lobby;
+        self.board = FULL_BOARD;
+        self.ready = false;
+        self.judged_moves = 0;
+        self.best_moves_made = 0;
+    }
+
+    /// Draws the amount of connected & ready players, along with the option to ready up.
+    fn lobby(&mut self, ui: &mut Ui) -> Result<(), ()> {
+        if let Some(result) = &self.last_result {
+            ui.heading(result);
+            ui.add_space(10.);
+        }
+
+        ui.label(format!("Connected players: {}", self.connected));
+        ui.label(format!("Ready players: {}", self.ready_count));
+
+        if ui.checkbox(&mut self.ready, "Ready").changed() {
+            self.write(ClientMessages::ReadyForStart(self.ready))?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the options for how many dice to roll.
+    fn rolling(&mut self, ui: &mut Ui, colors: &BoardColors) -> Result<(), ()> {
+        ui.label(tiles_layout(self.board, STANDARD_TILES, colors));
+        ui.label("Your turn to roll.");
+
+        let mut roll_request = None;
+        ui.horizontal(|ui| {
+            if ui.button("Roll both dice").clicked() {
+                roll_request = Some(RollRequest::BothDice);
+            }
+
+            // A single dice can only be rolled once the 7, 8, & 9 pieces are knocked down.
+            let single_dice = ui.add_enabled(
+                game_core::can_roll_single(self.board),
+                egui::Button::new("Roll one dice"),
+            );
+            if single_dice.clicked() {
+                roll_request = Some(RollRequest::SingleDice);
+            }
+        });
+
+        if let Some(roll_request) = roll_request {
+            self.last_error = None;
+            self.phase = Phase::Waiting;
+            self.write(ClientMessages::ChosenRoll(roll_request))?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the moves that can be made with the rolled dice, marking the move the server hinted.
+    /// The chosen move is compared against the best move, if it's known, to work out the user's accuracy.
+    fn moving(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        to_move: ClientToMove,
+        hint: Option<u16>,
+        best_moves: Option<&Policy>,
+    ) -> Result<(), ()> {
+        let (board, roll) = match to_move {
+            ClientToMove::OneDice(board, dice) => {
+                ui.label(format!("You rolled {dice}."));
+                (board, dice)
+            }
+            ClientToMove::TwoDice {
+                board,
+                dice_1,
+                dice_2,
+            } => {
+                ui.label(format!(
+                    "You rolled {} ({dice_1} & {dice_2}).",
+                    dice_1 + dice_2
+                ));
+                (board, dice_1 + dice_2)
+            }
+        };
+
+        let moves = game_core::enumerate_moves(board, roll);
+
+        // A hint can only be requested if the server didn't already send one.
+        if hint.is_none() && !moves.is_empty() {
+            let request = ui
+                .add_enabled(!self.hint_requested, egui::Button::new("Request hint"))
+                .on_hover_text("Ask the server for the best move, if it gives hints.");
+            if request.clicked() {
+                self.hint_requested = true;
+                self.write(ClientMessages::RequestHint)?;
+            }
+        }
+
+        let mut chosen = None;
+        if moves.is_empty() {
+            ui.label(tiles_layout(board, STANDARD_TILES, colors));
+            ui.label("There are no moves you can make.");
+            if ui.button("Continue").clicked() {
+                chosen = Some(ClientMove::CannotMove);
+            }
+        } else {
+            ui.label("Choose a move:");
+        }
+
+        for move_board in moves {
+            let layout = Main::generate_board(board, roll, move_board, STANDARD_TILES, colors);
+            let clicked = ui
+                .horizontal(|ui| {
+                    let clicked = ui.add(Label::new(layout).sense(Sense::click())).clicked();
+                    if hint == Some(move_board) {
+                        let hover = match self.hint_win_rate {
+                            Some(win_rate) => format!(
+                                "The server recommends this move, which wins {:.1}% of games.",
+                                win_rate * 100.
+                            ),
+                            None => String::from("The server recommends this move."),
+                        };
+                        ui.label(RichText::new("Hint").color(Color32::LIGHT_GREEN))
+                            .on_hover_text(hover);
+                    }
+                    clicked
+                })
+                .inner;
+            if clicked {
+                let moved_board = ClientMovedBoard(move_board);
+                chosen = Some(match to_move {
+                    ClientToMove::OneDice(..) => ClientMove::FirstDice(moved_board),
+                    ClientToMove::TwoDice { .. } => ClientMove::BothDice(moved_board),
+                });
+
+                let best =
+                    best_moves.and_then(|policy| policy.best_move(board, DiceRoll::from(roll)));
+                if let Some(best) = best {
+                    self.judged_moves += 1;
+                    self.best_moves_made += (best == move_board) as u32;
+                }
+                self.board = move_board;
+            }
+        }
+
+        if let Some(chosen) = chosen {
+            self.last_error = None;
+            self.phase = Phase::Waiting;
+            self.write(ClientMessages::ChosenMove(chosen))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the address other devices on the network can reach this one at.
+/// If it can't be found, then the loopback address is returned.
+fn local_ip() -> IpAddr {
+    // Connecting a udp socket doesn't send anything, but does choose the interface that would be used.
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
@@ -0,0 +1,1254 @@
+use std::sync::Arc;
+
+use eframe::epaint::Color32;
+use egui::ahash::HashMap;
+use egui::text::LayoutJob;
+use egui::{Label, RichText, ScrollArea, Sense, SidePanel, TextFormat, Ui};
+use fastrand::Rng;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use serde::{Deserialize, Serialize};
+
+use compute::{
+    BoardRoll, BotDifficulty, BuiltinStrategy, DiceDistribution, Policy, RankedMove, Strategy,
+    GOLF_HOLES,
+};
+use game_core::{board_value, rng, DiceRoll, MAX_TILES};
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use networked::replay::{Replay, ReplayTurn};
+#[cfg(not(target_arch = "wasm32"))]
+use stats::{GameMode, GameRecord, Outcome};
+
+use crate::settings::BoardColors;
+use crate::sound::Sound;
+use crate::variant::Variant;
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+use crate::variant::TILE_COUNTS;
+use crate::Main;
+
+/// The highest value the AI still rolls a single dice for, when the variant allows it.
+/// Any roll of two dice that's above this value would be a dying roll.
+pub(crate) const AI_SINGLE_DICE_VALUE: u8 = 6;
+// The id for the move history panel.
+const HISTORY_PANEL: &str = "Move history";
+// The id for the scoreboard of the session.
+const SCOREBOARD: &str = "Session";
+/// How long the dice tumble for before showing the rolled values, in seconds.
+const ROLL_ANIMATION: f64 = 1.;
+/// How many times a second the tumbling dice change faces at the start of the animation.
+const TUMBLE_RATE: f64 = 15.;
+/// The accuracies the AI can be set to, along with their names.
+const ACCURACIES: [(BotDifficulty, &str); 4] = [
+    (BotDifficulty::EASY, "Easy"),
+    (BotDifficulty::MEDIUM, "Medium"),
+    (BotDifficulty::HARD, "Hard"),
+    (BotDifficulty::PERFECT, "Perfect"),
+];
+
+/// How well the AI opponent plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), feature = "network"),
+    derive(Serialize, Deserialize)
+)]
+pub enum Difficulty {
+    /// Makes a random valid move.
+    Random,
+    /// Knocks down the highest valued pieces possible.
+    Greedy,
+    /// Makes the best move from the computed moves.
+    Optimal,
+    /// Makes the move chosen by the strategy set by the config.
+    External,
+}
+
+impl Difficulty {
+    /// Every difficulty, from the easiest to the hardest.
+    const ALL: [Difficulty; 3] = [Difficulty::Random, Difficulty::Greedy, Difficulty::Optimal];
+
+    /// Returns the human readable name of this difficulty.
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Random => "Random",
+            Difficulty::Greedy => "Greedy",
+            Difficulty::Optimal => "Optimal",
+            Difficulty::External => "External",
+        }
+    }
+}
+
+/// The player whose turn it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+    User,
+    Ai,
+}
+
+impl Turn {
+    /// Returns the human readable name of this player.
+    fn name(self) -> &'static str {
+        match self {
+            Turn::User => "You",
+            Turn::Ai => "AI",
+        }
+    }
+}
+
+/// A turn that was taken during the game, along with the boards after it.
+#[derive(Debug, Clone, Copy)]
+struct LoggedTurn {
+    /// The player who took the turn.
+    player: Turn,
+    /// The sum of the rolled dice.
+    roll: u8,
+    /// The pieces that were knocked down, or None if the player couldn't move.
+    knocked: Option<u16>,
+    /// The board of the user after the turn.
+    user_board: u16,
+    /// The board of the AI after the turn.
+    ai_board: u16,
+}
+
+impl LoggedTurn {
+    /// Describes the turn in a single line.
+    fn describe(&self) -> String {
+        let action = match self.knocked {
+            Some(knocked) => format!("flipped {}", knocked_down(knocked, 0)),
+            None => String::from("out"),
+        };
+
+        format!("{}: rolled {}, {action}", self.player.name(), self.roll)
+    }
+}
+
+/// The results of the games the user has finished this session.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), feature = "network"),
+    derive(Serialize, Deserialize),
+    serde(default)
+)]
+struct SessionScores {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    /// The sum of the final scores of the user.
+    total_score: u32,
+    /// The amount of games the user shut the box in.
+    shut_boxes: u32,
+    /// The amount of games the user has won in a row.
+    streak: u32,
+    /// The most games the user has won in a row.
+    best_streak: u32,
+    /// The games played in the current round of golf, which is 0 unless golf is being played.
+    holes_played: u8,
+    /// The sum of the user's scores in the current round of golf.
+    round_score: u32,
+    /// The sum of the AI's scores in the current round of golf.
+    ai_round_score: u32,
+}
+
+impl SessionScores {
+    /// Returns the amount of games finished this session.
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Adds a finished game with the given final scores of the user & the AI.
+    /// If the game is one of several holes, then the scores are added to the round too.
+    fn record(&mut self, user_score: u8, ai_score: u8, holes: u8) {
+        if holes > 1 {
+            // A finished round is only cleared once the next one starts, so its result can be shown.
+            if self.holes_played >= holes {
+                self.clear_round();
+            }
+            self.holes_played += 1;
+            self.round_score += user_score as u32;
+            self.ai_round_score += ai_score as u32;
+        }
+
+        match user_score.cmp(&ai_score) {
+            std::cmp::Ordering::Less => {
+                self.wins += 1;
+                self.streak += 1;
+                self.best_streak = self.best_streak.max(self.streak);
+            }
+            std::cmp::Ordering::Equal => {
+                self.draws += 1;
+                self.streak = 0;
+            }
+            std::cmp::Ordering::Greater => {
+                self.losses += 1;
+                self.streak = 0;
+            }
+        }
+
+        self.total_score += user_score as u32;
+        if user_score == 0 {
+            self.shut_boxes += 1;
+        }
+    }
+
+    /// Returns the result of the round of golf, once every hole of it has been played.
+    fn round_result(&self, holes: u8) -> Option<&'static str> {
+        if holes <= 1 || self.holes_played < holes {
+            return None;
+        }
+
+        Some(match self.round_score.cmp(&self.ai_round_score) {
+            std::cmp::Ordering::Less => "You win the round!",
+            std::cmp::Ordering::Equal => "The round is a draw.",
+            std::cmp::Ordering::Greater => "The AI wins the round.",
+        })
+    }
+
+    /// Starts the next round of golf from nothing.
+    fn clear_round(&mut self) {
+        self.holes_played = 0;
+        self.round_score = 0;
+        self.ai_round_score = 0;
+    }
+
+    /// Draws the scoreboard of the session.
+    /// Returns true if the user wants to reset the scores.
+    fn show(&self, ui: &mut Ui) -> bool {
+        egui::Grid::new(SCOREBOARD).show(ui, |ui| {
+            if self.holes_played > 0 {
+                ui.label("Round");
+                ui.label(format!(
+                    "You {}, AI {} after {} of {GOLF_HOLES} holes",
+                    self.round_score, self.ai_round_score, self.holes_played
+                ));
+                ui.end_row();
+            }
+
+            ui.label("Games");
+            ui.label(self.games().to_string());
+            ui.end_row();
+
+            ui.label("Wins");
+            ui.label(format!(
+                "{} ({} draws, {} losses)",
+                self.wins, self.draws, self.losses
+            ));
+            ui.end_row();
+
+            ui.label("Average score");
+            match self.games() {
+                0 => ui.label("-"),
+                games => ui.label(format!("{:.1}", self.total_score as f32 / games as f32)),
+            };
+            ui.end_row();
+
+            ui.label("Shut boxes");
+            ui.label(self.shut_boxes.to_string());
+            ui.end_row();
+
+            ui.label("Best streak");
+            ui.label(format!("{} (current {})", self.best_streak, self.streak));
+            ui.end_row();
+        });
+
+        ui.button("Reset session").clicked()
+    }
+}
+
+/// What the user asked to do with the saved game.
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveRequest {
+    /// Saves the game being played, replacing the saved game.
+    Save,
+    /// Continues the saved game instead of starting a new one.
+    Continue,
+}
+
+/// An unfinished game against the AI, which is saved so it can be continued later.
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedGame {
+    /// Every turn taken so far, along with the rules & the seed the dice are rolled from.
+    replay: Replay,
+    /// How well the AI opponent plays.
+    difficulty: Difficulty,
+    /// How often the AI opponent makes its strategy's move.
+    #[serde(default)]
+    accuracy: BotDifficulty,
+    /// The dice the user rolled, which haven't been used yet.
+    user_roll: Option<(u8, u8)>,
+    /// The state of the rng rolling the dice, so the game carries on with the same dice.
+    dice: u64,
+    /// The state of the rng used for the AI's random choices.
+    rng: u64,
+    /// The results of the games finished in the session the game was saved from.
+    session: SessionScores,
+    /// The amount of the user's moves that had a known best move.
+    judged_moves: u32,
+    /// The amount of the user's moves that were the best move.
+    best_moves_made: u32,
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+impl SavedGame {
+    /// Returns the rules the game is played with.
+    pub fn variant(&self) -> Variant {
+        self.replay.variant
+    }
+}
+
+/// A game of the user against the AI opponent.
+/// Each player has their own board & they take turns rolling, until neither can make a move.
+/// The player with the lowest value left on their board wins.
+pub struct PlayState {
+    /// How well the AI opponent plays.
+    pub difficulty: Difficulty,
+    /// How often the AI opponent makes its strategy's move, as it otherwise makes a random move.
+    pub accuracy: BotDifficulty,
+    /// The strategy the AI plays with on the external difficulty, which is None if it isn't set.
+    external: Option<Arc<dyn Strategy>>,
+    /// The rules of the game being played.
+    variant: Variant,
+    /// The board of the user.
+    user_board: u16,
+    /// The board of the AI.
+    ai_board: u16,
+    /// Whether the user can still make moves.
+    user_alive: bool,
+    /// Whether the AI can still make moves.
+    ai_alive: bool,
+    /// The player whose turn it is.
+    turn: Turn,
+    /// The dice the user rolled, which haven't been used yet.
+    /// If only a single dice was rolled, then the second dice is 0.
+    user_roll: Option<(u8, u8)>,
+    /// The time the user rolled the dice at, while the roll is being animated.
+    rolled_at: Option<f64>,
+    /// The tiles the user has selected to knock down with their roll.
+    selected: u16,
+    /// Whether the user is dragging over the tiles to select them.
+    dragging: bool,
+    /// Describes the last move made by the AI, along with the reasoning behind it.
+    ai_reasoning: Option<String>,
+    /// The rng used for the AI's random choices.
+    rng: Rng,
+    /// The rng used for rolling the dice, from the seed recorded in the replay.
+    dice: Rng,
+    /// Every turn of this game, as it's written to the replay once the game is over.
+    /// The web app has nowhere to write replays to, so it doesn't record them.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    replay: Replay,
+    /// Every turn taken in this game, from the first to the last.
+    log: Vec<LoggedTurn>,
+    /// The index of the logged turn being viewed, if the user is looking back at the game.
+    viewing: Option<usize>,
+    /// The sound effects caused by the game, which haven't been played yet.
+    sounds: Vec<Sound>,
+    /// The results of the games finished this session, which are kept between games.
+    session: SessionScores,
+    /// Whether the result of this game has been added to the session.
+    recorded: bool,
+    /// Whether the end of this game has been taken to be recorded.
+    finish_taken: bool,
+    /// The amount of the user's moves that had a known best move.
+    judged_moves: u32,
+    /// The amount of the user's moves that were the best move.
+    best_moves_made: u32,
+    /// Whether there's a saved game that can be continued.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    pub has_saved_game: bool,
+    /// What the user asked to do with the saved game, which hasn't been handled yet.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    save_request: Option<SaveRequest>,
+}
+
+impl Default for PlayState {
+    fn default() -> Self {
+        let mut rng = rng::stream("play");
+        let seed = rng.u64(..);
+
+        PlayState {
+            difficulty: Difficulty::Optimal,
+            accuracy: BotDifficulty::default(),
+            external: None,
+            variant: Variant::default(),
+            user_board: Variant::default().full_board(),
+            ai_board: Variant::default().full_board(),
+            user_alive: true,
+            ai_alive: true,
+            turn: Turn::User,
+            user_roll: None,
+            rolled_at: None,
+            selected: 0,
+            dragging: false,
+            ai_reasoning: None,
+            rng,
+            dice: Rng::with_seed(seed),
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            replay: Replay::new(
+                vec![Turn::User.name().to_string(), Turn::Ai.name().to_string()],
+                Variant::default(),
+                Some(seed),
+            ),
+            log: Vec::new(),
+            viewing: None,
+            sounds: Vec::new(),
+            session: SessionScores::default(),
+            recorded: false,
+            finish_taken: false,
+            judged_moves: 0,
+            best_moves_made: 0,
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            has_saved_game: false,
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            save_request: None,
+        }
+    }
+}
+
+impl PlayState {
+    /// Starts a new game, keeping the selected difficulty, variant, & session scores.
+    fn reset(&mut self) {
+        *self = PlayState {
+            difficulty: self.difficulty,
+            accuracy: self.accuracy,
+            external: self.external.clone(),
+            variant: self.variant,
+            session: self.session,
+            user_board: self.variant.full_board(),
+            ai_board: self.variant.full_board(),
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            has_saved_game: self.has_saved_game,
+            ..PlayState::default()
+        };
+        #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+        {
+            self.replay.variant = self.variant;
+        }
+    }
+
+    /// Returns what the user asked to do with the saved game since the last call, if anything.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    pub fn take_save_request(&mut self) -> Option<SaveRequest> {
+        self.save_request.take()
+    }
+
+    /// Returns the game so far, so it can be saved & continued later.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    pub fn save(&self) -> SavedGame {
+        SavedGame {
+            replay: self.replay.clone(),
+            difficulty: self.difficulty,
+            accuracy: self.accuracy,
+            user_roll: self.user_roll,
+            dice: self.dice.get_seed(),
+            rng: self.rng.get_seed(),
+            session: self.session,
+            judged_moves: self.judged_moves,
+            best_moves_made: self.best_moves_made,
+        }
+    }
+
+    /// Continues the saved game from its last turn, replacing the game being played.
+    /// The variant should be set to the saved game's variant first, so the rest of the gui matches it.
+    /// Returns an error if the turns of the saved game aren't legal.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    pub fn resume(&mut self, saved: SavedGame) -> Result<(), String> {
+        let variant = saved.variant();
+        if !TILE_COUNTS.contains(&variant.tiles) || saved.replay.players.len() != 2 {
+            return Err(String::from("The saved game isn't a game against the AI"));
+        }
+        saved
+            .replay
+            .verify()
+            .map_err(|e| format!("The saved game is invalid: {e}"))?;
+
+        *self = PlayState {
+            difficulty: saved.difficulty,
+            accuracy: saved.accuracy,
+            external: self.external.clone(),
+            variant,
+            user_board: variant.full_board(),
+            ai_board: variant.full_board(),
+            user_roll: saved.user_roll,
+            rng: Rng::with_seed(saved.rng),
+            dice: Rng::with_seed(saved.dice),
+            session: saved.session,
+            judged_moves: saved.judged_moves,
+            best_moves_made: saved.best_moves_made,
+            has_saved_game: self.has_saved_game,
+            ..PlayState::default()
+        };
+        // The strategy of the external difficulty isn't saved, as it's set by the config.
+        if self.difficulty == Difficulty::External && self.external.is_none() {
+            self.difficulty = Difficulty::Optimal;
+        }
+
+        for turn in &saved.replay.turns {
+            let player = match turn.player {
+                0 => Turn::User,
+                _ => Turn::Ai,
+            };
+            let (board, alive) = match player {
+                Turn::User => (&mut self.user_board, &mut self.user_alive),
+                Turn::Ai => (&mut self.ai_board, &mut self.ai_alive),
+            };
+            match turn.moved_board {
+                Some(moved_board) => *board = moved_board,
+                None => *alive = false,
+            }
+
+            if turn.moved_board == Some(0) {
+                self.shut_box(player);
+            }
+            self.turn = match (player, self.user_alive, self.ai_alive) {
+                (Turn::User, _, true) | (Turn::Ai, false, _) => Turn::Ai,
+                _ => Turn::User,
+            };
+
+            self.log.push(LoggedTurn {
+                player,
+                roll: turn.roll,
+                knocked: turn
+                    .moved_board
+                    .map(|moved_board| turn.board & !moved_board),
+                user_board: self.user_board,
+                ai_board: self.ai_board,
+            });
+        }
+
+        // The dice can only have been rolled on the user's turn.
+        if self.turn != Turn::User {
+            self.user_roll = None;
+        }
+        self.replay = saved.replay;
+        Ok(())
+    }
+
+    /// Sets the strategy the AI plays with on the external difficulty.
+    /// If it's removed while being played against, then the AI plays optimally instead.
+    pub fn set_external(&mut self, external: Option<Arc<dyn Strategy>>) {
+        if external.is_none() && self.difficulty == Difficulty::External {
+            self.difficulty = Difficulty::Optimal;
+        }
+        self.external = external;
+    }
+
+    /// Starts a new game with the given variant.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.session.clear_round();
+        self.reset();
+    }
+
+    /// Puts the player out once they've shut the box, as they have nothing left to knock down.
+    /// If shutting the box ends the game, then the other player is out too.
+    fn shut_box(&mut self, player: Turn) {
+        match player {
+            Turn::User => self.user_alive = false,
+            Turn::Ai => self.ai_alive = false,
+        }
+        if self.variant.ends_game(0) {
+            self.user_alive = false;
+            self.ai_alive = false;
+        }
+    }
+
+    /// Returns true if neither player can make any more moves.
+    fn is_over(&self) -> bool {
+        !self.user_alive && !self.ai_alive
+    }
+
+    /// Returns true if a turn of the game has been taken, but the game hasn't ended yet.
+    pub fn is_in_progress(&self) -> bool {
+        !self.log.is_empty() && !self.is_over()
+    }
+
+    /// Returns the board of the user & the board of the AI.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn boards(&self) -> (u16, u16) {
+        (self.user_board, self.ai_board)
+    }
+
+    /// Returns true once the game is over, which is only returned once for each game.
+    pub fn take_finished(&mut self) -> bool {
+        if !self.is_over() || self.finish_taken {
+            return false;
+        }
+
+        self.finish_taken = true;
+        true
+    }
+
+    /// Returns the replay of the game so far, with the winning score if the game is over.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+    pub fn replay(&self) -> Replay {
+        let mut replay = self.replay.clone();
+        if self.is_over() {
+            replay.winning_score =
+                Some(self.variant.winning_score([self.user_board, self.ai_board]));
+        }
+        replay
+    }
+
+    /// Returns the stats of the game, which should only be recorded once the game is over.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn game_record(&self) -> GameRecord {
+        let user_score = self.variant.score(self.user_board);
+        let ai_score = self.variant.score(self.ai_board);
+
+        GameRecord::now(
+            GameMode::Local,
+            vec![format!("AI ({})", self.difficulty.name())],
+            self.variant.tiles,
+            user_score,
+            user_score.min(ai_score),
+            Outcome::from_scores(user_score, &[ai_score]),
+        )
+        .with_accuracy(self.best_moves_made, self.judged_moves)
+    }
+
+    /// Returns the sound effects caused since the last call, in the order they happened.
+    pub fn take_sounds(&mut self) -> Vec<Sound> {
+        std::mem::take(&mut self.sounds)
+    }
+
+    /// Records a turn taken by the given player with the rolled dice, using the current boards.
+    /// The second dice of a single dice roll is 0.
+    fn log_turn(&mut self, player: Turn, (one, two): (u8, u8), knocked: Option<u16>) {
+        let roll = one + two;
+        #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+        {
+            let (index, board) = match player {
+                Turn::User => (0, self.user_board),
+                Turn::Ai => (1, self.ai_board),
+            };
+            let dice: &[u8] = match two {
+                0 => &[one],
+                _ => &[one, two],
+            };
+            // The board before the turn still has the knocked down pieces.
+            let before = board | knocked.unwrap_or_default();
+            self.replay
+                .turns
+                .push(ReplayTurn::new(index, dice, before, knocked.map(|_| board)));
+        }
+
+        match knocked {
+            Some(_) if self.user_board == 0 || self.ai_board == 0 => {
+                self.sounds.push(Sound::BoxShut)
+            }
+            Some(_) => self.sounds.push(Sound::TileFlip),
+            None => {}
+        }
+
+        self.log.push(LoggedTurn {
+            player,
+            roll,
+            knocked,
+            user_board: self.user_board,
+            ai_board: self.ai_board,
+        });
+    }
+
+    /// Draws the side panel listing every turn of the game.
+    /// Clicking a turn shows the boards at that point of the game.
+    pub fn side_panel(&mut self, context: &egui::Context) {
+        SidePanel::right(HISTORY_PANEL)
+            .resizable(true)
+            .show(context, |ui| {
+                ui.heading(SCOREBOARD);
+                ui.separator();
+                if self.session.show(ui) {
+                    self.session = SessionScores::default();
+                }
+                ui.add_space(10.);
+
+                ui.heading(HISTORY_PANEL);
+                ui.separator();
+
+                if self.log.is_empty() {
+                    ui.label("No turns have been taken yet.");
+                }
+
+                ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for (index, turn) in self.log.iter().enumerate() {
+                            let selected = self.viewing == Some(index);
+                            let text = format!("{}. {}", index + 1, turn.describe());
+
+                            if ui.selectable_label(selected, text).clicked() {
+                                self.viewing = match selected {
+                                    true => None,
+                                    false => Some(index),
+                                };
+                            }
+                        }
+                    });
+            });
+    }
+
+    /// Draws the game & handles the input of the user.
+    /// The best moves are used by the optimal AI, the ranked moves are used to show the win chance of the AI's moves.
+    /// If animate is false, then the dice show the rolled values straight away.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        animate: bool,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("AI difficulty:");
+            for difficulty in Difficulty::ALL {
+                ui.selectable_value(&mut self.difficulty, difficulty, difficulty.name());
+            }
+            if self.external.is_some() {
+                let external = Difficulty::External;
+                ui.selectable_value(&mut self.difficulty, external, external.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("AI accuracy:")
+                .on_hover_text("How often the AI makes its move, instead of a random move.");
+            for (accuracy, name) in ACCURACIES {
+                ui.selectable_value(&mut self.accuracy, accuracy, name);
+            }
+            // An accuracy from the config that isn't one of the presets.
+            if !ACCURACIES
+                .iter()
+                .any(|(accuracy, _)| *accuracy == self.accuracy)
+            {
+                let custom = self.accuracy;
+                ui.selectable_value(&mut self.accuracy, custom, custom.to_string());
+            }
+        });
+
+        if self.difficulty == Difficulty::Optimal && best_moves.is_none() {
+            ui.label(
+                RichText::new("No moves found, so the AI will play randomly.")
+                    .color(Color32::LIGHT_RED),
+            );
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("New game").clicked() {
+                self.reset();
+            }
+
+            #[cfg(all(not(target_arch = "wasm32"), feature = "network"))]
+            {
+                if ui
+                    .add_enabled(self.is_in_progress(), egui::Button::new("Save game"))
+                    .on_hover_text("Save the game, so it can be continued later.")
+                    .clicked()
+                {
+                    self.save_request = Some(SaveRequest::Save);
+                }
+                // The saved game can be continued until the first turn of a new game is taken.
+                if self.has_saved_game && self.log.is_empty() && ui.button("Continue").clicked() {
+                    self.save_request = Some(SaveRequest::Continue);
+                }
+            }
+        });
+
+        ui.add_space(10.);
+
+        // Shows the boards at the logged turn instead of the current game.
+        if let Some(turn) = self.viewing.and_then(|index| self.log.get(index)) {
+            ui.label(format!("Viewing turn {}", turn.describe()));
+            ui.label("You:");
+            ui.label(tiles_layout(turn.user_board, self.variant.tiles, colors));
+            ui.label("AI:");
+            ui.label(tiles_layout(turn.ai_board, self.variant.tiles, colors));
+
+            ui.add_space(10.);
+            if ui.button("Back to game").clicked() {
+                self.viewing = None;
+            }
+            return;
+        }
+
+        ui.label("You:");
+        ui.label(tiles_layout(self.user_board, self.variant.tiles, colors));
+        ui.label("AI:");
+        ui.label(tiles_layout(self.ai_board, self.variant.tiles, colors));
+
+        ui.add_space(10.);
+
+        if let Some(reasoning) = &self.ai_reasoning {
+            ui.label(reasoning);
+            ui.add_space(10.);
+        }
+
+        if self.is_over() {
+            let user_score = self.variant.score(self.user_board);
+            let ai_score = self.variant.score(self.ai_board);
+
+            // Each game is only added to the session once, even though its result is drawn every frame.
+            if !self.recorded {
+                self.session
+                    .record(user_score, ai_score, self.variant.holes());
+                self.recorded = true;
+            }
+
+            let result = match user_score.cmp(&ai_score) {
+                std::cmp::Ordering::Less => "You win!",
+                std::cmp::Ordering::Equal => "It's a draw.",
+                std::cmp::Ordering::Greater => "The AI wins.",
+            };
+
+            ui.heading(result);
+            ui.label(format!("Your score: {user_score}, AI score: {ai_score}"));
+            if let Some(round_result) = self.session.round_result(self.variant.holes()) {
+                ui.label(round_result);
+            }
+            return;
+        }
+
+        // The AI plays as soon as it's their turn, so the user only ever sees their own turn.
+        while self.turn == Turn::Ai && !self.is_over() {
+            self.ai_turn(best_moves, ranked_moves);
+        }
+
+        if self.is_over() {
+            return;
+        }
+
+        self.user_turn(ui, colors, animate, best_moves);
+    }
+
+    /// Lets the user roll the dice & choose a move.
+    /// The move is compared against the best move, if it's known, to work out the user's accuracy.
+    fn user_turn(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        animate: bool,
+        best_moves: Option<&Policy>,
+    ) {
+        let time = ui.input(|input| input.time);
+
+        let (one, two) = match self.user_roll {
+            Some(roll) => roll,
+            None => {
+                ui.label(format!(
+                    "Chance your next roll has a move: {:.1}%",
+                    move_chance(self.user_board, false) * 100.
+                ));
+                if self.variant.can_roll_single(self.user_board) {
+                    ui.label(format!(
+                        "Chance with one dice: {:.1}%",
+                        move_chance(self.user_board, true) * 100.
+                    ));
+                }
+
+                let mut single = None;
+                ui.horizontal(|ui| {
+                    if ui.button("Roll").clicked() {
+                        single = Some(false);
+                    }
+                    if self.variant.can_roll_single(self.user_board)
+                        && ui.button("Roll one dice").clicked()
+                    {
+                        single = Some(true);
+                    }
+                });
+
+                if let Some(single) = single {
+                    self.user_roll = Some(self.roll_dice(single));
+                    self.rolled_at = animate.then_some(time);
+                }
+                return;
+            }
+        };
+
+        // Tumbles the dice until the animation is over, or it gets skipped.
+        if let Some(rolled_at) = self.rolled_at {
+            let elapsed = time - rolled_at;
+
+            if elapsed < ROLL_ANIMATION && animate {
+                let (tumble_one, tumble_two) = tumbling_faces(elapsed);
+                // A single dice stays single while it tumbles.
+                let tumble_two = if two == 0 { 0 } else { tumble_two };
+                dice_faces(ui, tumble_one, tumble_two);
+                if ui.button("Skip").clicked() {
+                    self.rolled_at = None;
+                }
+
+                ui.ctx().request_repaint();
+                return;
+            }
+
+            self.rolled_at = None;
+        }
+
+        dice_faces(ui, one, two);
+
+        let roll = one + two;
+        match two {
+            0 => ui.label(format!("You rolled {roll}.")),
+            _ => ui.label(format!("You rolled {roll} ({one} & {two}).")),
+        };
+
+        let moves = game_core::enumerate_moves(self.user_board, roll);
+
+        // If there are no valid moves, then the user is out.
+        if moves.is_empty() {
+            ui.label("There are no moves you can make.");
+            if ui.button("Continue").clicked() {
+                self.user_alive = false;
+                self.log_turn(Turn::User, (one, two), None);
+                self.end_user_turn();
+            }
+            return;
+        }
+
+        let mut chosen = self.tile_selection(ui, roll, colors);
+
+        ui.add_space(10.);
+        ui.label("Or choose a move:");
+        for move_board in moves {
+            let layout = Main::generate_board(
+                self.user_board,
+                roll,
+                move_board,
+                self.variant.tiles,
+                colors,
+            );
+            if ui.add(Label::new(layout).sense(Sense::click())).clicked() {
+                chosen = Some(move_board);
+            }
+        }
+
+        if let Some(move_board) = chosen {
+            let best = best_moves
+                .and_then(|policy| policy.best_move(self.user_board, DiceRoll::from(roll)));
+            if let Some(best) = best {
+                self.judged_moves += 1;
+                self.best_moves_made += (best == move_board) as u32;
+            }
+
+            let knocked = self.user_board & !move_board;
+            self.user_board = move_board;
+            self.log_turn(Turn::User, (one, two), Some(knocked));
+            self.end_user_turn();
+        }
+    }
+
+    /// Draws the tiles of the user's board, which can be selected by tapping them or dragging across them.
+    /// Tapping the tiles makes the move once they sum to the roll, & dragging makes the move once released.
+    /// Returns the board after the move, if the selected tiles make one.
+    fn tile_selection(&mut self, ui: &mut Ui, roll: u8, colors: &BoardColors) -> Option<u16> {
+        ui.label("Select the tiles to knock down:");
+
+        let (pointer, down, released) = ui.input(|input| {
+            (
+                input.pointer.interact_pos(),
+                input.pointer.primary_down(),
+                input.pointer.primary_released(),
+            )
+        });
+        let mut tapped = false;
+
+        ui.horizontal(|ui| {
+            // Removes the gaps between the tiles, so dragging doesn't skip over any.
+            ui.spacing_mut().item_spacing.x = 0.;
+
+            for piece_index in (0..self.variant.tiles).rev() {
+                let tile = 1 << piece_index;
+                let background = match (self.user_board & tile != 0, self.selected & tile != 0) {
+                    (true, true) => colors.knocked,
+                    (true, false) => colors.alive,
+                    (false, _) => colors.down,
+                };
+
+                let mut layout = LayoutJob::default();
+                layout.append(
+                    &format!("{} ", piece_index + 1),
+                    0.,
+                    TextFormat {
+                        background,
+                        ..Default::default()
+                    },
+                );
+
+                let response = ui.add(Label::new(layout).sense(Sense::click_and_drag()));
+                // Knocked down tiles can't be selected.
+                if self.user_board & tile == 0 {
+                    continue;
+                }
+
+                if response.clicked() {
+                    self.selected ^= tile;
+                    tapped = true;
+                }
+                if response.drag_started() {
+                    self.selected = 0;
+                    self.dragging = true;
+                }
+                // Every tile the pointer passes over while dragging is selected.
+                // The dragged tile keeps the pointer, so the other tiles are checked by their position.
+                let over = pointer.is_some_and(|pointer| response.rect.contains(pointer));
+                if self.dragging && down && over {
+                    self.selected |= tile;
+                }
+            }
+        });
+
+        let selected: Vec<String> = (0..self.variant.tiles)
+            .filter(|index| self.selected >> index & 1 == 1)
+            .map(|index| (index + 1).to_string())
+            .collect();
+        let sum = board_value(self.selected);
+        let feedback = match sum.cmp(&roll) {
+            std::cmp::Ordering::Less => RichText::new(format!("{sum} of {roll}")),
+            std::cmp::Ordering::Equal => {
+                RichText::new(format!("{sum} of {roll}")).color(Color32::LIGHT_GREEN)
+            }
+            std::cmp::Ordering::Greater => {
+                RichText::new(format!("{sum} of {roll}, too high")).color(Color32::LIGHT_RED)
+            }
+        };
+        ui.horizontal(|ui| {
+            match selected.is_empty() {
+                true => ui.label("Selected: nothing,"),
+                false => ui.label(format!("Selected: {},", selected.join(" + "))),
+            };
+            ui.label(feedback);
+            if ui
+                .add_enabled(self.selected != 0, egui::Button::new("Clear"))
+                .clicked()
+            {
+                self.selected = 0;
+            }
+        });
+
+        // The move is made once the tiles are tapped or the drag is released.
+        let finished_drag = self.dragging && (released || !down);
+        if finished_drag {
+            self.dragging = false;
+        }
+        if !tapped && !finished_drag {
+            return None;
+        }
+
+        let moved = self.user_board & !self.selected;
+        self.variant
+            .is_legal_move(self.user_board, roll, moved)
+            .ok()
+            .map(|()| moved)
+    }
+
+    /// Passes the turn from the user to the AI.
+    fn end_user_turn(&mut self) {
+        self.user_roll = None;
+        self.selected = 0;
+        self.dragging = false;
+
+        if self.user_board == 0 {
+            self.shut_box(Turn::User);
+        }
+
+        self.turn = match self.ai_alive {
+            true => Turn::Ai,
+            false => Turn::User,
+        };
+    }
+
+    /// Rolls the dice & makes a move for the AI.
+    fn ai_turn(
+        &mut self,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) {
+        // A single dice is rolled if any roll of both dice would be too high to make a move.
+        let single = self.variant.can_roll_single(self.ai_board)
+            && board_value(self.ai_board) <= AI_SINGLE_DICE_VALUE;
+        let (one, two) = self.roll_dice(single);
+        let roll = one + two;
+        let moves = game_core::enumerate_moves(self.ai_board, roll);
+
+        let strategy: &dyn Strategy = match (self.difficulty, best_moves, &self.external) {
+            (Difficulty::Optimal, Some(policy), _) => policy,
+            (Difficulty::Greedy, _, _) => &BuiltinStrategy::HighestTilesFirst,
+            (Difficulty::External, _, Some(external)) => external.as_ref(),
+            _ => &BuiltinStrategy::Random,
+        };
+        let chosen = self.accuracy.choose_move(
+            strategy,
+            self.ai_board,
+            DiceRoll::from(roll),
+            &moves,
+            &mut self.rng,
+        );
+
+        match chosen {
+            Some(chosen) => {
+                debug_assert_eq!(
+                    self.variant.is_legal_move(self.ai_board, roll, chosen),
+                    Ok(()),
+                    "The AI made an illegal move."
+                );
+                // Gets the win chance of the chosen move, if it was computed.
+                let win_chance = ranked_moves
+                    .and_then(|ranked_moves| {
+                        ranked_moves.get(&BoardRoll::new(self.ai_board, DiceRoll::from(roll)))
+                    })
+                    .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == chosen))
+                    .map_or(String::from("unknown win chance"), |ranked| {
+                        format!("{:.1}% win chance", ranked.value as f32 / 10.)
+                    });
+
+                self.ai_reasoning = Some(format!(
+                    "The AI rolled {roll} & knocked down {} ({win_chance}).",
+                    knocked_down(self.ai_board, chosen)
+                ));
+                let knocked = self.ai_board & !chosen;
+                self.ai_board = chosen;
+                self.log_turn(Turn::Ai, (one, two), Some(knocked));
+
+                if chosen == 0 {
+                    self.shut_box(Turn::Ai);
+                }
+            }
+            None => {
+                self.ai_reasoning = Some(format!("The AI rolled {roll} & is out."));
+                self.ai_alive = false;
+                self.log_turn(Turn::Ai, (one, two), None);
+            }
+        }
+
+        if self.user_alive {
+            self.turn = Turn::User;
+        }
+    }
+
+    /// Rolls two dice, or a single dice if single is true.
+    /// The second dice of a single dice roll is 0.
+    fn roll_dice(&mut self, single: bool) -> (u8, u8) {
+        self.sounds.push(Sound::DiceRoll);
+        match single {
+            true => (self.dice.u8(1..=6), 0),
+            false => (self.dice.u8(1..=6), self.dice.u8(1..=6)),
+        }
+    }
+}
+
+/// Returns the faces shown by the tumbling dice, the given amount of seconds into the roll animation.
+/// The faces change quickly at first, then slow down as the dice settle.
+fn tumbling_faces(elapsed: f64) -> (u8, u8) {
+    // Easing out means fewer face changes happen towards the end.
+    let progress = elapsed / ROLL_ANIMATION;
+    let eased = 1. - (1. - progress).powi(2);
+    let step = (eased * ROLL_ANIMATION * TUMBLE_RATE) as u64;
+
+    // Steps through the faces at different strides, so the dice don't show the same face.
+    ((step * 5 % 6) as u8 + 1, ((step * 7 + 3) % 6) as u8 + 1)
+}
+
+/// Draws a pair of dice showing the given faces.
+/// A face of 0 isn't drawn, for when only a single dice was rolled.
+fn dice_faces(ui: &mut Ui, one: u8, two: u8) {
+    ui.horizontal(|ui| {
+        for face in [one, two].into_iter().filter(|face| *face != 0) {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.label(RichText::new(face.to_string()).size(32.).monospace());
+            });
+        }
+    });
+}
+
+/// Returns the chance that the next roll of fair dice has at least one legal move from the given board, between 0 & 1.
+/// If single is true, then only one dice is rolled.
+fn move_chance(board: u16, single: bool) -> f32 {
+    match single {
+        true => {
+            (1..=6u8)
+                .filter(|roll| !game_core::enumerate_moves(board, *roll).is_empty())
+                .count() as f32
+                / 6.
+        }
+        false => (2..=12u8)
+            .filter(|roll| !game_core::enumerate_moves(board, *roll).is_empty())
+            .map(|roll| DiceDistribution::Fair.probability(roll))
+            .sum(),
+    }
+}
+
+/// Lists the pieces that are knocked down when moving from the given board to the chosen board.
+fn knocked_down(board: u16, chosen: u16) -> String {
+    let pieces: Vec<String> = Main::board_to_array(board & !chosen, MAX_TILES)
+        .iter()
+        .enumerate()
+        .filter(|(_, knocked)| **knocked)
+        .map(|(index, _)| (index + 1).to_string())
+        .collect();
+
+    pieces.join(" & ")
+}
+
+/// Generates the layout for the pieces of the given board with the given amount of tiles, from the highest to the lowest.
+pub(crate) fn tiles_layout(board: u16, tiles: u8, colors: &BoardColors) -> LayoutJob {
+    let pieces = Main::board_to_array(board, tiles);
+    let mut board_text = LayoutJob::default();
+
+    for piece_index in (0..tiles as usize).rev() {
+        let background = match pieces[piece_index] {
+            // If the piece is alive then it should be green.
+            true => colors.alive,
+            // If the piece is down, then it should be grayed out.
+            false => colors.down,
+        };
+
+        board_text.append(
+            format!("{} ", piece_index + 1).as_str(),
+            0.,
+            TextFormat {
+                background,
+                ..Default::default()
+            },
+        );
+    }
+
+    board_text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_player_plays_on_once_the_box_is_shut() {
+        let mut state = PlayState {
+            user_board: 0,
+            ..PlayState::default()
+        };
+        state.end_user_turn();
+        assert!(!state.user_alive);
+        assert_eq!(state.turn, Turn::Ai);
+        assert!(!state.is_over());
+
+        // The AI can still shut its box too, which draws the game.
+        state.ai_board = 0;
+        state.shut_box(Turn::Ai);
+        assert!(state.is_over());
+        assert_eq!(
+            state
+                .variant
+                .winning_score([state.user_board, state.ai_board]),
+            0
+        );
+    }
+
+    #[test]
+    fn instant_shut_ends_the_game_for_both_players() {
+        let mut state = PlayState {
+            variant: Variant::INSTANT_SHUT,
+            user_board: 0,
+            ..PlayState::default()
+        };
+        state.end_user_turn();
+        assert!(state.is_over());
+    }
+}
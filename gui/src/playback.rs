@@ -0,0 +1,221 @@
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use egui::{ScrollArea, Window};
+use fastrand::Rng;
+
+use compute::{Policy, Strategy};
+use game_core::{board_value, rng, DiceRoll};
+
+use crate::play::AI_SINGLE_DICE_VALUE;
+use crate::settings::BoardColors;
+use crate::variant::Variant;
+use crate::Main;
+
+// The id for the playback window.
+const PLAY_OUT: &str = "Play Out";
+
+/// The range of seconds that can be waited between each move.
+const DELAY_RANGE: RangeInclusive<f64> = 0.1..=3.;
+
+/// A roll of the playback, along with the move that was made for it.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    /// The board the roll was made from.
+    board: u16,
+    roll: u8,
+    /// The board after the best move, or None if the roll had no moves.
+    chosen: Option<u16>,
+}
+
+/// Plays out a game from a board with the best moves, one move at a time.
+pub struct Playback {
+    /// Whether the playback window is open.
+    open: bool,
+    /// The rules of the game being played out.
+    variant: Variant,
+    /// The board the playback started from.
+    start: u16,
+    /// The board the next roll is made from.
+    board: u16,
+    /// The rolls that have been played out so far.
+    steps: Vec<Step>,
+    /// Whether the box was shut or a roll had no moves.
+    over: bool,
+    /// Whether the playback is waiting for the user to continue it.
+    paused: bool,
+    /// How many seconds are waited between each move.
+    delay: f64,
+    /// The time of the gui input the last move was made at.
+    last_step: Option<f64>,
+    /// The rng used for rolling the dice.
+    rng: Rng,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            open: false,
+            variant: Variant::default(),
+            start: 0,
+            board: 0,
+            steps: Vec::new(),
+            over: false,
+            paused: false,
+            delay: 1.,
+            last_step: None,
+            rng: rng::stream("playback"),
+        }
+    }
+}
+
+impl Playback {
+    /// Starts playing out the given board, opening the window.
+    pub fn start(&mut self, board: u16, variant: Variant) {
+        self.open = true;
+        self.variant = variant;
+        self.start = board;
+        self.restart();
+    }
+
+    /// Plays out the starting board again, with new rolls.
+    fn restart(&mut self) {
+        self.board = self.start;
+        self.steps.clear();
+        self.over = self.start == 0;
+        self.paused = false;
+        self.last_step = None;
+    }
+
+    /// Rolls the dice & makes the best move for the roll.
+    fn step(&mut self, best_moves: &Policy) {
+        // A single dice is rolled in the same situations as the AI would.
+        let single = self.variant.can_roll_single(self.board)
+            && board_value(self.board) <= AI_SINGLE_DICE_VALUE;
+        let roll = match single {
+            true => self.rng.u8(1..=6),
+            false => self.rng.u8(1..=6) + self.rng.u8(1..=6),
+        };
+
+        let moves = game_core::enumerate_moves(self.board, roll);
+        let chosen =
+            best_moves.choose_move(self.board, DiceRoll::from(roll), &moves, &mut self.rng);
+
+        self.steps.push(Step {
+            board: self.board,
+            roll,
+            chosen,
+        });
+
+        match chosen {
+            Some(chosen) => {
+                self.board = chosen;
+                self.over = chosen == 0;
+            }
+            None => self.over = true,
+        }
+    }
+
+    /// Draws the window of the playback, making the next move once enough time has passed.
+    pub fn window(
+        &mut self,
+        context: &egui::Context,
+        best_moves: Option<&Policy>,
+        colors: &BoardColors,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        let Some(best_moves) = best_moves else {
+            self.open = false;
+            return;
+        };
+
+        // Makes the next move once the delay has passed.
+        let time = context.input(|input| input.time);
+        if !self.over && !self.paused {
+            let waited = self
+                .last_step
+                .map_or(self.delay, |last_step| time - last_step);
+            if waited >= self.delay {
+                self.step(best_moves);
+                self.last_step = Some(time);
+            }
+
+            // Keeps redrawing without any user input, so the next move is made on time.
+            let remaining = self.delay - self.last_step.map_or(0., |last_step| time - last_step);
+            context.request_repaint_after(Duration::from_secs_f64(remaining.max(0.)));
+        }
+
+        let mut open = self.open;
+        let mut step = false;
+        let mut restart = false;
+
+        Window::new(PLAY_OUT).open(&mut open).show(context, |ui| {
+            ui.label(format!(
+                "Playing out the best moves from {}.",
+                Main::board_name(self.start)
+            ));
+
+            ui.horizontal(|ui| {
+                ui.label("Seconds per move:");
+                ui.add(egui::Slider::new(&mut self.delay, DELAY_RANGE).step_by(0.1));
+            });
+
+            ui.horizontal(|ui| {
+                let pause = match self.paused {
+                    true => "Resume",
+                    false => "Pause",
+                };
+                if ui
+                    .add_enabled(!self.over, egui::Button::new(pause))
+                    .clicked()
+                {
+                    self.paused = !self.paused;
+                }
+                if ui
+                    .add_enabled(!self.over && self.paused, egui::Button::new("Step"))
+                    .clicked()
+                {
+                    step = true;
+                }
+                if ui.button("Restart").clicked() {
+                    restart = true;
+                }
+            });
+            ui.add_space(10.);
+
+            ScrollArea::vertical().show(ui, |ui| {
+                for step in &self.steps {
+                    let layout = match step.chosen {
+                        Some(chosen) => Main::generate_board(
+                            step.board,
+                            step.roll,
+                            chosen,
+                            self.variant.tiles,
+                            colors,
+                        ),
+                        None => Main::generate_dying_board(step.board, step.roll, colors),
+                    };
+                    ui.label(layout);
+                }
+
+                if self.over {
+                    match self.board {
+                        0 => ui.label("The box was shut!"),
+                        board => ui.label(format!("Final score: {}", board_value(board))),
+                    };
+                }
+            });
+        });
+
+        if step {
+            self.step(best_moves);
+        }
+        if restart {
+            self.restart();
+        }
+        self.open = open;
+    }
+}
@@ -0,0 +1,348 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use egui::{RichText, Window};
+use serde::{Deserialize, Serialize};
+
+use crate::variant::{Variant, VariantFiles};
+
+// The id for the profile manager window.
+const PROFILE_MANAGER: &str = "Policy Profiles";
+
+/// The folder the profiles are kept in, within the folder eframe stores its state in.
+const PROFILE_FOLDER: &str = "profiles";
+/// The file the profiles are listed in, within the profile folder.
+const PROFILES_FILE: &str = "profiles.yml";
+
+/// A file of best moves that is managed by the user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// The name shown to the user, which doesn't have to match the file name.
+    pub name: String,
+    /// The file the best moves are kept in.
+    pub path: PathBuf,
+    /// The rules the best moves were calculated for.
+    pub variant: Variant,
+    /// The total amount of games simulated to calculate the best moves, if it's known.
+    pub games: Option<u64>,
+    /// When the best moves were calculated, in seconds since the unix epoch.
+    pub created: u64,
+}
+
+/// The profiles the user has calculated or imported, along with the default profile of each variant.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    /// The paths of the profiles that are loaded when switching to each variant.
+    defaults: Vec<(Variant, PathBuf)>,
+    /// Whether the profile manager window is open.
+    #[serde(skip)]
+    pub window_open: bool,
+    /// The index of the profile being renamed, along with its new name.
+    #[serde(skip)]
+    renaming: Option<(usize, String)>,
+}
+
+/// Returns the folder the profiles are kept in, or None if the platform doesn't have one.
+fn folder() -> Option<PathBuf> {
+    eframe::storage_dir(crate::WINDOW_NAME).map(|storage| storage.join(PROFILE_FOLDER))
+}
+
+/// Returns the current time in seconds since the unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl Profiles {
+    /// Loads the profiles from the profile folder.
+    /// If no profiles have been saved yet, then there are no profiles.
+    pub fn load() -> Result<Profiles, String> {
+        let Some(folder) = folder() else {
+            return Ok(Profiles::default());
+        };
+
+        let file = match File::open(folder.join(PROFILES_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Profiles::default()),
+            Err(e) => return Err(format!("Couldn't open the policy profiles: {e}")),
+        };
+
+        compute::codec::from_reader(BufReader::new(file))
+            .map_err(|e| format!("Couldn't read the policy profiles: {e}"))
+    }
+
+    /// Saves the profiles to the profile folder, creating it if needed.
+    pub fn save(&self) -> Result<(), String> {
+        let folder = folder().ok_or("There's no folder to save the policy profiles in.")?;
+        std::fs::create_dir_all(&folder)
+            .map_err(|e| format!("Couldn't create the policy profile folder: {e}"))?;
+
+        let contents = compute::codec::to_string(self)
+            .map_err(|e| format!("Couldn't save the policy profiles: {e}"))?;
+        compute::write_atomically(&folder.join(PROFILES_FILE), contents.as_bytes())
+            .map_err(|e| format!("Couldn't save the policy profiles: {e}"))
+    }
+
+    /// Returns a file within the profile folder that new best moves for the variant can be written to.
+    /// Returns None if the platform doesn't have a profile folder.
+    pub fn new_policy_path(variant: Variant) -> Option<PathBuf> {
+        let folder = folder()?;
+        std::fs::create_dir_all(&folder).ok()?;
+
+        // The variant's usual file name is kept, so the file can still be recognised outside the gui.
+        let stem = variant
+            .policy_file()
+            .file_stem()
+            .map_or(String::from("best_moves"), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+        Some(folder.join(format!("{stem}_{}.yml", now())))
+    }
+
+    /// Adds a profile for the best moves that were just calculated, making it the default if its variant has none.
+    pub fn add(&mut self, path: PathBuf, variant: Variant, games: Option<u64>) {
+        let name = format!("{} ({})", variant.name(), self.profiles.len() + 1);
+        if self.default_for(variant).is_none() {
+            self.defaults.push((variant, path.clone()));
+        }
+        self.profiles.retain(|profile| profile.path != path);
+        self.profiles.push(Profile {
+            name,
+            path,
+            variant,
+            games,
+            created: now(),
+        });
+    }
+
+    /// Returns the profile that is loaded when switching to the variant, if it has one.
+    pub fn default_for(&self, variant: Variant) -> Option<&Profile> {
+        let (_, path) = self
+            .defaults
+            .iter()
+            .find(|(default_variant, _)| *default_variant == variant)?;
+        self.profiles.iter().find(|profile| profile.path == *path)
+    }
+
+    /// Returns the profile whose best moves are kept in the given file, if there is one.
+    pub fn find(&self, path: &Path) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.path == path)
+    }
+
+    /// Sets the amount of games simulated for the profile kept in the given file, if there is one.
+    pub fn set_games(&mut self, path: &Path, games: u64) {
+        if let Some(profile) = self
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.path == path)
+        {
+            profile.games = Some(games);
+        }
+    }
+
+    /// Makes the profile the one loaded when switching to its variant.
+    fn set_default(&mut self, index: usize) {
+        let profile = &self.profiles[index];
+        self.defaults
+            .retain(|(variant, _)| *variant != profile.variant);
+        self.defaults.push((profile.variant, profile.path.clone()));
+    }
+
+    /// Copies the best moves the user picks into the profile folder, adding a profile for them.
+    /// The variant of the best moves can't be told from the file, so they're assumed to be for the given variant.
+    fn import(&mut self, variant: Variant) -> Result<(), String> {
+        let Some(picked) = rfd::FileDialog::new()
+            .add_filter("Best moves", &["yml", "yaml"])
+            .pick_file()
+        else {
+            return Ok(());
+        };
+
+        let path = Profiles::new_policy_path(variant)
+            .ok_or("There's no folder to import the best moves into.")?;
+        std::fs::copy(&picked, &path)
+            .map_err(|e| format!("Couldn't import the best moves: {e}"))?;
+
+        self.add(path, variant, None);
+        if let Some(profile) = self.profiles.last_mut() {
+            profile.name = picked.file_stem().map_or(profile.name.clone(), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes the profile, deleting its file.
+    fn delete(&mut self, index: usize) -> Result<(), String> {
+        let profile = self.profiles.remove(index);
+        self.defaults.retain(|(_, path)| *path != profile.path);
+
+        match std::fs::remove_file(&profile.path) {
+            Ok(()) => Ok(()),
+            // The profile is still removed if its file was already deleted.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Couldn't delete {}: {e}", profile.path.display())),
+        }
+    }
+
+    /// Draws the window listing every profile, with the options to load, rename, delete, or make them the default.
+    /// The profiles are saved whenever they are changed.
+    /// Returns the profile the user chose to load, along with any problem with the changes.
+    pub fn window(
+        &mut self,
+        context: &egui::Context,
+        loaded: &Path,
+        variant: Variant,
+    ) -> (Option<Profile>, Option<String>) {
+        let mut open = self.window_open;
+        let mut chosen = None;
+        let mut error = None;
+        let mut changed = false;
+
+        Window::new(PROFILE_MANAGER)
+            .open(&mut open)
+            .show(context, |ui| {
+                if let Some(folder) = folder() {
+                    ui.label(format!("Kept in {}", folder.display()));
+                }
+                ui.add_space(10.);
+
+                if self.profiles.is_empty() {
+                    ui.label("There are no profiles yet. Recalculating or importing best moves adds one.");
+                }
+
+                let mut deleted = None;
+                let mut made_default = None;
+
+                egui::Grid::new(PROFILE_MANAGER)
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (index, profile) in self.profiles.iter().enumerate() {
+                            // The name can be edited in place, & is saved once the edit is finished.
+                            match &mut self.renaming {
+                                Some((renaming, name)) if *renaming == index => {
+                                    let edit = ui.text_edit_singleline(name);
+                                    if edit.lost_focus() {
+                                        changed = true;
+                                    }
+                                    edit.request_focus();
+                                }
+                                _ => {
+                                    let mut name = RichText::new(&profile.name);
+                                    if profile.path == loaded {
+                                        name = name.strong();
+                                    }
+                                    if ui
+                                        .label(name)
+                                        .on_hover_text(profile.path.display().to_string())
+                                        .double_clicked()
+                                    {
+                                        self.renaming = Some((index, profile.name.clone()));
+                                    }
+                                }
+                            }
+
+                            ui.label(profile.variant.name());
+                            ui.label(profile.games.map_or(String::from("? games"), |games| {
+                                format!("{games} games")
+                            }));
+                            ui.label(format_date(profile.created));
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Load").clicked() {
+                                    chosen = Some(profile.clone());
+                                }
+                                if ui.button("Rename").clicked() {
+                                    self.renaming = Some((index, profile.name.clone()));
+                                }
+
+                                let is_default =
+                                    self.defaults.iter().any(|(_, path)| *path == profile.path);
+                                if ui
+                                    .add_enabled(!is_default, egui::Button::new("Set default"))
+                                    .on_hover_text("Loaded when switching to this variant.")
+                                    .clicked()
+                                {
+                                    made_default = Some(index);
+                                }
+                                if ui.button("Delete").clicked() {
+                                    deleted = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                // Finishes the rename once the name loses focus.
+                if changed {
+                    if let Some((index, name)) = self.renaming.take() {
+                        if let Some(profile) = self.profiles.get_mut(index) {
+                            if !name.trim().is_empty() {
+                                profile.name = name.trim().to_string();
+                            }
+                        }
+                    }
+                }
+                if let Some(index) = made_default {
+                    self.set_default(index);
+                    changed = true;
+                }
+                if let Some(index) = deleted {
+                    self.renaming = None;
+                    if let Err(e) = self.delete(index) {
+                        error = Some(e);
+                    }
+                    changed = true;
+                }
+
+                ui.add_space(10.);
+                if ui
+                    .button("Import")
+                    .on_hover_text(format!("Copies best moves for {} into the profiles.", variant.name()))
+                    .clicked()
+                {
+                    match self.import(variant) {
+                        Ok(()) => changed = true,
+                        Err(e) => error = Some(e),
+                    }
+                }
+            });
+
+        if changed {
+            if let Err(e) = self.save() {
+                error = Some(e);
+            }
+        }
+
+        self.window_open = open;
+        (chosen, error)
+    }
+}
+
+/// Formats the seconds since the unix epoch as a date, such as "2024-06-01".
+pub(crate) fn format_date(seconds: u64) -> String {
+    // Converts the days since the epoch into a civil date, from Howard Hinnant's date algorithms.
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,356 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use eframe::epaint::Color32;
+use egui::ahash::HashMap;
+use egui::{RichText, Ui};
+
+use compute::{BoardRoll, Policy, RankedMove};
+use game_core::DiceRoll;
+use networked::replay::{Replay, ReplayTurn, LEGACY_EXTENSION, REPLAY_EXTENSION};
+
+use crate::play::{tiles_layout, SavedGame};
+use crate::settings::BoardColors;
+use crate::Main;
+
+/// The folder the replays of the games against the AI are written to, within the gui's storage folder.
+const REPLAY_FOLDER: &str = "replays";
+/// The file the unfinished game against the AI is saved to, within the gui's storage folder.
+const SAVED_GAME_FILE: &str = "saved_game.yml";
+/// The range of seconds that can be waited between each turn.
+const DELAY_RANGE: RangeInclusive<f64> = 0.1..=3.;
+
+/// Returns the folder the replays of the games against the AI are kept in, or None if the platform doesn't have one.
+fn folder() -> Option<PathBuf> {
+    eframe::storage_dir(crate::WINDOW_NAME).map(|storage| storage.join(REPLAY_FOLDER))
+}
+
+/// Writes the replay of a game against the AI to a new file in the replay folder, creating it if needed.
+pub fn save(replay: &Replay) -> Result<PathBuf, String> {
+    let folder = folder().ok_or("There's no folder to save the replay in.")?;
+    std::fs::create_dir_all(&folder)
+        .map_err(|e| format!("Couldn't create the replay folder: {e}"))?;
+
+    // The time the game ended at keeps the file names unique & in order.
+    let ended = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = folder.join(format!("game-{ended}.{REPLAY_EXTENSION}"));
+
+    replay
+        .write(&path)
+        .map_err(|e| format!("Couldn't save the replay: {e}"))?;
+    Ok(path)
+}
+
+/// Returns the file the unfinished game against the AI is saved to, or None if the platform doesn't have one.
+fn saved_game_file() -> Option<PathBuf> {
+    eframe::storage_dir(crate::WINDOW_NAME).map(|storage| storage.join(SAVED_GAME_FILE))
+}
+
+/// Returns true if there's a saved game that can be continued.
+pub fn has_saved_game() -> bool {
+    saved_game_file().is_some_and(|path| path.exists())
+}
+
+/// Saves the unfinished game against the AI, replacing the game that was saved before.
+pub fn save_game(saved: &SavedGame) -> Result<PathBuf, String> {
+    let path = saved_game_file().ok_or("There's no folder to save the game in.")?;
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)
+            .map_err(|e| format!("Couldn't create the folder to save the game in: {e}"))?;
+    }
+
+    let file = File::create(&path).map_err(|e| format!("Couldn't save the game: {e}"))?;
+    compute::codec::to_writer(BufWriter::new(file), saved)
+        .map_err(|e| format!("Couldn't save the game: {e}"))?;
+    Ok(path)
+}
+
+/// Loads the saved game, then removes it, as it's continued from where it was saved.
+pub fn take_saved_game() -> Result<SavedGame, String> {
+    let path = saved_game_file().ok_or("There's no saved game.")?;
+    let file = File::open(&path).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => String::from("There's no saved game."),
+        _ => format!("Couldn't load the saved game: {e}"),
+    })?;
+    let saved = compute::codec::from_reader(BufReader::new(file))
+        .map_err(|e| format!("Couldn't parse the saved game: {e}"))?;
+
+    std::fs::remove_file(&path).map_err(|e| format!("Couldn't remove the saved game: {e}"))?;
+    Ok(saved)
+}
+
+/// Steps through the turns of a recorded game, annotating each move with the best move.
+pub struct ReplayViewer {
+    /// The game being replayed, along with the file it was loaded from.
+    log: Option<(PathBuf, Replay)>,
+    /// The amount of turns that have been replayed.
+    position: usize,
+    /// Whether the turns are being replayed without the user stepping through them.
+    playing: bool,
+    /// How many seconds are waited between each turn.
+    delay: f64,
+    /// The time of the gui input the last turn was replayed at.
+    last_step: Option<f64>,
+    /// Why the last replay couldn't be loaded.
+    error: Option<String>,
+}
+
+impl Default for ReplayViewer {
+    fn default() -> Self {
+        ReplayViewer {
+            log: None,
+            position: 0,
+            playing: false,
+            delay: 1.,
+            last_step: None,
+            error: None,
+        }
+    }
+}
+
+impl ReplayViewer {
+    /// Asks the user for a replay & loads it, starting the replay from the first turn.
+    fn open(&mut self) {
+        let mut dialog = rfd::FileDialog::new();
+        if let Some(folder) = folder().filter(|folder| folder.exists()) {
+            dialog = dialog.set_directory(folder);
+        }
+
+        let picked = dialog
+            .add_filter("Replays", &[REPLAY_EXTENSION, LEGACY_EXTENSION, "yaml"])
+            .pick_file();
+
+        if let Some(path) = picked {
+            self.load(&path);
+        }
+    }
+
+    /// Loads the replay from the given file, starting the replay from the first turn.
+    fn load(&mut self, path: &Path) {
+        match Replay::read(path) {
+            Ok(log) => {
+                self.log = Some((path.to_path_buf(), log));
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+
+        self.position = 0;
+        self.playing = false;
+        self.last_step = None;
+    }
+
+    /// Draws the replay controls, along with the boards of every player at the current turn.
+    /// The best moves & ranked moves are used to annotate the move of the current turn.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) {
+        ui.horizontal(|ui| {
+            if ui.button("Open replay").clicked() {
+                self.open();
+            }
+            if let Some((path, _)) = &self.log {
+                ui.label(path.display().to_string());
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+        }
+
+        let Some((_, log)) = &self.log else {
+            ui.label("Open a replay written by the server or by playing against the AI.");
+            return;
+        };
+        let turns = log.turns.len();
+
+        // Replays the next turn once the delay has passed.
+        let time = ui.input(|input| input.time);
+        if self.playing && self.position < turns {
+            let waited = self
+                .last_step
+                .map_or(self.delay, |last_step| time - last_step);
+            if waited >= self.delay {
+                self.position += 1;
+                self.last_step = Some(time);
+            }
+
+            // Keeps redrawing without any user input, so the next turn is replayed on time.
+            let remaining = self.delay - self.last_step.map_or(0., |last_step| time - last_step);
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f64(remaining.max(0.)));
+        }
+        if self.position >= turns {
+            self.playing = false;
+        }
+
+        ui.add_space(10.);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.position > 0, egui::Button::new("⏮"))
+                .on_hover_text("Previous turn")
+                .clicked()
+            {
+                self.position -= 1;
+            }
+
+            let play = match self.playing {
+                true => "Pause",
+                false => "Play",
+            };
+            if ui
+                .add_enabled(self.position < turns, egui::Button::new(play))
+                .clicked()
+            {
+                self.playing = !self.playing;
+                self.last_step = None;
+            }
+
+            if ui
+                .add_enabled(self.position < turns, egui::Button::new("⏭"))
+                .on_hover_text("Next turn")
+                .clicked()
+            {
+                self.position += 1;
+            }
+
+            ui.add(egui::Slider::new(&mut self.position, 0..=turns).text("Turn"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Seconds per turn:");
+            ui.add(egui::Slider::new(&mut self.delay, DELAY_RANGE).step_by(0.1));
+        });
+        ui.add_space(10.);
+
+        let boards = log.boards_after(self.position);
+        let tiles = log.variant.tiles;
+        let current = self
+            .position
+            .checked_sub(1)
+            .and_then(|index| log.turns.get(index));
+
+        egui::Grid::new("Replay")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (player, name) in log.players.iter().enumerate() {
+                    ui.label(name);
+
+                    // The player who took the current turn has their move highlighted.
+                    let layout = match current.filter(|turn| turn.player as usize == player) {
+                        Some(turn) => match turn.moved_board {
+                            Some(moved_board) => Main::generate_board(
+                                turn.board,
+                                turn.roll,
+                                moved_board,
+                                tiles,
+                                colors,
+                            ),
+                            None => Main::generate_dying_board(turn.board, turn.roll, colors),
+                        },
+                        None => tiles_layout(boards[player].0, tiles, colors),
+                    };
+                    ui.label(layout);
+
+                    let (board, out) = boards[player];
+                    let score = game_core::board_value(board);
+                    match out {
+                        true => ui.label(format!("Score: {score} (out)")),
+                        false => ui.label(format!("Score: {score}")),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(10.);
+        match current {
+            Some(turn) => annotate(ui, turn, log, colors, best_moves, ranked_moves),
+            None => {
+                ui.label("The game is about to start.");
+            }
+        }
+
+        if self.position == turns {
+            match log.winning_score {
+                Some(score) => ui.label(format!("The game ended with a winning score of {score}.")),
+                None => ui.label("The game didn't finish."),
+            };
+        }
+    }
+}
+
+/// Describes the move of the turn, comparing it against the best move for the board & roll.
+fn annotate(
+    ui: &mut Ui,
+    turn: &ReplayTurn,
+    replay: &Replay,
+    colors: &BoardColors,
+    best_moves: Option<&Policy>,
+    ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+) {
+    let name = replay
+        .players
+        .get(turn.player as usize)
+        .map_or("Unknown player", String::as_str);
+
+    let Some(moved_board) = turn.moved_board else {
+        ui.label(format!(
+            "{name} rolled {} & had no moves, so they're out.",
+            turn.roll
+        ));
+        return;
+    };
+    ui.label(format!(
+        "{name} rolled {} & moved to {}.",
+        turn.roll,
+        Main::board_name(moved_board)
+    ));
+
+    let roll = DiceRoll::from(turn.roll);
+    let Some(best) = best_moves.and_then(|best_moves| best_moves.best_move(turn.board, roll))
+    else {
+        ui.label("The best move for this roll isn't known.");
+        return;
+    };
+
+    // The win chances are only known if the moves were ranked.
+    let ranked = ranked_moves.and_then(|ranked| ranked.get(&BoardRoll::new(turn.board, roll)));
+    let value = |board: u16| {
+        ranked
+            .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == board))
+            .map(|ranked| format!(" ({:.1}% to win)", ranked.value as f32 / 10.))
+            .unwrap_or_default()
+    };
+
+    if best == moved_board {
+        ui.label(
+            RichText::new(format!("This was the best move{}.", value(best)))
+                .color(Color32::LIGHT_GREEN),
+        );
+        return;
+    }
+
+    ui.label(
+        RichText::new(format!("This wasn't the best move{}.", value(moved_board)))
+            .color(Color32::LIGHT_RED),
+    );
+    ui.horizontal(|ui| {
+        ui.label(format!("The best move was{}:", value(best)));
+        ui.label(Main::generate_board(
+            turn.board,
+            turn.roll,
+            best,
+            replay.variant.tiles,
+            colors,
+        ));
+    });
+}
@@ -0,0 +1,333 @@
+use eframe::epaint::Color32;
+use egui::gui_zoom::kb_shortcuts;
+use egui::{Style, Vec2, Visuals, Window};
+use serde::{Deserialize, Serialize};
+
+use crate::sound::Audio;
+use crate::Main;
+
+// The id for the settings window.
+const SETTINGS: &str = "Settings";
+
+/// The key the settings are persisted under.
+pub const SETTINGS_KEY: &str = "settings";
+
+/// The range the UI scale can be set within.
+const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+/// How much the UI scale changes with each zoom shortcut.
+const SCALE_STEP: f32 = 0.1;
+/// How much bigger the text is in the extra large text mode.
+const LARGE_TEXT_FACTOR: f32 = 1.5;
+/// The smallest size of anything that can be tapped in the touch layout.
+const TOUCH_INTERACT_SIZE: Vec2 = Vec2::new(48., 40.);
+/// The gap between the widgets in the touch layout, so a tap doesn't hit the wrong one.
+const TOUCH_ITEM_SPACING: Vec2 = Vec2::new(12., 10.);
+/// The padding within the buttons in the touch layout.
+const TOUCH_BUTTON_PADDING: Vec2 = Vec2::new(14., 8.);
+
+/// How the gui is laid out.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Uses the touch layout once touch input is used, otherwise uses the desktop layout.
+    Automatic,
+    /// Small widgets with the buttons along the top, for a mouse & keyboard.
+    Desktop,
+    /// Large widgets with the buttons along the bottom & fewer panels, for touchscreens.
+    Touch,
+}
+
+/// The colours used when drawing the boards.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct BoardColors {
+    /// The background of pieces that are alive.
+    pub alive: Color32,
+    /// The background of pieces that are knocked down.
+    pub down: Color32,
+    /// The background of pieces that get knocked down by a move.
+    pub knocked: Color32,
+    /// The background of the roll value & the back button.
+    pub roll: Color32,
+}
+
+impl BoardColors {
+    /// The colours the boards are drawn with unless the user changes them.
+    pub const STANDARD: BoardColors = BoardColors {
+        alive: Color32::DARK_GREEN,
+        down: Color32::DARK_GRAY,
+        knocked: Color32::GOLD,
+        roll: Color32::BLUE,
+    };
+
+    /// Colours that can be told apart by colourblind users, from the Okabe-Ito palette.
+    pub const HIGH_CONTRAST: BoardColors = BoardColors {
+        alive: Color32::from_rgb(0, 114, 178),
+        down: Color32::from_rgb(30, 30, 30),
+        knocked: Color32::from_rgb(230, 159, 0),
+        roll: Color32::from_rgb(204, 121, 167),
+    };
+
+    /// Blue & red, which stay apart with red-green colourblindness, from Paul Tol's bright palette.
+    pub const BLUE_RED: BoardColors = BoardColors {
+        alive: Color32::from_rgb(68, 119, 170),
+        down: Color32::from_rgb(40, 40, 40),
+        knocked: Color32::from_rgb(238, 102, 119),
+        roll: Color32::from_rgb(170, 51, 119),
+    };
+
+    /// Teal & red, which stay apart with blue-yellow colourblindness.
+    pub const TEAL_RED: BoardColors = BoardColors {
+        alive: Color32::from_rgb(0, 128, 128),
+        down: Color32::from_rgb(40, 40, 40),
+        knocked: Color32::from_rgb(220, 50, 32),
+        roll: Color32::from_rgb(90, 90, 90),
+    };
+
+    /// The colour schemes the user can pick from, along with their names.
+    pub const PRESETS: [(&'static str, BoardColors); 4] = [
+        ("Standard", BoardColors::STANDARD),
+        ("High contrast", BoardColors::HIGH_CONTRAST),
+        ("Blue & red (red-green safe)", BoardColors::BLUE_RED),
+        ("Teal & red (blue-yellow safe)", BoardColors::TEAL_RED),
+    ];
+
+    /// Returns the name of the preset these colours match, or None if they have been customised.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        BoardColors::PRESETS
+            .iter()
+            .find(|(_, preset)| preset == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Default for BoardColors {
+    fn default() -> Self {
+        BoardColors::STANDARD
+    }
+}
+
+/// The preferences of the user, which are kept between restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether the dark theme is used, otherwise the light theme is.
+    pub dark_mode: bool,
+    /// The amount of physical pixels for each logical point.
+    pub scale: f32,
+    /// The colours used when drawing the boards.
+    pub colors: BoardColors,
+    /// Whether the sound effects are muted.
+    pub muted: bool,
+    /// Whether animations are skipped, for faster play.
+    pub skip_animations: bool,
+    /// Whether the text is extra large, without scaling the rest of the gui.
+    pub large_text: bool,
+    /// Whether the raw encoding of the boards is shown alongside the tiles.
+    pub debug_view: bool,
+    /// How the gui is laid out.
+    pub layout: Layout,
+    /// Whether the loaded best moves are improved in the background while the gui is idle.
+    pub background_compute: bool,
+    /// Whether touch input has been used since the gui started.
+    #[serde(skip)]
+    pub touch_detected: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            dark_mode: true,
+            scale: 1.5,
+            colors: BoardColors::default(),
+            muted: false,
+            skip_animations: false,
+            large_text: false,
+            debug_view: false,
+            layout: Layout::Automatic,
+            background_compute: false,
+            touch_detected: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns true if the touch layout is being used.
+    pub fn is_touch(&self) -> bool {
+        match self.layout {
+            Layout::Automatic => self.touch_detected,
+            Layout::Desktop => false,
+            Layout::Touch => true,
+        }
+    }
+
+    /// Switches to the touch layout the first time touch input is used, if the layout is automatic.
+    pub fn detect_touch(&mut self, context: &egui::Context) {
+        if self.touch_detected {
+            return;
+        }
+
+        let touched = context.input(|input| {
+            input
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::Touch { .. }))
+        });
+        if touched {
+            self.touch_detected = true;
+            self.apply(context);
+        }
+    }
+
+    /// Applies the theme, scale, text size, & layout to the gui.
+    pub fn apply(&self, context: &egui::Context) {
+        let visuals = match self.dark_mode {
+            true => Visuals::dark(),
+            false => Visuals::light(),
+        };
+
+        // The text sizes start from the defaults, so applying the settings again doesn't grow them.
+        let mut style = Style {
+            visuals,
+            ..Style::default()
+        };
+        if self.large_text {
+            for font in style.text_styles.values_mut() {
+                font.size *= LARGE_TEXT_FACTOR;
+            }
+        }
+        if self.is_touch() {
+            style.spacing.interact_size = TOUCH_INTERACT_SIZE;
+            style.spacing.item_spacing = TOUCH_ITEM_SPACING;
+            style.spacing.button_padding = TOUCH_BUTTON_PADDING;
+        }
+
+        context.set_style(style);
+        context.set_pixels_per_point(self.scale);
+    }
+
+    /// Zooms the gui in & out with Ctrl + plus & Ctrl + minus, or resets the zoom with Ctrl + 0.
+    pub fn zoom_shortcuts(&mut self, context: &egui::Context) {
+        let previous = self.scale;
+
+        context.input_mut(|input| {
+            if input.consume_shortcut(&kb_shortcuts::ZOOM_RESET) {
+                self.scale = Settings::default().scale;
+            }
+            if input.consume_shortcut(&kb_shortcuts::ZOOM_IN)
+                || input.consume_shortcut(&kb_shortcuts::ZOOM_IN_SECONDARY)
+            {
+                self.scale += SCALE_STEP;
+            }
+            if input.consume_shortcut(&kb_shortcuts::ZOOM_OUT) {
+                self.scale -= SCALE_STEP;
+            }
+        });
+
+        if self.scale != previous {
+            // Rounds away the floating point error, so the scale lines up with the steps of the slider.
+            self.scale = ((self.scale / SCALE_STEP).round() * SCALE_STEP)
+                .clamp(*SCALE_RANGE.start(), *SCALE_RANGE.end());
+            self.apply(context);
+        }
+    }
+
+    /// Draws the window to change the settings, applying any changes that are made.
+    pub fn window(&mut self, context: &egui::Context, open: &mut bool) {
+        let previous = self.clone();
+
+        Window::new(SETTINGS).open(open).show(context, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Theme:");
+                ui.selectable_value(&mut self.dark_mode, true, "Dark");
+                ui.selectable_value(&mut self.dark_mode, false, "Light");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("UI scale:");
+                ui.add(egui::Slider::new(&mut self.scale, SCALE_RANGE).step_by(SCALE_STEP as f64))
+                    .on_hover_text(
+                        "Ctrl + plus & Ctrl + minus also zoom, while Ctrl + 0 resets the zoom.",
+                    );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Layout:");
+                ui.selectable_value(&mut self.layout, Layout::Automatic, "Automatic")
+                    .on_hover_text("Uses the touch layout once a touchscreen is used.");
+                ui.selectable_value(&mut self.layout, Layout::Desktop, "Desktop");
+                ui.selectable_value(&mut self.layout, Layout::Touch, "Touch");
+            });
+
+            ui.add_enabled(
+                Audio::is_available(),
+                egui::Checkbox::new(&mut self.muted, "Mute sound effects"),
+            )
+            .on_disabled_hover_text("Sound effects require the \"sound\" feature.");
+            ui.checkbox(&mut self.skip_animations, "Skip animations");
+            ui.checkbox(
+                &mut self.background_compute,
+                "Improve the best moves while idle",
+            )
+            .on_hover_text("Simulates games on one thread while the gui isn't being used, & pauses during games.");
+
+            ui.add_space(10.);
+            ui.label("Colours:");
+            egui::Grid::new("Colours").show(ui, |ui| {
+                ui.label("Scheme");
+                egui::ComboBox::from_id_source("Colour scheme")
+                    .selected_text(self.colors.preset_name().unwrap_or("Custom"))
+                    .show_ui(ui, |ui| {
+                        for (name, preset) in BoardColors::PRESETS {
+                            ui.selectable_value(&mut self.colors, preset, name);
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Alive pieces");
+                ui.color_edit_button_srgba(&mut self.colors.alive);
+                ui.end_row();
+
+                ui.label("Knocked down pieces");
+                ui.color_edit_button_srgba(&mut self.colors.down);
+                ui.end_row();
+
+                ui.label("Pieces knocked down by a move");
+                ui.color_edit_button_srgba(&mut self.colors.knocked);
+                ui.end_row();
+
+                ui.label("Rolls");
+                ui.color_edit_button_srgba(&mut self.colors.roll);
+                ui.end_row();
+            });
+
+            // Previews the colours on a move, so the pieces can be checked to be told apart.
+            let preview = Main::generate_board(0b110011101, 7, 0b110010001, 9, &self.colors);
+            ui.horizontal(|ui| {
+                ui.label("Preview:");
+                ui.label(preview);
+            });
+
+            ui.add_space(10.);
+            ui.label("Accessibility:");
+            ui.checkbox(&mut self.large_text, "Extra large text");
+
+            ui.add_space(10.);
+            ui.label("Developer:");
+            ui.checkbox(&mut self.debug_view, "Show the raw encoding of the boards")
+                .on_hover_text("The u16 value & binary of each board, as used by the best moves & the network protocol.");
+
+            ui.add_space(10.);
+            if ui.button("Reset to defaults").clicked() {
+                // Touch input was still used, even if the settings are reset.
+                *self = Settings {
+                    touch_detected: self.touch_detected,
+                    ..Settings::default()
+                };
+            }
+        });
+
+        if *self != previous {
+            self.apply(context);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "sound")]
+use std::time::Duration;
+
+#[cfg(feature = "sound")]
+use rodio::source::{SineWave, Source};
+#[cfg(feature = "sound")]
+use rodio::{OutputStream, OutputStreamHandle};
+
+/// A sound effect that can be played during a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    /// The dice rattling as they are rolled.
+    DiceRoll,
+    /// A piece clacking as it is knocked down.
+    TileFlip,
+    /// The fanfare for shutting the box.
+    BoxShut,
+}
+
+impl Sound {
+    /// Returns the notes that make up this sound, as the frequency, the delay before it plays, & how long it plays for.
+    #[cfg(feature = "sound")]
+    fn notes(self) -> Vec<(f32, Duration, Duration)> {
+        let millis = Duration::from_millis;
+
+        match self {
+            // Short high blips of varying pitch, like dice hitting each other.
+            Sound::DiceRoll => [880., 1320., 990., 1180., 1050.]
+                .iter()
+                .enumerate()
+                .map(|(index, frequency)| (*frequency, millis(index as u64 * 60), millis(25)))
+                .collect(),
+            Sound::TileFlip => vec![(220., millis(0), millis(50))],
+            // An ascending C major arpeggio.
+            Sound::BoxShut => [523.25, 659.25, 783.99, 1046.5]
+                .iter()
+                .enumerate()
+                .map(|(index, frequency)| (*frequency, millis(index as u64 * 150), millis(200)))
+                .collect(),
+        }
+    }
+}
+
+/// Plays the sound effects, if the gui was built with the "sound" feature.
+/// Without the feature, or if there is no audio device, the sounds are silently skipped.
+pub struct Audio {
+    /// The stream has to be kept alive for the handle to play sounds.
+    #[cfg(feature = "sound")]
+    output: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl Audio {
+    /// Opens the default audio device.
+    pub fn new() -> Self {
+        Audio {
+            #[cfg(feature = "sound")]
+            output: OutputStream::try_default().ok(),
+        }
+    }
+
+    /// Returns true if the sound effects can be played.
+    pub fn is_available() -> bool {
+        cfg!(feature = "sound")
+    }
+
+    /// Plays the given sound without blocking, unless the sounds are muted.
+    #[cfg_attr(not(feature = "sound"), allow(unused_variables))]
+    pub fn play(&self, sound: Sound, muted: bool) {
+        #[cfg(feature = "sound")]
+        {
+            let Some((_, handle)) = &self.output else {
+                return;
+            };
+            if muted {
+                return;
+            }
+
+            for (frequency, delay, duration) in sound.notes() {
+                let note = SineWave::new(frequency)
+                    .take_duration(duration)
+                    .amplify(0.2)
+                    .delay(delay);
+
+                // If the note couldn't be played, then the rest won't be either.
+                if handle.play_raw(note).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
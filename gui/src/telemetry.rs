@@ -0,0 +1,141 @@
+//! Counts how the gui is used, but only once the user opts in.
+//!
+//! The counts are coarse totals that are only kept on this device, alongside the other persisted state.
+//! Nothing is ever sent anywhere, the user can only share them by exporting them to a file themselves.
+
+use std::collections::BTreeMap;
+
+use egui::{RichText, ScrollArea, Window};
+use serde::{Deserialize, Serialize};
+
+/// The key the usage stats are persisted under.
+pub const TELEMETRY_KEY: &str = "telemetry";
+
+// The id for the usage stats window.
+const USAGE_STATS: &str = "Usage Stats";
+
+/// Something the user did, which is counted if they opted in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Usage {
+    /// Finished a game against the AI.
+    LocalGame,
+    /// Finished a game over the network.
+    NetworkedGame,
+    /// Started recalculating the best moves.
+    ComputeRun,
+    /// Switched to exploring the best moves.
+    Explore,
+    /// Switched to playing against the AI.
+    Play,
+    /// Switched to playing over the network.
+    Online,
+    /// Switched to the trainer.
+    Trainer,
+    /// Switched to viewing replays.
+    Replay,
+    /// Switched to exploring the game tree.
+    GameTree,
+    /// Exported the board view as an image.
+    ImageExport,
+    /// Started hosting a server.
+    Hosting,
+}
+
+/// What the usage stats look like when they're exported, which is exactly what is shown to the user.
+#[derive(Serialize)]
+struct Report<'a> {
+    /// The version of the gui that counted the usage.
+    version: &'a str,
+    counts: &'a BTreeMap<Usage, u32>,
+}
+
+/// The opt-in usage stats, which are off until the user turns them on.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Telemetry {
+    /// Whether the usage is counted.
+    enabled: bool,
+    /// The amount of times each thing was done since the counts were last reset.
+    counts: BTreeMap<Usage, u32>,
+    /// Whether the usage stats window is open.
+    #[serde(skip)]
+    pub window_open: bool,
+}
+
+impl Telemetry {
+    /// Counts the usage, if the user opted in.
+    pub fn record(&mut self, usage: Usage) {
+        if !self.enabled {
+            return;
+        }
+
+        let count = self.counts.entry(usage).or_default();
+        *count = count.saturating_add(1);
+    }
+
+    /// Returns the usage stats as they're exported.
+    fn report(&self) -> String {
+        let report = Report {
+            version: env!("CARGO_PKG_VERSION"),
+            counts: &self.counts,
+        };
+        compute::codec::to_string(&report).unwrap_or_default()
+    }
+
+    /// Draws the window to opt in to the usage stats, showing exactly what has been counted.
+    /// Returns the problem exporting the stats, if there was one.
+    pub fn window(&mut self, context: &egui::Context) -> Option<String> {
+        let mut open = self.window_open;
+
+        let response = Window::new(USAGE_STATS).open(&mut open).show(context, |ui| {
+            ui.set_width_range(200f32..=300f32);
+
+            ui.checkbox(&mut self.enabled, "Count how the app is used")
+                .on_hover_text("The counts are only kept on this device.");
+            ui.label("Only the totals below are counted. They're never sent anywhere, but can be exported to share them.");
+            ui.add_space(10.);
+
+            let report = self.report();
+            ScrollArea::vertical().max_height(200.).show(ui, |ui| {
+                ui.label(RichText::new(&report).monospace());
+            });
+            ui.add_space(10.);
+
+            ui.horizontal(|ui| {
+                if ui.button("Reset").clicked() {
+                    self.counts.clear();
+                }
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|output| output.copied_text = report.clone());
+                }
+
+                // The web app has nowhere to save the file to.
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Export").clicked() {
+                    return export(&report).err();
+                }
+                None
+            })
+            .inner
+        });
+
+        self.window_open = open;
+        response?.inner.flatten()
+    }
+}
+
+/// Asks the user where to save the usage stats, then saves them there.
+#[cfg(not(target_arch = "wasm32"))]
+fn export(report: &str) -> Result<(), String> {
+    let picked = rfd::FileDialog::new()
+        .add_filter("YAML", &["yml", "yaml"])
+        .set_file_name("usage.yml")
+        .save_file();
+
+    let Some(path) = picked else {
+        return Ok(());
+    };
+    std::fs::write(&path, report)
+        .map_err(|e| format!("Couldn't export the usage stats: {e} ({})", path.display()))
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use eframe::epaint::Color32;
+use egui::{Align2, Area, Frame, Id, Order, RichText, Vec2};
+
+// The id for the area the toasts are drawn in.
+const TOASTS_AREA: &str = "Toasts";
+
+/// How long each toast is shown for, in seconds.
+const TOAST_DURATION: f64 = 6.;
+/// The widest a toast can be, before its message wraps.
+const TOAST_WIDTH: f32 = 300.;
+/// The gap between the toasts & the bottom right corner, which leaves room for the status bar.
+const TOAST_OFFSET: Vec2 = Vec2::new(-10., -40.);
+
+/// A problem that is shown to the user for a short while.
+struct Toast {
+    message: String,
+    /// The time of the gui input the toast was first drawn at, if it has been drawn.
+    shown_at: Option<f64>,
+}
+
+/// The problems that didn't stop the gui from working, but that the user should know about.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    /// Queues the problem to be shown to the user.
+    /// The problem is also printed, so it can still be found once the toast is gone.
+    pub fn error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::warn!("{message}");
+
+        self.toasts.push(Toast {
+            message,
+            shown_at: None,
+        });
+    }
+
+    /// Draws the toasts in the bottom right corner, removing them once they have been shown for long enough.
+    /// A toast can also be removed early by clicking on it.
+    pub fn show(&mut self, context: &egui::Context) {
+        let time = context.input(|input| input.time);
+        self.toasts.retain(|toast| {
+            toast
+                .shown_at
+                .is_none_or(|shown_at| time - shown_at < TOAST_DURATION)
+        });
+
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        let mut next_expiry = TOAST_DURATION;
+
+        Area::new(Id::new(TOASTS_AREA))
+            .anchor(Align2::RIGHT_BOTTOM, TOAST_OFFSET)
+            .order(Order::Foreground)
+            .show(context, |ui| {
+                for (index, toast) in self.toasts.iter_mut().enumerate() {
+                    let shown_at = *toast.shown_at.get_or_insert(time);
+                    next_expiry = next_expiry.min(TOAST_DURATION - (time - shown_at));
+
+                    let clicked = Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            ui.set_max_width(TOAST_WIDTH);
+                            ui.label(RichText::new(&toast.message).color(Color32::LIGHT_RED));
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss")
+                        .clicked();
+
+                    if clicked {
+                        dismissed = Some(index);
+                    }
+                }
+            });
+
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+
+        // Keeps redrawing without any user input, so the toasts are removed on time.
+        context.request_repaint_after(Duration::from_secs_f64(next_expiry.max(0.)));
+    }
+}
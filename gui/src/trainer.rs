@@ -0,0 +1,441 @@
+use eframe::epaint::Color32;
+use egui::ahash::HashMap;
+use egui::{Label, RichText, Sense, Ui};
+use fastrand::Rng;
+
+use compute::{BoardRoll, Policy, RankedMove};
+use game_core::{rng, DiceRoll};
+
+use crate::settings::BoardColors;
+use crate::variant::Variant;
+use crate::Main;
+
+/// How many random situations are tried before giving up on finding one in the best moves.
+const DEAL_ATTEMPTS: u32 = 1000;
+/// The most a move's win chance can be below the best move's, while still being near-optimal.
+/// This is in tenths of a percent.
+const NEAR_OPTIMAL_MARGIN: u16 = 20;
+
+/// How good the answer of the user was, compared to the best move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Grade {
+    /// The answer was the best move.
+    Optimal,
+    /// The answer was almost as good as the best move.
+    NearOptimal,
+    /// The answer was much worse than the best move.
+    Blunder,
+}
+
+impl Grade {
+    /// Returns the human readable name of this grade.
+    fn name(self) -> &'static str {
+        match self {
+            Grade::Optimal => "Optimal",
+            Grade::NearOptimal => "Near-optimal",
+            Grade::Blunder => "Blunder",
+        }
+    }
+
+    /// Returns the colour the grade is displayed in.
+    fn color(self) -> Color32 {
+        match self {
+            Grade::Optimal => Color32::LIGHT_GREEN,
+            Grade::NearOptimal => Color32::YELLOW,
+            Grade::Blunder => Color32::LIGHT_RED,
+        }
+    }
+}
+
+/// A board & roll the user has to choose a move for.
+#[derive(Debug, Clone, Copy)]
+struct Situation {
+    board: u16,
+    roll: u8,
+}
+
+/// The graded answer of the user to a situation.
+#[derive(Debug, Clone, Copy)]
+struct Answer {
+    /// The board after the move the user chose.
+    chosen: u16,
+    /// The best move for the situation.
+    best: u16,
+    grade: Grade,
+    /// The win chance of the chosen & the best move, if they were computed.
+    values: Option<(u16, u16)>,
+}
+
+/// A quiz that deals random situations & grades the moves the user would make against the best moves.
+pub struct TrainerState {
+    /// The rules of the game the situations are dealt from.
+    variant: Variant,
+    /// The situation currently being asked.
+    situation: Option<Situation>,
+    /// The pieces the user has selected to flip.
+    selected: u16,
+    /// The graded answer to the current situation, once the user has answered.
+    answer: Option<Answer>,
+    /// Whether the last selection wasn't a legal move.
+    invalid_selection: bool,
+    /// The amount of answers for each grade this session.
+    optimal: u32,
+    near_optimal: u32,
+    blunders: u32,
+    /// Every situation answered this session, along with its answer.
+    answered: Vec<(Situation, Answer)>,
+    /// Whether the session has ended & its answers are being reviewed.
+    reviewing: bool,
+    /// The rng used for dealing the situations.
+    rng: Rng,
+}
+
+impl Default for TrainerState {
+    fn default() -> Self {
+        TrainerState {
+            variant: Variant::default(),
+            situation: None,
+            selected: 0,
+            answer: None,
+            invalid_selection: false,
+            optimal: 0,
+            near_optimal: 0,
+            blunders: 0,
+            answered: Vec::new(),
+            reviewing: false,
+            rng: rng::stream("trainer"),
+        }
+    }
+}
+
+impl TrainerState {
+    /// Deals the situations from the given variant, dropping the current situation.
+    /// The answers of the session are for the old variant, so they can no longer be reviewed.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.situation = None;
+        self.answer = None;
+        self.selected = 0;
+        self.invalid_selection = false;
+        self.answered.clear();
+        self.reviewing = false;
+    }
+
+    /// Draws the quiz & handles the input of the user.
+    /// The best moves are required to grade the answers, the ranked moves are used to tell near-optimal answers apart.
+    /// Returns the board & roll of an answered situation, if the user chose to study it in the explorer.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        best_moves: Option<&Policy>,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) -> Option<(u16, u8)> {
+        if self.reviewing {
+            return self.review(ui, colors);
+        }
+
+        let Some(best_moves) = best_moves else {
+            ui.label(
+                RichText::new("No moves found, so the answers can't be graded.")
+                    .color(Color32::LIGHT_RED),
+            );
+            return None;
+        };
+
+        self.score(ui);
+        ui.add_space(10.);
+
+        let situation = match self.situation {
+            Some(situation) => situation,
+            None => match self.deal(best_moves) {
+                Some(situation) => situation,
+                None => {
+                    ui.label("Couldn't find a situation within the best moves.");
+                    return None;
+                }
+            },
+        };
+
+        ui.label(format!(
+            "You rolled {}. Which pieces would you flip?",
+            situation.roll
+        ));
+
+        match self.answer {
+            Some(answer) => self.feedback(ui, colors, situation, answer),
+            None => self.question(ui, colors, situation, best_moves, ranked_moves),
+        }
+        None
+    }
+
+    /// Clears the score & the answers of the session.
+    fn reset_session(&mut self) {
+        self.optimal = 0;
+        self.near_optimal = 0;
+        self.blunders = 0;
+        self.answered.clear();
+    }
+
+    /// Draws the accuracy of the user over this session.
+    fn score(&mut self, ui: &mut Ui) {
+        let answered = self.optimal + self.near_optimal + self.blunders;
+
+        ui.horizontal(|ui| {
+            // Answers that aren't blunders are counted as accurate.
+            match answered {
+                0 => ui.label("Accuracy: -"),
+                _ => ui.label(format!(
+                    "Accuracy: {:.1}%",
+                    (self.optimal + self.near_optimal) as f32 / answered as f32 * 100.
+                )),
+            };
+            ui.label(format!(
+                "({} optimal, {} near-optimal, {} blunders)",
+                self.optimal, self.near_optimal, self.blunders
+            ));
+
+            if ui.button("Reset score").clicked() {
+                self.reset_session();
+            }
+
+            if ui
+                .add_enabled(!self.answered.is_empty(), egui::Button::new("End session"))
+                .on_hover_text("Review every answer of this session.")
+                .clicked()
+            {
+                self.reviewing = true;
+            }
+        });
+    }
+
+    /// Draws every answer of the session next to the best move, along with the win chance lost by each answer.
+    /// Returns the board & roll of the situation the user chose to open in the explorer, if any.
+    fn review(&mut self, ui: &mut Ui, colors: &BoardColors) -> Option<(u16, u8)> {
+        ui.heading("Session review");
+        self.score(ui);
+        ui.add_space(10.);
+
+        let mut opened = None;
+        let tiles = self.variant.tiles;
+
+        egui::Grid::new("Review")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Grade").strong());
+                ui.label(RichText::new("Your move").strong());
+                ui.label(RichText::new("The best move").strong());
+                ui.label(RichText::new("Win % lost").strong());
+                ui.end_row();
+
+                for (situation, answer) in &self.answered {
+                    ui.label(RichText::new(answer.grade.name()).color(answer.grade.color()));
+                    ui.label(Main::generate_board(
+                        situation.board,
+                        situation.roll,
+                        answer.chosen,
+                        tiles,
+                        colors,
+                    ));
+                    ui.label(Main::generate_board(
+                        situation.board,
+                        situation.roll,
+                        answer.best,
+                        tiles,
+                        colors,
+                    ));
+                    // The lost win chance is only known if both moves were ranked.
+                    match answer.values {
+                        Some((chosen, best)) => {
+                            ui.label(format!("{:.1}%", best.saturating_sub(chosen) as f32 / 10.))
+                        }
+                        None if answer.chosen == answer.best => ui.label("0.0%"),
+                        None => ui.label("?"),
+                    };
+                    if ui.button("Open in explorer").clicked() {
+                        opened = Some((situation.board, situation.roll));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(10.);
+        if ui.button("New session").clicked() {
+            self.reset_session();
+            self.reviewing = false;
+            self.situation = None;
+            self.answer = None;
+            self.selected = 0;
+        }
+
+        opened
+    }
+
+    /// Picks a random board & roll that has a best move.
+    /// Returns None if no such situation was found.
+    fn deal(&mut self, best_moves: &Policy) -> Option<Situation> {
+        for _ in 0..DEAL_ATTEMPTS {
+            let board = self.rng.u16(1..=self.variant.full_board());
+            let roll = match self.variant.can_roll_single(board) && self.rng.bool() {
+                true => self.rng.u8(1..=6),
+                false => self.rng.u8(1..=6) + self.rng.u8(1..=6),
+            };
+
+            if best_moves.best_move(board, DiceRoll::from(roll)).is_some() {
+                let situation = Situation { board, roll };
+                self.situation = Some(situation);
+                return Some(situation);
+            }
+        }
+
+        None
+    }
+
+    /// Lets the user select the pieces to flip & submit them as their answer.
+    fn question(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        situation: Situation,
+        best_moves: &Policy,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.;
+
+            for (index, alive) in Main::board_to_array(situation.board, self.variant.tiles)
+                .iter()
+                .enumerate()
+                .rev()
+            {
+                let selected = self.selected & (1 << index) != 0;
+                let background = match (alive, selected) {
+                    (false, _) => colors.down,
+                    (true, true) => colors.knocked,
+                    (true, false) => colors.alive,
+                };
+
+                let text = RichText::new(format!("{} ", index + 1)).background_color(background);
+                let clicked = ui.add(Label::new(text).sense(Sense::click())).clicked();
+
+                // Only the alive pieces can be flipped.
+                if clicked && *alive {
+                    self.selected ^= 1 << index;
+                    self.invalid_selection = false;
+                }
+            }
+        });
+
+        ui.add_space(10.);
+
+        if ui.button("Submit").clicked() {
+            let chosen = situation.board & !self.selected;
+            let moves = game_core::enumerate_moves(situation.board, situation.roll);
+
+            match moves.contains(&chosen) {
+                true => self.grade(situation, chosen, best_moves, ranked_moves),
+                false => self.invalid_selection = true,
+            }
+        }
+
+        if self.invalid_selection {
+            ui.label(
+                RichText::new(format!(
+                    "The flipped pieces must add up to {}.",
+                    situation.roll
+                ))
+                .color(Color32::LIGHT_RED),
+            );
+        }
+    }
+
+    /// Grades the chosen move against the best move, adding it to the score.
+    fn grade(
+        &mut self,
+        situation: Situation,
+        chosen: u16,
+        best_moves: &Policy,
+        ranked_moves: Option<&HashMap<BoardRoll, Vec<RankedMove>>>,
+    ) {
+        let roll = DiceRoll::from(situation.roll);
+        // Situations are only dealt if they have a best move, so one that doesn't isn't graded.
+        let Some(best) = best_moves.best_move(situation.board, roll) else {
+            return;
+        };
+
+        // The win chance of the chosen & the best move, if both were computed.
+        let values = ranked_moves
+            .and_then(|ranked_moves| ranked_moves.get(&BoardRoll::new(situation.board, roll)))
+            .and_then(|ranked| {
+                let chosen = ranked.iter().find(|ranked| ranked.board == chosen)?;
+                Some((chosen.value, ranked.first()?.value))
+            });
+
+        // Without the win chances, only the best move itself can be told apart.
+        let grade = match values.map(|(chosen, best)| best.saturating_sub(chosen)) {
+            _ if chosen == best => Grade::Optimal,
+            Some(0) => Grade::Optimal,
+            Some(difference) if difference <= NEAR_OPTIMAL_MARGIN => Grade::NearOptimal,
+            _ => Grade::Blunder,
+        };
+
+        match grade {
+            Grade::Optimal => self.optimal += 1,
+            Grade::NearOptimal => self.near_optimal += 1,
+            Grade::Blunder => self.blunders += 1,
+        }
+
+        let answer = Answer {
+            chosen,
+            best,
+            grade,
+            values,
+        };
+        self.answer = Some(answer);
+        self.answered.push((situation, answer));
+    }
+
+    /// Draws how the answer was graded, along with the best move.
+    fn feedback(
+        &mut self,
+        ui: &mut Ui,
+        colors: &BoardColors,
+        situation: Situation,
+        answer: Answer,
+    ) {
+        ui.heading(RichText::new(answer.grade.name()).color(answer.grade.color()));
+
+        ui.label("Your move:");
+        ui.label(Main::generate_board(
+            situation.board,
+            situation.roll,
+            answer.chosen,
+            self.variant.tiles,
+            colors,
+        ));
+        ui.label("The best move:");
+        ui.label(Main::generate_board(
+            situation.board,
+            situation.roll,
+            answer.best,
+            self.variant.tiles,
+            colors,
+        ));
+
+        if let Some((chosen, best)) = answer.values {
+            ui.label(format!(
+                "Your move wins {:.1}% of games, the best move wins {:.1}%.",
+                chosen as f32 / 10.,
+                best as f32 / 10.
+            ));
+        }
+
+        ui.add_space(10.);
+        if ui.button("Next").clicked() {
+            self.situation = None;
+            self.answer = None;
+            self.selected = 0;
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use compute::config::{DEFAULT_OUTPUT, DEFAULT_RANKED_OUTPUT};
+
+/// The rules of the game being played, which are shared with the simulation & the server.
+pub use compute::Ruleset as Variant;
+
+/// The key the variant is persisted under.
+pub const VARIANT_KEY: &str = "variant";
+
+/// The amount of tiles each variant can be played with.
+pub const TILE_COUNTS: [u8; 3] = [9, 10, 12];
+
+/// The files the best moves of a variant are kept in.
+/// The best moves only depend on the tiles & the dice, so the variants that only score differently share them.
+pub trait VariantFiles {
+    /// Returns true if the best moves for this variant are the ones the simulation defaults to.
+    fn is_standard(&self) -> bool;
+
+    /// Returns the file the best moves for this variant are kept in.
+    /// The standard variant uses the default output of the simulation.
+    fn policy_file(&self) -> PathBuf;
+
+    /// Returns the file the ranked moves for this variant are kept in.
+    /// The standard variant uses the default ranked output of the simulation.
+    fn ranked_file(&self) -> PathBuf;
+}
+
+impl VariantFiles for Variant {
+    fn is_standard(&self) -> bool {
+        self.tiles == game_core::STANDARD_TILES && !self.one_die
+    }
+
+    fn policy_file(&self) -> PathBuf {
+        if self.is_standard() {
+            return PathBuf::from(DEFAULT_OUTPUT);
+        }
+
+        PathBuf::from(format!("best_moves_{}.yml", file_suffix(*self)))
+    }
+
+    fn ranked_file(&self) -> PathBuf {
+        if self.is_standard() {
+            return PathBuf::from(DEFAULT_RANKED_OUTPUT);
+        }
+
+        PathBuf::from(format!("ranked_moves_{}.yml", file_suffix(*self)))
+    }
+}
+
+/// Returns the part of the file names that's unique to the variant.
+fn file_suffix(variant: Variant) -> String {
+    match variant.one_die {
+        true => format!("{}_one_die", variant.tiles),
+        false => variant.tiles.to_string(),
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How long the files have to stop changing for before they are reloaded.
+/// Files are often written in several steps, which shouldn't each cause a reload.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Watches the files the best moves are loaded from, so they can be reloaded when they change.
+#[derive(Default)]
+pub struct PolicyWatcher {
+    /// Watches the folders the files are in, as files are often replaced rather than written to.
+    /// This is None if the files couldn't be watched.
+    watcher: Option<RecommendedWatcher>,
+    /// Receives the changes to the watched folders.
+    receiver: Option<Receiver<notify::Result<Event>>>,
+    /// The files whose changes cause a reload.
+    files: Vec<PathBuf>,
+    /// When one of the files last changed, if they haven't been reloaded since.
+    changed_at: Option<Instant>,
+}
+
+impl PolicyWatcher {
+    /// Starts watching the given files, if they aren't already being watched.
+    /// The gui is redrawn whenever a file changes, so the change is noticed without any user input.
+    /// Returns why the files couldn't be watched, if they couldn't.
+    /// The same files aren't tried again, so the error is only returned once.
+    pub fn watch(&mut self, files: &[&Path], context: &egui::Context) -> Result<(), String> {
+        let files: Vec<PathBuf> = files.iter().map(|file| absolute(file)).collect();
+        if files == self.files {
+            return Ok(());
+        }
+
+        self.watcher = None;
+        self.receiver = None;
+        self.files = files;
+        self.changed_at = None;
+
+        let (tx, rx) = mpsc::channel();
+        let context = context.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The gui may have closed, in which case the change isn't needed.
+            let _ = tx.send(event);
+            context.request_repaint();
+        })
+        .map_err(|e| format!("Couldn't watch the best moves for changes: {e}"))?;
+
+        for folder in self.folders() {
+            watcher
+                .watch(&folder, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Couldn't watch {} for changes: {e}", folder.display()))?;
+        }
+
+        self.watcher = Some(watcher);
+        self.receiver = Some(rx);
+        Ok(())
+    }
+
+    /// Returns true once the watched files have changed & then stopped changing.
+    /// While a change is settling, the gui is redrawn so the files are reloaded on time.
+    pub fn poll(&mut self, context: &egui::Context) -> bool {
+        if let Some(receiver) = &self.receiver {
+            for event in receiver.try_iter().flatten() {
+                let changed = event.paths.iter().any(|path| self.files.contains(path));
+                if changed && (event.kind.is_create() || event.kind.is_modify()) {
+                    self.changed_at = Some(Instant::now());
+                }
+            }
+        }
+
+        let Some(changed_at) = self.changed_at else {
+            return false;
+        };
+
+        match changed_at.elapsed() >= SETTLE_TIME {
+            true => {
+                self.changed_at = None;
+                true
+            }
+            false => {
+                context.request_repaint_after(SETTLE_TIME - changed_at.elapsed());
+                false
+            }
+        }
+    }
+
+    /// Returns every folder that contains a watched file, without duplicates.
+    fn folders(&self) -> Vec<PathBuf> {
+        let mut folders: Vec<PathBuf> = Vec::new();
+        for file in &self.files {
+            if let Some(folder) = file.parent() {
+                if !folders.iter().any(|existing| existing == folder) {
+                    folders.push(folder.to_path_buf());
+                }
+            }
+        }
+        folders
+    }
+}
+
+/// Makes the path absolute, so it can be compared against the paths of the changes.
+fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(current) if path.is_relative() => current.join(path),
+        _ => path.to_path_buf(),
+    }
+}
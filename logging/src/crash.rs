@@ -0,0 +1,95 @@
+//! Writes a diagnostic file when a binary panics, so crash reports contain something to go on.
+//!
+//! The file has the panic, its backtrace, & the notes the binary kept of what it was doing,
+//! such as the state of the server or the board being played.
+
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, TryLockError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What the binary is doing, by what it is, which is written to the diagnostic file.
+static NOTES: Mutex<BTreeMap<&'static str, String>> = Mutex::new(BTreeMap::new());
+
+/// Notes what the binary is doing, replacing the previous note with the same key.
+pub fn note(key: &'static str, value: impl Display) {
+    // A poisoned lock only happens if the binary has already panicked.
+    let mut notes = NOTES.lock().unwrap_or_else(|e| e.into_inner());
+    notes.insert(key, value.to_string());
+}
+
+/// Removes the note with the given key, once it no longer applies.
+pub fn forget(key: &'static str) {
+    let mut notes = NOTES.lock().unwrap_or_else(|e| e.into_inner());
+    notes.remove(key);
+}
+
+/// Writes a diagnostic file to the given folder when the binary panics, or to the temp folder if none is given.
+/// The panic is still shown as it was before.
+pub(crate) fn install(name: &str, dir: Option<&Path>) {
+    let name = name.to_string();
+    let dir = dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir);
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        let location = info
+            .location()
+            .map_or_else(|| String::from("an unknown location"), ToString::to_string);
+
+        match write(&name, &dir, message, &location) {
+            Ok(path) => eprintln!("Wrote the crash diagnostics to {}", path.display()),
+            Err(e) => eprintln!("Couldn't write the crash diagnostics: {e}"),
+        }
+        previous(info);
+    }));
+}
+
+/// Writes the diagnostic file, returning where it was written.
+fn write(name: &str, dir: &Path, message: &str, location: &str) -> std::io::Result<PathBuf> {
+    let thread = std::thread::current();
+    let mut report = format!(
+        "{name} {} panicked on thread '{}' at {location}:\n{message}\n\nOS: {} {}\n\nState:\n",
+        env!("CARGO_PKG_VERSION"),
+        thread.name().unwrap_or("<unnamed>"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    );
+
+    // The panic could have happened while the notes were being changed.
+    match NOTES.try_lock() {
+        Ok(notes) => write_notes(&mut report, &notes),
+        Err(TryLockError::Poisoned(notes)) => write_notes(&mut report, &notes.into_inner()),
+        Err(TryLockError::WouldBlock) => report.push_str("  <unavailable>\n"),
+    }
+
+    let _ = write!(report, "\nBacktrace:\n{}\n", Backtrace::force_capture());
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{name}-crash-{seconds}.txt"));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Writes each note on its own line.
+fn write_notes(report: &mut String, notes: &BTreeMap<&'static str, String>) {
+    if notes.is_empty() {
+        report.push_str("  <none>\n");
+    }
+    for (key, value) in notes {
+        let _ = writeln!(report, "  {key}: {value}");
+    }
+}
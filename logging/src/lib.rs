@@ -0,0 +1,62 @@
+//! Sets up the tracing output shared by the gui, the server & client, and the compute binaries.
+//!
+//! The events shown are filtered by the `RUST_LOG` environment variable, such as `RUST_LOG=networked=debug`,
+//! which defaults to showing info & above.
+//! The events can also be written to a log file that is rolled over daily.
+//! The master seed is logged & noted, so the session can be reproduced with it.
+//! If the binary panics, then a diagnostic file is written next to the log files, see [`crash`].
+
+use std::path::{Path, PathBuf};
+
+use game_core::rng;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::EnvFilter;
+
+pub mod crash;
+
+/// The environment variable of the folder to write the log files to, if no folder was given.
+pub const LOG_DIR_VAR: &str = "SHUT_THE_BOX_LOG_DIR";
+/// The filter used if `RUST_LOG` isn't set.
+const DEFAULT_FILTER: &str = "info";
+
+/// Starts showing the tracing events on stderr.
+///
+/// If a folder is given, or one is set by [`LOG_DIR_VAR`], then the events are also written to
+/// a daily log file within it, named after the binary.
+/// The returned guard has to be kept until the binary exits, otherwise the last events may not be written.
+///
+/// A panic writes a diagnostic file to the same folder, or to the temp folder if there isn't one.
+pub fn init(name: &str, log_dir: Option<&Path>) -> Option<WorkerGuard> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let log_dir = log_dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(LOG_DIR_VAR).map(PathBuf::from));
+
+    crash::install(name, log_dir.as_deref());
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let guard = match log_dir {
+        Some(log_dir) => {
+            let appender = tracing_appender::rolling::daily(log_dir, format!("{name}.log"));
+            let (file, guard) = tracing_appender::non_blocking(appender);
+            let _ = builder
+                .with_writer(std::io::stderr.and(file))
+                .with_ansi(false)
+                .try_init();
+            Some(guard)
+        }
+        None => {
+            // Another subscriber being set only means the events go there instead.
+            let _ = builder.with_writer(std::io::stderr).try_init();
+            None
+        }
+    };
+
+    let seed = rng::master_seed();
+    crash::note("master seed", seed);
+    tracing::info!("Reproduce this session with {}={seed}", rng::SEED_VAR);
+    guard
+}
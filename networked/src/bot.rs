@@ -0,0 +1,213 @@
+//! A client that plays with a strategy instead of a person, so bots can be played against over the network.
+//!
+//! The bot readies up whenever another player is connected, so it plays every game it can.
+
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use compute::{BotDifficulty, Strategy};
+use fastrand::Rng;
+use game_core::{rng, DiceRoll};
+use mac_address2::MacAddress;
+use networked::client_states::ClientError;
+use networked::Channels;
+use protocol::{
+    ClientMessages, ClientMove, ClientMovedBoard, ClientToMove, RollRequest, ServerError,
+    ServerMessages, SpectatorEvent, PROTOCOL_VERSION,
+};
+use tracing::{info, warn};
+
+/// The highest value a board can have for the bot to roll a single dice, if the board allows it.
+const SINGLE_DICE_VALUE: u8 = 6;
+/// How often the bot checks whether it has been told to leave, while it waits for the server.
+const LEAVE_POLL: Duration = Duration::from_millis(250);
+
+/// Joins the server with the given name & plays every game with the strategy, until the server disconnects.
+/// The difficulty decides how often the bot makes a random move instead of the strategy's move.
+/// Once leave is set, the bot leaves the server as soon as it isn't playing a game.
+pub fn start(
+    socket_address: SocketAddr,
+    name: String,
+    strategy: Arc<dyn Strategy>,
+    difficulty: BotDifficulty,
+    leave: Arc<AtomicBool>,
+) -> Result<(), ClientError> {
+    let connection = networked::initialize_channels(TcpStream::connect(socket_address)?)?;
+    let mut bot = Bot {
+        connection,
+        strategy,
+        difficulty,
+        rng: rng::stream("bot"),
+        board: None,
+        players: 0,
+        ready: false,
+        playing: false,
+    };
+
+    bot.write(ClientMessages::Version(PROTOCOL_VERSION))?;
+    bot.write(ClientMessages::OptInForPlaying {
+        mac_address: MacAddress::default(),
+        name,
+    })?;
+    // The server answers with its version of the protocol before accepting or refusing the bot.
+    let mut versioned = false;
+    loop {
+        let message = bot.read().map_err(|e| match e {
+            // Servers older than the version exchange drop the bot for sending a message they can't read.
+            ClientError::ReadClosed(_) | ClientError::MalformedPacket(_) if !versioned => {
+                ClientError::OlderServer
+            }
+            e => e,
+        })?;
+        match message {
+            ServerMessages::Version(PROTOCOL_VERSION) => versioned = true,
+            ServerMessages::Version(version)
+            | ServerMessages::Error(ServerError::IncompatibleVersion(version)) => {
+                return Err(ClientError::IncompatibleVersion(version))
+            }
+            ServerMessages::OptInAccept => {
+                info!("Joined the server.");
+                break;
+            }
+            ServerMessages::OptInDeny => {
+                info!("Connection refused.");
+                return Ok(());
+            }
+            packet => return Err(ClientError::UnexpectedPacket(packet)),
+        }
+    }
+
+    loop {
+        // The bot never leaves partway through a game, so the other players can finish it.
+        if !bot.playing && leave.load(Ordering::Relaxed) {
+            info!("Leaving the server.");
+            return Ok(());
+        }
+
+        match bot.connection.reading.recv_timeout(LEAVE_POLL) {
+            Ok(message) => bot.handle(message?)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                info!("The server closed the connection.");
+                return Ok(());
+            }
+        }
+    }
+}
+
+struct Bot {
+    connection: Channels<ServerMessages, ClientMessages>,
+    strategy: Arc<dyn Strategy>,
+    /// How often the bot makes the strategy's move.
+    difficulty: BotDifficulty,
+    /// The rng used for the strategy's random choices.
+    rng: Rng,
+    /// The board of the bot in the game being played, or None if it hasn't moved yet.
+    board: Option<u16>,
+    /// The amount of players connected to the server, including the bot.
+    players: u8,
+    /// Whether the bot has told the server it's ready for the next game.
+    ready: bool,
+    /// Whether the bot is playing a game.
+    playing: bool,
+}
+
+impl Bot {
+    fn write(&self, client_message: ClientMessages) -> Result<(), ClientError> {
+        self.connection.writing.send(client_message)?;
+        Ok(())
+    }
+
+    fn read(&self) -> Result<ServerMessages, ClientError> {
+        match self.connection.reading.recv() {
+            Err(e) => Err(e.into()),
+            Ok(value) => Ok(value?),
+        }
+    }
+
+    /// Answers the message from the server.
+    fn handle(&mut self, message: ServerMessages) -> Result<(), ClientError> {
+        match message {
+            ServerMessages::PlayersConnected(players) => {
+                self.players = players;
+                self.update_ready()?;
+            }
+            ServerMessages::PlayerIndex(_) => {
+                self.board = None;
+                self.playing = true;
+            }
+            ServerMessages::Spectate(SpectatorEvent::GameStarted(_)) => self.board = None,
+            ServerMessages::QueryClientRoll => {
+                self.write(ClientMessages::ChosenRoll(self.roll()))?
+            }
+            ServerMessages::QueryClientForMove(to_move, _) => {
+                let chosen = self.choose_move(to_move);
+                self.write(ClientMessages::ChosenMove(chosen))?;
+            }
+            ServerMessages::SendWin | ServerMessages::SendDraw(_) | ServerMessages::SendLoss(_) => {
+                info!("Finished a game: {message:?}");
+                // Every player has to ready up again for the next game.
+                self.ready = false;
+                self.board = None;
+                self.playing = false;
+                self.update_ready()?;
+            }
+            ServerMessages::Error(e) => warn!("The server sent an error: {e:?}"),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Readies up if there is another player to play against, or unreadies if there isn't.
+    fn update_ready(&mut self) -> Result<(), ClientError> {
+        let ready = self.players > 1;
+        if ready != self.ready {
+            self.ready = ready;
+            self.write(ClientMessages::ReadyForStart(ready))?;
+        }
+        Ok(())
+    }
+
+    /// Rolls a single dice once the board's value is low enough, as any roll of both dice would be too high.
+    fn roll(&self) -> RollRequest {
+        let single = self.board.is_some_and(|board| {
+            game_core::can_roll_single(board) && game_core::board_value(board) <= SINGLE_DICE_VALUE
+        });
+        match single {
+            true => RollRequest::SingleDice,
+            false => RollRequest::BothDice,
+        }
+    }
+
+    /// Chooses the move for the rolled dice with the strategy.
+    fn choose_move(&mut self, to_move: ClientToMove) -> ClientMove {
+        let (board, roll, single) = match to_move {
+            ClientToMove::OneDice(board, dice) => (board, dice, true),
+            ClientToMove::TwoDice {
+                board,
+                dice_1,
+                dice_2,
+            } => (board, dice_1 + dice_2, false),
+        };
+
+        let moves = game_core::enumerate_moves(board, roll);
+        let chosen = self.difficulty.choose_move(
+            self.strategy.as_ref(),
+            board,
+            DiceRoll::from(roll),
+            &moves,
+            &mut self.rng,
+        );
+        self.board = Some(chosen.unwrap_or(board));
+        logging::crash::note("board", chosen.unwrap_or(board));
+
+        match (chosen, single) {
+            (None, _) => ClientMove::CannotMove,
+            (Some(chosen), true) => ClientMove::FirstDice(ClientMovedBoard(chosen)),
+            (Some(chosen), false) => ClientMove::BothDice(ClientMovedBoard(chosen)),
+        }
+    }
+}
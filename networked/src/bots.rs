@@ -0,0 +1,85 @@
+//! The bots a server keeps in its lobby, so there's always someone to play against.
+//!
+//! Each bot is a client on its own thread, which connects to the server like any other player.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use app_config::Config;
+use compute::{BotDifficulty, Strategy};
+use tracing::{error, info};
+
+use crate::bot;
+
+/// The bots playing on a server, which join & leave as the amount of bots is changed.
+pub struct Bots {
+    /// The address the bots connect to the server on.
+    address: SocketAddr,
+    /// The strategy the bots play with & how often they make its move, once it's been loaded.
+    strategy: Option<(Arc<dyn Strategy>, BotDifficulty)>,
+    /// Whether each bot has been told to leave, along with the thread it plays on.
+    joined: Vec<(Arc<AtomicBool>, JoinHandle<()>)>,
+    /// The number the next bot is named with.
+    next_number: u32,
+}
+
+impl Bots {
+    /// Creates the bots of the server listening on the given address, none of which have joined yet.
+    pub fn new(address: SocketAddr) -> Bots {
+        // A server listening on every address can still be connected to locally.
+        let ip = match address.ip() {
+            ip if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ip => ip,
+        };
+
+        Bots {
+            address: SocketAddr::new(ip, address.port()),
+            strategy: None,
+            joined: Vec::new(),
+            next_number: 1,
+        }
+    }
+
+    /// Adds or removes bots until the given amount are playing, with the config's strategy & difficulty.
+    /// The removed bots leave once they've finished the game they're playing.
+    pub fn resize(&mut self, amount: u8, config: &Config) -> Result<(), String> {
+        // A bot that was refused or lost its connection no longer counts.
+        self.joined.retain(|(_, thread)| !thread.is_finished());
+
+        while self.joined.len() > amount as usize {
+            if let Some((leave, _)) = self.joined.pop() {
+                leave.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.joined.len() == amount as usize {
+            return Ok(());
+        }
+
+        let (strategy, difficulty) = match &self.strategy {
+            Some(strategy) => strategy.clone(),
+            None => self.strategy.insert(crate::load_bot(config)?).clone(),
+        };
+        while self.joined.len() < amount as usize {
+            let name = format!("Bot {}", self.next_number);
+            self.next_number += 1;
+
+            let leave = Arc::new(AtomicBool::new(false));
+            let (address, strategy, bot_leave) = (self.address, strategy.clone(), leave.clone());
+            let thread = thread::Builder::new()
+                .name(name.clone())
+                .spawn(move || {
+                    info!("{name} is joining the server at {difficulty} difficulty.");
+                    if let Err(e) =
+                        bot::start(address, name.clone(), strategy, difficulty, bot_leave)
+                    {
+                        error!("{name} stopped playing: {e}");
+                    }
+                })
+                .map_err(|e| format!("Couldn't start a bot: {e}"))?;
+            self.joined.push((leave, thread));
+        }
+        Ok(())
+    }
+}
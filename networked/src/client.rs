@@ -9,7 +9,7 @@ use std::{
 use mac_address2::MacAddress;
 use networked::Channels;
 
-use crate::states::{ClientMessages, ServerMessages};
+use protocol::{ClientMessages, ServerMessages};
 
 #[derive(thiserror::Error, Debug)]
 enum ClientError {
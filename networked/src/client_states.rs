@@ -1,12 +1,23 @@
+//! The states a client moves through while playing on a server, from joining to playing each game.
+//!
+//! Each state only accepts the messages the server can send in it, & the client can only answer what it was asked.
+//! The client doesn't read from the server by itself, so it can be driven by a person, a bot, or a test,
+//! over a socket or over connections made in memory.
+
 use std::{
+    io,
     net::{SocketAddr, TcpStream},
-    sync::mpsc::{RecvError, SendError},
+    sync::mpsc::{RecvError, SendError, TryRecvError},
 };
 
 use mac_address2::MacAddress;
-use networked::{ChannelError, Channels};
+use protocol::{
+    ClientMessages, ClientMove, ClientToMove, RollRequest, ServerError, ServerMessages,
+    SpectatorEvent, PROTOCOL_VERSION,
+};
+use tracing::info;
 
-use crate::states::{ClientMessages, ServerMessages};
+use crate::{ChannelError, Channels};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
@@ -18,35 +29,96 @@ pub enum ClientError {
     MalformedPacket(#[from] ChannelError),
     #[error("Server responded with unexpected packet: {0:?}")]
     UnexpectedPacket(ServerMessages),
+    #[error("Couldn't connect to server, did you give the correct address? {0}")]
+    Connect(#[from] io::Error),
+    #[error("Couldn't get the mac address to identify this client")]
+    MacAddress,
+    #[error("The server hasn't asked for the client's {0}")]
+    NotAsked(&'static str),
+    #[error("The server speaks version {0} of the protocol, but this client speaks version {PROTOCOL_VERSION}")]
+    IncompatibleVersion(u32),
+    #[error("The server didn't answer with its version of the protocol, so it likely speaks a version older than {PROTOCOL_VERSION}")]
+    OlderServer,
 }
 
-pub fn start(socket_address: SocketAddr) -> Result<(), ClientError> {
-    let client = Client::new(socket_address);
-    client.connect()?;
-    if !client.connect_allowed()? {
-        println!("Connection refused.");
-        return Ok(());
-    };
+/// Joins the server at the given address with the given name, returning once the server has answered.
+pub fn start(socket_address: SocketAddr, name: String) -> Result<(), ClientError> {
+    let client = Client::new(socket_address, name)?;
+    client.join()?;
+    info!("Sent join request.");
 
-    let client = Client::<PreGame>::from(client);
-    // client.
+    // The server answers with its version of the protocol before accepting or refusing the client.
+    let mut state = ClientState::Joining(client);
+    while let ClientState::Joining(client) = &state {
+        let message = client.read().map_err(|e| client.unanswered(e))?;
+        state = state.handle(message)?;
+    }
 
+    match state {
+        ClientState::Refused => info!("Connection refused."),
+        _ => info!("Joined the server."),
+    }
     Ok(())
 }
 
-struct Client<S> {
+/// A client connected to a server, which can do what its state allows.
+pub struct Client<S> {
     connection: Channels<ServerMessages, ClientMessages>,
     state: S,
 }
 
-#[derive(Clone, Copy)]
-struct Joining {
-    // server_address: SocketAddr,
+/// Waiting for the server to accept the request to join.
+pub struct Joining {
     mac_address: MacAddress,
+    name: String,
+    /// Whether the server answered with the same version of the protocol.
+    versioned: bool,
 }
 
-struct PreGame {
+/// Waiting with the other players for the next game to start.
+pub struct PreGame {
+    /// Whether the client told the server it's ready for the game to start.
     ready: bool,
+    /// The amount of players connected to the server.
+    connected: u8,
+    /// The amount of players that are ready.
+    ready_players: u8,
+    /// The result of the last game the client played, if it has played one.
+    last_result: Option<ServerMessages>,
+}
+
+/// Playing a game, taking turns with the other players.
+pub struct InGame {
+    /// The index the server refers to the client by in this game.
+    index: u8,
+    /// The client's board, once the server has sent it.
+    board: Option<u16>,
+    /// Whether the client can no longer make moves.
+    out: bool,
+    /// What the server asked the client for, if the client hasn't answered yet.
+    asked: Option<Query>,
+    /// The move the server hinted for the move being asked for.
+    hint: Option<u16>,
+    /// The last error the server sent.
+    last_error: Option<ServerError>,
+}
+
+/// What the server asked a client in a game for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Query {
+    /// How many dice to roll.
+    Roll,
+    /// The move to make with the rolled dice.
+    Move(ClientToMove),
+}
+
+/// A client in any of its states, which moves between them as it handles the messages of the server.
+pub enum ClientState {
+    Joining(Client<Joining>),
+    PreGame(Client<PreGame>),
+    InGame(Client<InGame>),
+    /// The server refused to let the client join.
+    Refused,
 }
 
 impl<S> Client<S> {
@@ -55,52 +127,290 @@ impl<S> Client<S> {
         Ok(())
     }
 
-    fn read(&self) -> Result<ServerMessages, ClientError> {
+    /// Waits for the next message from the server.
+    pub fn read(&self) -> Result<ServerMessages, ClientError> {
         match self.connection.reading.recv() {
             Err(e) => Err(e.into()),
             Ok(value) => Ok(value?),
         }
     }
+
+    /// Returns the next message from the server, or None if it hasn't sent one.
+    pub fn try_read(&self) -> Result<Option<ServerMessages>, ClientError> {
+        match self.connection.reading.try_recv() {
+            Ok(value) => Ok(Some(value?)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(RecvError.into()),
+        }
+    }
+
+    /// Moves the client to the given state, keeping its connection.
+    fn into_state<T>(self, state: T) -> Client<T> {
+        Client {
+            connection: self.connection,
+            state,
+        }
+    }
 }
 
 impl Client<Joining> {
-    fn new(socket_address: SocketAddr) -> Self {
-        let connection = TcpStream::connect(socket_address)
-            .expect("Couldn't connect to server. Did you give the correct address?");
+    /// Connects to the server at the given address, identifying the client by its mac address.
+    pub fn new(socket_address: SocketAddr, name: String) -> Result<Self, ClientError> {
+        let connection = TcpStream::connect(socket_address)?;
 
         let mac_address = mac_address2::get_mac_address()
-            .expect("Couldn't get Mac address.")
-            .expect("Couldn't get Mac address");
+            .ok()
+            .flatten()
+            .ok_or(ClientError::MacAddress)?;
+
+        Ok(Client::with_connection(
+            crate::initialize_channels(connection)?,
+            mac_address,
+            name,
+        ))
+    }
 
+    /// Creates a client that talks to the server over the given connection, such as one made in memory.
+    pub fn with_connection(
+        connection: Channels<ServerMessages, ClientMessages>,
+        mac_address: MacAddress,
+        name: String,
+    ) -> Self {
         Client {
-            connection: networked::initialize_channels(connection),
+            connection,
             state: Joining {
-                // server_address: socket_address,
                 mac_address,
+                name,
+                versioned: false,
             },
         }
     }
 
-    fn connect(&self) -> Result<(), ClientError> {
-        let opt_in = ClientMessages::OptInForPlaying(self.state.mac_address);
-        self.write(opt_in)?;
-        println!("Sent join request.");
-        Ok(())
+    /// Requests to play in the server's games, after telling the server the version of the protocol the client speaks.
+    pub fn join(&self) -> Result<(), ClientError> {
+        self.write(ClientMessages::Version(PROTOCOL_VERSION))?;
+        self.write(ClientMessages::OptInForPlaying {
+            mac_address: self.state.mac_address,
+            name: self.state.name.clone(),
+        })
     }
 
-    fn connect_allowed(&self) -> Result<bool, ClientError> {
-        match self.read()? {
-            ServerMessages::OptInAccept => Ok(true),
-            ServerMessages::OptInDeny => Ok(false),
-            packet => Err(ClientError::UnexpectedPacket(packet)),
+    /// Returns the name the client joins with.
+    pub fn name(&self) -> &str {
+        &self.state.name
+    }
+
+    /// Explains the connection failing before the server answered with its version of the protocol,
+    /// as servers older than the version exchange drop the client for sending a message they can't read.
+    fn unanswered(&self, error: ClientError) -> ClientError {
+        match error {
+            ClientError::ReadClosed(_) | ClientError::MalformedPacket(_)
+                if !self.state.versioned =>
+            {
+                ClientError::OlderServer
+            }
+            error => error,
         }
     }
 }
 
-impl From<Client<Joining>> for Client<PreGame> {
-    fn from(value: Client<Joining>) -> Self {
-        todo!()
+impl Client<PreGame> {
+    /// Informs the server of whether the client is ready for the game to start.
+    pub fn set_ready(&mut self, ready: bool) -> Result<(), ClientError> {
+        self.write(ClientMessages::ReadyForStart(ready))?;
+        self.state.ready = ready;
+        Ok(())
+    }
+
+    /// Returns true if the client told the server it's ready for the game to start.
+    pub fn is_ready(&self) -> bool {
+        self.state.ready
+    }
+
+    /// Returns the amount of players connected to the server & the amount of them that are ready.
+    pub fn players(&self) -> (u8, u8) {
+        (self.state.connected, self.state.ready_players)
+    }
+
+    /// Returns whether the client won, drew, or lost the last game it played, if it has played one.
+    pub fn last_result(&self) -> Option<ServerMessages> {
+        self.state.last_result
+    }
+}
+
+impl Client<InGame> {
+    /// Requests the dice to roll, once the server has asked for the client's roll.
+    pub fn roll(&mut self, roll_request: RollRequest) -> Result<(), ClientError> {
+        if self.state.asked != Some(Query::Roll) {
+            return Err(ClientError::NotAsked("roll"));
+        }
+        self.write(ClientMessages::ChosenRoll(roll_request))?;
+        self.state.asked = None;
+        Ok(())
+    }
+
+    /// Makes the move, once the server has asked for the client's move.
+    /// The board is updated once the server has accepted the move.
+    pub fn make_move(&mut self, client_move: ClientMove) -> Result<(), ClientError> {
+        if !matches!(self.state.asked, Some(Query::Move(_))) {
+            return Err(ClientError::NotAsked("move"));
+        }
+        self.write(ClientMessages::ChosenMove(client_move))?;
+        self.state.asked = None;
+        self.state.hint = None;
+        Ok(())
+    }
+
+    /// Requests a hint for the move being asked for, which the server answers with the hinted move.
+    pub fn request_hint(&self) -> Result<(), ClientError> {
+        match self.state.asked {
+            Some(Query::Move(_)) => self.write(ClientMessages::RequestHint),
+            _ => Err(ClientError::NotAsked("move")),
+        }
+    }
+
+    /// Returns what the server asked the client for, if the client hasn't answered yet.
+    pub fn asked(&self) -> Option<Query> {
+        self.state.asked
+    }
+
+    /// Returns the index the server refers to the client by in this game.
+    pub fn index(&self) -> u8 {
+        self.state.index
+    }
+
+    /// Returns the client's board, once the server has sent it.
+    pub fn board(&self) -> Option<u16> {
+        self.state.board
+    }
+
+    /// Returns true if the client can no longer make moves in this game.
+    pub fn is_out(&self) -> bool {
+        self.state.out
+    }
+
+    /// Returns the move the server hinted for the move being asked for.
+    pub fn hint(&self) -> Option<u16> {
+        self.state.hint
+    }
+
+    /// Returns the last error the server sent, such as for an invalid move.
+    pub fn last_error(&self) -> Option<ServerError> {
+        self.state.last_error
     }
 }
 
-impl Client<PreGame> {}
+impl ClientState {
+    /// Handles the message the server sent, returning the client in its new state.
+    /// Returns an error if the server sent a message it can't send in the client's state.
+    pub fn handle(self, message: ServerMessages) -> Result<ClientState, ClientError> {
+        Ok(match self {
+            ClientState::Joining(mut client) => match message {
+                ServerMessages::Version(version) if version == PROTOCOL_VERSION => {
+                    client.state.versioned = true;
+                    ClientState::Joining(client)
+                }
+                ServerMessages::Version(version)
+                | ServerMessages::Error(ServerError::IncompatibleVersion(version)) => {
+                    return Err(ClientError::IncompatibleVersion(version))
+                }
+                ServerMessages::OptInAccept => ClientState::PreGame(client.into_state(PreGame {
+                    ready: false,
+                    connected: 0,
+                    ready_players: 0,
+                    last_result: None,
+                })),
+                ServerMessages::OptInDeny => ClientState::Refused,
+                packet => return Err(ClientError::UnexpectedPacket(packet)),
+            },
+            ClientState::PreGame(mut client) => match message {
+                ServerMessages::PlayersConnected(connected) => {
+                    client.state.connected = connected;
+                    ClientState::PreGame(client)
+                }
+                ServerMessages::PlayersReady(ready_players) => {
+                    client.state.ready_players = ready_players;
+                    ClientState::PreGame(client)
+                }
+                ServerMessages::PlayerIndex(index) => {
+                    ClientState::InGame(client.into_state(InGame {
+                        index,
+                        board: None,
+                        out: false,
+                        asked: None,
+                        hint: None,
+                        last_error: None,
+                    }))
+                }
+                // The games of the other players can be followed, but don't change the client.
+                ServerMessages::Spectate(_) | ServerMessages::Unknown => {
+                    ClientState::PreGame(client)
+                }
+                packet => return Err(ClientError::UnexpectedPacket(packet)),
+            },
+            ClientState::InGame(mut client) => {
+                match message {
+                    ServerMessages::QueryClientRoll => client.state.asked = Some(Query::Roll),
+                    ServerMessages::QueryClientForMove(to_move, hint) => {
+                        client.state.board = Some(match to_move {
+                            ClientToMove::OneDice(board, _) => board,
+                            ClientToMove::TwoDice { board, .. } => board,
+                        });
+                        client.state.asked = Some(Query::Move(to_move));
+                        client.state.hint = hint;
+                    }
+                    ServerMessages::Hint { best_move, .. } => client.state.hint = best_move,
+                    ServerMessages::Error(error) => client.state.last_error = Some(error),
+                    // The server tells every player about each turn, which is how the client learns its move was accepted.
+                    ServerMessages::Spectate(SpectatorEvent::Turn(turn))
+                        if turn.player == client.state.index =>
+                    {
+                        match turn.moved_board {
+                            Some(moved_board) => client.state.board = Some(moved_board),
+                            None => client.state.out = true,
+                        }
+                    }
+                    ServerMessages::Spectate(_) | ServerMessages::Unknown => {}
+                    ServerMessages::SendWin
+                    | ServerMessages::SendDraw(_)
+                    | ServerMessages::SendLoss(_) => {
+                        // Every player has to ready up again for the next game.
+                        return Ok(ClientState::PreGame(client.into_state(PreGame {
+                            ready: false,
+                            connected: 0,
+                            ready_players: 0,
+                            last_result: Some(message),
+                        })));
+                    }
+                    packet => return Err(ClientError::UnexpectedPacket(packet)),
+                }
+                ClientState::InGame(client)
+            }
+            ClientState::Refused => return Err(ClientError::UnexpectedPacket(message)),
+        })
+    }
+
+    /// Handles every message the server has sent, without waiting for any more.
+    /// Returns the client in its new state, along with whether any messages were handled.
+    pub fn update(self) -> Result<(ClientState, bool), ClientError> {
+        let mut state = self;
+        let mut handled = false;
+
+        loop {
+            let message = match &state {
+                ClientState::Joining(client) => {
+                    client.try_read().map_err(|e| client.unanswered(e))?
+                }
+                ClientState::PreGame(client) => client.try_read()?,
+                ClientState::InGame(client) => client.try_read()?,
+                ClientState::Refused => None,
+            };
+            let Some(message) = message else {
+                return Ok((state, handled));
+            };
+
+            state = state.handle(message)?;
+            handled = true;
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use networked::leaderboard::Leaderboard;
+use tracing::{error, info};
+
+/// The commands the admin console understands, as they're shown to the admin.
+const HELP: &str = "Commands:
+  export <file>  Exports the standings & match history as CSV, or JSON if the file ends in .json
+  reload         Reloads the turn timeout, max players, server bots, & ban list from the config
+  help           Shows this list";
+
+/// Starts reading the commands of the server's admin from stdin on another thread.
+/// The leaderboard is worked out from the replays in the folder of game logs, if the games are logged.
+pub fn spawn(game_logs: Option<PathBuf>) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+
+            let (command, argument) = match line.trim().split_once(' ') {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (line.trim(), ""),
+            };
+            match command {
+                "" => {}
+                "reload" => crate::reload::request(),
+                "export" if !argument.is_empty() => {
+                    if let Err(e) = export_leaderboard(game_logs.as_deref(), Path::new(argument)) {
+                        error!("{e}");
+                    }
+                }
+                _ => println!("{HELP}"),
+            }
+        }
+    });
+}
+
+/// Exports the leaderboard of the games in the folder of game logs to the given file.
+pub fn export_leaderboard(game_logs: Option<&Path>, path: &Path) -> Result<(), String> {
+    let Some(game_logs) = game_logs else {
+        return Err(String::from(
+            "The games aren't logged, so there's no leaderboard. Pass --game-logs to log them.",
+        ));
+    };
+
+    let leaderboard = Leaderboard::from_game_logs(game_logs).map_err(|e| e.to_string())?;
+    let written = leaderboard.export(path).map_err(|e| e.to_string())?;
+    for path in written {
+        info!(
+            "Exported the leaderboard of {} games to {}.",
+            leaderboard.matches.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,332 @@
+//! An append-only log of everything the server reads & does, which can be replayed offline to reproduce bugs.
+//!
+//! Each line of the log is an event as JSON, starting with the settings the server started with,
+//! followed by every message it read from its clients & every change to its state.
+//! The server only changes as it reads messages, & the dice of its games are rolled from the seed it started with,
+//! so feeding the logged messages to a new server in the same updates plays out exactly the same way.
+//! [`replay`] does this in memory, checking that the replayed server does exactly what was logged.
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, ErrorKind, LineWriter, Write},
+    path::Path,
+    sync::{mpsc, Arc},
+    time::SystemTime,
+};
+
+use protocol::{ClientMessages, ClientToMove};
+use rules::Ruleset;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::server_state::{Advisor, Listener, LiveSettings, Lobby, Running, Server, TurnClock};
+use crate::{ChannelError, Channels};
+
+/// The version of the events, which is raised whenever a change stops older logs from being replayed.
+pub const EVENT_LOG_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventLogError {
+    #[error("Couldn't access the event log: {0}")]
+    Io(#[from] io::Error),
+    #[error("Line {line} of the event log isn't an event: {error}")]
+    Parse {
+        line: usize,
+        error: serde_json::Error,
+    },
+    #[error("The event log doesn't start with the settings the server was started with")]
+    MissingStart,
+    #[error(
+        "The event log is version {0}, but only up to version {EVENT_LOG_VERSION} can be replayed"
+    )]
+    UnsupportedVersion(u32),
+    #[error("The replayed server diverged at event {index}, which was logged as {logged} but replayed as {replayed}")]
+    Diverged {
+        index: usize,
+        logged: String,
+        replayed: String,
+    },
+}
+
+/// Something the server read or did.
+/// The connections are known by the order they were made in, as a player's index changes when others leave.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// The server started, picking the seed of each game with the given seed.
+    /// The advisor can't be logged, so only whether there was one is.
+    Started {
+        version: u32,
+        seed: u64,
+        ruleset: Ruleset,
+        advised: bool,
+        assisted: bool,
+        hint_budget: u8,
+        #[serde(default)]
+        live: LiveSettings,
+    },
+    /// A new connection was made.
+    Connected { connection: u32 },
+    /// A message was read from the connection.
+    Received {
+        connection: u32,
+        message: ClientMessages,
+    },
+    /// A packet that couldn't be parsed was read from the connection.
+    BadPacket { connection: u32, packet: String },
+    /// The connection couldn't be read from.
+    ReadError { connection: u32 },
+    /// The connection was closed.
+    Disconnected { connection: u32 },
+    /// The host changed the settings of the lobby, or the config was reloaded.
+    LobbyChanged {
+        assisted: bool,
+        hint_budget: u8,
+        #[serde(default)]
+        live: LiveSettings,
+    },
+    /// The host started the game before every player was ready.
+    ForcedStart,
+    /// A game started with the players of the connections, in the order they take their turns.
+    GameStarted { players: Vec<u32>, seed: u64 },
+    /// The player of the connection was asked for their roll.
+    AskedRoll { connection: u32 },
+    /// The player of the connection was asked for their move with the rolled dice.
+    AskedMove {
+        connection: u32,
+        to_move: ClientToMove,
+    },
+    /// The player of the connection took too long to take their turn, so they're out.
+    TimedOut { connection: u32 },
+    /// Every player is out, so the game ended.
+    GameEnded { winning_score: u8 },
+    /// The server finished an update, in which every event since the last update was read or done.
+    Updated,
+}
+
+/// An event, along with when it happened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LoggedEvent {
+    /// When the event happened, in milliseconds since the unix epoch.
+    pub at: u64,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+/// Where the server records its events.
+#[derive(Debug, Default)]
+pub enum EventLog {
+    /// The events aren't recorded.
+    #[default]
+    Off,
+    /// Each event is appended to the file as a line of JSON.
+    File(LineWriter<File>),
+    /// The events are kept in memory, such as by a replayed server.
+    Memory(Vec<ServerEvent>),
+}
+
+impl EventLog {
+    /// Opens the file to append the events to, creating it if it doesn't exist.
+    pub fn append_to(path: &Path) -> io::Result<EventLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(EventLog::File(LineWriter::new(file)))
+    }
+
+    /// Records the event.
+    /// Failing to write the event doesn't affect the games, so the failure is only printed & no more events are written.
+    pub(crate) fn record(&mut self, event: ServerEvent) {
+        match self {
+            EventLog::Off => {}
+            EventLog::Memory(events) => events.push(event),
+            EventLog::File(file) => {
+                let at = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                // Each line is flushed as it's written, so the events leading up to a crash are kept.
+                let written = serde_json::to_writer(&mut *file, &LoggedEvent { at, event })
+                    .map_err(io::Error::from)
+                    .and_then(|()| file.write_all(b"\n"));
+                if let Err(e) = written {
+                    error!(
+                        "Failed to write to the event log, so no more events will be logged: {e}"
+                    );
+                    *self = EventLog::Off;
+                }
+            }
+        }
+    }
+
+    /// Returns the events kept in memory, which is none unless the events are recorded in memory.
+    pub fn recorded(&self) -> &[ServerEvent] {
+        match self {
+            EventLog::Memory(events) => events,
+            _ => &[],
+        }
+    }
+}
+
+/// Reads every event in the log, from the oldest to the newest.
+pub fn read_events(path: &Path) -> Result<Vec<LoggedEvent>, EventLogError> {
+    let mut events = Vec::new();
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|error| EventLogError::Parse {
+            line: index + 1,
+            error,
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Replays the events through a new server in memory, checking that it does exactly what was logged.
+/// Each time the server was started begins a new session, which is replayed by its own server.
+/// Returns the amount of sessions that were replayed.
+pub fn replay(events: &[LoggedEvent]) -> Result<usize, EventLogError> {
+    let starts: Vec<usize> = events
+        .iter()
+        .enumerate()
+        .filter(|(_, logged)| matches!(logged.event, ServerEvent::Started { .. }))
+        .map(|(index, _)| index)
+        .collect();
+    if starts.first() != Some(&0) {
+        return Err(EventLogError::MissingStart);
+    }
+
+    for (session, start) in starts.iter().enumerate() {
+        let end = starts.get(session + 1).copied().unwrap_or(events.len());
+        replay_session(&events[*start..end], *start)?;
+    }
+    Ok(starts.len())
+}
+
+/// Replays the events of a single session, which starts at the given index of the log.
+/// If the session ends partway through an update, such as when the server panicked, then that update is replayed too.
+fn replay_session(session: &[LoggedEvent], offset: usize) -> Result<(), EventLogError> {
+    let Some(ServerEvent::Started {
+        version,
+        seed,
+        ruleset,
+        advised,
+        assisted,
+        hint_budget,
+        live,
+    }) = session.first().map(|logged| &logged.event)
+    else {
+        return Err(EventLogError::MissingStart);
+    };
+    if *version > EVENT_LOG_VERSION {
+        return Err(EventLogError::UnsupportedVersion(*version));
+    }
+
+    // Only whether there was an advice changes what the server does, so the replayed advisor never has any.
+    let advisor = advised.then(|| Arc::new(|_, _| None) as Advisor);
+    let (connections, listener) = mpsc::channel();
+    let mut server = Running::Listening(Box::new(
+        Server::new(Listener::Memory(listener))
+            .with_seed(*seed)
+            .with_lobby(Lobby {
+                ruleset: *ruleset,
+                advisor,
+                assisted: *assisted,
+                hint_budget: *hint_budget,
+                live: live.clone(),
+            })
+            // The players are only timed out where the log says they were, however long the replay takes.
+            .with_clock(TurnClock::Replayed(Vec::new()))
+            .with_event_log(EventLog::Memory(Vec::new())),
+    ));
+
+    // The sending half of each connection, which is dropped once the connection closes.
+    let mut clients = HashMap::new();
+    // What the server sent each connection, which is kept so sending to them doesn't fail.
+    let mut sent = Vec::new();
+    let mut force_start = false;
+    let mut updated = true;
+
+    for (index, logged) in session.iter().enumerate().skip(1) {
+        logging::crash::note("replayed event", offset + index + 1);
+        updated = logged.event == ServerEvent::Updated;
+        let (connection, packet) = match &logged.event {
+            ServerEvent::Connected { connection } => {
+                let (client_sender, server_reader) = mpsc::channel();
+                let (server_writer, client_receiver) = mpsc::channel();
+                let _ = connections.send(Channels {
+                    reading: server_reader,
+                    writing: server_writer,
+                });
+                clients.insert(*connection, client_sender);
+                sent.push(client_receiver);
+                continue;
+            }
+            ServerEvent::Received {
+                connection,
+                message,
+            } => (connection, Ok(message.clone())),
+            ServerEvent::BadPacket { connection, packet } => {
+                (connection, Err(ChannelError::BadPacket(packet.clone())))
+            }
+            ServerEvent::ReadError { connection } => {
+                (connection, Err(ChannelError::ReadError(ErrorKind::Other)))
+            }
+            ServerEvent::Disconnected { connection } => {
+                clients.remove(connection);
+                continue;
+            }
+            ServerEvent::LobbyChanged {
+                assisted,
+                hint_budget,
+                live,
+            } => {
+                server.update_lobby(|lobby| {
+                    lobby.assisted = *assisted;
+                    lobby.hint_budget = *hint_budget;
+                    lobby.live = live.clone();
+                });
+                continue;
+            }
+            ServerEvent::TimedOut { connection } => {
+                server.time_out(*connection);
+                continue;
+            }
+            ServerEvent::ForcedStart => {
+                force_start = true;
+                continue;
+            }
+            ServerEvent::Updated => {
+                (server, _) = server.update(force_start);
+                force_start = false;
+                continue;
+            }
+            // The rest is what the server did, which is checked once the session is replayed.
+            _ => continue,
+        };
+
+        if let Some(client) = clients.get(connection) {
+            let _ = client.send(packet);
+        }
+    }
+    if !updated {
+        (server, _) = server.update(force_start);
+    }
+    logging::crash::forget("replayed event");
+
+    let replayed = server.event_log().recorded();
+    for (index, logged) in session.iter().enumerate() {
+        if replayed.get(index) != Some(&logged.event) {
+            return Err(EventLogError::Diverged {
+                index: offset + index + 1,
+                logged: format!("{:?}", logged.event),
+                replayed: replayed
+                    .get(index)
+                    .map_or(String::from("nothing"), |event| format!("{event:?}")),
+            });
+        }
+    }
+    Ok(())
+}
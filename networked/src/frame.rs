@@ -0,0 +1,84 @@
+//! Splits the bytes read from a connection into packets & decodes them.
+//!
+//! Every packet is serialized as YAML & ended with the [`ETX`] char.
+//! These functions don't do any I/O, so they can be fed any bytes, such as by the fuzz targets.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use game_core::codec;
+use protocol::{Message, MessageType, ETX};
+
+use crate::ChannelError;
+
+/// The most bytes a single packet can be, so a client can't use up the server's memory by never ending a packet.
+/// Every valid packet is far smaller than this.
+pub const MAX_PACKET_LEN: usize = 16 * 1024;
+
+/// Splits the bytes into the packets they contain, without the [`ETX`] chars that end them.
+/// Returns the packets, along with the bytes after the last packet, which are the start of the next packet.
+pub fn split_packets(bytes: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+    let mut packets = Vec::new();
+    let mut rest = bytes;
+
+    while let Some(end) = rest.iter().position(|byte| *byte == ETX as u8) {
+        packets.push(&rest[..end]);
+        rest = &rest[end + 1..];
+    }
+
+    (packets, rest)
+}
+
+/// Decodes a single packet, which doesn't include the [`ETX`] char that ended it.
+/// If the packet isn't valid UTF-8 or isn't a valid message, then it's returned as a [`ChannelError::BadPacket`].
+/// A message with a type that can't be decoded could have been sent by a newer version,
+/// so it's returned as [`Message::unknown`] instead.
+pub fn decode<T: Message + DeserializeOwned>(packet: &[u8]) -> Result<T, ChannelError> {
+    let Ok(text) = std::str::from_utf8(packet) else {
+        return Err(ChannelError::BadPacket(
+            String::from_utf8_lossy(packet).into_owned(),
+        ));
+    };
+
+    match codec::from_str(text) {
+        Ok(message) => Ok(message),
+        Err(_) if codec::from_str::<MessageType>(text).is_ok() => Ok(T::unknown()),
+        Err(_) => Err(ChannelError::BadPacket(text.to_string())),
+    }
+}
+
+/// Encodes the message as a packet, including the [`ETX`] char that ends it.
+pub fn encode<T: Serialize>(message: &T) -> Result<Vec<u8>, codec::Error> {
+    let mut packet = codec::to_string(message)?;
+    packet.push(ETX);
+    Ok(packet.into_bytes())
+}
+
+/// Collects the bytes read from a connection until they form whole packets.
+#[derive(Debug, Default)]
+pub struct PacketBuffer {
+    /// The bytes of the packet that hasn't been ended yet.
+    pending: Vec<u8>,
+}
+
+impl PacketBuffer {
+    /// Adds the read bytes, returning the decoded packets they ended.
+    /// Returns an error if the unended packet is longer than [`MAX_PACKET_LEN`],
+    /// after which the connection should be closed as the packets can't be told apart anymore.
+    pub fn push<T: Message + DeserializeOwned>(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<Vec<Result<T, ChannelError>>, ChannelError> {
+        self.pending.extend_from_slice(bytes);
+
+        let (packets, rest) = split_packets(&self.pending);
+        if rest.len() > MAX_PACKET_LEN {
+            return Err(ChannelError::ReadError(std::io::ErrorKind::InvalidData));
+        }
+
+        let decoded = packets.into_iter().map(decode).collect();
+        let consumed = self.pending.len() - rest.len();
+        self.pending.drain(..consumed);
+        Ok(decoded)
+    }
+}
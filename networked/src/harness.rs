@@ -0,0 +1,363 @@
+//! Runs the server with clients connected in memory, so whole games can be played deterministically.
+//!
+//! The server is only updated when the harness is stepped, so nothing happens in the background,
+//! & the dice are rolled from a seed, so the same steps always play out the same way.
+//! Each [`TestClient`] returned by [`Harness::connect`] is the client a player would use,
+//! whose messages are passed to the server the next time the harness is stepped, & back to the client once it's updated.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+use mac_address2::MacAddress;
+use protocol::{
+    ClientMessages, ClientMove, ClientMovedBoard, ClientToMove, RollRequest, ServerMessages,
+    PROTOCOL_VERSION,
+};
+
+use crate::client_states::{Client, ClientError, ClientState, InGame, PreGame, Query};
+use crate::server_state::{Channel, HostStatus, Listener, Lobby, Running, Server};
+use crate::{ChannelError, Channels};
+
+/// The most times the server is stepped by [`Harness::advance`], in case the clients never stop sending messages.
+const MAX_STEPS: usize = 10_000;
+
+/// A server that is only updated when it's stepped, which clients connect to in memory.
+pub struct Harness {
+    /// The server, which is only None while it's being updated.
+    server: Option<Running>,
+    /// Passes the new connections to the server's listener.
+    connections: Sender<Channel>,
+    /// Passes the messages between each client & the server, until both have closed the connection.
+    relays: Vec<Relay>,
+    /// Whether the next step starts the game, even if not every player is ready.
+    force_start: bool,
+}
+
+impl Harness {
+    /// Creates a server that only accepts connections made by the harness, rolling the dice from the given seed.
+    pub fn new(seed: u64) -> Harness {
+        let (connections, listener) = mpsc::channel();
+        let server = Server::new(Listener::Memory(listener)).with_seed(seed);
+
+        Harness {
+            server: Some(Running::Listening(Box::new(server))),
+            connections,
+            relays: Vec::new(),
+            force_start: false,
+        }
+    }
+
+    /// Writes the replay of each game played to a new file within the given folder.
+    /// Nothing happens if a game is already being played.
+    pub fn write_replays(&mut self, folder: PathBuf) {
+        self.server = self.server.take().map(|server| match server {
+            Running::Listening(server) => {
+                Running::Listening(Box::new(server.with_game_logs(Some(folder))))
+            }
+            playing => playing,
+        });
+    }
+
+    /// Hints the players the moves to make as the lobby allows.
+    /// Nothing happens if a game is already being played.
+    pub fn set_lobby(&mut self, lobby: Lobby) {
+        self.server = self.server.take().map(|server| match server {
+            Running::Listening(server) => Running::Listening(Box::new(server.with_lobby(lobby))),
+            playing => playing,
+        });
+    }
+
+    /// Connects a new client with the given name, which requests to join the games straight away.
+    /// The server handles the request the next time it's stepped.
+    pub fn connect(&mut self, name: &str) -> Result<TestClient, ClientError> {
+        self.connect_speaking(name, PROTOCOL_VERSION)
+    }
+
+    /// Connects a new client with the given name, which tells the server it speaks the given version of the protocol.
+    /// The server handles the request the next time it's stepped.
+    pub fn connect_speaking(
+        &mut self,
+        name: &str,
+        version: u32,
+    ) -> Result<TestClient, ClientError> {
+        let (client_writing, from_client) = mpsc::channel();
+        let (to_server, server_reading) = mpsc::channel();
+        let (server_writing, from_server) = mpsc::channel();
+        let (to_client, client_reading) = mpsc::channel();
+
+        // The harness owns the listener, so it's always listening.
+        let _ = self.connections.send(Channels {
+            reading: server_reading,
+            writing: server_writing,
+        });
+        self.relays.push(Relay {
+            from_client,
+            to_server: Some(to_server),
+            from_server,
+            to_client: Some(to_client),
+        });
+
+        let writing = client_writing.clone();
+        let client = Client::with_connection(
+            Channels {
+                reading: client_reading,
+                writing: client_writing,
+            },
+            MacAddress::default(),
+            name.to_string(),
+        );
+        match version == PROTOCOL_VERSION {
+            true => client.join()?,
+            // Joins as a client speaking another version of the protocol would.
+            false => {
+                writing.send(ClientMessages::Version(version))?;
+                writing.send(ClientMessages::OptInForPlaying {
+                    mac_address: MacAddress::default(),
+                    name: name.to_string(),
+                })?;
+            }
+        }
+
+        Ok(TestClient {
+            state: Some(ClientState::Joining(client)),
+        })
+    }
+
+    /// Starts the game on the next step, even if not every player is ready.
+    /// Nothing happens if a game is already being played or no players are connected.
+    pub fn force_start(&mut self) {
+        self.force_start = true;
+    }
+
+    /// Updates the server once, handling one new connection & every message it has been sent.
+    /// Returns true if the server handled anything.
+    pub fn step(&mut self) -> bool {
+        let Some(server) = self.server.take() else {
+            return false;
+        };
+
+        let relayed = self.relay();
+        let (server, progressed) = server.update(self.force_start);
+        self.server = Some(server);
+        self.force_start = false;
+        self.relay() || relayed || progressed
+    }
+
+    /// Steps the server until it's waiting for the clients, returning the amount of steps that handled anything.
+    pub fn advance(&mut self) -> usize {
+        (0..MAX_STEPS).take_while(|_| self.step()).count()
+    }
+
+    /// Returns true if a game is being played.
+    pub fn is_playing(&self) -> bool {
+        matches!(self.server, Some(Running::Playing(_)))
+    }
+
+    /// Returns the players connected to the server, as the [`Host`](crate::server_state::Host) would see them.
+    pub fn status(&self) -> HostStatus {
+        self.server
+            .as_ref()
+            .map(Running::status)
+            .unwrap_or_default()
+    }
+
+    /// Plays out the game being played, with each of the given clients rolling both dice & making the first legal move.
+    /// Returns the result each client was sent, in the same order as the clients.
+    /// A client's result is None if the game ended without sending them one.
+    pub fn play_out(
+        &mut self,
+        clients: &mut [&mut TestClient],
+    ) -> Result<Vec<Option<ServerMessages>>, ClientError> {
+        for _ in 0..MAX_STEPS {
+            let mut answered = false;
+            for client in clients.iter_mut() {
+                client.update()?;
+                answered |= client.respond()?;
+            }
+
+            let progressed = self.advance() > 0;
+            if !self.is_playing() && !answered && !progressed {
+                break;
+            }
+        }
+
+        for client in clients.iter_mut() {
+            client.update()?;
+        }
+        Ok(clients
+            .iter()
+            .map(|client| client.pre_game().and_then(Client::last_result))
+            .collect())
+    }
+
+    /// Passes every message sent between the clients & the server, returning true if any were passed.
+    /// The relays of the connections both sides have closed are dropped.
+    fn relay(&mut self) -> bool {
+        let mut relayed = false;
+        self.relays.retain_mut(|relay| {
+            let (open, passed) = relay.pass();
+            relayed |= passed;
+            open
+        });
+        relayed
+    }
+}
+
+/// Passes the messages of a client connected in memory to & from the server,
+/// as the threads reading & writing a socket would.
+struct Relay {
+    from_client: Receiver<ClientMessages>,
+    /// The server's end of the client's messages, which is dropped once the client disconnects.
+    to_server: Option<Sender<Result<ClientMessages, ChannelError>>>,
+    from_server: Receiver<ServerMessages>,
+    /// The client's end of the server's messages, which is dropped once the server drops the client.
+    to_client: Option<Sender<Result<ServerMessages, ChannelError>>>,
+}
+
+impl Relay {
+    /// Passes on every message sent in either direction.
+    /// Returns whether either side is still connected, along with whether anything was passed.
+    fn pass(&mut self) -> (bool, bool) {
+        let (to_server, to_server_passed) = pass(&self.from_client, self.to_server.take());
+        let (to_client, to_client_passed) = pass(&self.from_server, self.to_client.take());
+        self.to_server = to_server;
+        self.to_client = to_client;
+
+        let open = self.to_server.is_some() || self.to_client.is_some();
+        (open, to_server_passed || to_client_passed)
+    }
+}
+
+/// Passes every message from the receiver to the sender.
+/// Returns the sender if both ends are still connected, along with whether anything was passed.
+/// Dropping the sender once the receiver is disconnected passes on the disconnect.
+fn pass<T>(
+    from: &Receiver<T>,
+    to: Option<Sender<Result<T, ChannelError>>>,
+) -> (Option<Sender<Result<T, ChannelError>>>, bool) {
+    let Some(to) = to else {
+        return (None, false);
+    };
+
+    let mut passed = false;
+    loop {
+        match from.try_recv() {
+            Ok(message) => {
+                passed = true;
+                if to.send(Ok(message)).is_err() {
+                    return (None, true);
+                }
+            }
+            Err(TryRecvError::Empty) => return (Some(to), passed),
+            Err(TryRecvError::Disconnected) => return (None, true),
+        }
+    }
+}
+
+/// A client connected to the [`Harness`] in memory, which handles the server's messages whenever it's updated.
+/// The client is disconnected when it's dropped.
+pub struct TestClient {
+    /// The state of the client, which is only None once the client has errored.
+    state: Option<ClientState>,
+}
+
+impl TestClient {
+    /// Handles every message the server has passed to the client, returning true if there were any.
+    /// The client can't be used once this errors, as the server broke the protocol or disconnected.
+    pub fn update(&mut self) -> Result<bool, ClientError> {
+        let Some(state) = self.state.take() else {
+            return Ok(false);
+        };
+
+        let (state, handled) = state.update()?;
+        self.state = Some(state);
+        Ok(handled)
+    }
+
+    /// Returns the client in its current state, or None if the client has errored.
+    pub fn state(&self) -> Option<&ClientState> {
+        self.state.as_ref()
+    }
+
+    /// Returns true if the server accepted the client & it's waiting for the next game.
+    pub fn is_waiting(&self) -> bool {
+        self.pre_game().is_some()
+    }
+
+    /// Returns true if the client is waiting for the server to accept it.
+    pub fn is_joining(&self) -> bool {
+        matches!(self.state, Some(ClientState::Joining(_)))
+    }
+
+    /// Returns true if the server refused to let the client join.
+    pub fn is_refused(&self) -> bool {
+        matches!(self.state, Some(ClientState::Refused))
+    }
+
+    /// Returns the client if it's waiting for the next game.
+    pub fn pre_game(&self) -> Option<&Client<PreGame>> {
+        match &self.state {
+            Some(ClientState::PreGame(client)) => Some(client),
+            _ => None,
+        }
+    }
+
+    /// Returns the client if it's playing a game.
+    pub fn in_game(&self) -> Option<&Client<InGame>> {
+        match &self.state {
+            Some(ClientState::InGame(client)) => Some(client),
+            _ => None,
+        }
+    }
+
+    /// Returns the client if it's playing a game, so it can answer the server.
+    pub fn in_game_mut(&mut self) -> Option<&mut Client<InGame>> {
+        match &mut self.state {
+            Some(ClientState::InGame(client)) => Some(client),
+            _ => None,
+        }
+    }
+
+    /// Informs the server of whether the client is ready for the next game to start.
+    /// Returns false if the client isn't waiting for the next game.
+    pub fn ready(&mut self, ready: bool) -> Result<bool, ClientError> {
+        match &mut self.state {
+            Some(ClientState::PreGame(client)) => client.set_ready(ready).map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Answers what the server asked the client for, rolling both dice & making the first legal move.
+    /// Returns true if the client answered anything.
+    pub fn respond(&mut self) -> Result<bool, ClientError> {
+        let Some(client) = self.in_game_mut() else {
+            return Ok(false);
+        };
+
+        match client.asked() {
+            None => Ok(false),
+            Some(Query::Roll) => client.roll(RollRequest::BothDice).map(|_| true),
+            Some(Query::Move(to_move)) => client.make_move(first_move(to_move)).map(|_| true),
+        }
+    }
+}
+
+/// Returns the first legal move for the rolled dice, or [`ClientMove::CannotMove`] if there isn't one.
+fn first_move(to_move: ClientToMove) -> ClientMove {
+    match to_move {
+        ClientToMove::OneDice(board, dice) => game_core::enumerate_moves(board, dice)
+            .first()
+            .map_or(ClientMove::CannotMove, |moved| {
+                ClientMove::FirstDice(ClientMovedBoard(*moved))
+            }),
+        ClientToMove::TwoDice {
+            board,
+            dice_1,
+            dice_2,
+        } => game_core::enumerate_moves(board, dice_1 + dice_2)
+            .first()
+            .map_or(ClientMove::CannotMove, |moved| {
+                ClientMove::BothDice(ClientMovedBoard(*moved))
+            }),
+    }
+}
@@ -0,0 +1,286 @@
+//! The standings & match history of the games a server has logged, which can be exported as CSV or JSON.
+//!
+//! Nothing is kept besides the replays the server writes to its game logs,
+//! so the leaderboard is always worked out from them & can be exported while the server is running.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::replay::{Replay, ReplayError, REPLAY_EXTENSION};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LeaderboardError {
+    #[error("Couldn't access the leaderboard: {0}")]
+    Io(#[from] io::Error),
+    #[error("Couldn't read the replay {}: {error}", path.display())]
+    Replay { path: PathBuf, error: ReplayError },
+    #[error("Couldn't write the leaderboard as JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The format the leaderboard is exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The standings are written to the file, & the match history next to it with "-matches" after its name.
+    Csv,
+    /// The standings & the match history are both written to the file.
+    Json,
+}
+
+impl ExportFormat {
+    /// Returns the format of the file with the given extension, which is CSV unless it's a ".json" file.
+    pub fn from_path(path: &Path) -> ExportFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => ExportFormat::Json,
+            _ => ExportFormat::Csv,
+        }
+    }
+}
+
+/// The results of a player over every logged game.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// The amount of games the player shut the box in.
+    pub shut_boxes: u32,
+    /// The mean value left on the player's board at the end of their games.
+    pub average_score: f32,
+}
+
+/// A game that was played on the server.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Match {
+    /// When the game ended, in milliseconds since the unix epoch, if it's known from the replay's name.
+    pub ended_at: Option<u64>,
+    /// The names of the players, in the order they took their turns.
+    pub players: Vec<String>,
+    /// The value left on each player's board, in the same order as the players.
+    pub scores: Vec<u8>,
+    /// The names of the players with the lowest score.
+    pub winners: Vec<String>,
+    /// The amount of turns taken in the game.
+    pub turns: usize,
+}
+
+/// The standings of every player, along with every game they played.
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct Leaderboard {
+    /// The standings from the first place to the last.
+    pub standings: Vec<Standing>,
+    /// The games from the oldest to the newest.
+    pub matches: Vec<Match>,
+}
+
+impl Leaderboard {
+    /// Works out the leaderboard from every replay within the folder of game logs.
+    /// Unfinished games are left out, as they don't have a winner.
+    pub fn from_game_logs(folder: &Path) -> Result<Leaderboard, LeaderboardError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(folder)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == REPLAY_EXTENSION)
+            {
+                paths.push(path);
+            }
+        }
+        // The replays are named after when they ended, so they're sorted from the oldest to the newest.
+        paths.sort();
+
+        let mut replays = Vec::with_capacity(paths.len());
+        for path in paths {
+            let replay = Replay::read(&path).map_err(|error| LeaderboardError::Replay {
+                path: path.clone(),
+                error,
+            })?;
+            replays.push((ended_at(&path), replay));
+        }
+        Ok(Leaderboard::from_replays(replays))
+    }
+
+    /// Works out the leaderboard from the replays, along with when each game ended if it's known.
+    /// The replays should be from the oldest to the newest.
+    pub fn from_replays(replays: impl IntoIterator<Item = (Option<u64>, Replay)>) -> Leaderboard {
+        let mut matches = Vec::new();
+        let mut standings: Vec<Standing> = Vec::new();
+        let mut indices: HashMap<String, usize> = HashMap::new();
+        let mut total_scores: Vec<u32> = Vec::new();
+
+        for (ended_at, replay) in replays {
+            if replay.winning_score.is_none() {
+                continue;
+            }
+
+            let scores: Vec<u8> = replay
+                .boards_after(replay.turns.len())
+                .into_iter()
+                .map(|(board, _)| replay.variant.score(board))
+                .collect();
+            let winning_score = scores.iter().copied().min().unwrap_or_default();
+            let winners = scores
+                .iter()
+                .filter(|score| **score == winning_score)
+                .count();
+
+            for (name, score) in replay.players.iter().zip(&scores) {
+                let index = *indices.entry(name.clone()).or_insert_with(|| {
+                    standings.push(Standing {
+                        name: name.clone(),
+                        games: 0,
+                        wins: 0,
+                        draws: 0,
+                        losses: 0,
+                        shut_boxes: 0,
+                        average_score: 0.,
+                    });
+                    total_scores.push(0);
+                    standings.len() - 1
+                });
+
+                let standing = &mut standings[index];
+                standing.games += 1;
+                match (*score == winning_score, winners) {
+                    (true, 1) => standing.wins += 1,
+                    (true, _) => standing.draws += 1,
+                    (false, _) => standing.losses += 1,
+                }
+                standing.shut_boxes += (*score == 0) as u32;
+                total_scores[index] += *score as u32;
+                standing.average_score = total_scores[index] as f32 / standing.games as f32;
+            }
+
+            matches.push(Match {
+                ended_at,
+                winners: replay
+                    .players
+                    .iter()
+                    .zip(&scores)
+                    .filter(|(_, score)| **score == winning_score)
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                turns: replay.turns.len(),
+                players: replay.players,
+                scores,
+            });
+        }
+
+        // The most wins come first, then the fewest losses, then the lowest scores.
+        standings.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(a.losses.cmp(&b.losses))
+                .then(a.average_score.total_cmp(&b.average_score))
+                .then(a.name.cmp(&b.name))
+        });
+
+        Leaderboard { standings, matches }
+    }
+
+    /// Writes the standings as CSV, from the first place to the last.
+    pub fn write_standings_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "rank,name,games,wins,draws,losses,shut_boxes,average_score"
+        )?;
+        for (rank, standing) in self.standings.iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{:.2}",
+                rank + 1,
+                csv_field(&standing.name),
+                standing.games,
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                standing.shut_boxes,
+                standing.average_score
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the match history as CSV, from the oldest game to the newest.
+    /// The players, scores, & winners of a game are separated by semicolons.
+    pub fn write_matches_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "ended_at,players,scores,winners,turns")?;
+        for played in &self.matches {
+            let scores: Vec<String> = played.scores.iter().map(u8::to_string).collect();
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                played
+                    .ended_at
+                    .map(|ended| ended.to_string())
+                    .unwrap_or_default(),
+                csv_field(&played.players.join(";")),
+                scores.join(";"),
+                csv_field(&played.winners.join(";")),
+                played.turns
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the standings & the match history as JSON.
+    pub fn write_json(&self, writer: impl Write) -> Result<(), LeaderboardError> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Exports the leaderboard to the given file, in the format given by its extension.
+    /// Returns the files that were written.
+    pub fn export(&self, path: &Path) -> Result<Vec<PathBuf>, LeaderboardError> {
+        match ExportFormat::from_path(path) {
+            ExportFormat::Json => {
+                self.write_json(BufWriter::new(File::create(path)?))?;
+                Ok(vec![path.to_path_buf()])
+            }
+            ExportFormat::Csv => {
+                let matches_path = matches_path(path);
+                self.write_standings_csv(BufWriter::new(File::create(path)?))?;
+                self.write_matches_csv(BufWriter::new(File::create(&matches_path)?))?;
+                Ok(vec![path.to_path_buf(), matches_path])
+            }
+        }
+    }
+}
+
+/// Returns the file the match history is written next to the standings, such as "league-matches.csv".
+fn matches_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy())
+        .unwrap_or("csv".into());
+    path.with_file_name(format!("{stem}-matches.{extension}"))
+}
+
+/// Returns when the game ended from the name of its replay, such as "game-1718000000000.stbreplay".
+fn ended_at(path: &Path) -> Option<u64> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("game-")?
+        .parse()
+        .ok()
+}
+
+/// Quotes the field if it contains a character CSV treats specially.
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
@@ -6,26 +6,39 @@ use std::{
     thread,
 };
 
+use frame::PacketBuffer;
+use protocol::Message;
 use serde::{de::DeserializeOwned, Serialize};
-
-pub const ETX: char = 0b00000011 as char;
-
-pub fn get_ip_input() -> IpAddr {
+use tracing::{debug, info_span, trace, warn};
+
+pub mod client_states;
+pub mod event_log;
+pub mod frame;
+#[cfg(feature = "harness")]
+pub mod harness;
+pub mod leaderboard;
+pub mod replay;
+pub mod server_state;
+
+/// How many bytes are read from a connection at once.
+const READ_BUFFER_LEN: usize = 1024;
+
+/// Prompts for an IP address until a valid one is given.
+/// Returns an error if the terminal can't be written to or read from.
+pub fn get_ip_input() -> io::Result<IpAddr> {
     // Loops until valid IP is given
     loop {
         // Prompts for IP address to connect to
         print!("IP to connect to: ");
-        io::stdout().flush().expect("Cannot write text to stdout.");
+        io::stdout().flush()?;
 
         // Reads the given IP
         let mut data = String::new();
-        io::stdin()
-            .read_line(&mut data)
-            .expect("Cannot read from stdin");
+        io::stdin().read_line(&mut data)?;
 
         // Validates IP
         match data.trim().parse::<IpAddr>() {
-            Ok(ip_address) => return ip_address,
+            Ok(ip_address) => return Ok(ip_address),
             Err(_) => {
                 eprintln!("Invalid IP. Please try again.")
             }
@@ -33,22 +46,22 @@ pub fn get_ip_input() -> IpAddr {
     }
 }
 
-pub fn get_port_input() -> u16 {
+/// Prompts for a port until a valid one is given.
+/// Returns an error if the terminal can't be written to or read from.
+pub fn get_port_input() -> io::Result<u16> {
     // Loops until valid port is given
     loop {
         // Prompts for port address to connect to
         print!("Port to connect on: ");
-        io::stdout().flush().expect("Cannot write text to stdout.");
+        io::stdout().flush()?;
 
         // Reads the given IP
         let mut data = String::new();
-        io::stdin()
-            .read_line(&mut data)
-            .expect("Cannot read from stdin");
+        io::stdin().read_line(&mut data)?;
 
         // Validates IP
         match data.trim().parse::<u16>() {
-            Ok(port) => return port,
+            Ok(port) => return Ok(port),
             Err(_) => {
                 eprintln!("Invalid port. Please try again.")
             }
@@ -68,7 +81,7 @@ pub enum ChannelError {
 #[derive(Debug)]
 pub struct Channels<T, V>
 where
-    T: DeserializeOwned + Debug + Send,
+    T: Message + DeserializeOwned + Debug + Send,
     V: Serialize + Debug + Send,
 {
     pub reading: mpsc::Receiver<Result<T, ChannelError>>,
@@ -76,9 +89,10 @@ where
 }
 
 /// Creates a [`Channels`] struct, which can be used to send and receive data over the given tcp_stream.
-pub fn initialize_channels<T, V>(tcp_stream: TcpStream) -> Channels<T, V>
+/// Returns an error if the stream couldn't be shared between the reading & writing threads, or they couldn't start.
+pub fn initialize_channels<T, V>(tcp_stream: TcpStream) -> io::Result<Channels<T, V>>
 where
-    T: DeserializeOwned + Debug + Send + 'static,
+    T: Message + DeserializeOwned + Debug + Send + 'static,
     V: Serialize + Debug + Send + 'static,
 {
     let peer_addr: String = match tcp_stream.peer_addr() {
@@ -89,95 +103,95 @@ where
     let (read_sender, read_receiver) = mpsc::channel();
     let (write_sender, write_receiver) = mpsc::channel();
 
-    let mut read_stream = tcp_stream.try_clone().expect("Cannot clone tcp stream.");
+    let mut read_stream = tcp_stream.try_clone()?;
     let mut write_stream = tcp_stream;
 
+    // Both threads are part of the connection's span, so their events can be told apart from other connections.
+    let span = info_span!("connection", peer = %peer_addr);
+    let read_span = span.clone();
+
     // Reading thread
     thread::Builder::new()
         .name(format!("reading for {peer_addr}"))
         .spawn(move || {
-            'outer: loop {
-                let mut data = Vec::new();
-
-                // Reads until end of message (ETX char is sent)
-                loop {
-                    let mut buffer = [0u8; 1];
-
-                    match read_stream.read_exact(&mut buffer) {
-                        Ok(_) => {}
-                        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {}
-                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
-                        Err(e) => {
-                            if read_sender
-                                .send(Err(ChannelError::ReadError(e.kind())))
-                                .is_err()
-                            {
-                                eprintln!("Couldn't send fatal error to self.")
-                            };
-                            eprintln!("Reading dropped: {} {}", e, e.kind());
-                            break 'outer;
-                        }
-                    };
+            let _span = read_span.entered();
+            let mut packets = PacketBuffer::default();
+            let mut buffer = [0u8; READ_BUFFER_LEN];
 
-                    // This char equals end of message.
-                    if buffer[0] as char == ETX {
-                        break;
-                    }
-
-                    data.push(buffer[0] as char);
-                }
-
-                let message = String::from_iter(data.iter());
-                println!("{}", message);
-                let client_message = match serde_yml::from_str(&message).ok() {
-                    Some(parsed_packet) => Ok(parsed_packet),
-                    None => Err(ChannelError::BadPacket(message)),
+            loop {
+                let read = match read_stream.read(&mut buffer) {
+                    // The connection was closed by the other end.
+                    Ok(0) => Err(ErrorKind::UnexpectedEof),
+                    Ok(read) => packets
+                        .push::<T>(&buffer[..read])
+                        .map_err(|_| ErrorKind::InvalidData),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e.kind()),
                 };
 
-                // When the receiver is dropped the thread should terminate
-                if read_sender.send(client_message).is_err() {
-                    eprintln!("Reading dropped");
-                    break;
+                let decoded = match read {
+                    Ok(decoded) => decoded,
+                    Err(kind) => {
+                        if read_sender
+                            .send(Err(ChannelError::ReadError(kind)))
+                            .is_err()
+                        {
+                            warn!("Couldn't send fatal error to self.")
+                        };
+                        debug!("Reading dropped: {kind}");
+                        break;
+                    }
                 };
 
-                // Clears data buffer
-                data.clear();
+                for packet in decoded {
+                    trace!(packet = ?packet, "Received packet");
+                    // Messages added by newer versions are skipped, so they don't disrupt the connection.
+                    if matches!(&packet, Ok(message) if message.is_unknown()) {
+                        debug!("Skipped a message of an unknown type");
+                        continue;
+                    }
+                    // When the receiver is dropped the thread should terminate
+                    if read_sender.send(packet).is_err() {
+                        debug!("Reading dropped");
+                        return;
+                    };
+                }
             }
-        })
-        .expect("Wasn't able to create reading thread");
+        })?;
 
     // Writing thread
     thread::Builder::new()
         .name(format!("writing for {peer_addr}"))
         .spawn(move || {
+            let _span = span.entered();
             loop {
-                let received = write_receiver.recv();
                 // When the sender is dropped the thread should terminate
-                if received.is_err() {
-                    eprintln!("Writer dropped");
+                let Ok(received) = write_receiver.recv() else {
+                    debug!("Writer dropped");
                     break;
-                }
-
-                let data_to_send = received.unwrap();
-                let mut data_to_send = serde_yml::to_string(&data_to_send)
-                    .expect("Couldn't serializes Client Message to send.");
+                };
 
-                // Adds char for end of message
-                data_to_send.push(ETX);
+                // A message that can't be serialized is skipped, as the connection is still usable.
+                let data_to_send = match frame::encode(&received) {
+                    Ok(data_to_send) => data_to_send,
+                    Err(e) => {
+                        warn!("Couldn't serialize {received:?} to send: {e}");
+                        continue;
+                    }
+                };
 
-                let write_res = write_stream.write_all(data_to_send.as_bytes());
+                let write_res = write_stream.write_all(&data_to_send);
 
                 if let Err(e) = write_res {
-                    eprintln!("Writer dropped: {e}");
+                    debug!("Writer dropped: {e}");
                     break;
                 }
             }
-        })
-        .expect("Wasn't able to create writing thread");
+        })?;
 
     // Wrapper struct
-    Channels {
+    Ok(Channels {
         reading: read_receiver,
         writing: write_sender,
-    }
+    })
 }
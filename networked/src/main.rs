@@ -1,65 +1,560 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::io::IsTerminal;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+#[cfg(feature = "bot")]
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader},
+    sync::Arc,
+};
 
+use app_config::Config;
 use clap::Parser;
+#[cfg(feature = "bot")]
+use compute::config::{DEFAULT_OUTPUT, DEFAULT_RANKED_OUTPUT};
+#[cfg(feature = "bot")]
+use compute::{BoardRoll, BotDifficulty, DiceRoll, Policy, RankedMove, Strategy, StrategySpec};
+use networked::client_states;
+use networked::event_log::{self, EventLog};
+use networked::replay::Replay;
+use networked::server_state::{self, Lobby};
+#[cfg(feature = "bot")]
+use networked::server_state::{Advice, Advisor};
+use rules::Ruleset;
+use tracing::{error, info, warn};
 
-mod client_states;
-mod server_state;
-mod states;
+#[cfg(feature = "bot")]
+mod bot;
+#[cfg(feature = "bot")]
+mod bots;
+mod console;
+mod reload;
+#[cfg(unix)]
+mod systemd;
 
 /// A small program to act as a server or client in a game of shut the box.
 #[derive(Parser)]
 #[command(about, version)]
 pub struct CliArgs {
-    /// Whether the program should act as a server or client  
-    /// Pass "server" for a server & "client" for a client
+    /// Whether the program should act as a server or client, or show a replay  
+    /// Pass "server" for a server, "client" for a client, "bot" for a client played by a strategy,
+    /// "replay" to print a replay, & "events" to replay the event log of a server
     role: String,
 
-    /// The IP address to connect to
+    /// The replay to print when the role is "replay", or the event log to replay when the role is "events"
+    replay: Option<PathBuf>,
+
+    /// Checks that every turn of the replay was legal & that its dice were rolled from its seed
+    #[arg(long = "verify", default_value_t = false, action=clap::ArgAction::SetTrue)]
+    verify: bool,
+
+    /// The IP address to connect to, or for the server to be hosted on, which overrides the config
     #[arg(short = 'a', long = "ip")]
     ip_address: Option<IpAddr>,
 
-    /// The port to connect over
-    #[arg(short = 'p', long = "port", default_value_t = 3333)]
-    port: u16,
+    /// The port to connect over, which overrides the config
+    /// Defaults to 3333 if the config doesn't set it
+    #[arg(short = 'p', long = "port")]
+    port: Option<u16>,
+
+    /// The rules the server plays with, such as "classic", "one-die", or "instant-shut"
+    /// Golf can't be played on the server, as the results are sent after every game
+    /// Defaults to the config's ruleset, then to "one-die"
+    #[arg(long = "ruleset")]
+    ruleset: Option<String>,
+
+    /// The name to display to the other players
+    #[arg(short = 'n', long = "name", default_value = "Player")]
+    name: String,
+
+    /// The strategy the bot plays with, such as "highest-tiles-first", "policy:table.csv", or "exec:./my-bot"
+    /// Defaults to the config's bot strategy, then to its file of best moves
+    #[arg(short = 's', long = "strategy")]
+    strategy: Option<String>,
+
+    /// How often the bot makes its strategy's move, otherwise it makes a random move
+    /// Either "easy", "medium", "hard", "perfect", or a percentage such as "80%". Defaults to the config's, then to perfect
+    #[arg(long = "difficulty")]
+    difficulty: Option<String>,
+
+    /// The folder the server writes the replay of each game to, so the games can be replayed or verified
+    #[arg(short = 'l', long = "game-logs")]
+    game_logs: Option<PathBuf>,
+
+    /// The file the server appends everything it reads & does to, so its bugs can be reproduced with the "events" role
+    #[arg(long = "event-log")]
+    event_log: Option<PathBuf>,
+
+    /// Exports the standings & match history of the game logs to the file, then exits
+    /// The file is written as CSV with the matches next to it, or as JSON if it ends in ".json"
+    #[arg(long = "export-leaderboard")]
+    export_leaderboard: Option<PathBuf>,
+
+    /// Hints the players of the server the best move from the config's file of best moves
+    #[cfg(feature = "bot")]
+    #[arg(long = "assisted", default_value_t = false, action=clap::ArgAction::SetTrue)]
+    assisted: bool,
+
+    /// The amount of hints each player of the server can request in each game
+    #[cfg(feature = "bot")]
+    #[arg(long = "hints", default_value_t = 0)]
+    hints: u8,
+
+    /// The folder to write a daily log file to, as well as showing the log on stderr
+    #[arg(long = "log-dir")]
+    log_dir: Option<PathBuf>,
+
+    /// Never prompts for anything that wasn't given, failing instead, such as when running in a container
+    /// This is always the case when stdin isn't a terminal
+    #[arg(long = "non-interactive", default_value_t = false, action=clap::ArgAction::SetTrue)]
+    non_interactive: bool,
 
     /// Debug mode, don't enable this unless you're me
     #[arg(short = 'd', long = "debug", default_value_t = false, action=clap::ArgAction::SetTrue)]
     debug: bool,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = CliArgs::parse();
+    let _guard = logging::init("networked", args.log_dir.as_deref());
+
+    if args.role.eq_ignore_ascii_case("replay") {
+        let Some(path) = &args.replay else {
+            error!("No replay was given to print.");
+            return ExitCode::FAILURE;
+        };
+        return show_replay(path, args.verify);
+    }
+    if args.role.eq_ignore_ascii_case("events") {
+        let Some(path) = &args.replay else {
+            error!("No event log was given to replay.");
+            return ExitCode::FAILURE;
+        };
+        return replay_events(path);
+    }
+    if let Some(path) = &args.export_leaderboard {
+        return match console::export_leaderboard(args.game_logs.as_deref(), path) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                error!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let overrides = Config {
+        address: args.ip_address,
+        port: args.port,
+        ruleset: args.ruleset.clone(),
+        bot_strategy: args.strategy.clone(),
+        bot_difficulty: args.difficulty.clone(),
+        ..Config::default()
+    };
+    let config = match Config::load() {
+        Ok(config) => config.override_with(overrides.clone()),
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let port = config.port.unwrap_or(protocol::DEFAULT_PORT);
+
+    #[cfg(feature = "bot")]
+    let lobby = match args.assisted || args.hints > 0 {
+        true => match load_advisor(&config) {
+            Ok(advisor) => Lobby {
+                advisor: Some(advisor),
+                assisted: args.assisted,
+                hint_budget: args.hints,
+                ..Lobby::default()
+            },
+            Err(e) => {
+                error!("Couldn't load the best moves to hint: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        false => Lobby::default(),
+    };
+    #[cfg(not(feature = "bot"))]
+    let lobby = Lobby::default();
 
     if args.debug {
-        println!("-- In debug mode --");
+        info!("-- In debug mode --");
         // Loopback socket address
-        let loopback_socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 3333);
-        server_state::start(loopback_socket);
+        let loopback_socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let Some(listener) = bind(loopback_socket) else {
+            return ExitCode::FAILURE;
+        };
+        return serve(listener, lobby, config, overrides, args);
+    }
+
+    // A server started by systemd's socket activation is passed its socket, so it has no address to bind to.
+    #[cfg(unix)]
+    if args.role.eq_ignore_ascii_case("server") {
+        match systemd::take_listener() {
+            Ok(Some(listener)) => {
+                info!("Listening on the socket passed by systemd");
+                return serve(listener, lobby, config, overrides, args);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Couldn't listen on the socket passed by systemd: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
     }
 
-    // If no IP was given prompt for one
-    let ip_address = match args.ip_address {
+    // If no IP was given prompt for one, unless nobody is there to answer
+    let interactive = !args.non_interactive && std::io::stdin().is_terminal();
+    let ip_address = match config.address {
         Some(val) => val,
-        None => networked::get_ip_input(),
+        None if !interactive => {
+            error!(
+                "No IP address was given, so pass one with --ip or set it with {} or the config.",
+                app_config::ADDRESS_VAR
+            );
+            return ExitCode::FAILURE;
+        }
+        None => match networked::get_ip_input() {
+            Ok(ip_address) => ip_address,
+            Err(e) => {
+                error!("Couldn't read the IP: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
     };
 
-    let socket_address = SocketAddr::new(ip_address, args.port);
+    let socket_address = SocketAddr::new(ip_address, port);
 
     match args.role.to_ascii_lowercase().as_str() {
         "server" => {
-            println!(
+            info!(
                 "Starting server on {}:{}",
                 socket_address.ip(),
                 socket_address.port()
             );
-            server_state::start(socket_address);
+            let Some(listener) = bind(socket_address) else {
+                return ExitCode::FAILURE;
+            };
+            serve(listener, lobby, config, overrides, args)
         }
         "client" => {
-            println!("Starting client");
-            client_states::start(socket_address);
+            info!("Starting client");
+            match client_states::start(socket_address, args.name) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    error!("{e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        #[cfg(feature = "bot")]
+        "bot" => {
+            let (strategy, difficulty) = match load_bot(&config) {
+                Ok(bot) => bot,
+                Err(e) => {
+                    error!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            info!("Starting bot at {difficulty} difficulty");
+            match bot::start(
+                socket_address,
+                args.name,
+                strategy,
+                difficulty,
+                Arc::default(),
+            ) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    error!("{e}");
+                    ExitCode::FAILURE
+                }
+            }
         }
         _ => {
-            println!("Invalid arg, must be either \"server\" or \"client\". Exiting");
+            error!("Invalid arg, must be either \"server\", \"client\", \"bot\", \"replay\", or \"events\". Exiting");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Binds the server to the given address.
+/// Returns None if it couldn't be bound, after logging why.
+fn bind(socket_address: SocketAddr) -> Option<TcpListener> {
+    match TcpListener::bind(socket_address) {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            error!("Unable to bind to {socket_address}, is it already in use? {e}");
+            None
+        }
+    }
+}
+
+/// Runs the server on the listener with the config's settings, reloading them whenever it's requested.
+/// Only returns if the server stopped, after logging why.
+fn serve(
+    listener: TcpListener,
+    lobby: Lobby,
+    config: Config,
+    overrides: Config,
+    args: CliArgs,
+) -> ExitCode {
+    let address = match listener.local_addr() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Couldn't find the address the server is listening on: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(lobby) = with_config(lobby, &config) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(event_log) = open_event_log(args.event_log.as_deref()) else {
+        return ExitCode::FAILURE;
+    };
+    console::spawn(args.game_logs.clone());
+    #[cfg(unix)]
+    reload::reload_on_hangup();
+    let mut reloader = reload::Reloader::new(config, overrides, address);
+
+    // Connections made from here on wait in the listener's backlog until the server accepts them.
+    #[cfg(unix)]
+    if let Err(e) = systemd::notify(&format!("READY=1\nSTATUS=Listening on {address}")) {
+        warn!("Couldn't tell systemd the server is ready: {e}");
+    }
+    let Err(e) = server_state::start(listener, args.game_logs, lobby, event_log, move || {
+        reloader.poll()
+    });
+    error!("The server stopped listening on {address}: {e}");
+    ExitCode::FAILURE
+}
+
+/// Sets the rules the server plays with to the config's ruleset, which defaults to the one dice rule,
+/// along with the settings that can be reloaded while the server is running.
+/// Returns None if the server can't play with the rules, after logging why.
+fn with_config(lobby: Lobby, config: &Config) -> Option<Lobby> {
+    let ruleset = match config.ruleset(Ruleset::ONE_DIE) {
+        Ok(ruleset) => ruleset,
+        Err(e) => {
+            error!("{e}");
+            return None;
+        }
+    };
+    // The clients don't know the rules, so they always start from the standard board.
+    if ruleset.tiles != game_core::STANDARD_TILES {
+        error!(
+            "The server can't play with {} tiles, as every client starts from a board of {} tiles.",
+            ruleset.tiles,
+            game_core::STANDARD_TILES
+        );
+        return None;
+    }
+    // The results are sent at the end of every game, so there's no round for the scores to be added up over.
+    if ruleset.holes() > 1 {
+        error!(
+            "The server can't play with the {ruleset} rules, as it sends the results after every game instead of after {} holes.",
+            ruleset.holes()
+        );
+        return None;
+    }
+
+    info!("Playing with the {ruleset} rules.");
+    logging::crash::note("ruleset", ruleset);
+    Some(Lobby {
+        ruleset,
+        live: reload::live_settings(config),
+        ..lobby
+    })
+}
+
+/// Loads the strategy the bots play with & how often they make its move, as the config sets them.
+/// The strategy defaults to the config's file of best moves, & the bots make its move every time by default.
+#[cfg(feature = "bot")]
+fn load_bot(config: &Config) -> Result<(Arc<dyn Strategy>, BotDifficulty), String> {
+    let spec = match &config.bot_strategy {
+        Some(strategy) => strategy.parse(),
+        None => Ok(StrategySpec::Table(
+            config
+                .policy_path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT)),
+        )),
+    };
+    if let Ok(spec) = &spec {
+        logging::crash::note("strategy", spec);
+    }
+    let strategy = spec
+        .and_then(|spec| spec.load())
+        .map_err(|e| format!("Couldn't load the bot's strategy: {e}"))?;
+
+    let difficulty = match config
+        .bot_difficulty
+        .as_deref()
+        .map(str::parse::<BotDifficulty>)
+    {
+        Some(Ok(difficulty)) => difficulty,
+        Some(Err(e)) => return Err(e.to_string()),
+        None => BotDifficulty::default(),
+    };
+    logging::crash::note("difficulty", difficulty);
+    Ok((strategy, difficulty))
+}
+
+/// Opens the file the server appends its events to, if one is given.
+/// Returns None if the file couldn't be opened, after logging why.
+fn open_event_log(path: Option<&Path>) -> Option<EventLog> {
+    let Some(path) = path else {
+        return Some(EventLog::Off);
+    };
+    match EventLog::append_to(path) {
+        Ok(event_log) => {
+            info!("Logging the server's events to {}.", path.display());
+            Some(event_log)
+        }
+        Err(e) => {
+            error!("Couldn't open the event log {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Loads the best moves from the config's file of best moves, which the server hints to the players.
+/// The win rates of the moves are hinted too, if the config's file of ranked moves exists.
+#[cfg(feature = "bot")]
+fn load_advisor(config: &Config) -> Result<Advisor, String> {
+    let policy_path = config
+        .policy_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT));
+    let (policy, format) = Policy::load_detected(&policy_path).map_err(|e| e.to_string())?;
+    logging::crash::note(
+        "policy",
+        format!(
+            "{} ({format} format, {} moves)",
+            policy_path.display(),
+            policy.len()
+        ),
+    );
+
+    let ranked_path = config
+        .ranked_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RANKED_OUTPUT));
+    let ranked_moves: HashMap<BoardRoll, Vec<RankedMove>> = match File::open(&ranked_path) {
+        Ok(file) => compute::codec::from_reader(BufReader::new(file))
+            .map_err(|e| format!("Couldn't parse ranked moves: {e}"))?,
+        // The win rates are only hinted if the moves were ranked.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(format!("Couldn't read ranked moves file: {e}")),
+    };
+
+    Ok(Arc::new(move |board, roll| {
+        let roll = DiceRoll::from(roll);
+        let best_move = policy.best_move(board, roll)?;
+        // The ranked values are win rates in tenths of a percent.
+        let win_rate = ranked_moves
+            .get(&BoardRoll::new(board, roll))
+            .and_then(|ranked| ranked.iter().find(|ranked| ranked.board == best_move))
+            .map(|ranked| ranked.value as f32 / 1000.);
+        Some(Advice {
+            best_move,
+            win_rate,
+        })
+    }))
+}
+
+/// Prints every turn of the replay in the given file, checking that the game was legal if verify is true.
+fn show_replay(path: &Path, verify: bool) -> ExitCode {
+    let replay = match Replay::read(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            error!("{e} ({})", path.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "Replay version {} with {}",
+        replay.version,
+        replay.variant.name()
+    );
+    if let Some(seed) = replay.seed {
+        println!("Seed: {seed}");
+    }
+    println!("Players: {}", replay.players.join(", "));
+
+    for (index, turn) in replay.turns.iter().enumerate() {
+        let name = replay
+            .players
+            .get(turn.player as usize)
+            .map_or("Unknown player", String::as_str);
+        let dice = match turn.dice.as_slice() {
+            [] => String::new(),
+            dice => format!(" {dice:?}"),
+        };
+        let action = match turn.moved_board {
+            Some(moved_board) => format!(
+                "[{}] -> [{}]",
+                alive_tiles(turn.board),
+                alive_tiles(moved_board)
+            ),
+            None => format!("[{}] is out", alive_tiles(turn.board)),
+        };
+        println!(
+            "{:>3}. {name} rolled {}{dice}: {action}",
+            index + 1,
+            turn.roll
+        );
+    }
+
+    match replay.winning_score {
+        Some(score) => println!("Winning score: {score}"),
+        None => println!("The game didn't finish."),
+    }
+
+    if !verify {
+        return ExitCode::SUCCESS;
+    }
+    match replay.verify() {
+        Ok(()) => {
+            println!("The replay is valid.");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
         }
     }
 }
+
+/// Replays the server's event log, checking that the server still does exactly what was logged.
+/// A bug that happened while the events were logged happens again as they're replayed.
+fn replay_events(path: &Path) -> ExitCode {
+    let replayed = event_log::read_events(path).and_then(|events| {
+        let sessions = event_log::replay(&events)?;
+        Ok((events.len(), sessions))
+    });
+    match replayed {
+        Ok((events, sessions)) => {
+            println!("Replayed {events} events over {sessions} sessions, which all played out as they were logged.");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e} ({})", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Lists the tiles of the board that haven't been knocked down.
+fn alive_tiles(board: u16) -> String {
+    (0..u16::BITS)
+        .filter(|tile| board & (1 << tile) != 0)
+        .map(|tile| (tile + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
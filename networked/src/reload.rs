@@ -0,0 +1,134 @@
+//! Reloads the server's config while it's running, when it's sent SIGHUP or the admin enters "reload".
+//!
+//! Only the settings that can change without dropping the connected clients are reloaded,
+//! which are the turn timeout, the most players, the amount of bots, & the ban list.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use app_config::Config;
+use networked::server_state::LiveSettings;
+use tracing::{error, info, warn};
+
+#[cfg(feature = "bot")]
+use crate::bots::Bots;
+
+/// Whether a reload has been requested since the server last checked.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Requests the config to be reloaded, which happens the next time the server checks for a reload.
+pub fn request() {
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Requests the config to be reloaded whenever the process is sent SIGHUP.
+#[cfg(unix)]
+pub fn reload_on_hangup() {
+    extern "C" fn on_hangup(_: libc::c_int) {
+        request();
+    }
+
+    // SAFETY: The handler only stores to an atomic, which is safe to do within a signal handler.
+    unsafe {
+        libc::signal(libc::SIGHUP, on_hangup as *const () as libc::sighandler_t);
+    }
+}
+
+/// Returns the settings of the server that can be changed while it's running, as the config sets them.
+pub fn live_settings(config: &Config) -> LiveSettings {
+    LiveSettings {
+        // A timeout of 0 is no limit, as nobody could take their turn in time.
+        turn_timeout: config
+            .turn_timeout
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs),
+        max_players: config.max_players,
+        banned: config.banned.clone().unwrap_or_default(),
+    }
+}
+
+/// Reloads the config when a reload is requested, keeping the bots of the server in line with it.
+pub struct Reloader {
+    /// The config the server is running with.
+    config: Config,
+    /// The values set by the arguments, which override the reloaded config.
+    overrides: Config,
+    /// The bots the server keeps in its lobby.
+    #[cfg(feature = "bot")]
+    bots: Bots,
+    /// Whether the bots have joined, which they can only do once the server is listening.
+    started: bool,
+}
+
+impl Reloader {
+    /// Creates the reloader of the server listening on the given address, which is running with the config.
+    pub fn new(config: Config, overrides: Config, address: SocketAddr) -> Reloader {
+        #[cfg(not(feature = "bot"))]
+        let _ = address;
+
+        Reloader {
+            config,
+            overrides,
+            #[cfg(feature = "bot")]
+            bots: Bots::new(address),
+            started: false,
+        }
+    }
+
+    /// Reloads the config if a reload was requested, returning the settings that changed with it.
+    /// The bots join the first time this is checked, as the server is listening by then.
+    pub fn poll(&mut self) -> Option<LiveSettings> {
+        if !self.started {
+            self.started = true;
+            self.update_bots();
+        }
+        if !REQUESTED.swap(false, Ordering::Relaxed) {
+            return None;
+        }
+
+        let config = match Config::load() {
+            Ok(config) => config.override_with(self.overrides.clone()),
+            Err(e) => {
+                error!("Couldn't reload the config, so the server is keeping its settings: {e}");
+                return None;
+            }
+        };
+        if needs_restart(&self.config, &config) {
+            warn!("Only the turn timeout, max players, server bots, & ban list are reloaded, the rest of the changes need the server to restart.");
+        }
+        self.config = config;
+        self.update_bots();
+
+        let live = live_settings(&self.config);
+        info!("Reloaded the config: {live:?}");
+        logging::crash::note("live settings", format!("{live:?}"));
+        Some(live)
+    }
+
+    /// Adds or removes bots until the config's amount are playing.
+    fn update_bots(&mut self) {
+        let amount = self.config.server_bots.unwrap_or_default();
+        #[cfg(feature = "bot")]
+        if let Err(e) = self.bots.resize(amount, &self.config) {
+            error!("Couldn't add the server's bots: {e}");
+        }
+        #[cfg(not(feature = "bot"))]
+        if amount > 0 {
+            warn!("The server can't keep any bots, as it was built without them.");
+        }
+    }
+}
+
+/// Returns true if the new config changes a setting that can't be changed while the server is running.
+fn needs_restart(running: &Config, reloaded: &Config) -> bool {
+    running.address != reloaded.address
+        || running.port != reloaded.port
+        || running.ruleset != reloaded.ruleset
+        || running.tiles != reloaded.tiles
+        || running.one_die != reloaded.one_die
+        || running.policy_path != reloaded.policy_path
+        || running.ranked_path != reloaded.ranked_path
+        || running.bot_strategy != reloaded.bot_strategy
+        || running.bot_difficulty != reloaded.bot_difficulty
+}
@@ -0,0 +1,230 @@
+//! The `.stbreplay` format, which records every turn of a game so it can be replayed or verified.
+//!
+//! A replay is written as YAML, starting with the [`REPLAY_VERSION`] it was written with.
+//! The game logs written before the format was versioned are read as version 0,
+//! which didn't record the dice rolled or the seed they were rolled from.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use game_core::codec;
+use protocol::SpectatedTurn;
+use rules::{IllegalMove, Ruleset};
+
+/// The extension of the files the replays are written to.
+pub const REPLAY_EXTENSION: &str = "stbreplay";
+/// The extension of the game logs written before the replays were versioned, which can still be read.
+pub const LEGACY_EXTENSION: &str = "yml";
+/// The version of the replays that are written, which is increased whenever the format changes.
+/// Replays of a newer version than this can't be read.
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Couldn't access the replay: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The replay is malformed: {0}")]
+    Malformed(#[from] codec::Error),
+    #[error("The replay is version {0}, but only up to version {REPLAY_VERSION} can be read")]
+    UnsupportedVersion(u32),
+    #[error("Turn {turn} of the replay is invalid: {reason}")]
+    InvalidTurn { turn: usize, reason: &'static str },
+    #[error("Turn {turn} of the replay is an illegal move: {reason}")]
+    IllegalMove { turn: usize, reason: IllegalMove },
+    #[error("The replay has a winning score of {recorded}, but the game ended with {expected}")]
+    WrongWinningScore { recorded: u8, expected: u8 },
+}
+
+/// A turn taken by a player, along with the dice they rolled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayTurn {
+    /// The index of the player that took the turn.
+    pub player: u8,
+    /// The sum of the rolled dice.
+    pub roll: u8,
+    /// The value of each rolled dice, in the order they were rolled.
+    /// This is empty if the dice weren't recorded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dice: Vec<u8>,
+    /// The board of the player before the turn.
+    pub board: u16,
+    /// The board of the player after the turn, or None if they couldn't move & are out.
+    pub moved_board: Option<u16>,
+}
+
+impl ReplayTurn {
+    /// Creates a turn from the dice that were rolled.
+    pub fn new(player: u8, dice: &[u8], board: u16, moved_board: Option<u16>) -> ReplayTurn {
+        ReplayTurn {
+            player,
+            roll: dice.iter().sum(),
+            dice: dice.to_vec(),
+            board,
+            moved_board,
+        }
+    }
+}
+
+impl From<SpectatedTurn> for ReplayTurn {
+    fn from(turn: SpectatedTurn) -> Self {
+        ReplayTurn {
+            player: turn.player,
+            roll: turn.roll,
+            dice: Vec::new(),
+            board: turn.board,
+            moved_board: turn.moved_board,
+        }
+    }
+}
+
+/// A game that was played, which can be replayed turn by turn.
+/// Every player starts with every piece alive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    /// The version of the format the replay was written with, which is 0 for unversioned game logs.
+    #[serde(default)]
+    pub version: u32,
+    /// The names of the players, in the order they took their turns.
+    pub players: Vec<String>,
+    /// The rules the game was played with.
+    #[serde(default = "unversioned_ruleset")]
+    pub variant: Ruleset,
+    /// The seed the dice were rolled from, in the order they were rolled, if it's known.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Every turn taken in the game, from the first to the last.
+    pub turns: Vec<ReplayTurn>,
+    /// The lowest score at the end of the game, or None if the game didn't finish.
+    pub winning_score: Option<u8>,
+}
+
+impl Replay {
+    /// Creates a replay of a game between the given players, which hasn't had any turns taken yet.
+    pub fn new(players: Vec<String>, variant: Ruleset, seed: Option<u64>) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            players,
+            variant,
+            seed,
+            turns: Vec::new(),
+            winning_score: None,
+        }
+    }
+
+    /// Reads the replay from the given file, which can also be an unversioned game log.
+    pub fn read(path: &Path) -> Result<Replay, ReplayError> {
+        let reader = BufReader::new(File::open(path)?);
+        let replay: Replay = codec::from_reader(reader)?;
+
+        if replay.version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Writes the replay to the given file, replacing the file if it exists.
+    pub fn write(&self, path: &Path) -> Result<(), ReplayError> {
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(codec::to_writer(writer, self)?)
+    }
+
+    /// Returns the board & whether they're out, for every player after the given amount of turns.
+    /// Turns by players that aren't in the game are skipped.
+    pub fn boards_after(&self, turns: usize) -> Vec<(u16, bool)> {
+        let mut boards = vec![(self.variant.full_board(), false); self.players.len()];
+
+        for turn in self.turns.iter().take(turns) {
+            let Some((board, out)) = boards.get_mut(turn.player as usize) else {
+                continue;
+            };
+            match turn.moved_board {
+                Some(moved_board) => *board = moved_board,
+                None => *out = true,
+            }
+        }
+
+        boards
+    }
+
+    /// Checks that every turn follows from the last & was a legal move, so the replay can settle disputes.
+    /// If the seed is known, then the dice of each turn are checked against the dice the seed rolls.
+    pub fn verify(&self) -> Result<(), ReplayError> {
+        let mut boards = vec![self.variant.full_board(); self.players.len()];
+        let mut out = vec![false; self.players.len()];
+        let mut over = false;
+        let mut rng = self.seed.map(Rng::with_seed);
+
+        for (index, turn) in self.turns.iter().enumerate() {
+            let invalid = |reason| ReplayError::InvalidTurn {
+                turn: index + 1,
+                reason,
+            };
+
+            let player = turn.player as usize;
+            if player >= self.players.len() {
+                return Err(invalid("the player isn't in the game"));
+            }
+            if out[player] {
+                return Err(invalid("the player was already out"));
+            }
+            if over {
+                return Err(invalid("the game was already over"));
+            }
+            if turn.board != boards[player] {
+                return Err(invalid("the board doesn't match the player's last move"));
+            }
+
+            if !turn.dice.is_empty() {
+                if turn.dice.iter().sum::<u8>() != turn.roll {
+                    return Err(invalid("the roll isn't the sum of the dice"));
+                }
+                self.variant
+                    .check_dice(turn.board, &turn.dice)
+                    .map_err(invalid)?;
+
+                if let Some(rng) = &mut rng {
+                    let rolled = turn.dice.iter().all(|dice| rng.u8(1..=6) == *dice);
+                    if !rolled {
+                        return Err(invalid("the dice weren't rolled from the seed"));
+                    }
+                }
+            }
+
+            match turn.moved_board {
+                Some(moved_board) => {
+                    self.variant
+                        .is_legal_move(turn.board, turn.roll, moved_board)
+                        .map_err(|reason| ReplayError::IllegalMove {
+                            turn: index + 1,
+                            reason,
+                        })?;
+                    boards[player] = moved_board;
+                    over = self.variant.ends_game(moved_board);
+                }
+                // A player can give up or disconnect even if they had a move.
+                None => out[player] = true,
+            }
+        }
+
+        let Some(recorded) = self.winning_score else {
+            return Ok(());
+        };
+        let expected = self.variant.winning_score(boards);
+
+        match recorded == expected {
+            true => Ok(()),
+            false => Err(ReplayError::WrongWinningScore { recorded, expected }),
+        }
+    }
+}
+
+/// The rules the server played with before they could be changed, which every unversioned game log was written by.
+fn unversioned_ruleset() -> Ruleset {
+    Ruleset::ONE_DIE
+}
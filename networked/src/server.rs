@@ -10,8 +10,8 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use crate::server::ServerState::ListeningForClients;
-use crate::states::ClientMessages;
-use crate::states::ServerMessages;
+use protocol::ClientMessages;
+use protocol::ServerMessages;
 
 #[derive(thiserror::Error, Debug)]
 enum ServerError {
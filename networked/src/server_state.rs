@@ -1,182 +1,1401 @@
-use core::panic;
 use std::{
-    any::Any,
+    convert::Infallible,
+    io,
     net::{SocketAddr, TcpListener},
-    sync::mpsc::{SendError, TryRecvError},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use networked::Channels;
-type Channel = Channels<ClientMessages, ServerMessages>;
+use crate::event_log::{EventLog, ServerEvent, EVENT_LOG_VERSION};
+use crate::replay::{Replay, ReplayTurn, REPLAY_EXTENSION};
+use crate::{ChannelError, Channels};
+use fastrand::Rng;
+use game_core::rng;
+use rules::Ruleset;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, info_span, warn, Span};
+pub(crate) type Channel = Channels<ClientMessages, ServerMessages>;
 
-use crate::states::{ClientMessages, ServerMessages};
+use protocol::{
+    ClientMessages, ClientMove, ClientToMove, DrawingPlayerAmount, RollRequest, ServerMessages,
+    SpectatedTurn, SpectatorEvent, WinningScore, PROTOCOL_VERSION,
+};
+
+/// How long the server waits between checking for new connections & messages.
+const POLL_DELAY: Duration = Duration::from_millis(10);
+
+/// Recommends the move to make from a board with a roll, such as the best move of a policy.
+/// This is what the players are hinted, either with each query for their move or when they request a hint.
+pub type Advisor = Arc<dyn Fn(u16, u8) -> Option<Advice> + Send + Sync>;
 
-#[derive(Debug, thiserror::Error)]
-enum ServerError {
-    #[error("Server closed channels unexpectedly")]
-    ChannelsClosed(#[from] SendError<Box<dyn Any>>),
+/// The move an [`Advisor`] recommends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Advice {
+    /// The board the move leaves.
+    pub best_move: u16,
+    /// The chance of winning with the move, between 0 & 1, if it's known.
+    pub win_rate: Option<f32>,
 }
 
-pub fn start(socket_addr: SocketAddr) -> ! {
-    let mut server = Server::new(socket_addr);
-    loop {
-        server.listen();
-        server.register_client();
-        server.clients_ready()
+/// The settings of a lobby, which decide the rules & how the players can be helped with their moves.
+#[derive(Clone)]
+pub struct Lobby {
+    /// The rules the games are played with.
+    /// Every client starts from the standard board, so the rules have to keep the standard amount of tiles.
+    /// The results are sent after every game, so the scores can't be added up over the holes of a golf round.
+    pub ruleset: Ruleset,
+    /// Recommends the moves the players are hinted, without which no hints are given.
+    pub advisor: Option<Advisor>,
+    /// Whether the players are hinted the move to make with each query for their move.
+    pub assisted: bool,
+    /// The amount of hints each player can request in each game.
+    /// Requesting a hint in an assisted lobby doesn't use any up.
+    pub hint_budget: u8,
+    /// The settings that can be changed while the server is running, such as when its config is reloaded.
+    pub live: LiveSettings,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Lobby {
+            // The rules the server has always played with.
+            ruleset: Ruleset::ONE_DIE,
+            advisor: None,
+            assisted: false,
+            hint_budget: 0,
+            live: LiveSettings::default(),
+        }
+    }
+}
+
+/// The settings of a lobby that can be changed while the server is running, without dropping the connected clients.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LiveSettings {
+    /// How long a player has to take their roll or move before they're out of the game, if there's a limit.
+    pub turn_timeout: Option<Duration>,
+    /// The most players that can join, if there's a limit.
+    /// Lowering it doesn't remove the players that already joined.
+    pub max_players: Option<u8>,
+    /// The names & MAC addresses of the clients that can't join, ignoring their case.
+    /// Banning a player that already joined only stops them from joining again.
+    pub banned: Vec<String>,
+}
+
+impl LiveSettings {
+    /// Returns true if the client with the given name & MAC address is banned.
+    fn is_banned(&self, name: &str, mac_address: &str) -> bool {
+        self.banned.iter().any(|banned| {
+            banned.eq_ignore_ascii_case(name) || banned.eq_ignore_ascii_case(mac_address)
+        })
+    }
+}
+
+impl Lobby {
+    /// Returns true if the players are hinted the move to make with each query for their move.
+    pub fn is_assisted(&self) -> bool {
+        self.assisted && self.advisor.is_some()
+    }
+
+    /// Returns the move the advisor recommends for the rolled dice, if there is an advisor.
+    fn advise(&self, to_move: ClientToMove) -> Option<Advice> {
+        let advisor = self.advisor.as_ref()?;
+        match to_move {
+            ClientToMove::OneDice(board, dice) => advisor(board, dice),
+            ClientToMove::TwoDice {
+                board,
+                dice_1,
+                dice_2,
+            } => advisor(board, dice_1 + dice_2),
+        }
     }
 }
 
-struct Server<S> {
+/// Runs the server on the current thread, accepting connections from the listener.
+/// The listener can be bound by the caller or passed to the process already bound, such as by systemd.
+/// If a folder is given, then the replay of each game is written to a new file within it.
+/// The lobby decides how the players are hinted the moves to make.
+/// Everything the server reads & does is recorded to the event log, so it can be replayed.
+/// The reload is checked between updates, & any settings it returns replace the live settings of the lobby.
+/// Only returns if the listener couldn't be set up to accept connections.
+pub fn start(
     listener: TcpListener,
-    clients: Vec<Channel>,
+    game_logs: Option<PathBuf>,
+    lobby: Lobby,
+    event_log: EventLog,
+    mut reload: impl FnMut() -> Option<LiveSettings>,
+) -> io::Result<Infallible> {
+    let server = Server::new(Listener::from_tcp(listener)?)
+        .with_game_logs(game_logs)
+        .with_lobby(lobby)
+        .with_event_log(event_log);
+
+    let mut server = Running::Listening(Box::new(server));
+    loop {
+        if let Some(live) = reload() {
+            server.update_lobby(|lobby| lobby.live = live);
+        }
+
+        let progressed;
+        (server, progressed) = server.update(false);
+
+        // Stops the server from using a whole core while waiting.
+        if progressed {
+            server.note_diagnostics();
+        } else {
+            thread::sleep(POLL_DELAY);
+        }
+    }
+}
+
+/// Starts a server on another thread, which runs until the returned [`Host`] is dropped.
+/// The lobby decides how the players are hinted the moves to make, which the host can change.
+/// Returns an error if the server couldn't bind to the given address.
+pub fn host(socket_addr: SocketAddr, lobby: Lobby) -> io::Result<Host> {
+    let listener = Listener::bind(socket_addr)?;
+    let address = listener.local_addr()?;
+    let server = Server::new(listener).with_lobby(lobby);
+    let status = Arc::clone(&server.status);
+    let (commands, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut server = Running::Listening(Box::new(server));
+        loop {
+            let start_game = match receiver.try_recv() {
+                Ok(HostCommand::StartGame) => true,
+                Ok(HostCommand::SetAssisted(assisted)) => {
+                    server.update_lobby(|lobby| lobby.assisted = assisted);
+                    false
+                }
+                Ok(HostCommand::SetHintBudget(hint_budget)) => {
+                    server.update_lobby(|lobby| lobby.hint_budget = hint_budget);
+                    false
+                }
+                Err(TryRecvError::Empty) => false,
+                // The host was dropped, so the server is no longer wanted.
+                Err(TryRecvError::Disconnected) => return,
+            };
+
+            let progressed;
+            (server, progressed) = server.update(start_game);
+
+            // Stops the server from using a whole core while waiting.
+            if progressed {
+                server.note_diagnostics();
+            } else {
+                thread::sleep(POLL_DELAY);
+            }
+        }
+    });
+
+    Ok(Host {
+        address,
+        commands,
+        status,
+    })
+}
+
+/// A server running on another thread, which is stopped when this is dropped.
+/// A game that is being played is finished before the server stops.
+pub struct Host {
+    /// The address the server is listening on.
+    address: SocketAddr,
+    commands: Sender<HostCommand>,
+    status: Arc<Mutex<HostStatus>>,
+}
+
+impl Host {
+    /// Returns the address the server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the players connected to the server & whether a game is being played.
+    pub fn status(&self) -> HostStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    /// Starts a game with every connected player, even if they aren't ready.
+    /// Nothing happens if a game is already being played or no players are connected.
+    pub fn start_game(&self) {
+        // The server only stops once the host is dropped, so this can't fail.
+        let _ = self.commands.send(HostCommand::StartGame);
+    }
+
+    /// Sets whether the players are hinted the move to make, starting from their next query.
+    /// Nothing happens if the server wasn't given an advisor.
+    pub fn set_assisted(&self, assisted: bool) {
+        let _ = self.commands.send(HostCommand::SetAssisted(assisted));
+    }
+
+    /// Sets the amount of hints each player can request, starting from the next game.
+    /// Nothing happens if the server wasn't given an advisor.
+    pub fn set_hint_budget(&self, hint_budget: u8) {
+        let _ = self.commands.send(HostCommand::SetHintBudget(hint_budget));
+    }
+}
+
+/// The commands the [`Host`] sends to the server thread.
+enum HostCommand {
+    StartGame,
+    SetAssisted(bool),
+    SetHintBudget(u8),
+}
+
+/// What the server is currently doing, as seen by the [`Host`].
+#[derive(Debug, Clone, Default)]
+pub struct HostStatus {
+    /// The players that joined, along with whether they are ready.
+    pub players: Vec<(String, bool)>,
+    /// The amount of clients watching the games.
+    pub spectators: usize,
+    /// Whether a game is being played.
+    pub playing: bool,
+    /// Whether the server has an advisor, without which the players can't be hinted any moves.
+    pub advised: bool,
+    /// Whether the players are hinted the move to make.
+    pub assisted: bool,
+    /// The amount of hints each player can request in each game.
+    pub hint_budget: u8,
+}
+
+/// Where the server accepts new connections from.
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    /// Connections made within the same process, such as by the harness the tests play games with.
+    Memory(Receiver<Channel>),
+}
+
+impl Listener {
+    /// Binds to the given address, accepting the connections made to it.
+    fn bind(socket_address: SocketAddr) -> io::Result<Listener> {
+        Listener::from_tcp(TcpListener::bind(socket_address)?)
+    }
+
+    /// Accepts the connections made to a listener that's already bound.
+    fn from_tcp(listener: TcpListener) -> io::Result<Listener> {
+        // Connections are checked for between handling messages, so accepting them can't block.
+        listener.set_nonblocking(true)?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    /// Returns the address the listener is bound to.
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            Listener::Tcp(listener) => listener.local_addr(),
+            Listener::Memory(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "In-memory connections don't have an address.",
+            )),
+        }
+    }
+
+    /// Returns the next connection, if a new one was made.
+    fn accept(&self) -> Option<Channel> {
+        match self {
+            Listener::Tcp(listener) => match listener.accept() {
+                // The client is dropped if its connection can't be read from & written to.
+                Ok((stream, addr)) => match crate::initialize_channels(stream) {
+                    Ok(channels) => Some(channels),
+                    Err(err) => {
+                        warn!("Couldn't set up the connection to {addr}: {err}");
+                        None
+                    }
+                },
+
+                // If it's `WouldBlock` then there is no connection to handle.
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => None,
+
+                Err(err) => {
+                    warn!("Listening for client connection failed: {err}");
+                    None
+                }
+            },
+            Listener::Memory(connections) => connections.try_recv().ok(),
+        }
+    }
+}
+
+/// A server in either of its states, which moves between them as it's updated.
+pub(crate) enum Running {
+    /// The servers are boxed to keep them cheap to move between states.
+    Listening(Box<Server<Listening>>),
+    Playing(Box<Server<Playing>>),
+}
+
+impl Running {
+    /// Handles new connections & messages, playing a game once every client is ready.
+    /// If the game is forced to start, then it is played even if some clients aren't ready.
+    /// Returns the server in its new state, along with whether anything was handled.
+    pub(crate) fn update(self, force_start: bool) -> (Running, bool) {
+        let (mut running, progressed) = self.handle(force_start);
+        // A replayed server reads the logged messages in the same updates, so where each update ends is logged too.
+        if progressed {
+            running.event_log_mut().record(ServerEvent::Updated);
+        }
+        (running, progressed)
+    }
+
+    /// Handles the new connections & messages of a single update.
+    fn handle(self, force_start: bool) -> (Running, bool) {
+        match self {
+            Running::Listening(mut server) => {
+                let progressed = server.handle_clients();
+
+                let ready = server.clients_ready();
+                let forced = force_start && !server.clients.is_empty();
+                server.publish_status(false);
+
+                if !ready && !forced {
+                    return (Running::Listening(server), progressed);
+                }
+                if !ready {
+                    server.events.record(ServerEvent::ForcedStart);
+                }
+
+                // Plays the game, then goes back to waiting for clients once it's over.
+                server.publish_status(true);
+                let mut playing = Server::<Playing>::from(*server);
+                playing.start();
+                (Running::Playing(Box::new(playing)), true)
+            }
+            Running::Playing(mut server) => {
+                let mut progressed = false;
+                while server.step() {
+                    progressed = true;
+                }
+
+                if !server.is_over() {
+                    return (Running::Playing(server), progressed);
+                }
+
+                let server = Server::<Listening>::from(*server);
+                server.publish_status(false);
+                (Running::Listening(Box::new(server)), true)
+            }
+        }
+    }
+
+    /// Changes the settings of the lobby, sharing them with the [`Host`] if there is one.
+    pub(crate) fn update_lobby(&mut self, update: impl FnOnce(&mut Lobby)) {
+        let (lobby, status) = match self {
+            Running::Listening(server) => (&mut server.lobby, &server.status),
+            Running::Playing(server) => (&mut server.lobby, &server.status),
+        };
+        update(lobby);
+        publish_lobby(lobby, status);
+
+        let changed = ServerEvent::LobbyChanged {
+            assisted: lobby.assisted,
+            hint_budget: lobby.hint_budget,
+            live: lobby.live.clone(),
+        };
+        self.event_log_mut().record(changed);
+    }
+
+    /// Puts the player of the connection out the next time they're found not to have answered.
+    /// This is how a replayed server repeats the logged timeouts, as it doesn't time out the players itself.
+    pub(crate) fn time_out(&mut self, connection: u32) {
+        let clock = match self {
+            Running::Listening(server) => &mut server.clock,
+            Running::Playing(server) => &mut server.clock,
+        };
+        if let TurnClock::Replayed(timed_out) = clock {
+            timed_out.push(connection);
+        }
+    }
+
+    /// Returns where the server records its events.
+    pub(crate) fn event_log(&self) -> &EventLog {
+        match self {
+            Running::Listening(server) => &server.events,
+            Running::Playing(server) => &server.events,
+        }
+    }
+
+    fn event_log_mut(&mut self) -> &mut EventLog {
+        match self {
+            Running::Listening(server) => &mut server.events,
+            Running::Playing(server) => &mut server.events,
+        }
+    }
+
+    /// Notes what the server is doing, which is written to the crash diagnostics if the server panics.
+    pub(crate) fn note_diagnostics(&self) {
+        let (state, clients, spectators) = match self {
+            Running::Listening(server) => ("listening", &server.clients, server.spectators.len()),
+            Running::Playing(server) => ("playing", &server.clients, server.spectators.len()),
+        };
+        let names: Vec<&str> = clients.iter().map(|player| player.name.as_str()).collect();
+
+        logging::crash::note("server state", state);
+        logging::crash::note(
+            "connected clients",
+            format!("{names:?} & {spectators} spectators"),
+        );
+        match self {
+            Running::Listening(_) => logging::crash::forget("boards"),
+            Running::Playing(server) => {
+                logging::crash::note("boards", format!("{:?}", server.state.boards))
+            }
+        }
+    }
+
+    /// Returns what the server is doing, which is shared with the [`Host`] if there is one.
+    #[cfg(feature = "harness")]
+    pub(crate) fn status(&self) -> HostStatus {
+        let status = match self {
+            Running::Listening(server) => &server.status,
+            Running::Playing(server) => &server.status,
+        };
+        status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) struct Server<S> {
+    listener: Listener,
+    /// Picks the seed the dice of each game are rolled from.
+    rng: Rng,
+    clients: Vec<Player>,
+    /// The clients watching the games without playing in them.
+    spectators: Vec<Channel>,
+    /// What the server is doing, which is shared with the [`Host`] if there is one.
+    status: Arc<Mutex<HostStatus>>,
+    /// The folder the replay of each game is written to, if the games are logged.
+    game_logs: Option<PathBuf>,
+    /// How the players are hinted the moves to make.
+    lobby: Lobby,
+    /// Records everything the server reads & does, so it can be replayed.
+    events: EventLog,
+    /// The id the next connection is logged with.
+    next_connection: u32,
+    /// Decides when a player has taken too long to take their turn.
+    clock: TurnClock,
     state: S,
 }
 
-struct Listening {
+/// How the server decides that a player has taken too long to take their turn.
+pub(crate) enum TurnClock {
+    /// The player is out once the lobby's turn timeout has passed since they were asked.
+    System,
+    /// The players of the connections are out the next time they're found not to have answered,
+    /// which is how a replayed server repeats the logged timeouts.
+    Replayed(Vec<u32>),
+}
+
+/// A client that has been accepted into the game.
+struct Player {
+    /// The id the player's connection is logged with.
+    connection: u32,
+    channel: Channel,
+    name: String,
+    /// Whether the player is ready for the game to start.
+    ready: bool,
+}
+
+/// How a client that requested to join is handled.
+enum Registration {
+    /// Joins the games with the given name.
+    Player(String),
+    /// Watches the games without playing.
+    Spectator,
+    /// Is refused, as it sent a bad packet.
+    Denied,
+}
+
+/// A connection waiting to join, which has to tell the server its version of the protocol before opting in.
+pub(crate) struct Pending {
+    /// The id the connection is logged with.
+    connection: u32,
+    channel: Channel,
+    /// The version of the protocol the client speaks, once it has said.
+    version: Option<u32>,
+}
+
+pub(crate) struct Listening {
     previous_connected: u32,
     previous_ready: u32,
-    to_accept: Vec<Channel>,
-    accepted: Vec<(Channel, bool)>,
+    /// The connections waiting to join.
+    to_accept: Vec<Pending>,
+}
+
+pub(crate) struct Playing {
+    /// The board of each player, in the same order as the clients.
+    boards: Vec<u16>,
+    /// Whether each player can still make moves, in the same order as the clients.
+    alive: Vec<bool>,
+    /// The connections waiting to join, which are handled once the game ends.
+    to_accept: Vec<Pending>,
+    /// Rolls the dice of this game, from the seed recorded in the replay.
+    dice: Rng,
+    /// Every turn taken in the game so far.
+    replay: Replay,
+    /// The amount of hints each player can still request, in the same order as the clients.
+    hints_left: Vec<u8>,
+    /// The index of the player whose turn it is, along with what they were asked for.
+    /// This is None once the game is over.
+    turn: Option<(usize, Query)>,
+    /// When the player whose turn it is was last asked for their roll or move.
+    asked_at: Instant,
+    /// The span the events of the game are recorded in.
+    span: Span,
+}
+
+/// What the player whose turn it is was asked for.
+#[derive(Debug, Clone, Copy)]
+enum Query {
+    Roll,
+    Move(ClientToMove),
 }
 
-struct Playing {}
+/// Shares the settings of the lobby with the [`Host`].
+fn publish_lobby(lobby: &Lobby, status: &Mutex<HostStatus>) {
+    // A poisoned status only means the host can't see it, which doesn't affect the games.
+    if let Ok(mut status) = status.lock() {
+        status.advised = lobby.advisor.is_some();
+        status.assisted = lobby.is_assisted();
+        status.hint_budget = lobby.hint_budget;
+    }
+}
+
+/// Reads the next message from the connection with the given id, recording what was read.
+fn receive(
+    events: &mut EventLog,
+    connection: u32,
+    channel: &Channel,
+) -> Result<Result<ClientMessages, ChannelError>, TryRecvError> {
+    let received = channel.reading.try_recv();
+    let event = match &received {
+        Ok(Ok(message)) => ServerEvent::Received {
+            connection,
+            message: message.clone(),
+        },
+        Ok(Err(ChannelError::BadPacket(packet))) => ServerEvent::BadPacket {
+            connection,
+            packet: packet.clone(),
+        },
+        Ok(Err(ChannelError::ReadError(_))) => ServerEvent::ReadError { connection },
+        Err(TryRecvError::Disconnected) => ServerEvent::Disconnected { connection },
+        Err(TryRecvError::Empty) => return received,
+    };
+    events.record(event);
+    received
+}
 
 impl<S> Server<S> {
     fn write_to_all(&self, server_message: ServerMessages) {
-        for channel in &self.clients {
-            let send = channel.writing.send(server_message.clone());
-            // if send.is_err() {
-            //     eprintln!("Failed to send message to a client");
-            //     todo!("Drop bad client")
-            // };
-            match send {
-                Ok(_) => {}
-                Err(e) => {
-                    println!("{e}");
-                    panic!("AAAAAAAAA")
-                }
+        for player in &self.clients {
+            // Disconnected clients are removed when they are next read from.
+            if let Err(e) = player.channel.writing.send(server_message) {
+                warn!("Failed to send message to {}: {e}", player.name);
             }
         }
-        // Ok(())
+    }
+
+    /// Sends the event to every player & spectator, dropping the spectators that have disconnected.
+    fn broadcast(&mut self, event: SpectatorEvent) {
+        self.write_to_all(ServerMessages::Spectate(event));
+        self.spectators.retain(|spectator| {
+            let sent = spectator
+                .writing
+                .send(ServerMessages::Spectate(event))
+                .is_ok();
+            if !sent {
+                info!("Spectator disconnected.");
+            }
+            sent
+        });
     }
 }
 
 impl Server<Listening> {
-    fn new(socket_address: SocketAddr) -> Self {
-        let listener = TcpListener::bind(socket_address)
-            .expect("Unable to bind to given address. Is it already in use?");
-
+    pub(crate) fn new(listener: Listener) -> Self {
         Server {
             listener,
+            rng: rng::stream("server"),
             clients: Vec::new(),
+            spectators: Vec::new(),
+            status: Arc::default(),
+            game_logs: None,
+            lobby: Lobby::default(),
+            events: EventLog::Off,
+            next_connection: 0,
+            clock: TurnClock::System,
             state: Listening {
                 previous_connected: 0,
                 previous_ready: 0,
                 to_accept: Vec::new(),
-                accepted: Vec::new(),
             },
         }
     }
 
-    fn listen(&mut self) {
-        self.listener
-            .set_nonblocking(true)
-            .expect("Cannot set non-blocking.");
+    /// Rolls the dice of the games from the given seed, so the games can be replayed.
+    pub(crate) fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::with_seed(seed);
+        self
+    }
 
-        let client_channels = match self.listener.accept() {
-            Ok((stream, _addr)) => networked::initialize_channels(stream),
+    /// Writes the replay of each game to a new file within the given folder, if one is given.
+    pub(crate) fn with_game_logs(mut self, game_logs: Option<PathBuf>) -> Self {
+        self.game_logs = game_logs;
+        self
+    }
 
-            // If it's `WouldBlock` then there is no connection to handle.
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return,
+    /// Hints the players the moves to make as the lobby allows.
+    pub(crate) fn with_lobby(mut self, lobby: Lobby) -> Self {
+        self.lobby = lobby;
+        publish_lobby(&self.lobby, &self.status);
+        self
+    }
 
-            Err(err) => {
-                eprintln!("Listening for client connection failed: {err}");
-                return;
-            }
+    /// Records everything the server reads & does to the event log, starting with the settings it has now.
+    /// This should be given last, so the settings are logged as the games are played with them.
+    pub(crate) fn with_event_log(mut self, events: EventLog) -> Self {
+        self.events = events;
+        self.events.record(ServerEvent::Started {
+            version: EVENT_LOG_VERSION,
+            seed: self.rng.get_seed(),
+            ruleset: self.lobby.ruleset,
+            advised: self.lobby.advisor.is_some(),
+            assisted: self.lobby.assisted,
+            hint_budget: self.lobby.hint_budget,
+            live: self.lobby.live.clone(),
+        });
+        self
+    }
+
+    /// Decides when the players have taken too long to take their turn with the given clock.
+    pub(crate) fn with_clock(mut self, clock: TurnClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Handles new connections & messages from the clients waiting for a game.
+    /// Returns true if anything was handled.
+    fn handle_clients(&mut self) -> bool {
+        let listened = self.listen();
+        let registered = self.register_client();
+        let readied = self.update_ready();
+        listened || registered || readied
+    }
+
+    /// Shares the connected players with the [`Host`].
+    fn publish_status(&self, playing: bool) {
+        // A poisoned status only means the host can't see it, which doesn't affect the games.
+        if let Ok(mut status) = self.status.lock() {
+            status.players = self
+                .clients
+                .iter()
+                .map(|player| (player.name.clone(), player.ready))
+                .collect();
+            status.spectators = self.spectators.len();
+            status.playing = playing;
+        }
+    }
+
+    /// Accepts the next new connection, returning true if there was one.
+    fn listen(&mut self) -> bool {
+        let Some(client_channels) = self.listener.accept() else {
+            return false;
         };
 
-        self.state.to_accept.push(client_channels);
+        let connection = self.next_connection;
+        self.next_connection += 1;
+        self.events.record(ServerEvent::Connected { connection });
+        self.state.to_accept.push(Pending {
+            connection,
+            channel: client_channels,
+            version: None,
+        });
+        true
     }
 
-    fn register_client(&mut self) {
+    /// Registers the connections that have requested to join, returning true if any were handled.
+    fn register_client(&mut self) -> bool {
         // Stores the indices of the clients to drop.
         let mut to_remove = Vec::new();
-        // Stores the indices of the clients to add.
+        // Stores the indices of the clients to add, along with their names.
         let mut to_add = Vec::new();
+        // Stores the indices of the clients to add as spectators.
+        let mut to_spectate = Vec::new();
+        // Whether any client said which version of the protocol it speaks.
+        let mut versioned = false;
 
-        for (index, client) in self.state.to_accept.iter().enumerate() {
-            let received = match client.reading.try_recv() {
+        for (index, pending) in self.state.to_accept.iter_mut().enumerate() {
+            let received = match receive(&mut self.events, pending.connection, &pending.channel) {
                 Ok(val) => val,
                 Err(e) => match e {
                     TryRecvError::Empty => continue,
-                    TryRecvError::Disconnected => panic!("AAAAAAAAA"),
+                    TryRecvError::Disconnected => {
+                        to_remove.push(index);
+                        continue;
+                    }
                 },
             };
 
-            // Only an OptIn message is accepted currently.
+            // The client has to say which version of the protocol it speaks, then opt in.
             match received {
+                Ok(ClientMessages::Version(version)) if pending.version.is_none() => {
+                    pending.version = Some(version);
+                    versioned = true;
+                    if version == PROTOCOL_VERSION {
+                        let _ = pending
+                            .channel
+                            .writing
+                            .send(ServerMessages::Version(PROTOCOL_VERSION));
+                        continue;
+                    }
+
+                    info!("Refused a client speaking version {version} of the protocol, instead of version {PROTOCOL_VERSION}.");
+                    let _ = pending.channel.writing.send(ServerMessages::Error(
+                        protocol::ServerError::IncompatibleVersion(PROTOCOL_VERSION),
+                    ));
+                    to_remove.push(index);
+                    continue;
+                }
+                Ok(ClientMessages::OptInForPlaying { .. } | ClientMessages::OptInForSpectating)
+                    if pending.version.is_none() =>
+                {
+                    info!("Refused a client that opted in without saying which version of the protocol it speaks.");
+                    let _ = pending.channel.writing.send(ServerMessages::Error(
+                        protocol::ServerError::IncompatibleVersion(PROTOCOL_VERSION),
+                    ));
+                    to_remove.push(index);
+                    continue;
+                }
                 Ok(val) => {
-                    if let ClientMessages::OptInForPlaying(mac_address) = val {
-                        to_add.push((index, mac_address));
+                    if let ClientMessages::OptInForPlaying { mac_address, name } = val {
+                        let live = &self.lobby.live;
+                        if live.is_banned(&name, &mac_address.to_string()) {
+                            info!("Refused banned client: {name} ({mac_address})");
+                            to_remove.push(index);
+                            continue;
+                        }
+                        let players = self.clients.len() + to_add.len();
+                        if live.max_players.is_some_and(|max| players >= max as usize) {
+                            info!("Refused client {name} ({mac_address}), as the server is full.");
+                            to_remove.push(index);
+                            continue;
+                        }
+
+                        info!("Added client: {name} ({mac_address})");
+                        to_add.push((index, name));
+                        continue;
+                    }
+                    if val == ClientMessages::OptInForSpectating {
+                        info!("Added spectator.");
+                        to_spectate.push(index);
                         continue;
                     }
 
-                    eprintln!(
+                    warn!(
                         "A client sent a bad packet, dropping client. Packet: {:?}",
                         val
                     );
                     to_remove.push(index);
                     continue;
                 }
+                // Clients older than the version exchange can't send a packet this server understands.
+                Err(e) if pending.version.is_none() => {
+                    warn!("A client sent a bad packet before saying its version of the protocol, so it likely speaks a version older than {PROTOCOL_VERSION}, dropping client: {e}");
+                    to_remove.push(index);
+                    continue;
+                }
                 Err(e) => {
-                    eprintln!("A client sent a bad packet, dropping client: {e}");
+                    warn!("A client sent a bad packet, dropping client: {e}");
                     to_remove.push(index);
                     continue;
                 }
             };
         }
 
-        // Registers valid clients
-        for to_add in to_add {
-            let client_channels = self.state.to_accept.swap_remove(to_add.0);
-            client_channels
-                .writing
-                .send(ServerMessages::OptInAccept)
-                .expect("Couldn't accept client");
+        let joined = !to_add.is_empty();
+        let handled_any = joined || versioned || !to_spectate.is_empty() || !to_remove.is_empty();
+
+        // Removes the clients from the highest index to the lowest, so the lower indices stay valid.
+        let mut handled: Vec<(usize, Registration)> = to_add
+            .into_iter()
+            .map(|(index, name)| (index, Registration::Player(name)))
+            .chain(
+                to_spectate
+                    .into_iter()
+                    .map(|index| (index, Registration::Spectator)),
+            )
+            .chain(
+                to_remove
+                    .into_iter()
+                    .map(|index| (index, Registration::Denied)),
+            )
+            .collect();
+        handled.sort_unstable_by_key(|(index, _)| std::cmp::Reverse(*index));
+
+        for (index, registration) in handled {
+            let Pending {
+                connection,
+                channel: client_channels,
+                ..
+            } = self.state.to_accept.remove(index);
+
+            match registration {
+                // Registers valid clients
+                Registration::Player(name) => {
+                    if client_channels
+                        .writing
+                        .send(ServerMessages::OptInAccept)
+                        .is_err()
+                    {
+                        warn!("Couldn't accept client {name}.");
+                        continue;
+                    }
+
+                    self.clients.push(Player {
+                        connection,
+                        channel: client_channels,
+                        name,
+                        ready: false,
+                    });
+                }
+                // Spectators are only sent the events of the games.
+                Registration::Spectator => {
+                    if client_channels
+                        .writing
+                        .send(ServerMessages::OptInAccept)
+                        .is_ok()
+                    {
+                        self.spectators.push(client_channels);
+                    }
+                }
+                // Drops the clients that sent bad packets.
+                // If the client already disconnected, then there is no one to inform.
+                Registration::Denied => {
+                    let _ = client_channels.writing.send(ServerMessages::OptInDeny);
+                }
+            }
+        }
 
-            self.clients.push(client_channels);
-            println!("Added client: {}", to_add.1)
+        // Ensures the new clients are informed of how many players are ready.
+        if joined {
+            self.state.previous_ready = u32::MAX;
         }
+        handled_any
+    }
 
-        // Drops the clients that sent bad packets
-        for index_to_remove in to_remove {
-            let removed_client = self.state.to_accept.remove(index_to_remove);
-            removed_client
-                .writing
-                .send(ServerMessages::OptInDeny)
-                .expect("Couldn't gracefully disconnect from client.");
+    /// Reads whether each client is ready, dropping the clients that have disconnected.
+    /// Returns true if any messages or disconnects were handled.
+    fn update_ready(&mut self) -> bool {
+        let mut disconnected = Vec::new();
+        let mut received = false;
+
+        for (index, player) in self.clients.iter_mut().enumerate() {
+            loop {
+                let message = receive(&mut self.events, player.connection, &player.channel);
+                received |= message.is_ok();
+                match message {
+                    Ok(Ok(ClientMessages::ReadyForStart(ready))) => player.ready = ready,
+                    Ok(Ok(packet)) => {
+                        warn!("{} sent an unexpected packet: {packet:?}", player.name)
+                    }
+                    Ok(Err(ChannelError::BadPacket(packet))) => {
+                        warn!("{} sent a bad packet: {packet}", player.name)
+                    }
+                    // The connection is closed after a read error, so the client will disconnect.
+                    Ok(Err(ChannelError::ReadError(_))) => {}
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        disconnected.push(index);
+                        break;
+                    }
+                }
+            }
         }
+
+        let disconnects = !disconnected.is_empty();
+        for index in disconnected.into_iter().rev() {
+            let player = self.clients.remove(index);
+            info!("Client disconnected: {}", player.name);
+        }
+        received || disconnects
     }
-    fn clients_ready(&mut self) {
+
+    /// Informs the clients of the amount of connected & ready players, if they have changed.
+    /// Returns true if every connected client is ready for the game to start.
+    fn clients_ready(&mut self) -> bool {
         let connected = self.clients.len() as u32;
 
         let ready = self
-            .state
-            .accepted
+            .clients
             .iter()
-            .fold(0, |acc, channel| acc + channel.1 as u32);
+            .fold(0, |acc, player| acc + player.ready as u32);
 
         // Inform clients of new player connented/ready amount
         if connected != self.state.previous_connected {
-            self.write_to_all(ServerMessages::PlayersConnected(connected as u8))
+            self.write_to_all(ServerMessages::PlayersConnected(connected as u8));
+            self.state.previous_connected = connected;
         };
         if ready != self.state.previous_ready {
-            self.write_to_all(ServerMessages::PlayersReady(ready as u8))
+            self.write_to_all(ServerMessages::PlayersReady(ready as u8));
+            self.state.previous_ready = ready;
+        };
+
+        ready == connected && connected != 0
+    }
+}
+
+impl From<Server<Listening>> for Server<Playing> {
+    fn from(value: Server<Listening>) -> Self {
+        let players: Vec<String> = value
+            .clients
+            .iter()
+            .map(|player| player.name.clone())
+            .collect();
+
+        let mut rng = value.rng;
+        let seed = rng.u64(..);
+        let hint_budget = value.lobby.hint_budget;
+        let ruleset = value.lobby.ruleset;
+
+        Server {
+            listener: value.listener,
+            rng,
+            clients: value.clients,
+            spectators: value.spectators,
+            status: value.status,
+            game_logs: value.game_logs,
+            lobby: value.lobby,
+            events: value.events,
+            next_connection: value.next_connection,
+            clock: value.clock,
+            state: Playing {
+                boards: vec![ruleset.full_board(); players.len()],
+                alive: vec![true; players.len()],
+                hints_left: vec![hint_budget; players.len()],
+                to_accept: value.state.to_accept,
+                turn: None,
+                asked_at: Instant::now(),
+                span: info_span!("game", players = players.len()),
+                dice: Rng::with_seed(seed),
+                replay: Replay::new(players, ruleset, Some(seed)),
+            },
+        }
+    }
+}
+
+impl From<Server<Playing>> for Server<Listening> {
+    fn from(mut value: Server<Playing>) -> Self {
+        // Every player has to ready up again for the next game.
+        for player in &mut value.clients {
+            player.ready = false;
+        }
+
+        Server {
+            listener: value.listener,
+            rng: value.rng,
+            clients: value.clients,
+            spectators: value.spectators,
+            status: value.status,
+            game_logs: value.game_logs,
+            lobby: value.lobby,
+            events: value.events,
+            next_connection: value.next_connection,
+            clock: value.clock,
+            state: Listening {
+                // Ensures the clients are informed of the player amounts for the next game.
+                previous_connected: u32::MAX,
+                previous_ready: u32::MAX,
+                to_accept: value.state.to_accept,
+            },
+        }
+    }
+}
+
+impl Server<Playing> {
+    /// Starts a game with every client, asking the first player for their roll.
+    /// Each client takes turns rolling & moving on their own board, until no client can make a move.
+    /// The client with the lowest value left on their board wins.
+    fn start(&mut self) {
+        let _span = self.state.span.clone().entered();
+        info!("Starting game with {} players.", self.clients.len());
+        for index in 0..self.clients.len() {
+            self.write_to(index, ServerMessages::PlayerIndex(index as u8));
+        }
+        self.broadcast(SpectatorEvent::GameStarted(self.clients.len() as u8));
+        self.events.record(ServerEvent::GameStarted {
+            players: self
+                .clients
+                .iter()
+                .map(|player| player.connection)
+                .collect(),
+            seed: self.state.replay.seed.unwrap_or_default(),
+        });
+
+        self.next_turn(0);
+    }
+
+    /// Returns true once every player is out & the results have been sent.
+    fn is_over(&self) -> bool {
+        self.state.turn.is_none()
+    }
+
+    /// Handles the next message from the player whose turn it is.
+    /// Returns true if a message was handled, or false if the game is waiting for the player or is over.
+    fn step(&mut self) -> bool {
+        let Some((index, query)) = self.state.turn else {
+            return false;
         };
+        let _span = self.state.span.clone().entered();
 
-        // Starts the game
-        if ready == connected && connected != 0 {
-            todo!("Make game start features :P")
+        let player = &self.clients[index];
+        let packet = match receive(&mut self.events, player.connection, &player.channel) {
+            Ok(Ok(packet)) => packet,
+            Ok(Err(ChannelError::BadPacket(packet))) => {
+                warn!("{} sent a bad packet: {packet}", player.name);
+                return true;
+            }
+            Err(TryRecvError::Empty) if self.timed_out(index) => {
+                let connection = self.clients[index].connection;
+                self.events.record(ServerEvent::TimedOut { connection });
+                warn!(
+                    "{} took too long to take their turn, so they're out.",
+                    self.clients[index].name
+                );
+                // A player that runs out of time after rolling is out with the roll they had.
+                if let Query::Move(to_move) = query {
+                    self.spectate_turn(index, to_move, None);
+                }
+                self.state.alive[index] = false;
+                self.next_turn(index + 1);
+                return true;
+            }
+            Err(TryRecvError::Empty) => return false,
+            Ok(Err(ChannelError::ReadError(_))) | Err(TryRecvError::Disconnected) => {
+                // A player that disconnects after rolling is out with the roll they had.
+                if let Query::Move(to_move) = query {
+                    self.spectate_turn(index, to_move, None);
+                }
+                self.disconnect(index);
+                self.next_turn(index + 1);
+                return true;
+            }
         };
+
+        match query {
+            Query::Roll => self.answer_roll(index, packet),
+            Query::Move(to_move) => self.answer_move(index, to_move, packet),
+        }
+        true
+    }
+
+    /// Asks the next player who can still move for their roll, starting from the given index.
+    /// If no player can move, then the game is over & the results are sent.
+    fn next_turn(&mut self, from: usize) {
+        let players = self.clients.len();
+
+        for offset in 0..players {
+            let index = (from + offset) % players;
+            if !self.state.alive[index] {
+                continue;
+            }
+
+            // If the box was shut, then there are no more moves to make.
+            if self.state.boards[index] == 0 {
+                self.state.alive[index] = false;
+                continue;
+            }
+
+            return self.ask(index, Query::Roll);
+        }
+
+        self.state.turn = None;
+        self.send_results();
+    }
+
+    /// Asks the player at the given index for their roll or move, waiting for them to answer.
+    /// In an assisted lobby, the player is hinted the move to make.
+    fn ask(&mut self, index: usize, query: Query) {
+        let connection = self.clients[index].connection;
+        self.events.record(match query {
+            Query::Roll => ServerEvent::AskedRoll { connection },
+            Query::Move(to_move) => ServerEvent::AskedMove {
+                connection,
+                to_move,
+            },
+        });
+
+        let message = match query {
+            Query::Roll => ServerMessages::QueryClientRoll,
+            Query::Move(to_move) => {
+                let hint = match self.lobby.is_assisted() {
+                    true => self.lobby.advise(to_move).map(|advice| advice.best_move),
+                    false => None,
+                };
+                ServerMessages::QueryClientForMove(to_move, hint)
+            }
+        };
+        self.write_to(index, message);
+        self.state.turn = Some((index, query));
+        self.state.asked_at = Instant::now();
+    }
+
+    /// Returns true if the player at the given index has taken too long to answer what they were asked.
+    fn timed_out(&mut self, index: usize) -> bool {
+        match &mut self.clock {
+            TurnClock::System => self
+                .lobby
+                .live
+                .turn_timeout
+                .is_some_and(|timeout| self.state.asked_at.elapsed() >= timeout),
+            TurnClock::Replayed(timed_out) => {
+                let connection = self.clients[index].connection;
+                match timed_out
+                    .iter()
+                    .position(|timed_out| *timed_out == connection)
+                {
+                    Some(position) => {
+                        timed_out.remove(position);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Rolls the dice the player at the given index asked for, then asks them for their move.
+    /// If the player didn't send their roll, then they are asked for it again.
+    fn answer_roll(&mut self, index: usize, packet: ClientMessages) {
+        let roll_request = match packet {
+            ClientMessages::ChosenRoll(roll_request) => roll_request,
+            ClientMessages::ChosenMove(_) => {
+                self.write_to(
+                    index,
+                    ServerMessages::Error(protocol::ServerError::MoveBeforeRoll),
+                );
+                return self.ask(index, Query::Roll);
+            }
+            packet => {
+                self.unexpected_packet(index, packet);
+                return self.ask(index, Query::Roll);
+            }
+        };
+
+        let board = self.state.boards[index];
+        let to_move = match roll_request {
+            RollRequest::SingleDice if self.lobby.ruleset.can_roll_single(board) => {
+                ClientToMove::OneDice(board, self.roll_dice())
+            }
+            // If a single dice can't be rolled, then both are.
+            _ => ClientToMove::TwoDice {
+                board,
+                dice_1: self.roll_dice(),
+                dice_2: self.roll_dice(),
+            },
+        };
+
+        self.ask(index, Query::Move(to_move));
+    }
+
+    /// Updates the board of the player at the given index with their move, then moves on to the next player.
+    /// If the player cannot move, then they are out of the game.
+    /// If the move wasn't valid, then the player is asked for it again.
+    fn answer_move(&mut self, index: usize, to_move: ClientToMove, packet: ClientMessages) {
+        let client_move = match packet {
+            ClientMessages::ChosenMove(client_move) => client_move,
+            // The player is still being asked for their move, so they don't have to be asked again.
+            ClientMessages::RequestHint => return self.answer_hint(index, to_move),
+            packet => {
+                self.unexpected_packet(index, packet);
+                return self.ask(index, Query::Move(to_move));
+            }
+        };
+
+        let board = self.state.boards[index];
+
+        let valid = match (to_move, client_move) {
+            (_, ClientMove::CannotMove) => {
+                self.state.alive[index] = false;
+                self.spectate_turn(index, to_move, None);
+                return self.next_turn(index + 1);
+            }
+            (ClientToMove::OneDice(_, dice), ClientMove::FirstDice(moved_board)) => {
+                self.check_move(index, board, dice, moved_board.0)
+            }
+            (ClientToMove::TwoDice { dice_1, dice_2, .. }, ClientMove::BothDice(moved_board)) => {
+                self.check_move(index, board, dice_1 + dice_2, moved_board.0)
+            }
+            // The dice used don't match the dice that were rolled.
+            _ => None,
+        };
+
+        let Some(moved_board) = valid else {
+            self.write_to(
+                index,
+                ServerMessages::Error(protocol::ServerError::InvalidMove),
+            );
+            return self.ask(index, Query::Move(to_move));
+        };
+
+        self.spectate_turn(index, to_move, Some(moved_board));
+        self.state.boards[index] = moved_board;
+        // Ending the game puts every player out, so nobody else is asked to move.
+        if self.lobby.ruleset.ends_game(moved_board) {
+            self.state.alive.fill(false);
+        }
+        self.next_turn(index + 1);
+    }
+
+    /// Returns the moved board if the player at the given index made a legal move,
+    /// otherwise logs why the move isn't legal.
+    fn check_move(&self, index: usize, board: u16, roll: u8, moved_board: u16) -> Option<u16> {
+        match self.lobby.ruleset.is_legal_move(board, roll, moved_board) {
+            Ok(()) => Some(moved_board),
+            Err(e) => {
+                warn!("{} made an illegal move: {e}", self.clients[index].name);
+                None
+            }
+        }
+    }
+
+    /// Sends the player at the given index the move the advisor recommends for their rolled dice.
+    /// Unless the lobby is assisted, this uses up one of the player's hints.
+    /// If the player has no hints left, then they are sent an error instead.
+    fn answer_hint(&mut self, index: usize, to_move: ClientToMove) {
+        let assisted = self.lobby.is_assisted();
+        let hints_left = &mut self.state.hints_left[index];
+        if self.lobby.advisor.is_none() || (!assisted && *hints_left == 0) {
+            return self.write_to(
+                index,
+                ServerMessages::Error(protocol::ServerError::NoHintsLeft),
+            );
+        }
+        if !assisted {
+            *hints_left -= 1;
+        }
+
+        let advice = self.lobby.advise(to_move);
+        self.write_to(
+            index,
+            ServerMessages::Hint {
+                best_move: advice.map(|advice| advice.best_move),
+                win_rate: advice.and_then(|advice| advice.win_rate),
+            },
+        );
+    }
+
+    /// Rolls a single dice.
+    fn roll_dice(&mut self) -> u8 {
+        self.state.dice.u8(1..=6)
+    }
+
+    /// Informs the players & spectators of the turn the player at the given index took with the rolled dice.
+    /// The moved board is None if the player is out.
+    fn spectate_turn(&mut self, index: usize, to_move: ClientToMove, moved_board: Option<u16>) {
+        let board = self.state.boards[index];
+        let turn = match to_move {
+            ClientToMove::OneDice(_, dice) => {
+                ReplayTurn::new(index as u8, &[dice], board, moved_board)
+            }
+            ClientToMove::TwoDice { dice_1, dice_2, .. } => {
+                ReplayTurn::new(index as u8, &[dice_1, dice_2], board, moved_board)
+            }
+        };
+
+        self.broadcast(SpectatorEvent::Turn(SpectatedTurn {
+            player: turn.player,
+            roll: turn.roll,
+            board,
+            moved_board,
+        }));
+        self.state.replay.turns.push(turn);
+    }
+
+    /// Writes the replay of the game to a new file in the game log folder, if the games are logged.
+    /// Failing to write the replay doesn't affect the games, so the failure is only printed.
+    fn write_replay(&self) {
+        let Some(folder) = &self.game_logs else {
+            return;
+        };
+
+        // The time the game ended at keeps the file names unique & in order.
+        let ended = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = folder.join(format!("game-{ended}.{REPLAY_EXTENSION}"));
+
+        let written = std::fs::create_dir_all(folder)
+            .map_err(Into::into)
+            .and_then(|_| self.state.replay.write(&path));
+        match written {
+            Ok(()) => info!("Wrote the replay to {}.", path.display()),
+            Err(e) => error!("Failed to write the replay to {}: {e}", path.display()),
+        }
+    }
+
+    /// Informs each client of whether they won, drew, or lost.
+    fn send_results(&mut self) {
+        let ruleset = self.lobby.ruleset;
+        let scores: Vec<u8> = self
+            .state
+            .boards
+            .iter()
+            .map(|board| ruleset.score(*board))
+            .collect();
+
+        let winning_score = scores.iter().copied().min().unwrap_or_default();
+        let winners = scores
+            .iter()
+            .filter(|score| **score == winning_score)
+            .count() as u8;
+
+        for (index, score) in scores.into_iter().enumerate() {
+            let result = match score == winning_score {
+                true if winners == 1 => ServerMessages::SendWin,
+                true => ServerMessages::SendDraw(DrawingPlayerAmount(winners - 1)),
+                false => ServerMessages::SendLoss(WinningScore(winning_score)),
+            };
+
+            self.write_to(index, result);
+        }
+
+        self.broadcast(SpectatorEvent::GameEnded(WinningScore(winning_score)));
+        self.state.replay.winning_score = Some(winning_score);
+        self.events.record(ServerEvent::GameEnded { winning_score });
+        self.write_replay();
+        info!("Game finished with a winning score of {winning_score}.");
+    }
+
+    /// Sends the message to the client at the given index.
+    /// A failure to send is handled when the client is next read from.
+    fn write_to(&self, index: usize, server_message: ServerMessages) {
+        let player = &self.clients[index];
+        if let Err(e) = player.channel.writing.send(server_message) {
+            warn!("Failed to send message to {}: {e}", player.name);
+        }
+    }
+
+    fn unexpected_packet(&self, index: usize, packet: ClientMessages) {
+        warn!(
+            "{} sent an unexpected packet: {packet:?}",
+            self.clients[index].name
+        );
+    }
+
+    /// Removes the disconnected client at the given index from the game.
+    /// The client keeps the board they had when they disconnected.
+    fn disconnect(&mut self, index: usize) {
+        warn!("{} disconnected during the game.", self.clients[index].name);
+        self.state.alive[index] = false;
     }
 }
 
@@ -199,7 +1418,7 @@ impl Server<Listening> {
 //     fn create_server() -> anyhow::Result<Channels<ServerMessages, ClientMessages>> {
 //         let socket = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 9000);
 //         thread::spawn(move || start(socket));
-//         let channels = networked::initialize_channels(TcpStream::connect(socket)?);
+//         let channels = crate::initialize_channels(TcpStream::connect(socket)?);
 //         anyhow::Ok(channels)
 //     }
 
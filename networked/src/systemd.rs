@@ -0,0 +1,110 @@
+//! Runs the server as a systemd service, which can pass it a socket it has already bound
+//! & be told once the server is ready for connections.
+//!
+//! This follows the protocols of sd_listen_fds(3) & sd_notify(3), without needing libsystemd.
+
+use std::env;
+use std::io::{self, ErrorKind};
+use std::net::TcpListener;
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+
+use tracing::warn;
+
+/// The first file descriptor systemd passes sockets from, with any others following it.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Returns the listening socket systemd passed to the server, if it was started by socket activation.
+/// This should only be called once, as the socket is owned by the listener that's returned.
+/// The variables systemd passes the sockets with are unset, so the processes the server starts don't take them too.
+pub fn take_listener() -> io::Result<Option<TcpListener>> {
+    // The sockets are for another process if they aren't for this one, such as the shell that started the server.
+    let for_this_process = env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        == Some(std::process::id());
+    let fds = env::var("LISTEN_FDS").ok();
+    // Like sd_listen_fds(3) when told to unset the environment, which it does whether or not any sockets are taken.
+    // This is called before the server starts the threads that could read the environment at the same time.
+    for variable in ["LISTEN_FDS", "LISTEN_PID", "LISTEN_FDNAMES"] {
+        env::remove_var(variable);
+    }
+    let Some(fds) = fds.filter(|_| for_this_process) else {
+        return Ok(None);
+    };
+
+    let fds: RawFd = fds.parse().map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("LISTEN_FDS should be the amount of sockets, not \"{fds}\""),
+        )
+    })?;
+    match fds {
+        0 => return Ok(None),
+        1 => {}
+        _ => {
+            warn!("systemd passed {fds} sockets, but the server only listens on the first of them.")
+        }
+    }
+
+    // SAFETY: systemd passes the sockets from the first descriptor onwards, which nothing else in the process uses.
+    let listener = unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START) };
+    let fd = listener.as_raw_fd();
+
+    // The unit could pass a socket that isn't listening for connections, such as a datagram socket.
+    let mut accepting: libc::c_int = 0;
+    let mut length = size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: The value & its length point to an int that lives for the whole call.
+    let result = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ACCEPTCONN,
+            &mut accepting as *mut libc::c_int as *mut libc::c_void,
+            &mut length,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if accepting == 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "the socket systemd passed isn't listening for connections",
+        ));
+    }
+    // Only TCP sockets have an address the clients can connect to.
+    listener.local_addr()?;
+
+    // Unlike the sockets the server opens itself, the passed socket would be inherited by the processes it starts.
+    // SAFETY: The descriptor is owned by the listener, which is still open.
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(Some(listener))
+}
+
+/// Tells systemd the state of the server, such as "READY=1" once it's accepting connections.
+/// Nothing is sent unless the server was started by systemd with a socket to notify it on.
+pub fn notify(state: &str) -> io::Result<()> {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let socket = UnixDatagram::unbound()?;
+
+    match path.as_bytes().strip_prefix(b"@") {
+        // A socket starting with @ is in the abstract namespace, which only Linux has.
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &address)?;
+        }
+        _ => {
+            socket.send_to(state.as_bytes(), &path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Checks that messages are still sent & received exactly as older versions of the protocol send them.
+//! A fixture should never be changed, as that would break the clients & servers that send it.
+//! New messages get new fixtures instead.
+
+use game_core::codec;
+use mac_address2::MacAddress;
+use networked::frame::decode;
+use networked::ChannelError;
+use protocol::{
+    ClientMessages, ClientMove, ClientMovedBoard, ClientToMove, DrawingPlayerAmount, Message,
+    RollRequest, ServerError, ServerMessages, SpectatedTurn, SpectatorEvent, WinningScore,
+};
+
+fn client_fixtures() -> Vec<(&'static str, ClientMessages)> {
+    vec![
+        (
+            "type: OptInForPlaying\ndata:\n  mac_address: 01:23:45:67:89:AB\n  name: Player\n",
+            ClientMessages::OptInForPlaying {
+                mac_address: MacAddress::new([0x01, 0x23, 0x45, 0x67, 0x89, 0xAB]),
+                name: String::from("Player"),
+            },
+        ),
+        (
+            "type: OptInForSpectating\n",
+            ClientMessages::OptInForSpectating,
+        ),
+        (
+            "type: ReadyForStart\ndata: true\n",
+            ClientMessages::ReadyForStart(true),
+        ),
+        (
+            "type: ChosenRoll\ndata: SingleDice\n",
+            ClientMessages::ChosenRoll(RollRequest::SingleDice),
+        ),
+        (
+            "type: ChosenMove\ndata: !BothDice 5\n",
+            ClientMessages::ChosenMove(ClientMove::BothDice(ClientMovedBoard(5))),
+        ),
+        ("type: RequestHint\n", ClientMessages::RequestHint),
+        (
+            "type: ChosenMove\ndata: CannotMove\n",
+            ClientMessages::ChosenMove(ClientMove::CannotMove),
+        ),
+        ("type: Version\ndata: 1\n", ClientMessages::Version(1)),
+    ]
+}
+
+fn server_fixtures() -> Vec<(&'static str, ServerMessages)> {
+    vec![
+        ("type: OptInAccept\n", ServerMessages::OptInAccept),
+        ("type: OptInDeny\n", ServerMessages::OptInDeny),
+        (
+            "type: PlayersConnected\ndata: 3\n",
+            ServerMessages::PlayersConnected(3),
+        ),
+        ("type: PlayersReady\ndata: 2\n", ServerMessages::PlayersReady(2)),
+        ("type: PlayerIndex\ndata: 1\n", ServerMessages::PlayerIndex(1)),
+        ("type: QueryClientRoll\n", ServerMessages::QueryClientRoll),
+        (
+            "type: QueryClientForMove\ndata: !OneDice\n- 7\n- 4\n",
+            ServerMessages::QueryClientForMove(ClientToMove::OneDice(7, 4), None),
+        ),
+        (
+            "type: QueryClientForMove\ndata: !TwoDice\n  board: 511\n  dice_1: 3\n  dice_2: 6\n",
+            ServerMessages::QueryClientForMove(ClientToMove::TwoDice {
+                board: 511,
+                dice_1: 3,
+                dice_2: 6,
+            }, None),
+        ),
+        (
+            "type: QueryClientForMove\ndata:\n- !TwoDice\n  board: 511\n  dice_1: 3\n  dice_2: 6\n- 447\n",
+            ServerMessages::QueryClientForMove(ClientToMove::TwoDice {
+                board: 511,
+                dice_1: 3,
+                dice_2: 6,
+            }, Some(447)),
+        ),
+        (
+            "type: Hint\ndata:\n  move: 447\n  win_rate: 0.25\n",
+            ServerMessages::Hint {
+                best_move: Some(447),
+                win_rate: Some(0.25),
+            },
+        ),
+        ("type: SendWin\n", ServerMessages::SendWin),
+        (
+            "type: SendDraw\ndata: 2\n",
+            ServerMessages::SendDraw(DrawingPlayerAmount(2)),
+        ),
+        (
+            "type: SendLoss\ndata: 4\n",
+            ServerMessages::SendLoss(WinningScore(4)),
+        ),
+        (
+            "type: Spectate\ndata: !GameStarted 2\n",
+            ServerMessages::Spectate(SpectatorEvent::GameStarted(2)),
+        ),
+        (
+            "type: Spectate\ndata: !Turn\n  player: 1\n  roll: 9\n  board: 511\n  moved_board: 255\n",
+            ServerMessages::Spectate(SpectatorEvent::Turn(SpectatedTurn {
+                player: 1,
+                roll: 9,
+                board: 511,
+                moved_board: Some(255),
+            })),
+        ),
+        (
+            "type: Error\ndata: InvalidMove\n",
+            ServerMessages::Error(ServerError::InvalidMove),
+        ),
+        (
+            "type: Error\ndata: NoHintsLeft\n",
+            ServerMessages::Error(ServerError::NoHintsLeft),
+        ),
+        ("type: Version\ndata: 1\n", ServerMessages::Version(1)),
+        (
+            "type: Error\ndata: !IncompatibleVersion 1\n",
+            ServerMessages::Error(ServerError::IncompatibleVersion(1)),
+        ),
+    ]
+}
+
+#[test]
+fn client_messages_are_sent_as_before() {
+    for (fixture, message) in client_fixtures() {
+        assert_eq!(codec::to_string(&message).unwrap(), fixture);
+    }
+}
+
+#[test]
+fn client_messages_are_received_as_before() {
+    for (fixture, message) in client_fixtures() {
+        assert_eq!(
+            decode::<ClientMessages>(fixture.as_bytes()).unwrap(),
+            message
+        );
+    }
+}
+
+#[test]
+fn server_messages_are_sent_as_before() {
+    for (fixture, message) in server_fixtures() {
+        assert_eq!(codec::to_string(&message).unwrap(), fixture);
+    }
+}
+
+#[test]
+fn server_messages_are_received_as_before() {
+    for (fixture, message) in server_fixtures() {
+        assert_eq!(
+            decode::<ServerMessages>(fixture.as_bytes()).unwrap(),
+            message
+        );
+    }
+}
+
+#[test]
+fn unknown_messages_are_skipped() {
+    let newer = [
+        "type: SomethingNew\n",
+        "type: SomethingNew\ndata: 3\n",
+        "type: SomethingNew\ndata:\n  board: 511\n  hints: [1, 2]\n",
+        "type: ChosenMove\ndata: !SomethingNew 5\n",
+    ];
+    for fixture in newer {
+        let client = decode::<ClientMessages>(fixture.as_bytes()).unwrap();
+        let server = decode::<ServerMessages>(fixture.as_bytes()).unwrap();
+        assert!(client.is_unknown() && server.is_unknown());
+    }
+}
+
+#[test]
+fn packets_without_a_type_are_bad() {
+    for fixture in ["", "nonsense", "- 1\n- 2\n", "data: 3\n"] {
+        assert!(matches!(
+            decode::<ClientMessages>(fixture.as_bytes()),
+            Err(ChannelError::BadPacket(_))
+        ));
+        assert!(matches!(
+            decode::<ServerMessages>(fixture.as_bytes()),
+            Err(ChannelError::BadPacket(_))
+        ));
+    }
+}
+
+#[test]
+fn unknown_messages_are_never_sent() {
+    assert!(codec::to_string(&ClientMessages::Unknown).is_err());
+    assert!(codec::to_string(&ServerMessages::Unknown).is_err());
+}
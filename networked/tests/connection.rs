@@ -0,0 +1,43 @@
+//! Checks that the channels of a connection read its messages & report when it's closed.
+
+use std::io::{ErrorKind, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use networked::frame::encode;
+use networked::{initialize_channels, ChannelError, Channels};
+use protocol::{ClientMessages, ServerMessages};
+
+/// How long a message can take to be read before the test fails.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connects to a new local listener, returning the client's stream & the server's channels.
+fn connect() -> (TcpStream, Channels<ClientMessages, ServerMessages>) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).expect("Should bind locally");
+    let client = TcpStream::connect(listener.local_addr().expect("Should have an address"))
+        .expect("Should connect locally");
+    let (stream, _) = listener.accept().expect("Should accept the client");
+    let channels = initialize_channels(stream).expect("Should start the channels");
+    (client, channels)
+}
+
+#[test]
+fn closed_connection_is_reported_once() {
+    let (mut client, channels) = connect();
+    client
+        .write_all(&encode(&ClientMessages::ReadyForStart(true)).expect("Should encode"))
+        .expect("Should write");
+    drop(client);
+
+    // The message sent before the connection closed is still read.
+    assert!(matches!(
+        channels.reading.recv_timeout(TIMEOUT).expect("Should read"),
+        Ok(ClientMessages::ReadyForStart(true))
+    ));
+    assert!(matches!(
+        channels.reading.recv_timeout(TIMEOUT).expect("Should read"),
+        Err(ChannelError::ReadError(ErrorKind::UnexpectedEof))
+    ));
+    // The reading thread stops once the connection is closed, rather than reading it again.
+    assert!(channels.reading.recv_timeout(TIMEOUT).is_err());
+}
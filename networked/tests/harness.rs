@@ -0,0 +1,254 @@
+//! Plays games between the server & the clients' states in memory, from joining through to reconnecting.
+
+use networked::client_states::{ClientError, Query};
+use networked::harness::{Harness, TestClient};
+use networked::server_state::{LiveSettings, Lobby};
+use protocol::{ServerMessages, PROTOCOL_VERSION};
+
+/// The seed the dice are rolled from, so each game plays out the same way.
+const SEED: u64 = 7;
+
+/// Steps the server & updates the clients until neither has anything left to handle.
+fn settle(harness: &mut Harness, clients: &mut [&mut TestClient]) {
+    loop {
+        let progressed = harness.advance() > 0;
+        let mut handled = false;
+        for client in clients.iter_mut() {
+            handled |= client
+                .update()
+                .expect("The server should keep to the protocol");
+        }
+        if !progressed && !handled {
+            return;
+        }
+    }
+}
+
+/// Connects a client with the given name & waits until the server has accepted it.
+fn join(harness: &mut Harness, name: &str) -> TestClient {
+    let mut client = harness.connect(name).expect("Should request to join");
+    settle(harness, &mut [&mut client]);
+    assert!(client.is_waiting(), "{name} should have been accepted");
+    client
+}
+
+/// Readies each client & waits until the game has started.
+fn start(harness: &mut Harness, clients: &mut [&mut TestClient]) {
+    for client in clients.iter_mut() {
+        assert!(client.ready(true).expect("Should send that it's ready"));
+    }
+    settle(harness, clients);
+    assert!(harness.is_playing());
+}
+
+/// Returns whether a result tells the client they won.
+fn is_win(result: Option<ServerMessages>) -> bool {
+    result == Some(ServerMessages::SendWin)
+}
+
+#[test]
+fn joining_informs_every_player() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+    settle(&mut harness, &mut [&mut alice, &mut bob]);
+
+    for client in [&alice, &bob] {
+        assert_eq!(
+            client.pre_game().map(|client| client.players()),
+            Some((2, 0))
+        );
+    }
+    let names: Vec<String> = harness
+        .status()
+        .players
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, ["alice", "bob"]);
+}
+
+#[test]
+fn game_starts_once_every_player_is_ready() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+
+    alice.ready(true).expect("Should send that it's ready");
+    settle(&mut harness, &mut [&mut alice, &mut bob]);
+    assert!(!harness.is_playing());
+    assert_eq!(bob.pre_game().map(|client| client.players()), Some((2, 1)));
+
+    bob.ready(true).expect("Should send that it's ready");
+    settle(&mut harness, &mut [&mut alice, &mut bob]);
+    assert!(harness.is_playing());
+    assert_eq!(alice.in_game().map(|client| client.index()), Some(0));
+    assert_eq!(bob.in_game().map(|client| client.index()), Some(1));
+    // The first player is asked for their roll, while the other waits for their turn.
+    assert_eq!(
+        alice.in_game().and_then(|client| client.asked()),
+        Some(Query::Roll)
+    );
+    assert_eq!(bob.in_game().and_then(|client| client.asked()), None);
+}
+
+#[test]
+fn client_only_answers_what_it_was_asked() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+    start(&mut harness, &mut [&mut alice, &mut bob]);
+
+    let bob = bob.in_game_mut().expect("Bob should be playing");
+    assert!(bob.roll(protocol::RollRequest::BothDice).is_err());
+    assert!(bob.request_hint().is_err());
+}
+
+#[test]
+fn full_game_sends_every_player_a_result() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+    start(&mut harness, &mut [&mut alice, &mut bob]);
+
+    let results = harness
+        .play_out(&mut [&mut alice, &mut bob])
+        .expect("The server should keep to the protocol");
+    assert!(!harness.is_playing());
+    assert!(results.iter().all(Option::is_some));
+    // Only one player can win, unless they drew.
+    let draws = results
+        .iter()
+        .filter(|result| matches!(result, Some(ServerMessages::SendDraw(_))))
+        .count();
+    assert!(draws == 2 || results.iter().filter(|result| is_win(**result)).count() == 1);
+
+    // Every player has to ready up again for the next game.
+    for client in [&alice, &bob] {
+        assert_eq!(
+            client.pre_game().map(|client| client.is_ready()),
+            Some(false)
+        );
+        assert_eq!(
+            client.pre_game().map(|client| client.players()),
+            Some((2, 0))
+        );
+    }
+}
+
+#[test]
+fn same_seed_plays_the_same_game() {
+    let play = || {
+        let mut harness = Harness::new(SEED);
+        let mut alice = join(&mut harness, "alice");
+        let mut bob = join(&mut harness, "bob");
+        start(&mut harness, &mut [&mut alice, &mut bob]);
+        harness
+            .play_out(&mut [&mut alice, &mut bob])
+            .expect("The server should keep to the protocol")
+    };
+
+    assert_eq!(play(), play());
+}
+
+#[test]
+fn full_server_refuses_clients() {
+    let mut harness = Harness::new(SEED);
+    harness.set_lobby(Lobby {
+        live: LiveSettings {
+            max_players: Some(1),
+            ..LiveSettings::default()
+        },
+        ..Lobby::default()
+    });
+    let _alice = join(&mut harness, "alice");
+
+    let mut bob = harness.connect("bob").expect("Should request to join");
+    settle(&mut harness, &mut [&mut bob]);
+    assert!(bob.is_refused());
+    assert_eq!(harness.status().players.len(), 1);
+}
+
+#[test]
+fn server_refuses_other_protocol_versions() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = harness
+        .connect_speaking("alice", PROTOCOL_VERSION + 1)
+        .expect("Should request to join");
+    harness.advance();
+
+    assert!(matches!(
+        alice.update(),
+        Err(ClientError::IncompatibleVersion(PROTOCOL_VERSION))
+    ));
+    assert!(harness.status().players.is_empty());
+}
+
+#[test]
+fn disconnecting_before_the_game_informs_the_others() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let bob = join(&mut harness, "bob");
+    settle(&mut harness, &mut [&mut alice]);
+    assert_eq!(
+        alice.pre_game().map(|client| client.players()),
+        Some((2, 0))
+    );
+
+    drop(bob);
+    settle(&mut harness, &mut [&mut alice]);
+    assert_eq!(
+        alice.pre_game().map(|client| client.players()),
+        Some((1, 0))
+    );
+
+    // The game can start without the player that left.
+    start(&mut harness, &mut [&mut alice]);
+}
+
+#[test]
+fn disconnecting_during_the_game_lets_the_others_finish() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+    start(&mut harness, &mut [&mut alice, &mut bob]);
+
+    drop(bob);
+    let results = harness
+        .play_out(&mut [&mut alice])
+        .expect("The server should keep to the protocol");
+    assert!(!harness.is_playing());
+    assert!(results[0].is_some(), "Alice should have been sent a result");
+    assert_eq!(harness.status().players.len(), 1);
+}
+
+#[test]
+fn reconnecting_during_a_game_joins_the_next_game() {
+    let mut harness = Harness::new(SEED);
+    let mut alice = join(&mut harness, "alice");
+    let mut bob = join(&mut harness, "bob");
+    start(&mut harness, &mut [&mut alice, &mut bob]);
+
+    // Bob leaves & comes back while the game is still being played.
+    drop(bob);
+    let mut bob = harness.connect("bob").expect("Should request to join");
+    settle(&mut harness, &mut [&mut alice, &mut bob]);
+    assert!(harness.is_playing());
+    assert!(bob.is_joining(), "Bob should wait for the game to end");
+
+    harness
+        .play_out(&mut [&mut alice, &mut bob])
+        .expect("The server should keep to the protocol");
+    settle(&mut harness, &mut [&mut alice, &mut bob]);
+    assert!(
+        bob.is_waiting(),
+        "Bob should be accepted once the game ended"
+    );
+    assert_eq!(bob.pre_game().map(|client| client.players()), Some((2, 0)));
+
+    start(&mut harness, &mut [&mut alice, &mut bob]);
+    let results = harness
+        .play_out(&mut [&mut alice, &mut bob])
+        .expect("The server should keep to the protocol");
+    assert!(results.iter().all(Option::is_some));
+}
@@ -0,0 +1,274 @@
+//! The messages sent between the shut the box server & its clients.
+//!
+//! Each message is serialized as yaml & followed by an [`ETX`] char, which marks where the message ends.
+//! The client sends [`ClientMessages`] & the server sends [`ServerMessages`].
+//! Anything that sends these messages in this framing can play on, or host, a networked game.
+
+use mac_address2::MacAddress;
+use serde::{Deserialize, Serialize};
+
+mod move_query;
+
+/// The version of the protocol described by this crate, which the client & server exchange when the client connects.
+/// This is raised whenever a change stops older clients or servers from understanding the messages.
+/// Version 2 tags each message with its type, which the untagged messages of version 1 can't be read as.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The port the server listens on, unless it is told otherwise.
+pub const DEFAULT_PORT: u16 = 3333;
+
+/// The char sent after every message, which marks where the message ends.
+pub const ETX: char = 0b00000011 as char;
+
+/// A message that is sent between the server & its clients.
+pub trait Message: Sized {
+    /// Returns the message that stands in for a message of a type this version of the protocol doesn't know.
+    fn unknown() -> Self;
+
+    /// Returns true if the message is of a type that this version of the protocol doesn't know.
+    /// These messages should be skipped, as they could have been sent by a newer version.
+    fn is_unknown(&self) -> bool;
+}
+
+impl Message for ClientMessages {
+    fn unknown() -> Self {
+        ClientMessages::Unknown
+    }
+
+    fn is_unknown(&self) -> bool {
+        matches!(self, ClientMessages::Unknown)
+    }
+}
+
+impl Message for ServerMessages {
+    fn unknown() -> Self {
+        ServerMessages::Unknown
+    }
+
+    fn is_unknown(&self) -> bool {
+        matches!(self, ServerMessages::Unknown)
+    }
+}
+
+// Possible Packets //
+
+/// Contains every message that the client could send.
+// Adjacently tagged, as the YAML codec cannot serialize enums nested within externally tagged enums.
+// Each variant is renamed to its tag, so renaming a variant can't change the tag it's sent with.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", content = "data")]
+pub enum ClientMessages {
+    // Joining
+    /// Tells the server the [`PROTOCOL_VERSION`] the client speaks, which is sent before opting in.
+    /// The server answers with a [`ServerMessages::Version`], or refuses the client if their versions differ.
+    #[serde(rename = "Version")]
+    Version(u32),
+    /// Requests to join the game.
+    /// The [MacAddress] will be used to identify the player & the name will be displayed to others.
+    #[serde(rename = "OptInForPlaying")]
+    OptInForPlaying {
+        mac_address: MacAddress,
+        name: String,
+    },
+    /// Requests to watch the games without playing in them.
+    /// Spectators are sent a [`ServerMessages::Spectate`] for everything that happens in each game.
+    #[serde(rename = "OptInForSpectating")]
+    OptInForSpectating,
+
+    // Starting
+    /// Informs the server that the client is ready to start the game.
+    #[serde(rename = "ReadyForStart")]
+    ReadyForStart(bool),
+
+    // Playing
+    /// Informs the server of how many dice the client wants rolled.
+    #[serde(rename = "ChosenRoll")]
+    ChosenRoll(RollRequest),
+    /// Sends the move the client made back to the server.
+    #[serde(rename = "ChosenMove")]
+    ChosenMove(ClientMove),
+    /// Requests a hint for the move to make, while being queried for a move.
+    /// The server answers with a [`ServerMessages::Hint`], or [`ServerError::NoHintsLeft`] if none are left.
+    #[serde(rename = "RequestHint")]
+    RequestHint,
+
+    /// If there was an error inform the server
+    #[serde(rename = "Error")]
+    Error(ClientError),
+
+    /// A message of a type that this version of the protocol doesn't know, such as one added by a newer client.
+    /// This is only ever received, so it can't be sent.
+    #[serde(skip)]
+    Unknown,
+}
+
+/// Contains every message that the server could send.
+// Adjacently tagged, as the YAML codec cannot serialize enums nested within externally tagged enums.
+// Each variant is renamed to its tag, so renaming a variant can't change the tag it's sent with.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(tag = "type", content = "data")]
+pub enum ServerMessages {
+    // Joining
+    /// Answers a [`ClientMessages::Version`] with the [`PROTOCOL_VERSION`] the server speaks.
+    #[serde(rename = "Version")]
+    Version(u32),
+    /// Informs the client that they were accepted into the game.
+    #[serde(rename = "OptInAccept")]
+    OptInAccept,
+    /// Informs the client that they were rejected from the game.
+    #[serde(rename = "OptInDeny")]
+    OptInDeny,
+
+    // Starting
+    /// Informs the client of the number of connected players.
+    #[serde(rename = "PlayersConnected")]
+    PlayersConnected(u8),
+    /// Informs the client of the number of ready players.
+    #[serde(rename = "PlayersReady")]
+    PlayersReady(u8),
+    /// Informs the client of their index in the game that is starting.
+    /// This is the index the [`SpectatorEvent`]s refer to them by.
+    #[serde(rename = "PlayerIndex")]
+    PlayerIndex(u8),
+
+    // Playing
+    /// Queries the client over how many dice they want rolled this move.
+    #[serde(rename = "QueryClientRoll")]
+    QueryClientRoll,
+    /// Queries the client for their move.
+    /// In an assisted lobby this includes the board the best move would leave, as a hint.
+    /// A query without a hint is sent exactly as it was before hints, so older clients can still play without them.
+    #[serde(rename = "QueryClientForMove", with = "move_query")]
+    QueryClientForMove(ClientToMove, Option<u16>),
+    /// Hints the client the move to make, in answer to a [`ClientMessages::RequestHint`].
+    #[serde(rename = "Hint")]
+    Hint {
+        /// The board the best move leaves, or None if there is no move to make.
+        #[serde(rename = "move")]
+        best_move: Option<u16>,
+        /// The chance of winning with the best move, between 0 & 1, if it's known.
+        win_rate: Option<f32>,
+    },
+
+    // Ending
+    /// Informs the client that they won.
+    #[serde(rename = "SendWin")]
+    SendWin,
+    /// Informs the client that they drew
+    #[serde(rename = "SendDraw")]
+    SendDraw(DrawingPlayerAmount),
+    /// Informs the client that they lost.
+    #[serde(rename = "SendLoss")]
+    SendLoss(WinningScore),
+
+    // Spectating
+    /// Informs a client of something that happened in the game.
+    /// These are sent to the players as well as the spectators, so the players can follow each other.
+    #[serde(rename = "Spectate")]
+    Spectate(SpectatorEvent),
+
+    /// If there was an error inform the client
+    #[serde(rename = "Error")]
+    Error(ServerError),
+
+    /// A message of a type that this version of the protocol doesn't know, such as one added by a newer server.
+    /// This is only ever received, so it can't be sent.
+    #[serde(skip)]
+    Unknown,
+}
+
+// Data types //
+
+/// The type of any message, which tells a message of an unknown type apart from a bad packet.
+/// A message that has a type but can't be read is skipped as [`Message::unknown`],
+/// which lets newer clients & servers add messages without older ones dropping their connection.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+pub struct MessageType {
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// Whether the client wants one dice rolled or two dice rolled.
+/// A single dice can only be rolled once the 7, 8, & 9 pieces are knocked down,
+/// otherwise both dice are rolled.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum RollRequest {
+    BothDice,
+    SingleDice,
+}
+
+/// Contains the possible dice the client used when making the move.
+/// Only [`ClientMove::BothDice`] is valid for two rolled dice & only [`ClientMove::FirstDice`] is valid for one.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum ClientMove {
+    BothDice(ClientMovedBoard),
+    FirstDice(ClientMovedBoard),
+    SecondDice(ClientMovedBoard),
+    CannotMove,
+}
+
+/// Contains the board the client made the move to.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct ClientMovedBoard(pub u16);
+
+/// Contains the winners score.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct WinningScore(pub u8);
+
+/// Contains the amount of players that you drew with.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct DrawingPlayerAmount(pub u8);
+
+/// Contains the data for the client to make a move upon.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum ClientToMove {
+    /// Contains the board state of the current game & one rolled dice.
+    OneDice(u16, u8),
+    /// Contains the board state of the current game & two rolled dice.
+    TwoDice { board: u16, dice_1: u8, dice_2: u8 },
+}
+
+/// Something that happened in the game, which is broadcast to the players & spectators.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum SpectatorEvent {
+    /// A game started with the given amount of players, who all start with every piece alive.
+    /// The players are referred to by their index, from 0 up to the amount of players.
+    GameStarted(u8),
+    /// A player took their turn.
+    Turn(SpectatedTurn),
+    /// The game ended, with the given score winning.
+    GameEnded(WinningScore),
+}
+
+/// A turn that was taken by a player, as seen by the spectators.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct SpectatedTurn {
+    /// The index of the player that took the turn.
+    pub player: u8,
+    /// The sum of the rolled dice.
+    pub roll: u8,
+    /// The board of the player before the turn.
+    pub board: u16,
+    /// The board of the player after the turn, or None if they couldn't move & are out.
+    pub moved_board: Option<u16>,
+}
+
+// Errors //
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum ServerError {
+    /// Sent to the client if it requests a move before requesting a roll.
+    MoveBeforeRoll,
+    /// Sent to the client if the move it made wasn't valid for its board & roll.
+    /// The client will be queried for its move again.
+    InvalidMove,
+    UnexpectedDisconnect,
+    /// Sent to the client if it requests a hint when it has none left, or the server doesn't give hints.
+    NoHintsLeft,
+    /// Sent to the client if it speaks another version of the protocol, or opted in without saying which,
+    /// along with the [`PROTOCOL_VERSION`] the server speaks. The client is then refused.
+    IncompatibleVersion(u32),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum ClientError {}
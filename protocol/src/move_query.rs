@@ -0,0 +1,55 @@
+//! Sends [`ServerMessages::QueryClientForMove`](crate::ServerMessages::QueryClientForMove)
+//! as just the [`ClientToMove`] when there's no hint, or as the [`ClientToMove`] followed by the hint when there is.
+//! This keeps the queries of lobbies that aren't assisted readable by clients from before hints.
+
+use std::fmt::{self, Formatter};
+
+use serde::de::value::EnumAccessDeserializer;
+use serde::de::{EnumAccess, Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ClientToMove;
+
+pub(crate) fn serialize<S: Serializer>(
+    to_move: &ClientToMove,
+    hint: &Option<u16>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match hint {
+        None => to_move.serialize(serializer),
+        Some(hint) => (to_move, hint).serialize(serializer),
+    }
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(ClientToMove, Option<u16>), D::Error> {
+    deserializer.deserialize_any(MoveQueryVisitor)
+}
+
+struct MoveQueryVisitor;
+
+impl<'de> Visitor<'de> for MoveQueryVisitor {
+    type Value = (ClientToMove, Option<u16>);
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("the dice to move with, optionally followed by a hint")
+    }
+
+    /// Reads a query without a hint.
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let to_move = ClientToMove::deserialize(EnumAccessDeserializer::new(data))?;
+        Ok((to_move, None))
+    }
+
+    /// Reads a query with a hint.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let to_move = seq
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(0, &self))?;
+        let hint = seq
+            .next_element()?
+            .ok_or_else(|| Error::invalid_length(1, &self))?;
+        Ok((to_move, Some(hint)))
+    }
+}
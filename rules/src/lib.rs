@@ -0,0 +1,267 @@
+//! The rulesets shut the box can be played with, which the simulation, the server, & the gui all play by.
+//!
+//! Each variant of the game is a [`Ruleset`], & the variants with a name are kept in the [`REGISTRY`].
+//! A house rule only has to be added to the ruleset for every part of the game to play by it.
+
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use game_core::MAX_TILES;
+
+/// The amount of games a round of golf is played over.
+pub const GOLF_HOLES: u8 = 9;
+
+/// Every ruleset with a name, from the most to the least common.
+pub const REGISTRY: [(&str, Ruleset); 5] = [
+    ("classic", Ruleset::CLASSIC),
+    ("12-tile", Ruleset::TWELVE_TILES),
+    ("one-die", Ruleset::ONE_DIE),
+    ("golf", Ruleset::GOLF),
+    ("instant-shut", Ruleset::INSTANT_SHUT),
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum RulesetError {
+    #[error("\"{0}\" isn't a known ruleset, it has to be one of classic, 12-tile, one-die, golf, or instant-shut")]
+    UnknownName(String),
+    #[error("A board can't have {0} tiles, it has to have between 1 & {MAX_TILES}")]
+    InvalidTiles(u8),
+}
+
+/// Why a move isn't legal, as returned by [`Ruleset::is_legal_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum IllegalMove {
+    #[error("the board {board} has tiles beyond the {tiles} tiles being played with")]
+    OutOfRange { board: u16, tiles: u8 },
+    #[error("a roll of {0} can't be made")]
+    ImpossibleRoll(u8),
+    #[error("the tiles {} were already down, so they can't be brought back up", game_core::list_tiles(*.0))]
+    Resurrected(u16),
+    #[error("the knocked down tiles add up to {knocked}, but the roll was {roll}")]
+    SumMismatch { knocked: u8, roll: u8 },
+}
+
+/// How the winner of a game is decided.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Scoring {
+    /// The lowest value left on the board wins the game.
+    #[default]
+    Lowest,
+    /// The values left are added up over [`GOLF_HOLES`] games, & the lowest total wins the round.
+    Golf,
+}
+
+/// The rules a game is played with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Ruleset {
+    /// The amount of tiles on the board.
+    pub tiles: u8,
+    /// Whether a single dice can be rolled once the 7 & higher pieces are knocked down.
+    pub one_die: bool,
+    /// How the winner is decided.
+    pub scoring: Scoring,
+    /// Whether shutting the box ends the game straight away, so the other players can't catch up.
+    pub instant_shut: bool,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::CLASSIC
+    }
+}
+
+impl Ruleset {
+    /// Nine tiles, which are knocked down with both dice.
+    pub const CLASSIC: Ruleset = Ruleset {
+        tiles: game_core::STANDARD_TILES,
+        one_die: false,
+        scoring: Scoring::Lowest,
+        instant_shut: false,
+    };
+    /// The classic rules on a board of twelve tiles.
+    pub const TWELVE_TILES: Ruleset = Ruleset {
+        tiles: 12,
+        ..Ruleset::CLASSIC
+    };
+    /// The classic rules, except a single dice can be rolled once the 7 & higher pieces are down.
+    pub const ONE_DIE: Ruleset = Ruleset {
+        one_die: true,
+        ..Ruleset::CLASSIC
+    };
+    /// The classic rules, with the scores added up over a round of [`GOLF_HOLES`] games.
+    pub const GOLF: Ruleset = Ruleset {
+        scoring: Scoring::Golf,
+        ..Ruleset::CLASSIC
+    };
+    /// The classic rules, except the first player to shut the box wins.
+    pub const INSTANT_SHUT: Ruleset = Ruleset {
+        instant_shut: true,
+        ..Ruleset::CLASSIC
+    };
+    /// The loosest rules, which allow every move that's legal with any of the rulesets.
+    /// This is used to check moves that aren't tied to a ruleset, such as the best moves of a policy.
+    pub const ANY: Ruleset = Ruleset {
+        tiles: MAX_TILES,
+        one_die: true,
+        ..Ruleset::CLASSIC
+    };
+
+    /// Returns the ruleset registered with the given name, ignoring its case.
+    pub fn named(name: &str) -> Option<Ruleset> {
+        REGISTRY
+            .iter()
+            .find(|(registered, _)| registered.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, ruleset)| *ruleset)
+    }
+
+    /// Returns the name this ruleset is registered with, or None if it isn't registered.
+    pub fn registered_name(self) -> Option<&'static str> {
+        REGISTRY
+            .iter()
+            .find(|(_, ruleset)| *ruleset == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// Returns an error if a game can't be played with this ruleset.
+    pub fn validate(self) -> Result<(), RulesetError> {
+        match (1..=MAX_TILES).contains(&self.tiles) {
+            true => Ok(()),
+            false => Err(RulesetError::InvalidTiles(self.tiles)),
+        }
+    }
+
+    /// Returns the board with every piece alive, which every game starts from.
+    pub fn full_board(self) -> u16 {
+        game_core::full_board(self.tiles)
+    }
+
+    /// Returns true if a single dice can be rolled for the given board.
+    pub fn can_roll_single(self, board: u16) -> bool {
+        self.one_die && game_core::can_roll_single(board)
+    }
+
+    /// Returns the rolls that can be made, from the lowest to the highest.
+    pub fn rolls(self) -> RangeInclusive<u8> {
+        match self.one_die {
+            // A roll of 1 is only possible with a single dice.
+            true => 1..=12,
+            false => 2..=12,
+        }
+    }
+
+    /// Returns why the dice couldn't have been rolled for the board, if they couldn't have been.
+    pub fn check_dice(self, board: u16, dice: &[u8]) -> Result<(), &'static str> {
+        if dice.iter().any(|dice| !(1..=6).contains(dice)) {
+            return Err("a dice has an impossible value");
+        }
+
+        match dice.len() {
+            1 if self.can_roll_single(board) => Ok(()),
+            1 => Err("a single dice can't be rolled for the board"),
+            2 => Ok(()),
+            _ => Err("only one or two dice can be rolled"),
+        }
+    }
+
+    /// Returns every board that can legally be reached from the board with the roll.
+    pub fn moves(self, board: u16, roll: u8) -> Vec<u16> {
+        game_core::enumerate_moves(board, roll)
+    }
+
+    /// Returns why moving from the board to the target board isn't legal for the roll, if it isn't.
+    /// A legal move only knocks down tiles that are up, which add up to the roll.
+    pub fn is_legal_move(self, board: u16, roll: u8, target: u16) -> Result<(), IllegalMove> {
+        let tiles = self.tiles;
+        for board in [board, target] {
+            if board & !self.full_board() != 0 {
+                return Err(IllegalMove::OutOfRange { board, tiles });
+            }
+        }
+        if !self.rolls().contains(&roll) {
+            return Err(IllegalMove::ImpossibleRoll(roll));
+        }
+
+        let resurrected = target & !board;
+        if resurrected != 0 {
+            return Err(IllegalMove::Resurrected(resurrected));
+        }
+        let knocked = game_core::board_value(board & !target);
+        if knocked != roll {
+            return Err(IllegalMove::SumMismatch { knocked, roll });
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if reaching the board ends the game for every player.
+    pub fn ends_game(self, board: u16) -> bool {
+        self.instant_shut && board == 0
+    }
+
+    /// Returns the score of the board at the end of a game, where the lowest score is the best.
+    pub fn score(self, board: u16) -> u8 {
+        game_core::board_value(board)
+    }
+
+    /// Returns the lowest score of the boards at the end of a game, which is the score of the winners.
+    pub fn winning_score(self, boards: impl IntoIterator<Item = u16>) -> u8 {
+        boards
+            .into_iter()
+            .map(|board| self.score(board))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Returns the amount of games whose scores are added up to decide the winner.
+    pub fn holes(self) -> u8 {
+        match self.scoring {
+            Scoring::Lowest => 1,
+            Scoring::Golf => GOLF_HOLES,
+        }
+    }
+
+    /// Returns true if the best moves for this ruleset can be simulated.
+    /// The simulation always rolls both dice, so it can't play with the one dice rule.
+    pub fn can_simulate(self) -> bool {
+        !self.one_die
+    }
+
+    /// Returns the human readable description of the rules, such as "9 tiles, one dice".
+    pub fn name(self) -> String {
+        let mut name = format!("{} tiles", self.tiles);
+        if self.one_die {
+            name.push_str(", one dice");
+        }
+        if self.scoring == Scoring::Golf {
+            name.push_str(", golf");
+        }
+        if self.instant_shut {
+            name.push_str(", instant shut");
+        }
+        name
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = RulesetError;
+
+    /// Parses the name of a registered ruleset, such as "golf".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ruleset::named(name).ok_or_else(|| RulesetError::UnknownName(name.to_string()))
+    }
+}
+
+impl Display for Ruleset {
+    /// Writes the registered name of the ruleset, or describes the rules if it isn't registered.
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self.registered_name() {
+            Some(name) => write!(formatter, "{name}"),
+            None => write!(formatter, "{}", self.name()),
+        }
+    }
+}
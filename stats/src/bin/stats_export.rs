@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use stats::{GameMode, Stats, Summary};
+use tracing::{error, info};
+
+/// Exports the stats of every game played in the gui as CSV, or summarises them.
+#[derive(Parser)]
+#[command(about, version)]
+struct CliArgs {
+    /// The stats database to read, instead of the one in the platform's data folder
+    #[arg(long = "db")]
+    database: Option<PathBuf>,
+
+    /// The file to write the CSV to, instead of stdout
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+
+    /// Prints the totals of the games instead of exporting them
+    #[arg(short = 's', long = "summary", default_value_t = false, action=clap::ArgAction::SetTrue)]
+    summary: bool,
+}
+
+fn main() -> ExitCode {
+    let args = CliArgs::parse();
+    let _guard = logging::init("stats-export", None);
+
+    let opened = match &args.database {
+        Some(path) => Stats::open(path),
+        None => Stats::open_default(),
+    };
+    let stats = match opened {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.summary {
+        return match print_summaries(&stats) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                error!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let exported = match &args.output {
+        Some(path) => File::create(path)
+            .map_err(Into::into)
+            .and_then(|file| stats.export_csv(BufWriter::new(file))),
+        None => stats.export_csv(io::stdout().lock()),
+    };
+    match exported {
+        Ok(games) => {
+            info!("Exported {games} games.");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the totals of every game, then of the games in each mode.
+fn print_summaries(stats: &Stats) -> Result<(), stats::StatsError> {
+    print_summary("All games", stats.summary(None)?);
+    for mode in GameMode::ALL {
+        let title = match mode {
+            GameMode::Local => "Games against the AI",
+            GameMode::Networked => "Networked games",
+        };
+        print_summary(title, stats.summary(Some(mode))?);
+    }
+
+    let opponents = stats.opponents()?;
+    if !opponents.is_empty() {
+        println!("Opponents:");
+    }
+    for opponent in opponents {
+        println!(
+            "  {}: {} games, {} wins, {} losses",
+            opponent.name, opponent.games, opponent.wins, opponent.losses
+        );
+    }
+    Ok(())
+}
+
+fn print_summary(title: &str, summary: Summary) {
+    println!(
+        "{title}: {} games, {} wins, {} draws, {} losses, {} shut boxes",
+        summary.games, summary.wins, summary.draws, summary.losses, summary.shut_boxes
+    );
+    if let Some(score) = summary.average_score {
+        println!("  Average score: {score:.1}");
+    }
+    if let Some(accuracy) = summary.average_accuracy {
+        println!("  Average accuracy: {:.1}%", accuracy * 100.);
+    }
+}
@@ -0,0 +1,460 @@
+//! Remembers every game the user plays, both against the AI & over the network, in a local SQLite database.
+//!
+//! The database is kept within the platform's data folder, such as `~/.local/share/shut-the-box/stats.sqlite3` on Linux,
+//! & backs the stats dashboard of the gui & the `stats-export` binary.
+
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, ToSql};
+
+/// The folder the database is kept in, within the platform's data folder.
+pub const STATS_FOLDER: &str = "shut-the-box";
+/// The name of the database file.
+pub const STATS_FILE: &str = "stats.sqlite3";
+/// The environment variable of the database to use instead of the one in the platform's data folder.
+pub const STATS_VAR: &str = "SHUT_THE_BOX_STATS";
+
+/// The version of the tables, which is kept in the database so older databases can be upgraded.
+const SCHEMA_VERSION: i32 = 1;
+/// Creates the tables of the first version.
+const SCHEMA: &str = "
+CREATE TABLE games (
+    id INTEGER PRIMARY KEY,
+    played_at INTEGER NOT NULL,
+    mode TEXT NOT NULL,
+    tiles INTEGER NOT NULL,
+    score INTEGER NOT NULL,
+    winning_score INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    accuracy REAL
+);
+CREATE TABLE opponents (
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (game_id, position)
+);
+CREATE INDEX games_played_at ON games(played_at);
+";
+
+#[derive(thiserror::Error, Debug)]
+pub enum StatsError {
+    #[error("The stats database failed: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Couldn't access the stats: {0}")]
+    Io(#[from] io::Error),
+    #[error("There's no folder to keep the stats in")]
+    NoFolder,
+    #[error("The stats database is version {0}, which is newer than this version can read")]
+    NewerSchema(i32),
+}
+
+/// Where a game was played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    /// Against the AI within the gui.
+    Local,
+    /// Against other players on a server.
+    Networked,
+}
+
+impl GameMode {
+    /// Every mode, in the order they're shown.
+    pub const ALL: [GameMode; 2] = [GameMode::Local, GameMode::Networked];
+}
+
+impl Display for GameMode {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            GameMode::Local => write!(formatter, "local"),
+            GameMode::Networked => write!(formatter, "networked"),
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "local" => Ok(GameMode::Local),
+            "networked" => Ok(GameMode::Networked),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ToSql for GameMode {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for GameMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        GameMode::from_str(value.as_str()?).map_err(|_| FromSqlError::InvalidType)
+    }
+}
+
+/// How a game ended for the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Won,
+    Drew,
+    Lost,
+}
+
+impl Outcome {
+    /// Returns how the game ended for a player with the given score, against the scores of their opponents.
+    /// The lowest score wins.
+    pub fn from_scores(score: u8, opponent_scores: &[u8]) -> Outcome {
+        let best_opponent = opponent_scores.iter().copied().min().unwrap_or(u8::MAX);
+        match score.cmp(&best_opponent) {
+            std::cmp::Ordering::Less => Outcome::Won,
+            std::cmp::Ordering::Equal => Outcome::Drew,
+            std::cmp::Ordering::Greater => Outcome::Lost,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Outcome::Won => write!(formatter, "won"),
+            Outcome::Drew => write!(formatter, "drew"),
+            Outcome::Lost => write!(formatter, "lost"),
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "won" => Ok(Outcome::Won),
+            "drew" => Ok(Outcome::Drew),
+            "lost" => Ok(Outcome::Lost),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ToSql for Outcome {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for Outcome {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Outcome::from_str(value.as_str()?).map_err(|_| FromSqlError::InvalidType)
+    }
+}
+
+/// A game the user finished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// When the game ended, in seconds since the unix epoch.
+    pub played_at: u64,
+    pub mode: GameMode,
+    /// The names of the other players, in the order they took their turns.
+    pub opponents: Vec<String>,
+    /// The amount of tiles on the board.
+    pub tiles: u8,
+    /// The value left on the user's board.
+    pub score: u8,
+    /// The lowest value left on any board.
+    pub winning_score: u8,
+    pub outcome: Outcome,
+    /// The fraction of the user's moves that were the best move, or None if the best moves weren't known.
+    pub accuracy: Option<f32>,
+}
+
+impl GameRecord {
+    /// Creates a record of a game that ended now.
+    pub fn now(
+        mode: GameMode,
+        opponents: Vec<String>,
+        tiles: u8,
+        score: u8,
+        winning_score: u8,
+        outcome: Outcome,
+    ) -> GameRecord {
+        let played_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        GameRecord {
+            played_at,
+            mode,
+            opponents,
+            tiles,
+            score,
+            winning_score,
+            outcome,
+            accuracy: None,
+        }
+    }
+
+    /// Sets the accuracy from the amount of moves that were judged & how many of them were the best move.
+    /// If no moves were judged, then the accuracy isn't known.
+    pub fn with_accuracy(mut self, best: u32, judged: u32) -> GameRecord {
+        self.accuracy = (judged > 0).then(|| best as f32 / judged as f32);
+        self
+    }
+}
+
+/// The totals of the recorded games.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// The amount of games the user shut the box in.
+    pub shut_boxes: u32,
+    /// The mean of the user's scores, or None if there are no games.
+    pub average_score: Option<f32>,
+    /// The mean accuracy of the games it's known for, or None if it isn't known for any.
+    pub average_accuracy: Option<f32>,
+}
+
+/// The totals of the games played against a single opponent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpponentSummary {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// The database of every recorded game.
+pub struct Stats {
+    connection: Connection,
+}
+
+impl Stats {
+    /// Returns the database that is used, which is set by [`STATS_VAR`] or is within the platform's data folder.
+    /// Returns None if the platform doesn't have a data folder.
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os(STATS_VAR) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::data_dir().map(|data| data.join(STATS_FOLDER).join(STATS_FILE)),
+        }
+    }
+
+    /// Opens the database at [`Stats::path`], creating it if needed.
+    pub fn open_default() -> Result<Stats, StatsError> {
+        let path = Stats::path().ok_or(StatsError::NoFolder)?;
+        Stats::open(&path)
+    }
+
+    /// Opens the database at the given file, creating it & its folder if needed.
+    pub fn open(path: &Path) -> Result<Stats, StatsError> {
+        if let Some(folder) = path
+            .parent()
+            .filter(|folder| !folder.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(folder)?;
+        }
+        Stats::migrate(Connection::open(path)?)
+    }
+
+    /// Opens a database that is only kept in memory, which is lost once it's dropped.
+    pub fn in_memory() -> Result<Stats, StatsError> {
+        Stats::migrate(Connection::open_in_memory()?)
+    }
+
+    /// Creates the tables if the database is new.
+    fn migrate(connection: Connection) -> Result<Stats, StatsError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+
+        let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        match version {
+            0 => {
+                connection.execute_batch(SCHEMA)?;
+                connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            SCHEMA_VERSION => {}
+            newer => return Err(StatsError::NewerSchema(newer)),
+        }
+
+        Ok(Stats { connection })
+    }
+
+    /// Adds the finished game to the database.
+    pub fn record(&mut self, game: &GameRecord) -> Result<(), StatsError> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO games (played_at, mode, tiles, score, winning_score, outcome, accuracy)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                game.played_at as i64,
+                game.mode,
+                game.tiles,
+                game.score,
+                game.winning_score,
+                game.outcome,
+                game.accuracy,
+            ],
+        )?;
+
+        let game_id = transaction.last_insert_rowid();
+        for (position, name) in game.opponents.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO opponents (game_id, position, name) VALUES (?1, ?2, ?3)",
+                params![game_id, position as i64, name],
+            )?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    /// Returns the most recent games played in the given mode, or in any mode if none is given.
+    /// The games are from the newest to the oldest.
+    pub fn recent(
+        &self,
+        mode: Option<GameMode>,
+        limit: usize,
+    ) -> Result<Vec<GameRecord>, StatsError> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, played_at, mode, tiles, score, winning_score, outcome, accuracy
+             FROM games WHERE ?1 IS NULL OR mode = ?1
+             ORDER BY played_at DESC, id DESC LIMIT ?2",
+        )?;
+        let rows = statement.query_map(params![mode, limit as i64], |row| {
+            let game = GameRecord {
+                played_at: row.get::<_, i64>(1)?.max(0) as u64,
+                mode: row.get(2)?,
+                opponents: Vec::new(),
+                tiles: row.get(3)?,
+                score: row.get(4)?,
+                winning_score: row.get(5)?,
+                outcome: row.get(6)?,
+                accuracy: row
+                    .get::<_, Option<f64>>(7)?
+                    .map(|accuracy| accuracy as f32),
+            };
+            Ok((row.get::<_, i64>(0)?, game))
+        })?;
+
+        rows.map(|row| {
+            let (id, mut game) = row?;
+            game.opponents = self.opponents_of(id)?;
+            Ok(game)
+        })
+        .collect()
+    }
+
+    /// Returns the names of the opponents of the game with the given id, in the order they took their turns.
+    fn opponents_of(&self, game_id: i64) -> Result<Vec<String>, StatsError> {
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT name FROM opponents WHERE game_id = ?1 ORDER BY position")?;
+        let names = statement.query_map([game_id], |row| row.get(0))?;
+        Ok(names.collect::<Result<_, _>>()?)
+    }
+
+    /// Returns the totals of the games played in the given mode, or of every game if no mode is given.
+    pub fn summary(&self, mode: Option<GameMode>) -> Result<Summary, StatsError> {
+        let summary = self.connection.query_row(
+            "SELECT COUNT(*),
+                    SUM(outcome = 'won'), SUM(outcome = 'drew'), SUM(outcome = 'lost'),
+                    SUM(score = 0), AVG(score), AVG(accuracy)
+                 FROM games WHERE ?1 IS NULL OR mode = ?1",
+            [mode],
+            |row| {
+                Ok(Summary {
+                    games: row.get(0)?,
+                    wins: row.get::<_, Option<u32>>(1)?.unwrap_or_default(),
+                    draws: row.get::<_, Option<u32>>(2)?.unwrap_or_default(),
+                    losses: row.get::<_, Option<u32>>(3)?.unwrap_or_default(),
+                    shut_boxes: row.get::<_, Option<u32>>(4)?.unwrap_or_default(),
+                    average_score: row.get::<_, Option<f64>>(5)?.map(|score| score as f32),
+                    average_accuracy: row
+                        .get::<_, Option<f64>>(6)?
+                        .map(|accuracy| accuracy as f32),
+                })
+            },
+        )?;
+
+        Ok(summary)
+    }
+
+    /// Returns the totals against each opponent, from the most played to the least.
+    pub fn opponents(&self) -> Result<Vec<OpponentSummary>, StatsError> {
+        let mut statement = self.connection.prepare(
+            "SELECT opponents.name, COUNT(*),
+                SUM(games.outcome = 'won'), SUM(games.outcome = 'lost')
+             FROM opponents JOIN games ON games.id = opponents.game_id
+             GROUP BY opponents.name ORDER BY COUNT(*) DESC, opponents.name",
+        )?;
+        let opponents = statement.query_map([], |row| {
+            Ok(OpponentSummary {
+                name: row.get(0)?,
+                games: row.get(1)?,
+                wins: row.get(2)?,
+                losses: row.get(3)?,
+            })
+        })?;
+
+        Ok(opponents.collect::<Result<_, _>>()?)
+    }
+
+    /// Writes every game as CSV, from the oldest to the newest, returning the amount of games written.
+    /// The opponents of a game are separated by semicolons.
+    pub fn export_csv(&self, mut writer: impl Write) -> Result<usize, StatsError> {
+        writeln!(
+            writer,
+            "date,mode,opponents,tiles,score,winning_score,outcome,accuracy"
+        )?;
+
+        let mut statement = self.connection.prepare(
+            "SELECT datetime(games.played_at, 'unixepoch'), games.mode,
+                COALESCE((SELECT group_concat(name, ';') FROM
+                    (SELECT name FROM opponents WHERE game_id = games.id ORDER BY position)), ''),
+                games.tiles, games.score, games.winning_score, games.outcome, games.accuracy
+             FROM games ORDER BY games.played_at, games.id",
+        )?;
+        let mut rows = statement.query([])?;
+
+        let mut written = 0;
+        while let Some(row) = rows.next()? {
+            let date: String = row.get(0)?;
+            let mode: String = row.get(1)?;
+            let opponents: String = row.get(2)?;
+            let tiles: u8 = row.get(3)?;
+            let score: u8 = row.get(4)?;
+            let winning_score: u8 = row.get(5)?;
+            let outcome: String = row.get(6)?;
+            let accuracy = row
+                .get::<_, Option<f64>>(7)?
+                .map(|accuracy| format!("{accuracy:.3}"))
+                .unwrap_or_default();
+
+            writeln!(
+                writer,
+                "{date},{mode},{},{tiles},{score},{winning_score},{outcome},{accuracy}",
+                csv_field(&opponents)
+            )?;
+            written += 1;
+        }
+
+        Ok(written)
+    }
+}
+
+/// Quotes the field if it contains a character CSV treats specially.
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}